@@ -1,5 +1,11 @@
 //! Account data and flags.
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+
+use crate::amount::Stroops;
+use crate::crypto::PublicKey;
 use crate::error::{Error, Result};
+use crate::transaction::{Transaction, TransactionBuilder};
 use crate::xdr;
 
 bitflags! {
@@ -27,6 +33,36 @@ bitflags! {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DataValue(Vec<u8>);
 
+/// An account with its current sequence number, used as transaction source.
+///
+/// The sequence number is the one of the account in the ledger. Transactions
+/// built with `Account::build_transaction` use the next sequence number, and
+/// increment it once the transaction is built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    account_id: PublicKey,
+    sequence: i64,
+}
+
+/// Hands out consecutive sequence numbers of an account to transactions
+/// built concurrently.
+///
+/// Sequence numbers of transactions that could not be submitted are released
+/// and handed out again, lowest first, so that later transactions don't
+/// leave a gap.
+#[derive(Debug)]
+pub struct SequenceAllocator {
+    account_id: PublicKey,
+    state: Mutex<SequenceState>,
+}
+
+#[derive(Debug)]
+struct SequenceState {
+    base: i64,
+    last: i64,
+    released: BTreeSet<i64>,
+}
+
 impl DataValue {
     /// Converts a slice of bytes to a DataValue.
     ///
@@ -54,13 +90,246 @@ impl DataValue {
     /// Returns the DataValue xdr object.
     pub fn to_xdr(&self) -> Result<xdr::DataValue> {
         let inner = self.as_bytes();
-        Ok(xdr::DataValue(
-            inner.try_into().map_err(|_| Error::XdrError)?,
-        ))
+        Ok(xdr::DataValue(inner.try_into().map_err(Error::XdrError)?))
     }
 
     /// Creates a DataValue from xdr object.
     pub fn from_xdr(x: &xdr::DataValue) -> Result<DataValue> {
-        DataValue::from_slice(&x)
+        DataValue::from_slice(x)
+    }
+}
+
+impl Account {
+    /// Creates an account with `account_id` and its current `sequence` number.
+    pub fn new(account_id: PublicKey, sequence: i64) -> Account {
+        Account {
+            account_id,
+            sequence,
+        }
+    }
+
+    /// Retrieves the account id.
+    pub fn account_id(&self) -> &PublicKey {
+        &self.account_id
+    }
+
+    /// Retrieves the current sequence number.
+    pub fn sequence(&self) -> i64 {
+        self.sequence
+    }
+
+    /// Returns the sequence number of the next transaction of the account,
+    /// without incrementing it.
+    pub fn next_sequence(&self) -> Result<i64> {
+        self.sequence
+            .checked_add(1)
+            .ok_or(Error::InvalidSequenceNumber)
+    }
+
+    /// Increments the sequence number, and returns the new value.
+    pub fn increment_sequence(&mut self) -> Result<i64> {
+        self.sequence = self.next_sequence()?;
+        Ok(self.sequence)
+    }
+
+    /// Builds a transaction with the account as source account and the next
+    /// sequence number, adding the operations with `f`.
+    ///
+    /// The sequence number is incremented only if the transaction is built.
+    pub fn build_transaction<F>(&mut self, base_fee: Stroops, f: F) -> Result<Transaction>
+    where
+        F: FnOnce(TransactionBuilder) -> TransactionBuilder,
+    {
+        let tx = f(TransactionBuilder::from_account(self, base_fee)).into_transaction()?;
+        self.sequence = *tx.sequence();
+        Ok(tx)
+    }
+
+    /// Creates from the xdr account entry.
+    pub fn from_xdr_account_entry(x: &xdr::AccountEntry) -> Result<Account> {
+        let account_id = PublicKey::from_xdr_account_id(&x.account_id)?;
+        Ok(Account::new(account_id, x.seq_num.0))
+    }
+}
+
+impl SequenceAllocator {
+    /// Creates an allocator for `account`, handing out sequence numbers
+    /// after its current sequence number.
+    pub fn new(account: Account) -> SequenceAllocator {
+        SequenceAllocator {
+            account_id: account.account_id,
+            state: Mutex::new(SequenceState {
+                base: account.sequence,
+                last: account.sequence,
+                released: BTreeSet::new(),
+            }),
+        }
+    }
+
+    /// Retrieves the account id.
+    pub fn account_id(&self) -> &PublicKey {
+        &self.account_id
+    }
+
+    /// Returns the last sequence number handed out, or the account sequence
+    /// number if none was.
+    pub fn last_sequence(&self) -> i64 {
+        self.lock().last
+    }
+
+    /// Returns the next sequence number, reusing released sequence numbers first.
+    pub fn next_sequence(&self) -> Result<i64> {
+        let mut state = self.lock();
+        if let Some(sequence) = state.released.pop_first() {
+            return Ok(sequence);
+        }
+        state.last = state
+            .last
+            .checked_add(1)
+            .ok_or(Error::InvalidSequenceNumber)?;
+        Ok(state.last)
+    }
+
+    /// Releases `sequence` of a transaction that failed to submit, so that it's
+    /// handed out again.
+    ///
+    /// Returns an error if `sequence` was not handed out, or was already used
+    /// by the account before the allocator was created or reset.
+    pub fn release(&self, sequence: i64) -> Result<()> {
+        let mut state = self.lock();
+        if sequence <= state.base || sequence > state.last || state.released.contains(&sequence) {
+            return Err(Error::InvalidSequenceNumber);
+        }
+        if sequence < state.last {
+            state.released.insert(sequence);
+            return Ok(());
+        }
+        state.last -= 1;
+        while let Some(&last) = state.released.last() {
+            if last != state.last {
+                break;
+            }
+            state.released.pop_last();
+            state.last -= 1;
+        }
+        Ok(())
+    }
+
+    /// Resets the allocator to the current `sequence` number of the account,
+    /// for example after reloading the account from the network.
+    ///
+    /// Sequence numbers up to `sequence` can't be released afterwards.
+    pub fn reset(&self, sequence: i64) {
+        let mut state = self.lock();
+        state.base = sequence;
+        state.last = sequence;
+        state.released.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SequenceState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Account, SequenceAllocator};
+    use crate::error::Error;
+    use crate::operations::tests::*;
+
+    #[test]
+    fn test_account_sequence() {
+        let mut account = Account::new(keypair0().public_key(), 10);
+        assert_eq!(11, account.next_sequence().unwrap());
+        assert_eq!(10, account.sequence());
+        assert_eq!(11, account.increment_sequence().unwrap());
+        assert_eq!(11, account.sequence());
+
+        let mut account = Account::new(keypair0().public_key(), i64::MAX);
+        assert!(matches!(
+            account.increment_sequence(),
+            Err(Error::InvalidSequenceNumber)
+        ));
+        assert_eq!(i64::MAX, account.sequence());
+    }
+
+    #[test]
+    fn test_sequence_allocator_release() {
+        let allocator = SequenceAllocator::new(Account::new(keypair0().public_key(), 10));
+        let sequences: Vec<_> = (0..4).map(|_| allocator.next_sequence().unwrap()).collect();
+        assert_eq!(vec![11, 12, 13, 14], sequences);
+
+        allocator.release(12).unwrap();
+        assert!(matches!(
+            allocator.release(12),
+            Err(Error::InvalidSequenceNumber)
+        ));
+        assert!(matches!(
+            allocator.release(15),
+            Err(Error::InvalidSequenceNumber)
+        ));
+        assert_eq!(12, allocator.next_sequence().unwrap());
+        assert_eq!(15, allocator.next_sequence().unwrap());
+
+        // Releasing the last sequence numbers rolls back.
+        allocator.release(13).unwrap();
+        allocator.release(15).unwrap();
+        assert_eq!(14, allocator.last_sequence());
+        allocator.release(14).unwrap();
+        assert_eq!(12, allocator.last_sequence());
+        assert_eq!(13, allocator.next_sequence().unwrap());
+
+        allocator.reset(20);
+        assert_eq!(21, allocator.next_sequence().unwrap());
+    }
+
+    #[test]
+    fn test_sequence_allocator_rejects_used_sequences() {
+        let allocator = SequenceAllocator::new(Account::new(keypair0().public_key(), 10));
+        for sequence in [10, 3] {
+            assert!(matches!(
+                allocator.release(sequence),
+                Err(Error::InvalidSequenceNumber)
+            ));
+        }
+        assert_eq!(10, allocator.last_sequence());
+        assert_eq!(11, allocator.next_sequence().unwrap());
+        allocator.release(11).unwrap();
+        assert_eq!(10, allocator.last_sequence());
+
+        assert_eq!(11, allocator.next_sequence().unwrap());
+        assert_eq!(12, allocator.next_sequence().unwrap());
+        allocator.reset(20);
+        for sequence in [11, 12, 20] {
+            assert!(matches!(
+                allocator.release(sequence),
+                Err(Error::InvalidSequenceNumber)
+            ));
+        }
+        assert_eq!(21, allocator.next_sequence().unwrap());
+        allocator.release(21).unwrap();
+        assert_eq!(20, allocator.last_sequence());
+    }
+
+    #[test]
+    fn test_sequence_allocator_threads() {
+        let allocator = SequenceAllocator::new(Account::new(keypair0().public_key(), 0));
+        let mut sequences: Vec<i64> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..100)
+                            .map(|_| allocator.next_sequence().unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        sequences.sort_unstable();
+        assert_eq!((1..=800).collect::<Vec<_>>(), sequences);
     }
 }
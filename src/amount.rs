@@ -3,7 +3,7 @@ use crate::error::{Error, Result};
 use crate::xdr;
 use num_rational::Ratio;
 use num_traits::cast::ToPrimitive;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use std::convert::TryFrom;
 use std::fmt;
 use std::io::{Read, Write};
@@ -11,6 +11,9 @@ use std::str::FromStr;
 
 const STELLAR_SCALE: u32 = 7;
 
+/// Basis points in a unit, used to express fees.
+pub const BASIS_POINTS: i64 = 10_000;
+
 /// Amount in base units. For example, 2 XLM.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Amount(pub(crate) Decimal);
@@ -23,6 +26,27 @@ pub struct Stroops(pub(crate) i64);
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Price(Ratio<i32>);
 
+/// Amount of a token with `decimals` decimal places, as used by Soroban
+/// (SEP-41) tokens.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TokenAmount {
+    raw: i128,
+    decimals: u32,
+}
+
+/// How to round values that can't be represented exactly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceil,
+    /// Round to the nearest value, ties to the even value.
+    HalfEven,
+    /// Round towards zero.
+    Truncate,
+}
+
 impl Amount {
     pub(crate) fn from_decimal(decimal: Decimal) -> Amount {
         Amount(decimal)
@@ -59,6 +83,91 @@ impl Amount {
         self.0.checked_rem(other.0).map(Amount)
     }
 
+    /// Creates with the maximum amount, `i64::MAX` stroops.
+    pub fn max() -> Amount {
+        Amount(Decimal::new(i64::MAX, STELLAR_SCALE))
+    }
+
+    /// Creates from a token amount `value` with `decimals` decimal places,
+    /// as used by Soroban tokens.
+    pub fn from_i128(value: i128, decimals: u32) -> Result<Amount> {
+        let inner = Decimal::try_from_i128_with_scale(value, decimals)
+            .map_err(|_| Error::AmountOverflow)?;
+        Ok(Amount(inner))
+    }
+
+    /// Returns the token amount with `decimals` decimal places, rounding the
+    /// digits that don't fit with `mode`.
+    pub fn to_i128(&self, decimals: u32, mode: RoundingMode) -> Result<i128> {
+        let rounded = self.0.round_dp_with_strategy(decimals, mode.strategy());
+        10i128
+            .checked_pow(decimals - rounded.scale())
+            .and_then(|factor| rounded.mantissa().checked_mul(factor))
+            .ok_or(Error::AmountOverflow)
+    }
+
+    /// Returns the amount rounded to 7 decimal places with `mode`.
+    pub fn round(&self, mode: RoundingMode) -> Amount {
+        let mut inner = self
+            .0
+            .round_dp_with_strategy(STELLAR_SCALE, mode.strategy());
+        inner.rescale(STELLAR_SCALE);
+        Amount(inner)
+    }
+
+    /// Computes `self * other`, rounded to 7 decimal places with `mode`.
+    ///
+    /// Returns an error if the result doesn't fit in `i64` stroops.
+    pub fn mul_rounded(&self, other: &Amount, mode: RoundingMode) -> Result<Amount> {
+        let inner = self.0.checked_mul(other.0).ok_or(Error::AmountOverflow)?;
+        Amount(inner).bounded(mode)
+    }
+
+    /// Computes `self / other`, rounded to 7 decimal places with `mode`.
+    ///
+    /// Returns an error if `other` is zero or the result doesn't fit in `i64` stroops.
+    pub fn div_rounded(&self, other: &Amount, mode: RoundingMode) -> Result<Amount> {
+        let inner = self.0.checked_div(other.0).ok_or(Error::AmountOverflow)?;
+        Amount(inner).bounded(mode)
+    }
+
+    /// Computes `percent` percent of the amount, rounded to 7 decimal places with `mode`.
+    ///
+    /// Returns an error if the result doesn't fit in `i64` stroops.
+    pub fn percentage(&self, percent: &Amount, mode: RoundingMode) -> Result<Amount> {
+        let inner = self
+            .0
+            .checked_mul(percent.0)
+            .and_then(|value| value.checked_div(Decimal::ONE_HUNDRED))
+            .ok_or(Error::AmountOverflow)?;
+        Amount(inner).bounded(mode)
+    }
+
+    /// Computes `self * price` like stellar-core `bigDivide` does, rounding
+    /// the stroops with `mode`.
+    ///
+    /// Returns an error if the amount has more than 7 decimals or the result
+    /// doesn't fit in `i64` stroops.
+    pub fn mul_price(&self, price: &Price, mode: RoundingMode) -> Result<Amount> {
+        let stroops = self
+            .to_stroops()?
+            .checked_mul_price(price, mode)
+            .ok_or(Error::AmountOverflow)?;
+        Amount::from_stroops(&stroops)
+    }
+
+    /// Returns the equivalent amount in stroops, rounding the digits past the
+    /// 7th decimal place with `mode`.
+    pub fn to_stroops_rounded(&self, mode: RoundingMode) -> Result<Stroops> {
+        self.round(mode).to_stroops()
+    }
+
+    fn bounded(&self, mode: RoundingMode) -> Result<Amount> {
+        let amount = self.round(mode);
+        amount.to_stroops().map_err(|_| Error::AmountOverflow)?;
+        Ok(amount)
+    }
+
     /// Returns the equivalent amount in stroops.
     pub fn to_stroops(&self) -> Result<Stroops> {
         let scale = self.0.scale();
@@ -136,6 +245,40 @@ impl Stroops {
         self.0.checked_rem(other.0).map(Stroops)
     }
 
+    /// Computes `self * numerator / denominator` without intermediate overflow,
+    /// rounding the result with `mode`.
+    ///
+    /// Returns None if `denominator` is zero or the result doesn't fit in `i64`.
+    pub fn checked_mul_div(
+        &self,
+        numerator: i64,
+        denominator: i64,
+        mode: RoundingMode,
+    ) -> Option<Stroops> {
+        let value = i128::from(self.0) * i128::from(numerator);
+        let result = mode.divide(value, i128::from(denominator))?;
+        i64::try_from(result).ok().map(Stroops)
+    }
+
+    /// Computes `self * price` without intermediate overflow, rounding with `mode`.
+    ///
+    /// With `RoundingMode::Floor` and `RoundingMode::Ceil` this matches
+    /// stellar-core `bigDivide` rounding down and up.
+    pub fn checked_mul_price(&self, price: &Price, mode: RoundingMode) -> Option<Stroops> {
+        self.checked_mul_div(
+            i64::from(price.numerator()),
+            i64::from(price.denominator()),
+            mode,
+        )
+    }
+
+    /// Computes the fee of `basis_points` on the amount, rounding with `mode`.
+    ///
+    /// Returns None if the result doesn't fit in `i64`.
+    pub fn checked_basis_points(&self, basis_points: i64, mode: RoundingMode) -> Option<Stroops> {
+        self.checked_mul_div(basis_points, BASIS_POINTS, mode)
+    }
+
     /// Returns stroops amount as xdr object.
     pub fn to_xdr_int64(&self) -> Result<xdr::Int64> {
         Ok(self.0)
@@ -161,6 +304,167 @@ impl Stroops {
     }
 }
 
+impl TokenAmount {
+    /// Creates with the `raw` amount in the token smallest unit.
+    pub fn new(raw: i128, decimals: u32) -> TokenAmount {
+        TokenAmount { raw, decimals }
+    }
+
+    /// Parses a decimal amount such as `12.5` for a token with `decimals` decimal places.
+    pub fn parse(s: &str, decimals: u32) -> Result<TokenAmount> {
+        let invalid = || Error::InvalidTokenAmount(s.to_string());
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_number = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || digits.ends_with('.') || !is_number(whole) || !is_number(fraction) {
+            return Err(invalid());
+        }
+        if fraction.len() > decimals as usize {
+            return Err(Error::InvalidAmountScale);
+        }
+        let mut raw: i128 = 0;
+        let padding = decimals as usize - fraction.len();
+        for b in whole.bytes().chain(fraction.bytes()) {
+            raw = raw
+                .checked_mul(10)
+                .and_then(|raw| raw.checked_add(i128::from(b - b'0')))
+                .ok_or(Error::AmountOverflow)?;
+        }
+        raw = 10i128
+            .checked_pow(padding as u32)
+            .and_then(|factor| raw.checked_mul(factor))
+            .ok_or(Error::AmountOverflow)?;
+        if negative {
+            raw = -raw;
+        }
+        Ok(TokenAmount { raw, decimals })
+    }
+
+    /// Creates from a classic amount, with 7 decimals.
+    pub fn from_amount(amount: &Amount) -> Result<TokenAmount> {
+        Ok(TokenAmount::from_stroops(&amount.to_stroops()?))
+    }
+
+    /// Creates from an amount in stroops, with 7 decimals.
+    pub fn from_stroops(stroops: &Stroops) -> TokenAmount {
+        TokenAmount::new(i128::from(stroops.0), STELLAR_SCALE)
+    }
+
+    /// Creates from the xdr `ScVal::I128` object.
+    pub fn from_xdr_sc_val(x: &xdr::ScVal, decimals: u32) -> Result<TokenAmount> {
+        match x {
+            xdr::ScVal::I128(parts) => {
+                let raw = (i128::from(parts.hi) << 64) | i128::from(parts.lo);
+                Ok(TokenAmount::new(raw, decimals))
+            }
+            _ => Err(Error::InvalidTokenAmount(format!(
+                "expected i128 value, found {:?}",
+                x.discriminant()
+            ))),
+        }
+    }
+
+    /// Retrieves the amount in the token smallest unit.
+    pub fn raw(&self) -> i128 {
+        self.raw
+    }
+
+    /// Retrieves the token decimals.
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    /// Returns the equivalent classic amount. Decimals must be 7.
+    pub fn to_amount(&self) -> Result<Amount> {
+        Amount::from_stroops(&self.to_stroops()?)
+    }
+
+    /// Returns the equivalent amount in stroops. Decimals must be 7.
+    pub fn to_stroops(&self) -> Result<Stroops> {
+        if self.decimals != STELLAR_SCALE {
+            return Err(Error::TokenDecimalsMismatch);
+        }
+        i64::try_from(self.raw)
+            .map(Stroops)
+            .map_err(|_| Error::InvalidStroopsAmount)
+    }
+
+    /// Returns the amount converted to `decimals` decimal places, rounding
+    /// with `mode` if it loses precision.
+    pub fn rescale(&self, decimals: u32, mode: RoundingMode) -> Result<TokenAmount> {
+        let raw = if decimals >= self.decimals {
+            10i128
+                .checked_pow(decimals - self.decimals)
+                .and_then(|factor| self.raw.checked_mul(factor))
+        } else {
+            10i128
+                .checked_pow(self.decimals - decimals)
+                .and_then(|factor| mode.divide(self.raw, factor))
+        };
+        let raw = raw.ok_or(Error::AmountOverflow)?;
+        Ok(TokenAmount { raw, decimals })
+    }
+
+    /// Checked addition. Computes `self + other`, returning an error if
+    /// overflow occurred or the decimals are different.
+    pub fn checked_add(&self, other: &TokenAmount) -> Result<TokenAmount> {
+        self.checked_op(other, i128::checked_add)
+    }
+
+    /// Checked subtraction. Computes `self - other`, returning an error if
+    /// overflow occurred or the decimals are different.
+    pub fn checked_sub(&self, other: &TokenAmount) -> Result<TokenAmount> {
+        self.checked_op(other, i128::checked_sub)
+    }
+
+    /// Returns the xdr `ScVal::I128` object.
+    pub fn to_xdr_sc_val(&self) -> xdr::ScVal {
+        xdr::ScVal::I128(xdr::Int128Parts {
+            hi: (self.raw >> 64) as i64,
+            lo: self.raw as u64,
+        })
+    }
+
+    fn checked_op<F>(&self, other: &TokenAmount, op: F) -> Result<TokenAmount>
+    where
+        F: Fn(i128, i128) -> Option<i128>,
+    {
+        if self.decimals != other.decimals {
+            return Err(Error::TokenDecimalsMismatch);
+        }
+        let raw = op(self.raw, other.raw).ok_or(Error::AmountOverflow)?;
+        Ok(TokenAmount::new(raw, self.decimals))
+    }
+}
+
+impl PartialOrd for TokenAmount {
+    /// Compares amounts with the same decimals, returns None otherwise.
+    fn partial_cmp(&self, other: &TokenAmount) -> Option<std::cmp::Ordering> {
+        if self.decimals == other.decimals {
+            Some(self.raw.cmp(&other.raw))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.raw < 0 { "-" } else { "" };
+        let digits = self.raw.unsigned_abs().to_string();
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
 impl Price {
     /// Creates price from numerator and denominator.
     pub fn new(numerator: i32, denominator: i32) -> Price {
@@ -184,29 +488,37 @@ impl Price {
         Price(inner)
     }
 
-    /// Returns price as xdr object.
-    pub fn to_xdr(&self) -> Result<xdr::Price> {
-        Ok(xdr::Price {
-            n: self.numerator(),
-            d: self.denominator(),
-        })
+    /// Returns the inverse price, swapping numerator and denominator.
+    ///
+    /// A sell offer at `price` is a buy offer at the inverse price.
+    pub fn inverted(&self) -> Price {
+        Price::new(self.denominator(), self.numerator())
     }
 
-    /// Creates price from xdr object.
-    pub fn from_xdr(x: &xdr::Price) -> Result<Price> {
-        Ok(Price::new(x.n, x.d))
+    /// Returns true if both numerator and denominator are positive, as
+    /// stellar-core requires for offers.
+    pub fn is_valid(&self) -> bool {
+        self.numerator() > 0 && self.denominator() > 0
     }
-}
 
-impl FromStr for Price {
-    type Err = Error;
+    /// Returns the price as a decimal number, rounded to the 28 significant
+    /// digits `Decimal` supports.
+    pub fn to_decimal(&self) -> Result<Decimal> {
+        Decimal::from(self.numerator())
+            .checked_div(Decimal::from(self.denominator()))
+            .ok_or(Error::InvalidPrice)
+    }
 
-    fn from_str(s: &str) -> Result<Price> {
-        if s.is_empty() {
-            return Err(Error::ParsePriceError);
-        }
+    /// Returns the price as an amount, rounded to 7 decimal places with `mode`.
+    pub fn to_amount(&self, mode: RoundingMode) -> Result<Amount> {
+        Ok(Amount(self.to_decimal()?).round(mode))
+    }
+
+    /// Creates the price closest to `value` that can be represented, stopping
+    /// at the first continued fraction approximation within `tolerance`.
+    pub fn from_decimal(value: Decimal, tolerance: &Decimal) -> Result<Price> {
         let max_i32 = Decimal::new(i32::MAX as i64, 0);
-        let mut number = Decimal::from_str(s).map_err(|_| Error::ParsePriceError)?;
+        let mut number = value;
         let zero = Decimal::new(0, 0);
         let one = Decimal::new(1, 0);
 
@@ -225,7 +537,7 @@ impl FromStr for Price {
                 break;
             }
             fractions.push((h, k));
-            if fract == zero {
+            if fract == zero || (h / k - value).abs() <= *tolerance {
                 break;
             }
             number = one / fract;
@@ -245,6 +557,115 @@ impl FromStr for Price {
             }
         }
     }
+
+    /// Parses the decimal price `s`, stopping at the first approximation
+    /// within `tolerance`.
+    ///
+    /// `Price::from_str` is the same with a zero tolerance.
+    pub fn from_str_with_tolerance(s: &str, tolerance: &Decimal) -> Result<Price> {
+        if s.is_empty() {
+            return Err(Error::ParsePriceError);
+        }
+        let number = Decimal::from_str(s).map_err(|_| Error::ParsePriceError)?;
+        Price::from_decimal(number, tolerance)
+    }
+
+    /// Returns the prices `basis_points` below and above the price.
+    ///
+    /// Useful to set the `min_price` and `max_price` of a liquidity pool
+    /// deposit from the current pool price and the accepted slippage.
+    pub fn slippage_bounds(&self, basis_points: u32) -> Result<(Price, Price)> {
+        let basis_points = i64::from(basis_points);
+        if !self.is_valid() || basis_points >= BASIS_POINTS {
+            return Err(Error::InvalidPrice);
+        }
+        let numerator = i64::from(self.numerator());
+        let denominator = i64::from(self.denominator()) * BASIS_POINTS;
+        let min = Price::from_ratio(numerator * (BASIS_POINTS - basis_points), denominator)?;
+        let max = Price::from_ratio(numerator * (BASIS_POINTS + basis_points), denominator)?;
+        Ok((min, max))
+    }
+
+    /// Creates the price `numerator / denominator`, approximating it if it
+    /// can't be represented with `i32` terms.
+    fn from_ratio(numerator: i64, denominator: i64) -> Result<Price> {
+        let ratio = Ratio::new(numerator, denominator);
+        match (i32::try_from(*ratio.numer()), i32::try_from(*ratio.denom())) {
+            (Ok(n), Ok(d)) => Ok(Price::new(n, d)),
+            _ => {
+                let value = Decimal::from(*ratio.numer()) / Decimal::from(*ratio.denom());
+                Price::from_decimal(value, &Decimal::ZERO)
+            }
+        }
+    }
+
+    /// Returns price as xdr object.
+    pub fn to_xdr(&self) -> Result<xdr::Price> {
+        Ok(xdr::Price {
+            n: self.numerator(),
+            d: self.denominator(),
+        })
+    }
+
+    /// Creates price from xdr object.
+    pub fn from_xdr(x: &xdr::Price) -> Result<Price> {
+        Ok(Price::new(x.n, x.d))
+    }
+}
+
+impl RoundingMode {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            RoundingMode::Floor => RoundingStrategy::ToNegativeInfinity,
+            RoundingMode::Ceil => RoundingStrategy::ToPositiveInfinity,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::Truncate => RoundingStrategy::ToZero,
+        }
+    }
+
+    /// Divides `n` by `d`, rounding the quotient.
+    fn divide(self, n: i128, d: i128) -> Option<i128> {
+        let quotient = n.checked_div(d)?;
+        let remainder = n % d;
+        if remainder == 0 {
+            return Some(quotient);
+        }
+        // The quotient was truncated, step away from zero when needed.
+        let step = if (n < 0) != (d < 0) { -1 } else { 1 };
+        let away = match self {
+            RoundingMode::Truncate => false,
+            RoundingMode::Floor => step < 0,
+            RoundingMode::Ceil => step > 0,
+            RoundingMode::HalfEven => {
+                let twice = remainder.unsigned_abs() * 2;
+                let divisor = d.unsigned_abs();
+                twice > divisor || (twice == divisor && quotient % 2 != 0)
+            }
+        };
+        if away {
+            quotient.checked_add(step)
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
+impl FromStr for Price {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Price> {
+        Price::from_str_with_tolerance(s, &Decimal::ZERO)
+    }
+}
+
+impl fmt::Display for Price {
+    /// Formats the price as a decimal number with 7 decimal places.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_amount(RoundingMode::HalfEven) {
+            Ok(amount) => amount.fmt(f),
+            Err(_) => write!(f, "{}/{}", self.numerator(), self.denominator()),
+        }
+    }
 }
 
 impl TryFrom<Amount> for Stroops {
@@ -263,6 +684,20 @@ impl TryFrom<Stroops> for Amount {
     }
 }
 
+impl From<Stroops> for TokenAmount {
+    fn from(stroops: Stroops) -> Self {
+        TokenAmount::from_stroops(&stroops)
+    }
+}
+
+impl TryFrom<TokenAmount> for Stroops {
+    type Error = Error;
+
+    fn try_from(amount: TokenAmount) -> std::result::Result<Self, Self::Error> {
+        amount.to_stroops()
+    }
+}
+
 impl xdr::WriteXdr for Price {
     fn write_xdr<W: Write>(&self, w: &mut xdr::Limited<W>) -> xdr::Result<()> {
         let xdr_price = self.to_xdr().map_err(|_| xdr::Error::Invalid)?;
@@ -279,8 +714,10 @@ impl xdr::ReadXdr for Price {
 
 #[cfg(test)]
 mod tests {
-    use super::{Amount, Price, Stroops};
+    use super::{Amount, Price, RoundingMode, Stroops, TokenAmount};
+    use crate::error::Error;
     use crate::xdr::{XDRDeserialize, XDRSerialize};
+    use rust_decimal::Decimal;
     use std::str;
     use std::str::FromStr;
 
@@ -308,6 +745,202 @@ mod tests {
         assert_eq!(stroops, Stroops::new(1234567800));
     }
 
+    #[test]
+    fn test_amount_to_stroops_rounded() {
+        let amount = str::parse::<Amount>("1.5")
+            .unwrap()
+            .checked_div(&str::parse::<Amount>("7").unwrap())
+            .unwrap();
+        assert!(amount.to_stroops().is_err());
+        let cases = [
+            (RoundingMode::Floor, 2142857),
+            (RoundingMode::Ceil, 2142858),
+            (RoundingMode::HalfEven, 2142857),
+            (RoundingMode::Truncate, 2142857),
+        ];
+        for (mode, expected) in cases {
+            assert_eq!(
+                Stroops::new(expected),
+                amount.to_stroops_rounded(mode).unwrap()
+            );
+        }
+
+        let half = Amount::from_i128(-25, 8).unwrap();
+        assert_eq!(
+            Stroops::new(-3),
+            half.to_stroops_rounded(RoundingMode::Floor).unwrap()
+        );
+        assert_eq!(
+            Stroops::new(-2),
+            half.to_stroops_rounded(RoundingMode::Ceil).unwrap()
+        );
+        assert_eq!(
+            Stroops::new(-2),
+            half.to_stroops_rounded(RoundingMode::HalfEven).unwrap()
+        );
+        assert_eq!(
+            Stroops::new(-2),
+            half.to_stroops_rounded(RoundingMode::Truncate).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_amount_bounded_operations() {
+        let amount = str::parse::<Amount>("100").unwrap();
+        let percent = str::parse::<Amount>("0.3").unwrap();
+        let fee = amount.percentage(&percent, RoundingMode::Ceil).unwrap();
+        assert_eq!(Stroops::new(3000000), fee.to_stroops().unwrap());
+
+        let third = amount
+            .div_rounded(&str::parse::<Amount>("3").unwrap(), RoundingMode::HalfEven)
+            .unwrap();
+        assert_eq!(Stroops::new(333333333), third.to_stroops().unwrap());
+
+        let two = str::parse::<Amount>("2").unwrap();
+        assert!(matches!(
+            Amount::max().mul_rounded(&two, RoundingMode::Floor),
+            Err(Error::AmountOverflow)
+        ));
+        assert!(matches!(
+            amount.div_rounded(&str::parse::<Amount>("0").unwrap(), RoundingMode::Floor),
+            Err(Error::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_amount_i128_conversion() {
+        let amount = Amount::from_i128(1_234_567_890_123_456_789, 18).unwrap();
+        assert_eq!("1.234567890123456789", amount.to_string());
+        assert_eq!(
+            1_234_567_890_123_456_789,
+            amount.to_i128(18, RoundingMode::Truncate).unwrap()
+        );
+        assert_eq!(
+            1_234_568,
+            amount.to_i128(6, RoundingMode::HalfEven).unwrap()
+        );
+        assert_eq!(1_234_567, amount.to_i128(6, RoundingMode::Floor).unwrap());
+        assert_eq!(
+            12_345_678_901_234_567_890_000_000_000_000_000,
+            amount.to_i128(34, RoundingMode::Floor).unwrap()
+        );
+        assert!(amount.to_i128(39, RoundingMode::Floor).is_err());
+        assert!(Amount::from_i128(i128::MAX, 7).is_err());
+    }
+
+    #[test]
+    fn test_stroops_mul_div() {
+        let amount = Stroops::max();
+        assert_eq!(
+            Some(Stroops::new(i64::MAX / 2)),
+            amount.checked_mul_div(3, 6, RoundingMode::Floor)
+        );
+        assert_eq!(None, amount.checked_mul_div(2, 1, RoundingMode::Floor));
+        assert_eq!(None, amount.checked_mul_div(1, 0, RoundingMode::Floor));
+
+        let amount = Stroops::new(25);
+        assert_eq!(
+            Some(Stroops::new(2)),
+            amount.checked_mul_div(1, 10, RoundingMode::HalfEven)
+        );
+        assert_eq!(
+            Some(Stroops::new(4)),
+            Stroops::new(35).checked_mul_div(1, 10, RoundingMode::HalfEven)
+        );
+        assert_eq!(
+            Some(Stroops::new(-3)),
+            amount.checked_mul_div(-1, 10, RoundingMode::Floor)
+        );
+        assert_eq!(
+            Some(Stroops::new(-2)),
+            amount.checked_mul_div(-1, 10, RoundingMode::Ceil)
+        );
+        assert_eq!(
+            Some(Stroops::new(3)),
+            amount.checked_mul_div(1, 10, RoundingMode::Ceil)
+        );
+
+        let fee = Stroops::new(1_000_001).checked_basis_points(30, RoundingMode::Ceil);
+        assert_eq!(Some(Stroops::new(3001)), fee);
+    }
+
+    #[test]
+    fn test_token_amount_parse_and_display() {
+        let cases = [
+            ("12.5", 6, 12_500_000, "12.500000"),
+            ("-0.000001", 6, -1, "-0.000001"),
+            ("42", 0, 42, "42"),
+            ("0.1", 18, 100_000_000_000_000_000, "0.100000000000000000"),
+        ];
+        for (s, decimals, raw, formatted) in cases {
+            let amount = TokenAmount::parse(s, decimals).unwrap();
+            assert_eq!(raw, amount.raw());
+            assert_eq!(decimals, amount.decimals());
+            assert_eq!(formatted, amount.to_string());
+            assert_eq!(amount, TokenAmount::parse(formatted, decimals).unwrap());
+        }
+
+        assert!(matches!(
+            TokenAmount::parse("1.0000001", 6),
+            Err(Error::InvalidAmountScale)
+        ));
+        for invalid in ["", "-", ".5", "1.", "1,5", "+1", "1e3"] {
+            assert!(TokenAmount::parse(invalid, 6).is_err(), "{:?}", invalid);
+        }
+        assert!(matches!(
+            TokenAmount::parse("1", 40),
+            Err(Error::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_token_amount_conversions() {
+        let amount = str::parse::<Amount>("1.5").unwrap();
+        let token = TokenAmount::from_amount(&amount).unwrap();
+        assert_eq!(TokenAmount::new(15_000_000, 7), token);
+        assert_eq!(amount, token.to_amount().unwrap());
+        assert_eq!(Stroops::new(15_000_000), token.to_stroops().unwrap());
+        assert_eq!(token, TokenAmount::from(Stroops::new(15_000_000)));
+
+        let rescaled = token.rescale(18, RoundingMode::Floor).unwrap();
+        assert_eq!(1_500_000_000_000_000_000, rescaled.raw());
+        assert!(matches!(
+            rescaled.to_stroops(),
+            Err(Error::TokenDecimalsMismatch)
+        ));
+        let rescaled = TokenAmount::new(-15, 1)
+            .rescale(0, RoundingMode::HalfEven)
+            .unwrap();
+        assert_eq!(-2, rescaled.raw());
+
+        for raw in [0, -1, i128::MAX, i128::MIN, 1 << 64, -(1 << 64) + 3] {
+            let token = TokenAmount::new(raw, 7);
+            let sc_val = token.to_xdr_sc_val();
+            assert_eq!(token, TokenAmount::from_xdr_sc_val(&sc_val, 7).unwrap());
+        }
+        assert!(TokenAmount::from_xdr_sc_val(&crate::xdr::ScVal::U32(1), 7).is_err());
+    }
+
+    #[test]
+    fn test_token_amount_checked_arithmetic() {
+        let a = TokenAmount::new(100, 6);
+        let b = TokenAmount::new(30, 6);
+        assert_eq!(TokenAmount::new(130, 6), a.checked_add(&b).unwrap());
+        assert_eq!(TokenAmount::new(70, 6), a.checked_sub(&b).unwrap());
+        assert!(a > b);
+
+        let c = TokenAmount::new(30, 7);
+        assert!(matches!(
+            a.checked_add(&c),
+            Err(Error::TokenDecimalsMismatch)
+        ));
+        assert_eq!(None, a.partial_cmp(&c));
+        assert!(matches!(
+            TokenAmount::new(i128::MAX, 6).checked_add(&a),
+            Err(Error::AmountOverflow)
+        ));
+    }
+
     #[test]
     fn test_price_from_str() {
         let one_22 = "1".repeat(22);
@@ -355,6 +988,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_price_from_str_with_tolerance() {
+        let tolerance = Decimal::new(1, 3);
+        let price = Price::from_str_with_tolerance("3.14159265", &tolerance).unwrap();
+        assert_eq!((333, 106), (price.numerator(), price.denominator()));
+        let price = Price::from_str("3.14159265").unwrap();
+        assert_eq!(
+            (62831853, 20000000),
+            (price.numerator(), price.denominator())
+        );
+    }
+
+    #[test]
+    fn test_price_conversions() {
+        let price = Price::new(1, 3);
+        assert_eq!("0.3333333", price.to_string());
+        assert_eq!("0.5000000", Price::new(2, 4).to_string());
+        assert_eq!("1/0", Price::new(1, 0).to_string());
+        assert_eq!(
+            Stroops::new(3333334),
+            price
+                .to_amount(RoundingMode::Ceil)
+                .unwrap()
+                .to_stroops()
+                .unwrap()
+        );
+        assert_eq!(Decimal::new(25, 2), Price::new(1, 4).to_decimal().unwrap());
+        assert!(Price::new(1, 0).to_decimal().is_err());
+
+        let inverted = Price::new(2, 7).inverted();
+        assert_eq!((7, 2), (inverted.numerator(), inverted.denominator()));
+        assert!(!Price::new(0, 1).is_valid());
+        assert!(!Price::new(1, -1).is_valid());
+    }
+
+    #[test]
+    fn test_price_exact_comparison() {
+        let a = Price::new(i32::MAX - 1, i32::MAX);
+        let b = Price::new(i32::MAX - 2, i32::MAX - 1);
+        assert!(b < a);
+        assert_eq!(Price::new(1, 2), Price::new(3, 6));
+    }
+
+    #[test]
+    fn test_amount_mul_price() {
+        let price = Price::new(1, 3);
+        let amount = Stroops::new(10);
+        assert_eq!(
+            Some(Stroops::new(3)),
+            amount.checked_mul_price(&price, RoundingMode::Floor)
+        );
+        assert_eq!(
+            Some(Stroops::new(4)),
+            amount.checked_mul_price(&price, RoundingMode::Ceil)
+        );
+        assert_eq!(
+            Some(Stroops::max()),
+            Stroops::max().checked_mul_price(&Price::new(i32::MAX, i32::MAX), RoundingMode::Floor)
+        );
+        assert_eq!(
+            None,
+            Stroops::max().checked_mul_price(&Price::new(2, 1), RoundingMode::Floor)
+        );
+
+        let amount = str::parse::<Amount>("100").unwrap();
+        let result = amount
+            .mul_price(&Price::new(3, 2), RoundingMode::Floor)
+            .unwrap();
+        assert_eq!(str::parse::<Amount>("150").unwrap(), result);
+    }
+
+    #[test]
+    fn test_price_slippage_bounds() {
+        let (min, max) = Price::new(3, 2).slippage_bounds(100).unwrap();
+        assert_eq!(Price::new(297, 200), min);
+        assert_eq!(Price::new(303, 200), max);
+
+        let price = Price::new(i32::MAX, 3);
+        let (min, max) = price.slippage_bounds(1).unwrap();
+        assert!(min < price && price < max);
+        assert!(min.is_valid() && max.is_valid());
+
+        assert!(Price::new(1, 0).slippage_bounds(100).is_err());
+        assert!(Price::new(1, 2).slippage_bounds(10_000).is_err());
+    }
+
     #[test]
     fn test_price_xdr_ser() {
         let price = Price::new(123, 456);
@@ -0,0 +1,1236 @@
+//! In memory application of classic transactions.
+//!
+//! `LedgerState` holds a set of ledger entries together with the ledger
+//! header values that affect validation (ledger sequence, close time, base
+//! fee and base reserve). Applying a transaction follows the same steps as
+//! stellar-core:
+//!
+//!  1. the transaction is validated. Invalid transactions are rejected: they
+//!     don't change the state and don't pay any fee;
+//!  2. the fee is charged to the fee source account;
+//!  3. the source account sequence number is bumped, together with the
+//!     ledger and time of the update checked by the `min_seq_age` and
+//!     `min_seq_ledger_gap` preconditions, and pre-authorized transaction
+//!     signers are removed;
+//!  4. the operations are applied in order. If any operation fails, all
+//!     operations are rolled back and the transaction fails.
+//!
+//! Only classic operations that don't involve the order book or liquidity
+//! pools are supported, other operations fail with `OpNotSupported`.
+//!
+//! ```rust
+//! use stellar_base::amount::Stroops;
+//! use stellar_base::apply::LedgerState;
+//! use stellar_base::crypto::DalekKeyPair;
+//! use stellar_base::network::Network;
+//! use stellar_base::operations::Operation;
+//! use stellar_base::transaction::{Transaction, MIN_BASE_FEE};
+//! # fn run() -> stellar_base::error::Result<()> {
+//! let source = DalekKeyPair::random()?;
+//! let destination = DalekKeyPair::random()?;
+//!
+//! let mut state = LedgerState::new(Network::new_test());
+//! state.create_account(&source.public_key(), Stroops::new(100_000_000))?;
+//!
+//! let sequence = state.account(&source.public_key())?.unwrap().seq_num.0 + 1;
+//! let mut tx = Transaction::builder(source.public_key(), sequence, MIN_BASE_FEE)
+//!     .add_operation(
+//!         Operation::new_create_account()
+//!             .with_destination(destination.public_key())
+//!             .with_starting_balance(Stroops::new(20_000_000))?
+//!             .build()?,
+//!     )
+//!     .into_transaction()?;
+//! tx.sign(source.as_ref(), &Network::new_test())?;
+//!
+//! let result = state.apply_transaction(&tx.into_envelope())?;
+//! assert!(result.is_success());
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::amount::Stroops;
+use crate::error::Result;
+use crate::operations::ThresholdLevel;
+use crate::transaction::TransactionEnvelope;
+use crate::transaction_result::TransactionResult;
+use crate::xdr;
+
+mod operations;
+mod signatures;
+mod state;
+
+pub use self::state::{ledger_entry_key, LedgerState, DEFAULT_BASE_FEE, DEFAULT_BASE_RESERVE};
+
+use self::operations::{apply_operation, is_operation_success, OperationContext};
+use self::signatures::{threshold_level, SignatureChecker};
+use self::state::{
+    account_seq_ledger_and_time, available_balance, update_account_seq_ledger_and_time, LedgerTxn,
+};
+
+/// Result of applying a transaction to a `LedgerState`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionApplyResult {
+    hash: Vec<u8>,
+    applied: bool,
+    result: TransactionResult,
+    xdr_result: xdr::TransactionResult,
+    fee_changes: Vec<xdr::LedgerEntryChange>,
+    changes_before: Vec<xdr::LedgerEntryChange>,
+    operation_changes: Vec<Vec<xdr::LedgerEntryChange>>,
+}
+
+/// Ledger changes made by a transaction.
+#[derive(Default)]
+struct TransactionChanges {
+    fee_changes: Vec<xdr::LedgerEntryChange>,
+    changes_before: Vec<xdr::LedgerEntryChange>,
+    operation_changes: Vec<Vec<xdr::LedgerEntryChange>>,
+}
+
+impl TransactionApplyResult {
+    /// Retrieves the transaction hash.
+    pub fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+
+    /// Retrieves the transaction result.
+    pub fn result(&self) -> &TransactionResult {
+        &self.result
+    }
+
+    /// Retrieves the fee charged.
+    pub fn fee_charged(&self) -> Stroops {
+        Stroops::new(self.xdr_result.fee_charged)
+    }
+
+    /// Returns true if the transaction was included in the ledger.
+    ///
+    /// Transactions that are not applied were rejected during validation:
+    /// they didn't pay any fee nor consume their sequence number.
+    pub fn is_applied(&self) -> bool {
+        self.applied
+    }
+
+    /// Returns true if the transaction (or the inner transaction of a fee
+    /// bump transaction) succeeded.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.xdr_result.result,
+            xdr::TransactionResultResult::TxSuccess(_)
+                | xdr::TransactionResultResult::TxFeeBumpInnerSuccess(_)
+        )
+    }
+
+    /// Retrieves the changes made to charge the fee.
+    pub fn fee_changes(&self) -> &[xdr::LedgerEntryChange] {
+        &self.fee_changes
+    }
+
+    /// Retrieves the changes made before applying the operations.
+    pub fn changes_before(&self) -> &[xdr::LedgerEntryChange] {
+        &self.changes_before
+    }
+
+    /// Retrieves the changes made by each operation.
+    ///
+    /// Empty if the transaction failed.
+    pub fn operation_changes(&self) -> &[Vec<xdr::LedgerEntryChange>] {
+        &self.operation_changes
+    }
+
+    /// Returns the xdr transaction result.
+    pub fn to_xdr(&self) -> xdr::TransactionResult {
+        self.xdr_result.clone()
+    }
+
+    /// Returns the xdr transaction result meta, as it would appear in the
+    /// ledger close meta.
+    pub fn to_xdr_meta(&self) -> Result<xdr::TransactionResultMeta> {
+        let operations = self
+            .operation_changes
+            .iter()
+            .map(|changes| {
+                Ok(xdr::OperationMeta {
+                    changes: xdr::LedgerEntryChanges(changes.clone().try_into()?),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(xdr::TransactionResultMeta {
+            result: xdr::TransactionResultPair {
+                transaction_hash: xdr::Hash(self.hash.clone().try_into().unwrap_or([0; 32])),
+                result: self.xdr_result.clone(),
+            },
+            fee_processing: xdr::LedgerEntryChanges(self.fee_changes.clone().try_into()?),
+            tx_apply_processing: xdr::TransactionMeta::V2(xdr::TransactionMetaV2 {
+                tx_changes_before: xdr::LedgerEntryChanges(self.changes_before.clone().try_into()?),
+                operations: operations.try_into()?,
+                tx_changes_after: xdr::LedgerEntryChanges(Default::default()),
+            }),
+        })
+    }
+
+    fn new(
+        hash: Vec<u8>,
+        fee_charged: i64,
+        result: xdr::TransactionResultResult,
+        changes: Option<TransactionChanges>,
+    ) -> Result<TransactionApplyResult> {
+        let xdr_result = xdr::TransactionResult {
+            fee_charged,
+            result,
+            ext: xdr::TransactionResultExt::V0,
+        };
+        let result = TransactionResult::from_xdr(&xdr_result)?;
+        let applied = changes.is_some();
+        let changes = changes.unwrap_or_default();
+        Ok(TransactionApplyResult {
+            hash,
+            applied,
+            result,
+            xdr_result,
+            fee_changes: changes.fee_changes,
+            changes_before: changes.changes_before,
+            operation_changes: changes.operation_changes,
+        })
+    }
+}
+
+impl LedgerState {
+    /// Applies the transaction `envelope`.
+    ///
+    /// Returns an error only if the envelope can't be converted to xdr, all
+    /// transaction failures are reported in the result.
+    pub fn apply_transaction(
+        &mut self,
+        envelope: &TransactionEnvelope,
+    ) -> Result<TransactionApplyResult> {
+        let hash = envelope.hash(self.network())?;
+        match envelope.to_xdr()? {
+            xdr::TransactionEnvelope::TxV0(envelope) => {
+                let tx = xdr::Transaction {
+                    source_account: xdr::MuxedAccount::Ed25519(envelope.tx.source_account_ed25519),
+                    fee: envelope.tx.fee,
+                    seq_num: envelope.tx.seq_num,
+                    cond: match envelope.tx.time_bounds {
+                        None => xdr::Preconditions::None,
+                        Some(time_bounds) => xdr::Preconditions::Time(time_bounds),
+                    },
+                    memo: envelope.tx.memo,
+                    operations: envelope.tx.operations,
+                    ext: xdr::TransactionExt::V0,
+                };
+                self.apply_classic(hash, &tx, &envelope.signatures)
+            }
+            xdr::TransactionEnvelope::Tx(envelope) => {
+                self.apply_classic(hash, &envelope.tx, &envelope.signatures)
+            }
+            xdr::TransactionEnvelope::TxFeeBump(fee_bump) => {
+                let inner_hash = match envelope.as_fee_bump_transaction() {
+                    Some(fee_bump) => fee_bump.inner_transaction().hash(self.network())?,
+                    None => hash.clone(),
+                };
+                self.apply_fee_bump(hash, inner_hash, &fee_bump)
+            }
+        }
+    }
+
+    fn apply_classic(
+        &mut self,
+        hash: Vec<u8>,
+        tx: &xdr::Transaction,
+        signatures: &[xdr::DecoratedSignature],
+    ) -> Result<TransactionApplyResult> {
+        let source = tx.source_account.clone().account_id();
+        let fee = self.fee_to_charge(i64::from(tx.fee), tx.operations.len());
+        match self.validate(&hash, tx, signatures, Some(fee)) {
+            Err(result) => TransactionApplyResult::new(hash, 0, transaction_result(result), None),
+            Ok(authorized) => {
+                let (fee_charged, fee_changes) = self.charge_fee(&source, fee);
+                let (result, mut changes) = self.apply_operations(&hash, tx, &authorized)?;
+                changes.fee_changes = fee_changes;
+                TransactionApplyResult::new(
+                    hash,
+                    fee_charged,
+                    transaction_result(result),
+                    Some(changes),
+                )
+            }
+        }
+    }
+
+    fn apply_fee_bump(
+        &mut self,
+        hash: Vec<u8>,
+        inner_hash: Vec<u8>,
+        envelope: &xdr::FeeBumpTransactionEnvelope,
+    ) -> Result<TransactionApplyResult> {
+        let xdr::FeeBumpTransactionInnerTx::Tx(ref inner) = envelope.tx.inner_tx;
+        let fee_source = envelope.tx.fee_source.clone().account_id();
+        let fee = self.fee_to_charge(envelope.tx.fee, inner.tx.operations.len() + 1);
+
+        if let Err(result) = self.validate_fee_bump(&hash, envelope, fee) {
+            return TransactionApplyResult::new(hash, 0, result, None);
+        }
+        let inner_fee_charged =
+            self.fee_to_charge(i64::from(inner.tx.fee), inner.tx.operations.len());
+        let inner_result = |result: xdr::InnerTransactionResultResult| {
+            let is_success = matches!(result, xdr::InnerTransactionResultResult::TxSuccess(_));
+            let pair = xdr::InnerTransactionResultPair {
+                transaction_hash: xdr::Hash(inner_hash.clone().try_into().unwrap_or([0; 32])),
+                result: xdr::InnerTransactionResult {
+                    fee_charged: inner_fee_charged,
+                    result,
+                    ext: xdr::InnerTransactionResultExt::V0,
+                },
+            };
+            if is_success {
+                xdr::TransactionResultResult::TxFeeBumpInnerSuccess(pair)
+            } else {
+                xdr::TransactionResultResult::TxFeeBumpInnerFailed(pair)
+            }
+        };
+        match self.validate(&inner_hash, &inner.tx, &inner.signatures, None) {
+            Err(result) => TransactionApplyResult::new(hash, 0, inner_result(result), None),
+            Ok(authorized) => {
+                let (fee_charged, fee_changes) = self.charge_fee(&fee_source, fee);
+                let (result, mut changes) =
+                    self.apply_operations(&inner_hash, &inner.tx, &authorized)?;
+                changes.fee_changes = fee_changes;
+                TransactionApplyResult::new(hash, fee_charged, inner_result(result), Some(changes))
+            }
+        }
+    }
+
+    /// Returns the fee charged to a transaction with `operations` operations
+    /// that bids `fee`.
+    fn fee_to_charge(&self, fee: i64, operations: usize) -> i64 {
+        fee.min(self.min_fee(operations))
+    }
+
+    fn min_fee(&self, operations: usize) -> i64 {
+        i64::from(self.base_fee()).saturating_mul(operations as i64)
+    }
+
+    /// Validates `tx`, returning whether each operation is authorized.
+    ///
+    /// The fee is checked only if `fee` is some, the inner transaction of a
+    /// fee bump transaction doesn't pay its own fee.
+    fn validate(
+        &self,
+        hash: &[u8],
+        tx: &xdr::Transaction,
+        signatures: &[xdr::DecoratedSignature],
+        fee: Option<i64>,
+    ) -> std::result::Result<Vec<bool>, xdr::InnerTransactionResultResult> {
+        use xdr::InnerTransactionResultResult as Code;
+
+        if tx.operations.is_empty() {
+            return Err(Code::TxMissingOperation);
+        }
+
+        let (time_bounds, ledger_bounds, min_seq_num, extra_signers) = match tx.cond {
+            xdr::Preconditions::None => (None, None, None, &[][..]),
+            xdr::Preconditions::Time(ref time_bounds) => (Some(time_bounds), None, None, &[][..]),
+            xdr::Preconditions::V2(ref cond) => (
+                cond.time_bounds.as_ref(),
+                cond.ledger_bounds.as_ref(),
+                cond.min_seq_num.as_ref(),
+                cond.extra_signers.as_slice(),
+            ),
+        };
+        let (min_seq_age, min_seq_ledger_gap) = match tx.cond {
+            xdr::Preconditions::V2(ref cond) => (cond.min_seq_age.0, cond.min_seq_ledger_gap),
+            _ => (0, 0),
+        };
+        if let Some(time_bounds) = time_bounds {
+            if time_bounds.min_time.0 > 0 && self.close_time() < time_bounds.min_time.0 {
+                return Err(Code::TxTooEarly);
+            }
+            if time_bounds.max_time.0 > 0 && self.close_time() > time_bounds.max_time.0 {
+                return Err(Code::TxTooLate);
+            }
+        }
+        if let Some(ledger_bounds) = ledger_bounds {
+            if self.ledger_seq() < ledger_bounds.min_ledger {
+                return Err(Code::TxTooEarly);
+            }
+            if ledger_bounds.max_ledger > 0 && self.ledger_seq() >= ledger_bounds.max_ledger {
+                return Err(Code::TxTooLate);
+            }
+        }
+
+        if fee.is_some() && i64::from(tx.fee) < self.min_fee(tx.operations.len()) {
+            return Err(Code::TxInsufficientFee);
+        }
+
+        let source_id = tx.source_account.clone().account_id();
+        let Some(source) = self.load_account(&source_id) else {
+            return Err(Code::TxNoAccount);
+        };
+
+        let is_bad_seq = match min_seq_num {
+            None => source.seq_num.0.checked_add(1) != Some(tx.seq_num.0),
+            Some(min_seq_num) => {
+                source.seq_num.0 < min_seq_num.0 || source.seq_num.0 >= tx.seq_num.0
+            }
+        };
+        if is_bad_seq {
+            return Err(Code::TxBadSeq);
+        }
+
+        let (seq_ledger, seq_time) = account_seq_ledger_and_time(&source);
+        let close_time = self.close_time();
+        let ledger_seq = self.ledger_seq();
+        if min_seq_age > close_time
+            || close_time - min_seq_age < seq_time
+            || min_seq_ledger_gap > ledger_seq
+            || ledger_seq - min_seq_ledger_gap < seq_ledger
+        {
+            return Err(Code::TxBadMinSeqAgeOrGap);
+        }
+
+        let mut checker = SignatureChecker::new(hash, signatures);
+        if !checker.check_account(&source, ThresholdLevel::Low) {
+            return Err(Code::TxBadAuth);
+        }
+        for signer in extra_signers {
+            let signer = xdr::Signer {
+                key: signer.clone(),
+                weight: 1,
+            };
+            if !checker.check(&[signer], 1) {
+                return Err(Code::TxBadAuth);
+            }
+        }
+
+        if let Some(fee) = fee {
+            if available_balance(&source, self.base_reserve()) < fee {
+                return Err(Code::TxInsufficientBalance);
+            }
+        }
+
+        let authorized = tx
+            .operations
+            .iter()
+            .map(|op| {
+                let op_source = op
+                    .source_account
+                    .clone()
+                    .map(|account| account.account_id())
+                    .unwrap_or_else(|| source_id.clone());
+                match self.load_account(&op_source) {
+                    Some(account) => checker.check_account(&account, threshold_level(&op.body)),
+                    None => checker.check_master_key(&op_source),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if !checker.all_signatures_used() {
+            return Err(Code::TxBadAuthExtra);
+        }
+        Ok(authorized)
+    }
+
+    fn validate_fee_bump(
+        &self,
+        hash: &[u8],
+        envelope: &xdr::FeeBumpTransactionEnvelope,
+        fee: i64,
+    ) -> std::result::Result<(), xdr::TransactionResultResult> {
+        use xdr::TransactionResultResult as Code;
+
+        let xdr::FeeBumpTransactionInnerTx::Tx(ref inner) = envelope.tx.inner_tx;
+        if inner.tx.operations.is_empty() {
+            return Err(Code::TxMissingOperation);
+        }
+        if envelope.tx.fee < self.min_fee(inner.tx.operations.len() + 1) {
+            return Err(Code::TxInsufficientFee);
+        }
+        // The fee rate can't be lower than the inner transaction fee rate.
+        let inner_operations = inner.tx.operations.len() as i128;
+        if i128::from(envelope.tx.fee) * inner_operations
+            < i128::from(inner.tx.fee) * (inner_operations + 1)
+        {
+            return Err(Code::TxInsufficientFee);
+        }
+        let fee_source_id = envelope.tx.fee_source.clone().account_id();
+        let Some(fee_source) = self.load_account(&fee_source_id) else {
+            return Err(Code::TxNoAccount);
+        };
+        let mut checker = SignatureChecker::new(hash, &envelope.signatures);
+        if !checker.check_account(&fee_source, ThresholdLevel::Low) {
+            return Err(Code::TxBadAuth);
+        }
+        if !checker.all_signatures_used() {
+            return Err(Code::TxBadAuthExtra);
+        }
+        if available_balance(&fee_source, self.base_reserve()) < fee {
+            return Err(Code::TxInsufficientBalance);
+        }
+        Ok(())
+    }
+
+    /// Charges `fee` to `fee_source`, returning the fee charged and the
+    /// ledger changes.
+    fn charge_fee(
+        &mut self,
+        fee_source: &xdr::AccountId,
+        fee: i64,
+    ) -> (i64, Vec<xdr::LedgerEntryChange>) {
+        let mut ltx = LedgerTxn::new(self);
+        let Some(mut account) = ltx.load_account(fee_source) else {
+            return (0, Vec::new());
+        };
+        let charged = fee.min(account.balance);
+        account.balance -= charged;
+        ltx.update(xdr::LedgerEntryData::Account(account));
+        ltx.state_mut().add_fee(charged);
+        (charged, ltx.commit())
+    }
+
+    fn apply_operations(
+        &mut self,
+        hash: &[u8],
+        tx: &xdr::Transaction,
+        authorized: &[bool],
+    ) -> Result<(xdr::InnerTransactionResultResult, TransactionChanges)> {
+        let source = tx.source_account.clone().account_id();
+
+        let mut ltx = LedgerTxn::new(self);
+        if let Some(mut account) = ltx.load_account(&source) {
+            account.seq_num = tx.seq_num.clone();
+            let (ledger_seq, close_time) = (ltx.state().ledger_seq(), ltx.state().close_time());
+            update_account_seq_ledger_and_time(&mut account, ledger_seq, close_time);
+            ltx.update(xdr::LedgerEntryData::Account(account));
+        }
+        let mut sources = BTreeSet::new();
+        sources.insert(source.clone());
+        for op in tx.operations.iter() {
+            if let Some(ref op_source) = op.source_account {
+                sources.insert(op_source.clone().account_id());
+            }
+        }
+        for account_id in sources {
+            let index = ltx.load_account(&account_id).and_then(|account| {
+                account.signers.iter().position(|signer| {
+                    matches!(signer.key, xdr::SignerKey::PreAuthTx(ref key) if key.0 == hash)
+                })
+            });
+            if let Some(index) = index {
+                ltx.remove_signer(&account_id, index)?;
+            }
+        }
+        let changes_before = ltx.commit();
+
+        let snapshot = self.clone();
+        let mut sponsorships = BTreeMap::new();
+        let mut results = Vec::with_capacity(tx.operations.len());
+        let mut operation_changes = Vec::with_capacity(tx.operations.len());
+        let mut is_success = true;
+
+        for (index, op) in tx.operations.iter().enumerate() {
+            let op_source = op
+                .source_account
+                .clone()
+                .map(|account| account.account_id())
+                .unwrap_or_else(|| source.clone());
+            let mut ltx = LedgerTxn::new(self);
+            let result = if ltx.load_account(&op_source).is_none() {
+                Ok(xdr::OperationResult::OpNoAccount)
+            } else if !authorized[index] {
+                Ok(xdr::OperationResult::OpBadAuth)
+            } else {
+                let mut ctx = OperationContext {
+                    source: op_source,
+                    tx_source: &source,
+                    tx_seq: tx.seq_num.0,
+                    index: index as u32,
+                    sponsorships: &mut sponsorships,
+                };
+                apply_operation(&mut ltx, &mut ctx, &op.body)
+            };
+            match result {
+                Ok(result) if is_operation_success(&result) => {
+                    operation_changes.push(ltx.commit());
+                    results.push(result);
+                }
+                Ok(result) => {
+                    ltx.rollback();
+                    is_success = false;
+                    results.push(result);
+                }
+                Err(err) => {
+                    ltx.rollback();
+                    *self = snapshot;
+                    return Err(err);
+                }
+            }
+        }
+
+        let result = if !is_success {
+            xdr::InnerTransactionResultResult::TxFailed(results.try_into()?)
+        } else if !sponsorships.is_empty() {
+            xdr::InnerTransactionResultResult::TxBadSponsorship
+        } else {
+            xdr::InnerTransactionResultResult::TxSuccess(results.try_into()?)
+        };
+        if !is_success || !sponsorships.is_empty() {
+            *self = snapshot;
+            operation_changes.clear();
+        }
+
+        let changes = TransactionChanges {
+            fee_changes: Vec::new(),
+            changes_before,
+            operation_changes,
+        };
+        Ok((result, changes))
+    }
+}
+
+fn transaction_result(result: xdr::InnerTransactionResultResult) -> xdr::TransactionResultResult {
+    use xdr::InnerTransactionResultResult as Inner;
+    use xdr::TransactionResultResult as Outer;
+
+    match result {
+        Inner::TxSuccess(results) => Outer::TxSuccess(results),
+        Inner::TxFailed(results) => Outer::TxFailed(results),
+        Inner::TxTooEarly => Outer::TxTooEarly,
+        Inner::TxTooLate => Outer::TxTooLate,
+        Inner::TxMissingOperation => Outer::TxMissingOperation,
+        Inner::TxBadSeq => Outer::TxBadSeq,
+        Inner::TxBadAuth => Outer::TxBadAuth,
+        Inner::TxInsufficientBalance => Outer::TxInsufficientBalance,
+        Inner::TxNoAccount => Outer::TxNoAccount,
+        Inner::TxInsufficientFee => Outer::TxInsufficientFee,
+        Inner::TxBadAuthExtra => Outer::TxBadAuthExtra,
+        Inner::TxInternalError => Outer::TxInternalError,
+        Inner::TxNotSupported => Outer::TxNotSupported,
+        Inner::TxBadSponsorship => Outer::TxBadSponsorship,
+        Inner::TxBadMinSeqAgeOrGap => Outer::TxBadMinSeqAgeOrGap,
+        Inner::TxMalformed => Outer::TxMalformed,
+        Inner::TxSorobanInvalid => Outer::TxSorobanInvalid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{AccountFlags, DataValue, TrustLineFlags};
+    use crate::asset::Asset;
+    use crate::claim::{ClaimPredicate, ClaimableBalanceId, Claimant};
+    use crate::crypto::{DalekKeyPair, MuxedAccount, Signer, SignerKey};
+    use crate::network::Network;
+    use crate::operations::tests::*;
+    use crate::operations::Operation;
+    use crate::transaction::{FeeBumpTransaction, Transaction, MIN_BASE_FEE};
+
+    const XLM: i64 = 10_000_000;
+
+    fn new_state(accounts: &[&DalekKeyPair]) -> LedgerState {
+        let mut state = LedgerState::new(Network::new_test()).with_ledger_seq(10);
+        for account in accounts {
+            state
+                .create_account(&account.public_key(), Stroops::new(100 * XLM))
+                .unwrap();
+        }
+        state
+    }
+
+    fn balance(state: &LedgerState, account: &DalekKeyPair) -> i64 {
+        state
+            .account(&account.public_key())
+            .unwrap()
+            .unwrap()
+            .balance
+    }
+
+    fn transaction(
+        state: &LedgerState,
+        source: &DalekKeyPair,
+        operations: Vec<Operation>,
+    ) -> Transaction {
+        let sequence = state
+            .account(&source.public_key())
+            .unwrap()
+            .unwrap()
+            .seq_num
+            .0
+            + 1;
+        let mut builder = Transaction::builder(source.public_key(), sequence, MIN_BASE_FEE);
+        for operation in operations {
+            builder = builder.add_operation(operation);
+        }
+        builder.into_transaction().unwrap()
+    }
+
+    fn apply(
+        state: &mut LedgerState,
+        signers: &[&DalekKeyPair],
+        operations: Vec<Operation>,
+    ) -> TransactionApplyResult {
+        let mut tx = transaction(state, signers[0], operations);
+        for signer in signers {
+            tx.sign(signer.as_ref(), state.network()).unwrap();
+        }
+        state.apply_transaction(&tx.into_envelope()).unwrap()
+    }
+
+    fn operation_results(result: &TransactionApplyResult) -> Vec<xdr::OperationResult> {
+        match result.to_xdr().result {
+            xdr::TransactionResultResult::TxSuccess(results)
+            | xdr::TransactionResultResult::TxFailed(results) => results.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn payment(destination: &DalekKeyPair, asset: Asset, amount: i64) -> Operation {
+        Operation::new_payment()
+            .with_destination(destination.public_key())
+            .with_asset(asset)
+            .with_amount(Stroops::new(amount))
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_create_account_and_payment() {
+        let source = keypair0();
+        let destination = keypair1();
+        let mut state = new_state(&[&source]);
+
+        let create_account = Operation::new_create_account()
+            .with_destination(destination.public_key())
+            .with_starting_balance(Stroops::new(10 * XLM))
+            .unwrap()
+            .build()
+            .unwrap();
+        let result = apply(
+            &mut state,
+            &[&source],
+            vec![
+                create_account,
+                payment(&destination, Asset::new_native(), XLM),
+            ],
+        );
+
+        assert!(result.is_applied());
+        assert!(result.is_success());
+        assert_eq!(Stroops::new(200), result.fee_charged());
+        assert_eq!(Stroops::new(200), state.fee_pool());
+        assert_eq!(100 * XLM - 11 * XLM - 200, balance(&state, &source));
+        assert_eq!(11 * XLM, balance(&state, &destination));
+        assert_eq!(
+            (10i64 << 32) + 1,
+            state
+                .account(&source.public_key())
+                .unwrap()
+                .unwrap()
+                .seq_num
+                .0
+        );
+
+        // fee, sequence number bump, created account and updated source.
+        assert_eq!(2, result.fee_changes().len());
+        assert_eq!(2, result.changes_before().len());
+        assert_eq!(2, result.operation_changes().len());
+        assert!(matches!(
+            result.operation_changes()[0][..],
+            [
+                xdr::LedgerEntryChange::State(_),
+                xdr::LedgerEntryChange::Updated(_),
+                xdr::LedgerEntryChange::Created(_)
+            ] | [
+                xdr::LedgerEntryChange::Created(_),
+                xdr::LedgerEntryChange::State(_),
+                xdr::LedgerEntryChange::Updated(_)
+            ]
+        ));
+        let meta = result.to_xdr_meta().unwrap();
+        assert_eq!(result.hash(), meta.result.transaction_hash.0.as_slice());
+    }
+
+    #[test]
+    fn test_rejected_transactions() {
+        let source = keypair0();
+        let other = keypair1();
+        let mut state = new_state(&[&source, &other]);
+        let before = state.clone();
+
+        let mut tx = transaction(
+            &state,
+            &source,
+            vec![payment(&other, Asset::new_native(), XLM)],
+        );
+        tx.sign(other.as_ref(), state.network()).unwrap();
+        let result = state.apply_transaction(&tx.into_envelope()).unwrap();
+        assert!(!result.is_applied());
+        assert!(result.result().is_bad_auth());
+        assert_eq!(before, state);
+
+        let mut tx = transaction(
+            &state,
+            &source,
+            vec![payment(&other, Asset::new_native(), XLM)],
+        );
+        *tx.sequence_mut() += 1;
+        tx.sign(source.as_ref(), state.network()).unwrap();
+        let result = state.apply_transaction(&tx.into_envelope()).unwrap();
+        assert!(result.result().is_bad_sequence());
+        assert_eq!(Stroops::new(0), result.fee_charged());
+        assert_eq!(before, state);
+
+        let result = apply(
+            &mut state,
+            &[&source, &other],
+            vec![payment(&other, Asset::new_native(), XLM)],
+        );
+        assert!(result.result().is_bad_auth_extra());
+        assert_eq!(before, state);
+    }
+
+    #[test]
+    fn test_failed_operation_rolls_back_transaction() {
+        let source = keypair0();
+        let destination = keypair1();
+        let missing = keypair2();
+        let mut state = new_state(&[&source, &destination]);
+
+        let result = apply(
+            &mut state,
+            &[&source],
+            vec![
+                payment(&destination, Asset::new_native(), XLM),
+                payment(&missing, Asset::new_native(), XLM),
+            ],
+        );
+        assert!(result.is_applied());
+        assert!(!result.is_success());
+        assert!(result.operation_changes().is_empty());
+        assert_eq!(
+            vec![
+                xdr::OperationResult::OpInner(xdr::OperationResultTr::Payment(
+                    xdr::PaymentResult::Success
+                )),
+                xdr::OperationResult::OpInner(xdr::OperationResultTr::Payment(
+                    xdr::PaymentResult::NoDestination
+                )),
+            ],
+            operation_results(&result)
+        );
+        // The fee is charged and the sequence number consumed.
+        assert_eq!(100 * XLM - 200, balance(&state, &source));
+        assert_eq!(100 * XLM, balance(&state, &destination));
+        assert_eq!(
+            (10i64 << 32) + 1,
+            state
+                .account(&source.public_key())
+                .unwrap()
+                .unwrap()
+                .seq_num
+                .0
+        );
+    }
+
+    #[test]
+    fn test_trustlines_and_authorization() {
+        let issuer = keypair0();
+        let holder = keypair1();
+        let mut state = new_state(&[&issuer, &holder]);
+        let asset = Asset::new_credit("USD", issuer.public_key()).unwrap();
+
+        let set_flags = Operation::new_set_options()
+            .with_set_flags(Some(
+                AccountFlags::AUTH_REQUIRED | AccountFlags::AUTH_REVOCABLE,
+            ))
+            .build()
+            .unwrap();
+        assert!(apply(&mut state, &[&issuer], vec![set_flags]).is_success());
+
+        let change_trust = Operation::new_change_trust()
+            .with_asset(asset.clone().into())
+            .with_limit(Some(Stroops::max()))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(apply(&mut state, &[&holder], vec![change_trust]).is_success());
+        let trustline = state
+            .trustline(&holder.public_key(), &asset)
+            .unwrap()
+            .unwrap();
+        assert_eq!(0, trustline.flags);
+        assert_eq!(
+            1,
+            state
+                .account(&holder.public_key())
+                .unwrap()
+                .unwrap()
+                .num_sub_entries
+        );
+
+        let result = apply(
+            &mut state,
+            &[&issuer],
+            vec![payment(&holder, asset.clone(), XLM)],
+        );
+        assert_eq!(
+            vec![xdr::OperationResult::OpInner(
+                xdr::OperationResultTr::Payment(xdr::PaymentResult::NotAuthorized)
+            )],
+            operation_results(&result)
+        );
+
+        let authorize = Operation::new_set_trustline_flags()
+            .with_trustor(holder.public_key())
+            .with_asset(asset.clone())
+            .with_set_flags(TrustLineFlags::AUTHORIZED)
+            .build()
+            .unwrap();
+        let result = apply(
+            &mut state,
+            &[&issuer],
+            vec![authorize, payment(&holder, asset.clone(), XLM)],
+        );
+        assert!(result.is_success());
+        assert_eq!(
+            Some(Stroops::new(XLM)),
+            state.balance(&holder.public_key(), &asset).unwrap()
+        );
+
+        let remove_trust = Operation::new_change_trust()
+            .with_asset(asset.clone().into())
+            .with_limit(Some(Stroops::new(0)))
+            .unwrap()
+            .build()
+            .unwrap();
+        let result = apply(&mut state, &[&holder], vec![remove_trust]);
+        assert_eq!(
+            vec![xdr::OperationResult::OpInner(
+                xdr::OperationResultTr::ChangeTrust(xdr::ChangeTrustResult::InvalidLimit)
+            )],
+            operation_results(&result)
+        );
+    }
+
+    #[test]
+    fn test_manage_data_and_bump_sequence() {
+        let source = keypair0();
+        let mut state = new_state(&[&source]);
+
+        let manage_data = Operation::new_manage_data()
+            .with_data_name("name".to_string())
+            .with_data_value(Some(DataValue::from_slice(b"value").unwrap()))
+            .build()
+            .unwrap();
+        let bump_sequence = Operation::new_bump_sequence()
+            .with_bump_to(1 << 40)
+            .build()
+            .unwrap();
+        let result = apply(&mut state, &[&source], vec![manage_data, bump_sequence]);
+        assert!(result.is_success());
+        let data = state.data(&source.public_key(), "name").unwrap().unwrap();
+        assert_eq!(b"value", data.data_value.as_slice());
+        let account = state.account(&source.public_key()).unwrap().unwrap();
+        assert_eq!(1 << 40, account.seq_num.0);
+        assert_eq!(1, account.num_sub_entries);
+
+        let delete_data = Operation::new_manage_data()
+            .with_data_name("name".to_string())
+            .with_data_value(None)
+            .build()
+            .unwrap();
+        assert!(apply(&mut state, &[&source], vec![delete_data.clone()]).is_success());
+        assert!(state.data(&source.public_key(), "name").unwrap().is_none());
+
+        let result = apply(&mut state, &[&source], vec![delete_data]);
+        assert_eq!(
+            vec![xdr::OperationResult::OpInner(
+                xdr::OperationResultTr::ManageData(xdr::ManageDataResult::NameNotFound)
+            )],
+            operation_results(&result)
+        );
+    }
+
+    #[test]
+    fn test_signers_thresholds_and_merge() {
+        let source = keypair0();
+        let signer = keypair1();
+        let destination = keypair2();
+        let mut state = new_state(&[&source, &destination]);
+
+        let set_options = Operation::new_set_options()
+            .with_signer(Some(Signer::new(
+                SignerKey::new_from_public_key(signer.public_key()),
+                1,
+            )))
+            .with_high_threshold(Some(2))
+            .build()
+            .unwrap();
+        assert!(apply(&mut state, &[&source], vec![set_options]).is_success());
+        let account = state.account(&source.public_key()).unwrap().unwrap();
+        assert_eq!(1, account.signers.len());
+        assert_eq!(1, account.num_sub_entries);
+
+        let merge = Operation::new_account_merge()
+            .with_destination(MuxedAccount::from(destination.public_key()))
+            .build()
+            .unwrap();
+        let result = apply(&mut state, &[&source], vec![merge.clone()]);
+        assert_eq!(
+            vec![xdr::OperationResult::OpBadAuth],
+            operation_results(&result)
+        );
+
+        // Accounts can't be merged in the ledger they were created.
+        let result = apply(&mut state, &[&source, &signer], vec![merge.clone()]);
+        assert_eq!(
+            vec![xdr::OperationResult::OpInner(
+                xdr::OperationResultTr::AccountMerge(xdr::AccountMergeResult::SeqnumTooFar)
+            )],
+            operation_results(&result)
+        );
+
+        *state.ledger_seq_mut() += 1;
+        let result = apply(&mut state, &[&source, &signer], vec![merge]);
+        let remaining = 100 * XLM - 400;
+        assert_eq!(
+            vec![xdr::OperationResult::OpInner(
+                xdr::OperationResultTr::AccountMerge(xdr::AccountMergeResult::Success(remaining))
+            )],
+            operation_results(&result)
+        );
+        assert!(state.account(&source.public_key()).unwrap().is_none());
+        assert_eq!(100 * XLM + remaining, balance(&state, &destination));
+    }
+
+    #[test]
+    fn test_claimable_balances() {
+        let source = keypair0();
+        let claimant = keypair1();
+        let mut state = new_state(&[&source, &claimant]).with_close_time(1000);
+
+        let sequence = state
+            .account(&source.public_key())
+            .unwrap()
+            .unwrap()
+            .seq_num
+            .0
+            + 1;
+        let create = Operation::new_create_claimable_balance()
+            .with_asset(Asset::new_native())
+            .with_amount(Stroops::new(10 * XLM))
+            .unwrap()
+            .with_claimants(vec![Claimant::new(
+                claimant.public_key(),
+                ClaimPredicate::new_unconditional(),
+            )])
+            .build()
+            .unwrap();
+        let result = apply(&mut state, &[&source], vec![create]);
+        assert!(result.is_success());
+
+        let preimage = xdr::HashIdPreimage::OpId(xdr::HashIdPreimageOperationId {
+            source_account: source.public_key().to_xdr_account_id().unwrap(),
+            seq_num: xdr::SequenceNumber(sequence),
+            op_num: 0,
+        });
+        let id = ClaimableBalanceId::new(crate::crypto::hash(
+            &crate::xdr::XDRSerialize::xdr_bytes(&preimage).unwrap(),
+        ))
+        .unwrap();
+        let entry = state.claimable_balance(&id).unwrap();
+        assert_eq!(10 * XLM, entry.amount);
+        let account = state.account(&source.public_key()).unwrap().unwrap();
+        assert_eq!(1, state::num_sponsoring(account));
+
+        let claim = Operation::new_claim_claimable_balance()
+            .with_claimable_balance_id(id.clone())
+            .build()
+            .unwrap();
+        let result = apply(&mut state, &[&source], vec![claim.clone()]);
+        assert_eq!(
+            vec![xdr::OperationResult::OpInner(
+                xdr::OperationResultTr::ClaimClaimableBalance(
+                    xdr::ClaimClaimableBalanceResult::CannotClaim
+                )
+            )],
+            operation_results(&result)
+        );
+
+        let result = apply(&mut state, &[&claimant], vec![claim]);
+        assert!(result.is_success());
+        assert!(state.claimable_balance(&id).is_none());
+        assert_eq!(110 * XLM - 100, balance(&state, &claimant));
+        let account = state.account(&source.public_key()).unwrap().unwrap();
+        assert_eq!(0, state::num_sponsoring(account));
+    }
+
+    #[test]
+    fn test_sponsored_account_creation() {
+        let sponsor = keypair0();
+        let sponsored = keypair1();
+        let mut state = new_state(&[&sponsor]);
+
+        let begin = Operation::new_begin_sponsoring_future_reserves()
+            .with_sponsored_id(sponsored.public_key())
+            .build()
+            .unwrap();
+        let create_account = Operation::new_create_account()
+            .with_destination(sponsored.public_key())
+            .with_starting_balance(Stroops::new(0))
+            .unwrap()
+            .build()
+            .unwrap();
+        let end = Operation::new_end_sponsoring_future_reserves()
+            .with_source_account(sponsored.public_key())
+            .build();
+
+        let result = apply(
+            &mut state,
+            &[&sponsor],
+            vec![begin.clone(), create_account.clone()],
+        );
+        assert!(result.result().is_bad_sponsorship());
+        assert!(state.account(&sponsored.public_key()).unwrap().is_none());
+
+        let result = apply(
+            &mut state,
+            &[&sponsor, &sponsored],
+            vec![begin, create_account, end],
+        );
+        assert!(result.is_success());
+        let account = state.account(&sponsored.public_key()).unwrap().unwrap();
+        assert_eq!(2, state::num_sponsored(account));
+        let account = state.account(&sponsor.public_key()).unwrap().unwrap();
+        assert_eq!(2, state::num_sponsoring(account));
+    }
+
+    #[test]
+    fn test_fee_bump_transaction() {
+        let source = keypair0();
+        let fee_source = keypair1();
+        let destination = keypair2();
+        let mut state = new_state(&[&source, &fee_source, &destination]);
+
+        let mut tx = transaction(
+            &state,
+            &source,
+            vec![payment(&destination, Asset::new_native(), XLM)],
+        );
+        tx.sign(source.as_ref(), state.network()).unwrap();
+        let mut fee_bump =
+            FeeBumpTransaction::new(fee_source.public_key().into(), Stroops::new(1000), tx);
+        fee_bump.sign(fee_source.as_ref(), state.network()).unwrap();
+
+        let result = state.apply_transaction(&fee_bump.into_envelope()).unwrap();
+        assert!(result.is_success());
+        assert!(result.result().is_fee_bump_success());
+        assert_eq!(Stroops::new(200), result.fee_charged());
+        match result.to_xdr().result {
+            xdr::TransactionResultResult::TxFeeBumpInnerSuccess(pair) => {
+                assert_eq!(100, pair.result.fee_charged);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(100 * XLM - XLM, balance(&state, &source));
+        assert_eq!(100 * XLM - 200, balance(&state, &fee_source));
+    }
+
+    #[test]
+    fn test_fee_bump_fee_rate_below_inner_fee_rate() {
+        let source = keypair0();
+        let fee_source = keypair1();
+        let destination = keypair2();
+        let mut state = new_state(&[&source, &fee_source, &destination]);
+
+        let mut tx = transaction(
+            &state,
+            &source,
+            vec![payment(&destination, Asset::new_native(), XLM)],
+        );
+        *tx.fee_mut() = Stroops::new(500);
+        tx.sign(source.as_ref(), state.network()).unwrap();
+        let network = state.network().clone();
+        let fee_bump = |fee| {
+            let mut fee_bump = FeeBumpTransaction::new(
+                fee_source.public_key().into(),
+                Stroops::new(fee),
+                tx.clone(),
+            );
+            fee_bump.sign(fee_source.as_ref(), &network).unwrap();
+            fee_bump.into_envelope()
+        };
+
+        // 999 / 2 operations is below the inner 500 / 1 operation.
+        let low = fee_bump(999);
+        let result = state.apply_transaction(&low).unwrap();
+        assert!(matches!(
+            result.to_xdr().result,
+            xdr::TransactionResultResult::TxInsufficientFee
+        ));
+        assert_eq!(100 * XLM, balance(&state, &fee_source));
+
+        let result = state.apply_transaction(&fee_bump(1000)).unwrap();
+        assert!(result.is_success());
+    }
+
+    #[test]
+    fn test_min_seq_age_and_ledger_gap() {
+        let source = keypair0();
+        let mut state = new_state(&[&source]).with_close_time(1_000);
+        let bump_sequence = || {
+            Operation::new_bump_sequence()
+                .with_bump_to(0)
+                .build()
+                .unwrap()
+        };
+        assert!(apply(&mut state, &[&source], vec![bump_sequence()]).is_success());
+        let account = state.account(&source.public_key()).unwrap().unwrap();
+        assert_eq!((10, 1_000), state::account_seq_ledger_and_time(account));
+
+        let apply_with_cond = |state: &mut LedgerState, min_seq_age, min_seq_ledger_gap| {
+            let mut tx = transaction(state, &source, vec![bump_sequence()])
+                .to_xdr()
+                .unwrap();
+            tx.cond = xdr::Preconditions::V2(xdr::PreconditionsV2 {
+                time_bounds: None,
+                ledger_bounds: None,
+                min_seq_num: None,
+                min_seq_age: xdr::Duration(min_seq_age),
+                min_seq_ledger_gap,
+                extra_signers: Default::default(),
+            });
+            let payload = xdr::TransactionSignaturePayload {
+                network_id: xdr::Hash(state.network().network_id()),
+                tagged_transaction: xdr::TransactionSignaturePayloadTaggedTransaction::Tx(
+                    tx.clone(),
+                ),
+            };
+            let mut data = Vec::new();
+            xdr::XDRSerialize::write_xdr(&payload, &mut data).unwrap();
+            let hash = crate::crypto::hash(&data);
+            let signature = source.as_ref().sign_decorated(&hash).to_xdr().unwrap();
+            state.apply_classic(hash, &tx, &[signature]).unwrap()
+        };
+
+        for (min_seq_age, min_seq_ledger_gap) in [(100, 0), (0, 2), (2_000, 0), (0, 20)] {
+            let result = apply_with_cond(&mut state, min_seq_age, min_seq_ledger_gap);
+            assert!(!result.is_applied());
+            assert_eq!(
+                xdr::TransactionResultResult::TxBadMinSeqAgeOrGap,
+                result.to_xdr().result
+            );
+        }
+
+        *state.ledger_seq_mut() = 12;
+        *state.close_time_mut() = 1_100;
+        let result = apply_with_cond(&mut state, 100, 2);
+        assert!(result.is_success());
+        let account = state.account(&source.public_key()).unwrap().unwrap();
+        assert_eq!((12, 1_100), state::account_seq_ledger_and_time(account));
+    }
+}
@@ -0,0 +1,976 @@
+use std::collections::BTreeMap;
+
+use sha2::Digest;
+
+use super::state::{
+    account_ext_v2, account_ext_v2_mut, account_key, asset_issuer, available_balance, max_receive,
+    new_account_entry, num_sponsoring, trustline_asset, trustline_key, trustline_liabilities,
+    update_account_seq_ledger_and_time, LedgerTxn, ReserveError, ACCOUNT_FLAGS_MASK,
+    AUTHORIZED_FLAG, AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG, AUTH_CLAWBACK_ENABLED_FLAG,
+    AUTH_IMMUTABLE_FLAG, AUTH_REQUIRED_FLAG, AUTH_REVOCABLE_FLAG,
+    CLAIMABLE_BALANCE_CLAWBACK_ENABLED_FLAG, TRUSTLINE_CLAWBACK_ENABLED_FLAG,
+};
+use crate::error::Result;
+use crate::xdr::{self, XDRSerialize};
+
+/// Maximum depth of a claim predicate.
+const MAX_PREDICATE_DEPTH: u32 = 4;
+
+/// Context of the operation being applied.
+pub(super) struct OperationContext<'a> {
+    /// Source account of the operation.
+    pub source: xdr::AccountId,
+    /// Source account of the transaction.
+    pub tx_source: &'a xdr::AccountId,
+    /// Sequence number of the transaction.
+    pub tx_seq: i64,
+    /// Index of the operation in the transaction.
+    pub index: u32,
+    /// Active sponsorships, from sponsored account to sponsor.
+    pub sponsorships: &'a mut BTreeMap<xdr::AccountId, xdr::AccountId>,
+}
+
+/// Reasons an asset can't be moved in or out of an account.
+enum BalanceError {
+    NoTrust,
+    NotAuthorized,
+    /// Underfunded when sending, line full when receiving.
+    Exceeded,
+}
+
+impl OperationContext<'_> {
+    fn sponsor(&self) -> Option<xdr::AccountId> {
+        self.sponsorships.get(&self.source).cloned()
+    }
+}
+
+/// Applies the operation `body`, returning its result.
+///
+/// The changes are recorded in `ltx` even if the operation fails, it's up
+/// to the caller to roll them back.
+pub(super) fn apply_operation(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    body: &xdr::OperationBody,
+) -> Result<xdr::OperationResult> {
+    match body {
+        xdr::OperationBody::CreateAccount(op) => Ok(create_account(ltx, ctx, op)),
+        xdr::OperationBody::Payment(op) => Ok(payment(ltx, ctx, op)),
+        xdr::OperationBody::SetOptions(op) => set_options(ltx, ctx, op),
+        xdr::OperationBody::ChangeTrust(op) => Ok(change_trust(ltx, ctx, op)),
+        xdr::OperationBody::AllowTrust(op) => Ok(allow_trust(ltx, ctx, op)),
+        xdr::OperationBody::AccountMerge(destination) => Ok(account_merge(ltx, ctx, destination)),
+        xdr::OperationBody::ManageData(op) => Ok(manage_data(ltx, ctx, op)),
+        xdr::OperationBody::BumpSequence(op) => Ok(bump_sequence(ltx, ctx, op)),
+        xdr::OperationBody::CreateClaimableBalance(op) => create_claimable_balance(ltx, ctx, op),
+        xdr::OperationBody::ClaimClaimableBalance(op) => Ok(claim_claimable_balance(ltx, ctx, op)),
+        xdr::OperationBody::BeginSponsoringFutureReserves(op) => Ok(begin_sponsoring(ctx, op)),
+        xdr::OperationBody::EndSponsoringFutureReserves => Ok(end_sponsoring(ctx)),
+        xdr::OperationBody::SetTrustLineFlags(op) => Ok(set_trustline_flags(ltx, ctx, op)),
+        _ => Ok(xdr::OperationResult::OpNotSupported),
+    }
+}
+
+/// Returns true if `result` is the result of a successful operation.
+pub(super) fn is_operation_success(result: &xdr::OperationResult) -> bool {
+    let xdr::OperationResult::OpInner(result) = result else {
+        return false;
+    };
+    matches!(
+        result,
+        xdr::OperationResultTr::CreateAccount(xdr::CreateAccountResult::Success)
+            | xdr::OperationResultTr::Payment(xdr::PaymentResult::Success)
+            | xdr::OperationResultTr::SetOptions(xdr::SetOptionsResult::Success)
+            | xdr::OperationResultTr::ChangeTrust(xdr::ChangeTrustResult::Success)
+            | xdr::OperationResultTr::AllowTrust(xdr::AllowTrustResult::Success)
+            | xdr::OperationResultTr::AccountMerge(xdr::AccountMergeResult::Success(_))
+            | xdr::OperationResultTr::ManageData(xdr::ManageDataResult::Success)
+            | xdr::OperationResultTr::BumpSequence(xdr::BumpSequenceResult::Success)
+            | xdr::OperationResultTr::CreateClaimableBalance(
+                xdr::CreateClaimableBalanceResult::Success(_)
+            )
+            | xdr::OperationResultTr::ClaimClaimableBalance(
+                xdr::ClaimClaimableBalanceResult::Success
+            )
+            | xdr::OperationResultTr::BeginSponsoringFutureReserves(
+                xdr::BeginSponsoringFutureReservesResult::Success
+            )
+            | xdr::OperationResultTr::EndSponsoringFutureReserves(
+                xdr::EndSponsoringFutureReservesResult::Success
+            )
+            | xdr::OperationResultTr::SetTrustLineFlags(xdr::SetTrustLineFlagsResult::Success)
+    )
+}
+
+/// Returns the result of a failed reserve check, `low_reserve` is the
+/// operation specific low reserve result.
+fn reserve_error_result(
+    error: ReserveError,
+    low_reserve: xdr::OperationResult,
+) -> xdr::OperationResult {
+    match error {
+        ReserveError::LowReserve => low_reserve,
+        ReserveError::TooManySubEntries => xdr::OperationResult::OpTooManySubentries,
+        ReserveError::TooManySponsoring => xdr::OperationResult::OpTooManySponsoring,
+    }
+}
+
+fn create_account(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::CreateAccountOp,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::CreateAccount(code));
+
+    if op.starting_balance < 0 || op.destination == ctx.source {
+        return result(xdr::CreateAccountResult::Malformed);
+    }
+    if ltx.load_account(&op.destination).is_some() {
+        return result(xdr::CreateAccountResult::AlreadyExist);
+    }
+
+    let base_reserve = ltx.state().base_reserve();
+    let ledger_seq = ltx.state().ledger_seq();
+    let mut account = new_account_entry(op.destination.clone(), op.starting_balance, ledger_seq);
+    let sponsor = ctx.sponsorships.get(&op.destination).cloned();
+    match sponsor {
+        None => {
+            if available_balance(&account, base_reserve) < 0 {
+                return result(xdr::CreateAccountResult::LowReserve);
+            }
+        }
+        Some(ref sponsor) => {
+            if let Err(err) = ltx.add_sponsoring(sponsor, 2) {
+                return reserve_error_result(err, result(xdr::CreateAccountResult::LowReserve));
+            }
+            account_ext_v2_mut(&mut account).num_sponsored = 2;
+        }
+    }
+
+    let Some(mut source) = ltx.load_account(&ctx.source) else {
+        return result(xdr::CreateAccountResult::Underfunded);
+    };
+    if available_balance(&source, base_reserve) < op.starting_balance {
+        return result(xdr::CreateAccountResult::Underfunded);
+    }
+    source.balance -= op.starting_balance;
+    ltx.update(xdr::LedgerEntryData::Account(source));
+    ltx.create(xdr::LedgerEntryData::Account(account), sponsor);
+    result(xdr::CreateAccountResult::Success)
+}
+
+fn payment(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::PaymentOp,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::Payment(code));
+
+    if op.amount <= 0 {
+        return result(xdr::PaymentResult::Malformed);
+    }
+    let destination = op.destination.clone().account_id();
+    if ltx.load_account(&destination).is_none() {
+        return result(xdr::PaymentResult::NoDestination);
+    }
+    if destination == ctx.source && op.asset == xdr::Asset::Native {
+        return result(xdr::PaymentResult::Success);
+    }
+
+    match credit(ltx, &destination, &op.asset, op.amount) {
+        Ok(()) => {}
+        Err(BalanceError::NoTrust) => return result(xdr::PaymentResult::NoTrust),
+        Err(BalanceError::NotAuthorized) => return result(xdr::PaymentResult::NotAuthorized),
+        Err(BalanceError::Exceeded) => return result(xdr::PaymentResult::LineFull),
+    }
+    match debit(ltx, &ctx.source, &op.asset, op.amount) {
+        Ok(()) => {}
+        Err(BalanceError::NoTrust) => return result(xdr::PaymentResult::SrcNoTrust),
+        Err(BalanceError::NotAuthorized) => return result(xdr::PaymentResult::SrcNotAuthorized),
+        Err(BalanceError::Exceeded) => return result(xdr::PaymentResult::Underfunded),
+    }
+    result(xdr::PaymentResult::Success)
+}
+
+fn set_options(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::SetOptionsOp,
+) -> Result<xdr::OperationResult> {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::SetOptions(code));
+
+    let weights = [
+        op.master_weight,
+        op.low_threshold,
+        op.med_threshold,
+        op.high_threshold,
+    ];
+    if weights
+        .iter()
+        .flatten()
+        .any(|weight| *weight > u8::MAX as u32)
+    {
+        return Ok(result(xdr::SetOptionsResult::ThresholdOutOfRange));
+    }
+    let set_flags = op.set_flags.unwrap_or(0);
+    let clear_flags = op.clear_flags.unwrap_or(0);
+    if set_flags & clear_flags != 0 {
+        return Ok(result(xdr::SetOptionsResult::BadFlags));
+    }
+    if (set_flags | clear_flags) & !ACCOUNT_FLAGS_MASK != 0 {
+        return Ok(result(xdr::SetOptionsResult::UnknownFlag));
+    }
+    if let Some(ref signer) = op.signer {
+        let xdr::PublicKey::PublicKeyTypeEd25519(ref source_key) = ctx.source.0;
+        let is_bad_signer = match signer.key {
+            xdr::SignerKey::Ed25519(ref key) => key == source_key,
+            xdr::SignerKey::Ed25519SignedPayload(ref payload) => payload.payload.is_empty(),
+            _ => false,
+        };
+        if is_bad_signer || signer.weight > u8::MAX as u32 {
+            return Ok(result(xdr::SetOptionsResult::BadSigner));
+        }
+    }
+    if let Some(ref home_domain) = op.home_domain {
+        if !is_valid_string(home_domain.as_slice()) && !home_domain.is_empty() {
+            return Ok(result(xdr::SetOptionsResult::InvalidHomeDomain));
+        }
+    }
+
+    let Some(mut account) = ltx.load_account(&ctx.source) else {
+        return Ok(xdr::OperationResult::OpNoAccount);
+    };
+    if let Some(ref inflation_dest) = op.inflation_dest {
+        if *inflation_dest != ctx.source && ltx.load_account(inflation_dest).is_none() {
+            return Ok(result(xdr::SetOptionsResult::InvalidInflation));
+        }
+        account.inflation_dest = Some(inflation_dest.clone());
+    }
+    if op.set_flags.is_some() || op.clear_flags.is_some() {
+        if account.flags & AUTH_IMMUTABLE_FLAG != 0 {
+            return Ok(result(xdr::SetOptionsResult::CantChange));
+        }
+        account.flags = (account.flags & !clear_flags) | set_flags;
+        if account.flags & AUTH_CLAWBACK_ENABLED_FLAG != 0
+            && account.flags & AUTH_REVOCABLE_FLAG == 0
+        {
+            return Ok(result(xdr::SetOptionsResult::AuthRevocableRequired));
+        }
+    }
+    for (index, weight) in weights.iter().enumerate() {
+        if let Some(weight) = weight {
+            account.thresholds.0[index] = *weight as u8;
+        }
+    }
+    if let Some(ref home_domain) = op.home_domain {
+        account.home_domain = home_domain.clone();
+    }
+    ltx.update(xdr::LedgerEntryData::Account(account));
+
+    match op.signer {
+        None => Ok(result(xdr::SetOptionsResult::Success)),
+        Some(ref signer) => update_signer(ltx, ctx, signer),
+    }
+}
+
+fn update_signer(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    signer: &xdr::Signer,
+) -> Result<xdr::OperationResult> {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::SetOptions(code));
+
+    let Some(mut account) = ltx.load_account(&ctx.source) else {
+        return Ok(xdr::OperationResult::OpNoAccount);
+    };
+    let mut signers = account.signers.to_vec();
+    let position = signers.iter().position(|s| s.key == signer.key);
+
+    match position {
+        Some(index) if signer.weight == 0 => {
+            ltx.remove_signer(&ctx.source, index)?;
+        }
+        Some(index) => {
+            signers[index].weight = signer.weight;
+            account.signers = signers.try_into()?;
+            ltx.update(xdr::LedgerEntryData::Account(account));
+        }
+        None if signer.weight == 0 => {}
+        None => {
+            if signers.len() >= xdr::MAX_SIGNERS as usize {
+                return Ok(result(xdr::SetOptionsResult::TooManySigners));
+            }
+            let sponsor = ctx.sponsor();
+            if let Err(err) = ltx.add_sub_entries(&ctx.source, sponsor.as_ref(), 1) {
+                return Ok(reserve_error_result(
+                    err,
+                    result(xdr::SetOptionsResult::LowReserve),
+                ));
+            }
+            let Some(mut account) = ltx.load_account(&ctx.source) else {
+                return Ok(xdr::OperationResult::OpNoAccount);
+            };
+            let index = signers.partition_point(|s| s.key < signer.key);
+            if sponsor.is_some() || account_ext_v2(&account).is_some() {
+                let ext = account_ext_v2_mut(&mut account);
+                let mut ids = ext.signer_sponsoring_i_ds.to_vec();
+                ids.insert(index.min(ids.len()), xdr::SponsorshipDescriptor(sponsor));
+                ext.signer_sponsoring_i_ds = ids.try_into()?;
+            }
+            signers.insert(index, signer.clone());
+            account.signers = signers.try_into()?;
+            ltx.update(xdr::LedgerEntryData::Account(account));
+        }
+    }
+    Ok(result(xdr::SetOptionsResult::Success))
+}
+
+fn change_trust(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::ChangeTrustOp,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::ChangeTrust(code));
+
+    let (asset, issuer) = match op.line {
+        xdr::ChangeTrustAsset::Native => return result(xdr::ChangeTrustResult::Malformed),
+        xdr::ChangeTrustAsset::CreditAlphanum4(ref asset) => (
+            xdr::TrustLineAsset::CreditAlphanum4(asset.clone()),
+            asset.issuer.clone(),
+        ),
+        xdr::ChangeTrustAsset::CreditAlphanum12(ref asset) => (
+            xdr::TrustLineAsset::CreditAlphanum12(asset.clone()),
+            asset.issuer.clone(),
+        ),
+        xdr::ChangeTrustAsset::PoolShare(_) => return xdr::OperationResult::OpNotSupported,
+    };
+    if op.limit < 0 || issuer == ctx.source {
+        return result(xdr::ChangeTrustResult::Malformed);
+    }
+
+    match ltx.load_trustline(&ctx.source, &asset) {
+        Some(mut trustline) => {
+            let (buying, _) = trustline_liabilities(&trustline);
+            if op.limit == 0 {
+                if trustline.balance > 0 || buying > 0 {
+                    return result(xdr::ChangeTrustResult::InvalidLimit);
+                }
+                let key = trustline_key(&ctx.source, asset);
+                let sponsor = ltx.sponsor_of(&key);
+                ltx.erase(&key);
+                ltx.remove_sub_entries(&ctx.source, sponsor.as_ref(), 1);
+            } else {
+                if op.limit < trustline.balance.saturating_add(buying) {
+                    return result(xdr::ChangeTrustResult::InvalidLimit);
+                }
+                trustline.limit = op.limit;
+                ltx.update(xdr::LedgerEntryData::Trustline(trustline));
+            }
+        }
+        None => {
+            if op.limit == 0 {
+                return result(xdr::ChangeTrustResult::InvalidLimit);
+            }
+            let Some(issuer) = ltx.load_account(&issuer) else {
+                return result(xdr::ChangeTrustResult::NoIssuer);
+            };
+            let mut flags = 0;
+            if issuer.flags & AUTH_REQUIRED_FLAG == 0 {
+                flags |= AUTHORIZED_FLAG;
+            }
+            if issuer.flags & AUTH_CLAWBACK_ENABLED_FLAG != 0 {
+                flags |= TRUSTLINE_CLAWBACK_ENABLED_FLAG;
+            }
+            let sponsor = ctx.sponsor();
+            if let Err(err) = ltx.add_sub_entries(&ctx.source, sponsor.as_ref(), 1) {
+                return reserve_error_result(err, result(xdr::ChangeTrustResult::LowReserve));
+            }
+            let trustline = xdr::TrustLineEntry {
+                account_id: ctx.source.clone(),
+                asset,
+                balance: 0,
+                limit: op.limit,
+                flags,
+                ext: xdr::TrustLineEntryExt::V0,
+            };
+            ltx.create(xdr::LedgerEntryData::Trustline(trustline), sponsor);
+        }
+    }
+    result(xdr::ChangeTrustResult::Success)
+}
+
+fn allow_trust(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::AllowTrustOp,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::AllowTrust(code));
+
+    let auth_flags = AUTHORIZED_FLAG | AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG;
+    if op.authorize & !auth_flags != 0 || op.authorize == auth_flags || op.trustor == ctx.source {
+        return result(xdr::AllowTrustResult::Malformed);
+    }
+    let asset = match op.asset {
+        xdr::AssetCode::CreditAlphanum4(ref code) => {
+            xdr::TrustLineAsset::CreditAlphanum4(xdr::AlphaNum4 {
+                asset_code: code.clone(),
+                issuer: ctx.source.clone(),
+            })
+        }
+        xdr::AssetCode::CreditAlphanum12(ref code) => {
+            xdr::TrustLineAsset::CreditAlphanum12(xdr::AlphaNum12 {
+                asset_code: code.clone(),
+                issuer: ctx.source.clone(),
+            })
+        }
+    };
+    let Some(mut trustline) = ltx.load_trustline(&op.trustor, &asset) else {
+        return result(xdr::AllowTrustResult::NoTrustLine);
+    };
+    let Some(issuer) = ltx.load_account(&ctx.source) else {
+        return xdr::OperationResult::OpNoAccount;
+    };
+    let flags = (trustline.flags & !auth_flags) | op.authorize;
+    if issuer.flags & AUTH_REVOCABLE_FLAG == 0 && is_revoking(trustline.flags, flags) {
+        return result(xdr::AllowTrustResult::CantRevoke);
+    }
+    trustline.flags = flags;
+    ltx.update(xdr::LedgerEntryData::Trustline(trustline));
+    result(xdr::AllowTrustResult::Success)
+}
+
+fn set_trustline_flags(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::SetTrustLineFlagsOp,
+) -> xdr::OperationResult {
+    let result =
+        |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::SetTrustLineFlags(code));
+
+    let auth_flags = AUTHORIZED_FLAG | AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG;
+    let mask = auth_flags | TRUSTLINE_CLAWBACK_ENABLED_FLAG;
+    let is_malformed = asset_issuer(&op.asset) != Some(&ctx.source)
+        || op.trustor == ctx.source
+        || (op.set_flags | op.clear_flags) & !mask != 0
+        || op.set_flags & op.clear_flags != 0
+        || op.set_flags & TRUSTLINE_CLAWBACK_ENABLED_FLAG != 0
+        || op.set_flags & auth_flags == auth_flags;
+    if is_malformed {
+        return result(xdr::SetTrustLineFlagsResult::Malformed);
+    }
+    let Some(asset) = trustline_asset(&op.asset) else {
+        return result(xdr::SetTrustLineFlagsResult::Malformed);
+    };
+    let Some(mut trustline) = ltx.load_trustline(&op.trustor, &asset) else {
+        return result(xdr::SetTrustLineFlagsResult::NoTrustLine);
+    };
+    let Some(issuer) = ltx.load_account(&ctx.source) else {
+        return xdr::OperationResult::OpNoAccount;
+    };
+    let flags = (trustline.flags & !op.clear_flags) | op.set_flags;
+    if flags & auth_flags == auth_flags {
+        return result(xdr::SetTrustLineFlagsResult::InvalidState);
+    }
+    if issuer.flags & AUTH_REVOCABLE_FLAG == 0 && is_revoking(trustline.flags, flags) {
+        return result(xdr::SetTrustLineFlagsResult::CantRevoke);
+    }
+    trustline.flags = flags;
+    ltx.update(xdr::LedgerEntryData::Trustline(trustline));
+    result(xdr::SetTrustLineFlagsResult::Success)
+}
+
+fn account_merge(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    destination: &xdr::MuxedAccount,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::AccountMerge(code));
+
+    let destination = destination.clone().account_id();
+    if destination == ctx.source {
+        return result(xdr::AccountMergeResult::Malformed);
+    }
+    let Some(dest_account) = ltx.load_account(&destination) else {
+        return result(xdr::AccountMergeResult::NoAccount);
+    };
+    let Some(source) = ltx.load_account(&ctx.source) else {
+        return xdr::OperationResult::OpNoAccount;
+    };
+    if source.flags & AUTH_IMMUTABLE_FLAG != 0 {
+        return result(xdr::AccountMergeResult::ImmutableSet);
+    }
+    if source.num_sub_entries as usize != source.signers.len() {
+        return result(xdr::AccountMergeResult::HasSubEntries);
+    }
+    if source.seq_num.0 >= i64::from(ltx.state().ledger_seq()) << 32 {
+        return result(xdr::AccountMergeResult::SeqnumTooFar);
+    }
+    if num_sponsoring(&source) > 0 {
+        return result(xdr::AccountMergeResult::IsSponsor);
+    }
+    if max_receive(&dest_account) < source.balance {
+        return result(xdr::AccountMergeResult::DestFull);
+    }
+
+    if let Some(ext) = account_ext_v2(&source) {
+        for sponsor in ext.signer_sponsoring_i_ds.iter() {
+            if let Some(ref sponsor) = sponsor.0 {
+                ltx.remove_sponsoring(sponsor, 1);
+            }
+        }
+    }
+    let key = account_key(&ctx.source);
+    if let Some(sponsor) = ltx.sponsor_of(&key) {
+        ltx.remove_sponsoring(&sponsor, 2);
+    }
+    ltx.erase(&key);
+
+    let Some(mut dest_account) = ltx.load_account(&destination) else {
+        return result(xdr::AccountMergeResult::NoAccount);
+    };
+    dest_account.balance += source.balance;
+    ltx.update(xdr::LedgerEntryData::Account(dest_account));
+    result(xdr::AccountMergeResult::Success(source.balance))
+}
+
+fn manage_data(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::ManageDataOp,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::ManageData(code));
+
+    if op.data_name.is_empty() || !is_valid_string(op.data_name.as_slice()) {
+        return result(xdr::ManageDataResult::InvalidName);
+    }
+    let key = xdr::LedgerKey::Data(xdr::LedgerKeyData {
+        account_id: ctx.source.clone(),
+        data_name: op.data_name.clone(),
+    });
+    let existing = match ltx.get(&key).map(|entry| &entry.data) {
+        Some(xdr::LedgerEntryData::Data(entry)) => Some(entry.clone()),
+        _ => None,
+    };
+
+    match (existing, &op.data_value) {
+        (Some(mut entry), Some(value)) => {
+            entry.data_value = value.clone();
+            ltx.update(xdr::LedgerEntryData::Data(entry));
+        }
+        (None, Some(value)) => {
+            let sponsor = ctx.sponsor();
+            if let Err(err) = ltx.add_sub_entries(&ctx.source, sponsor.as_ref(), 1) {
+                return reserve_error_result(err, result(xdr::ManageDataResult::LowReserve));
+            }
+            let entry = xdr::DataEntry {
+                account_id: ctx.source.clone(),
+                data_name: op.data_name.clone(),
+                data_value: value.clone(),
+                ext: xdr::DataEntryExt::V0,
+            };
+            ltx.create(xdr::LedgerEntryData::Data(entry), sponsor);
+        }
+        (Some(_), None) => {
+            let sponsor = ltx.sponsor_of(&key);
+            ltx.erase(&key);
+            ltx.remove_sub_entries(&ctx.source, sponsor.as_ref(), 1);
+        }
+        (None, None) => return result(xdr::ManageDataResult::NameNotFound),
+    }
+    result(xdr::ManageDataResult::Success)
+}
+
+fn bump_sequence(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::BumpSequenceOp,
+) -> xdr::OperationResult {
+    let result = |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::BumpSequence(code));
+
+    if op.bump_to.0 < 0 {
+        return result(xdr::BumpSequenceResult::BadSeq);
+    }
+    let Some(mut account) = ltx.load_account(&ctx.source) else {
+        return xdr::OperationResult::OpNoAccount;
+    };
+    if op.bump_to.0 > account.seq_num.0 {
+        account.seq_num = op.bump_to.clone();
+        let (ledger_seq, close_time) = (ltx.state().ledger_seq(), ltx.state().close_time());
+        update_account_seq_ledger_and_time(&mut account, ledger_seq, close_time);
+        ltx.update(xdr::LedgerEntryData::Account(account));
+    }
+    result(xdr::BumpSequenceResult::Success)
+}
+
+fn create_claimable_balance(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::CreateClaimableBalanceOp,
+) -> Result<xdr::OperationResult> {
+    let result =
+        |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::CreateClaimableBalance(code));
+
+    let destinations = op
+        .claimants
+        .iter()
+        .map(|claimant| {
+            let xdr::Claimant::ClaimantTypeV0(ref claimant) = claimant;
+            &claimant.destination
+        })
+        .collect::<std::collections::BTreeSet<_>>();
+    let is_malformed = op.amount <= 0
+        || op.claimants.is_empty()
+        || destinations.len() != op.claimants.len()
+        || op.claimants.iter().any(|claimant| {
+            let xdr::Claimant::ClaimantTypeV0(ref claimant) = claimant;
+            !is_valid_predicate(&claimant.predicate, 1)
+        });
+    if is_malformed {
+        return Ok(result(xdr::CreateClaimableBalanceResult::Malformed));
+    }
+
+    let is_clawback_enabled = match asset_issuer(&op.asset) {
+        None => false,
+        Some(issuer) if *issuer == ctx.source => ltx
+            .load_account(issuer)
+            .is_some_and(|issuer| issuer.flags & AUTH_CLAWBACK_ENABLED_FLAG != 0),
+        Some(_) => trustline_asset(&op.asset)
+            .and_then(|asset| ltx.load_trustline(&ctx.source, &asset))
+            .is_some_and(|trustline| trustline.flags & TRUSTLINE_CLAWBACK_ENABLED_FLAG != 0),
+    };
+
+    match debit(ltx, &ctx.source, &op.asset, op.amount) {
+        Ok(()) => {}
+        Err(BalanceError::NoTrust) => {
+            return Ok(result(xdr::CreateClaimableBalanceResult::NoTrust))
+        }
+        Err(BalanceError::NotAuthorized) => {
+            return Ok(result(xdr::CreateClaimableBalanceResult::NotAuthorized))
+        }
+        Err(BalanceError::Exceeded) => {
+            return Ok(result(xdr::CreateClaimableBalanceResult::Underfunded))
+        }
+    }
+
+    let sponsor = ctx.sponsor().unwrap_or_else(|| ctx.source.clone());
+    if let Err(err) = ltx.add_sponsoring(&sponsor, op.claimants.len() as u32) {
+        return Ok(reserve_error_result(
+            err,
+            result(xdr::CreateClaimableBalanceResult::LowReserve),
+        ));
+    }
+
+    let preimage = xdr::HashIdPreimage::OpId(xdr::HashIdPreimageOperationId {
+        source_account: ctx.tx_source.clone(),
+        seq_num: xdr::SequenceNumber(ctx.tx_seq),
+        op_num: ctx.index,
+    });
+    let balance_id = xdr::ClaimableBalanceId::ClaimableBalanceIdTypeV0(xdr::Hash(
+        sha2::Sha256::digest(preimage.xdr_bytes()?).into(),
+    ));
+
+    let close_time = ltx.state().close_time();
+    let claimants = op
+        .claimants
+        .iter()
+        .map(|claimant| {
+            let xdr::Claimant::ClaimantTypeV0(ref claimant) = claimant;
+            Ok(xdr::Claimant::ClaimantTypeV0(xdr::ClaimantV0 {
+                destination: claimant.destination.clone(),
+                predicate: absolute_predicate(&claimant.predicate, close_time)?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let ext = if is_clawback_enabled {
+        xdr::ClaimableBalanceEntryExt::V1(xdr::ClaimableBalanceEntryExtensionV1 {
+            ext: xdr::ClaimableBalanceEntryExtensionV1Ext::V0,
+            flags: CLAIMABLE_BALANCE_CLAWBACK_ENABLED_FLAG,
+        })
+    } else {
+        xdr::ClaimableBalanceEntryExt::V0
+    };
+    let entry = xdr::ClaimableBalanceEntry {
+        balance_id: balance_id.clone(),
+        claimants: claimants.try_into()?,
+        asset: op.asset.clone(),
+        amount: op.amount,
+        ext,
+    };
+    ltx.create(xdr::LedgerEntryData::ClaimableBalance(entry), Some(sponsor));
+    Ok(result(xdr::CreateClaimableBalanceResult::Success(
+        balance_id,
+    )))
+}
+
+fn claim_claimable_balance(
+    ltx: &mut LedgerTxn,
+    ctx: &mut OperationContext,
+    op: &xdr::ClaimClaimableBalanceOp,
+) -> xdr::OperationResult {
+    let result =
+        |code| xdr::OperationResult::OpInner(xdr::OperationResultTr::ClaimClaimableBalance(code));
+
+    let key = xdr::LedgerKey::ClaimableBalance(xdr::LedgerKeyClaimableBalance {
+        balance_id: op.balance_id.clone(),
+    });
+    let entry = match ltx.get(&key).map(|entry| &entry.data) {
+        Some(xdr::LedgerEntryData::ClaimableBalance(entry)) => entry.clone(),
+        _ => return result(xdr::ClaimClaimableBalanceResult::DoesNotExist),
+    };
+    let close_time = ltx.state().close_time();
+    let can_claim = entry.claimants.iter().any(|claimant| {
+        let xdr::Claimant::ClaimantTypeV0(ref claimant) = claimant;
+        claimant.destination == ctx.source
+            && is_predicate_satisfied(&claimant.predicate, close_time)
+    });
+    if !can_claim {
+        return result(xdr::ClaimClaimableBalanceResult::CannotClaim);
+    }
+
+    match credit(ltx, &ctx.source, &entry.asset, entry.amount) {
+        Ok(()) => {}
+        Err(BalanceError::NoTrust) => return result(xdr::ClaimClaimableBalanceResult::NoTrust),
+        Err(BalanceError::NotAuthorized) => {
+            return result(xdr::ClaimClaimableBalanceResult::NotAuthorized)
+        }
+        Err(BalanceError::Exceeded) => return result(xdr::ClaimClaimableBalanceResult::LineFull),
+    }
+
+    let sponsor = ltx.sponsor_of(&key);
+    ltx.erase(&key);
+    if let Some(sponsor) = sponsor {
+        ltx.remove_sponsoring(&sponsor, entry.claimants.len() as u32);
+    }
+    result(xdr::ClaimClaimableBalanceResult::Success)
+}
+
+fn begin_sponsoring(
+    ctx: &mut OperationContext,
+    op: &xdr::BeginSponsoringFutureReservesOp,
+) -> xdr::OperationResult {
+    let result = |code| {
+        xdr::OperationResult::OpInner(xdr::OperationResultTr::BeginSponsoringFutureReserves(code))
+    };
+
+    if op.sponsored_id == ctx.source {
+        return result(xdr::BeginSponsoringFutureReservesResult::Malformed);
+    }
+    if ctx.sponsorships.contains_key(&op.sponsored_id) {
+        return result(xdr::BeginSponsoringFutureReservesResult::AlreadySponsored);
+    }
+    if ctx.sponsorships.contains_key(&ctx.source)
+        || ctx
+            .sponsorships
+            .values()
+            .any(|sponsor| *sponsor == op.sponsored_id)
+    {
+        return result(xdr::BeginSponsoringFutureReservesResult::Recursive);
+    }
+    ctx.sponsorships
+        .insert(op.sponsored_id.clone(), ctx.source.clone());
+    result(xdr::BeginSponsoringFutureReservesResult::Success)
+}
+
+fn end_sponsoring(ctx: &mut OperationContext) -> xdr::OperationResult {
+    let result = |code| {
+        xdr::OperationResult::OpInner(xdr::OperationResultTr::EndSponsoringFutureReserves(code))
+    };
+
+    if ctx.sponsorships.remove(&ctx.source).is_none() {
+        return result(xdr::EndSponsoringFutureReservesResult::NotSponsored);
+    }
+    result(xdr::EndSponsoringFutureReservesResult::Success)
+}
+
+/// Moves `amount` of `asset` out of `account`. Issuers can always send their
+/// own assets.
+fn debit(
+    ltx: &mut LedgerTxn,
+    account: &xdr::AccountId,
+    asset: &xdr::Asset,
+    amount: i64,
+) -> std::result::Result<(), BalanceError> {
+    match trustline_asset(asset) {
+        None => {
+            let base_reserve = ltx.state().base_reserve();
+            let mut entry = ltx.load_account(account).ok_or(BalanceError::NoTrust)?;
+            if available_balance(&entry, base_reserve) < amount {
+                return Err(BalanceError::Exceeded);
+            }
+            entry.balance -= amount;
+            ltx.update(xdr::LedgerEntryData::Account(entry));
+        }
+        Some(_) if asset_issuer(asset) == Some(account) => {}
+        Some(asset) => {
+            let mut trustline = ltx
+                .load_trustline(account, &asset)
+                .ok_or(BalanceError::NoTrust)?;
+            if trustline.flags & AUTHORIZED_FLAG == 0 {
+                return Err(BalanceError::NotAuthorized);
+            }
+            let (_, selling) = trustline_liabilities(&trustline);
+            if trustline.balance - selling < amount {
+                return Err(BalanceError::Exceeded);
+            }
+            trustline.balance -= amount;
+            ltx.update(xdr::LedgerEntryData::Trustline(trustline));
+        }
+    }
+    Ok(())
+}
+
+/// Moves `amount` of `asset` into `account`. Issuers can always receive
+/// their own assets.
+fn credit(
+    ltx: &mut LedgerTxn,
+    account: &xdr::AccountId,
+    asset: &xdr::Asset,
+    amount: i64,
+) -> std::result::Result<(), BalanceError> {
+    match trustline_asset(asset) {
+        None => {
+            let mut entry = ltx.load_account(account).ok_or(BalanceError::NoTrust)?;
+            if max_receive(&entry) < amount {
+                return Err(BalanceError::Exceeded);
+            }
+            entry.balance += amount;
+            ltx.update(xdr::LedgerEntryData::Account(entry));
+        }
+        Some(_) if asset_issuer(asset) == Some(account) => {}
+        Some(asset) => {
+            let mut trustline = ltx
+                .load_trustline(account, &asset)
+                .ok_or(BalanceError::NoTrust)?;
+            if trustline.flags & AUTHORIZED_FLAG == 0 {
+                return Err(BalanceError::NotAuthorized);
+            }
+            let (buying, _) = trustline_liabilities(&trustline);
+            if trustline.limit - trustline.balance - buying < amount {
+                return Err(BalanceError::Exceeded);
+            }
+            trustline.balance += amount;
+            ltx.update(xdr::LedgerEntryData::Trustline(trustline));
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if going from `old` to `new` trust line flags reduces the
+/// authorization of the trust line.
+fn is_revoking(old: u32, new: u32) -> bool {
+    let was_authorized = old & AUTHORIZED_FLAG != 0;
+    let was_maintain = old & AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG != 0;
+    let is_authorized = new & AUTHORIZED_FLAG != 0;
+    let is_maintain = new & AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG != 0;
+    (was_authorized && !is_authorized) || (was_maintain && !is_authorized && !is_maintain)
+}
+
+/// Returns true if `value` doesn't contain control characters.
+fn is_valid_string(value: &[u8]) -> bool {
+    value.iter().all(|c| (0x20..0x7f).contains(c))
+}
+
+fn is_valid_predicate(predicate: &xdr::ClaimPredicate, depth: u32) -> bool {
+    if depth > MAX_PREDICATE_DEPTH {
+        return false;
+    }
+    match predicate {
+        xdr::ClaimPredicate::Unconditional => true,
+        xdr::ClaimPredicate::And(predicates) | xdr::ClaimPredicate::Or(predicates) => {
+            predicates.len() == 2
+                && predicates
+                    .iter()
+                    .all(|predicate| is_valid_predicate(predicate, depth + 1))
+        }
+        xdr::ClaimPredicate::Not(Some(predicate)) => is_valid_predicate(predicate, depth + 1),
+        xdr::ClaimPredicate::Not(None) => false,
+        xdr::ClaimPredicate::BeforeAbsoluteTime(time)
+        | xdr::ClaimPredicate::BeforeRelativeTime(time) => *time >= 0,
+    }
+}
+
+/// Converts the relative times of `predicate` to absolute times.
+fn absolute_predicate(
+    predicate: &xdr::ClaimPredicate,
+    close_time: u64,
+) -> Result<xdr::ClaimPredicate> {
+    let convert = |predicates: &[xdr::ClaimPredicate]| {
+        predicates
+            .iter()
+            .map(|predicate| absolute_predicate(predicate, close_time))
+            .collect::<Result<Vec<_>>>()
+    };
+    Ok(match predicate {
+        xdr::ClaimPredicate::And(predicates) => {
+            xdr::ClaimPredicate::And(convert(predicates)?.try_into()?)
+        }
+        xdr::ClaimPredicate::Or(predicates) => {
+            xdr::ClaimPredicate::Or(convert(predicates)?.try_into()?)
+        }
+        xdr::ClaimPredicate::Not(Some(predicate)) => {
+            xdr::ClaimPredicate::Not(Some(Box::new(absolute_predicate(predicate, close_time)?)))
+        }
+        xdr::ClaimPredicate::BeforeRelativeTime(time) => xdr::ClaimPredicate::BeforeAbsoluteTime(
+            (close_time.min(i64::MAX as u64) as i64).saturating_add(*time),
+        ),
+        predicate => predicate.clone(),
+    })
+}
+
+fn is_predicate_satisfied(predicate: &xdr::ClaimPredicate, close_time: u64) -> bool {
+    match predicate {
+        xdr::ClaimPredicate::Unconditional => true,
+        xdr::ClaimPredicate::And(predicates) => predicates
+            .iter()
+            .all(|predicate| is_predicate_satisfied(predicate, close_time)),
+        xdr::ClaimPredicate::Or(predicates) => predicates
+            .iter()
+            .any(|predicate| is_predicate_satisfied(predicate, close_time)),
+        xdr::ClaimPredicate::Not(Some(predicate)) => !is_predicate_satisfied(predicate, close_time),
+        xdr::ClaimPredicate::Not(None) => false,
+        xdr::ClaimPredicate::BeforeAbsoluteTime(time) => (close_time as i128) < (*time as i128),
+        xdr::ClaimPredicate::BeforeRelativeTime(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_revoking() {
+        let maintain = AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG;
+        assert!(is_revoking(AUTHORIZED_FLAG, 0));
+        assert!(is_revoking(AUTHORIZED_FLAG, maintain));
+        assert!(is_revoking(maintain, 0));
+        assert!(!is_revoking(0, AUTHORIZED_FLAG));
+        assert!(!is_revoking(maintain, AUTHORIZED_FLAG));
+    }
+
+    #[test]
+    fn test_claim_predicates() {
+        let relative = xdr::ClaimPredicate::BeforeRelativeTime(100);
+        let predicate = xdr::ClaimPredicate::Not(Some(Box::new(relative)));
+        assert!(is_valid_predicate(&predicate, 1));
+
+        let absolute = absolute_predicate(&predicate, 1000).unwrap();
+        assert_eq!(
+            xdr::ClaimPredicate::Not(Some(Box::new(xdr::ClaimPredicate::BeforeAbsoluteTime(
+                1100
+            )))),
+            absolute
+        );
+        assert!(!is_predicate_satisfied(&absolute, 1000));
+        assert!(is_predicate_satisfied(&absolute, 1100));
+
+        let mut deep = xdr::ClaimPredicate::Unconditional;
+        for _ in 0..4 {
+            deep = xdr::ClaimPredicate::Not(Some(Box::new(deep)));
+        }
+        assert!(!is_valid_predicate(&deep, 1));
+        assert!(!is_valid_predicate(
+            &xdr::ClaimPredicate::BeforeAbsoluteTime(-1),
+            1
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::crypto::{hash, PublicKey, Signature};
+use crate::operations::{self, ThresholdLevel};
+use crate::xdr;
+
+/// Checks the signatures of a transaction against the signers of its
+/// source accounts, keeping track of the signatures used.
+pub(super) struct SignatureChecker<'a> {
+    contents_hash: &'a [u8],
+    signatures: &'a [xdr::DecoratedSignature],
+    used: Vec<bool>,
+}
+
+impl<'a> SignatureChecker<'a> {
+    pub(super) fn new(
+        contents_hash: &'a [u8],
+        signatures: &'a [xdr::DecoratedSignature],
+    ) -> SignatureChecker<'a> {
+        SignatureChecker {
+            contents_hash,
+            signatures,
+            used: vec![false; signatures.len()],
+        }
+    }
+
+    /// Returns true if the signatures of `account` reach the threshold `level`.
+    pub(super) fn check_account(
+        &mut self,
+        account: &xdr::AccountEntry,
+        level: ThresholdLevel,
+    ) -> bool {
+        let needed = needed_threshold(account, level);
+        let signers = account_signers(account);
+        self.check(&signers, needed)
+    }
+
+    /// Returns true if the master key of `account_id` signed the transaction.
+    ///
+    /// Used when the account doesn't exist yet.
+    pub(super) fn check_master_key(&mut self, account_id: &xdr::AccountId) -> bool {
+        let xdr::PublicKey::PublicKeyTypeEd25519(ref key) = account_id.0;
+        let signer = xdr::Signer {
+            key: xdr::SignerKey::Ed25519(key.clone()),
+            weight: 1,
+        };
+        self.check(&[signer], 0)
+    }
+
+    /// Returns true if the weight of the signers that signed the transaction
+    /// reaches `needed`.
+    ///
+    /// At least one signer needs to match, even if `needed` is zero.
+    pub(super) fn check(&mut self, signers: &[xdr::Signer], needed: u32) -> bool {
+        let contents_hash = self.contents_hash;
+        let mut total = 0u32;
+
+        for signer in signers {
+            if let xdr::SignerKey::PreAuthTx(ref key) = signer.key {
+                if key.0.as_slice() == contents_hash {
+                    total += signer.weight.min(u8::MAX as u32);
+                    if total >= needed {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        for signer in signers {
+            let matched = match signer.key {
+                xdr::SignerKey::PreAuthTx(_) => continue,
+                xdr::SignerKey::Ed25519(ref key) => self.find_signature(|sig| {
+                    sig.hint.0 == hint(&key.0) && verify(&key.0, contents_hash, sig)
+                }),
+                xdr::SignerKey::HashX(ref key) => self.find_signature(|sig| {
+                    sig.hint.0 == hint(&key.0) && hash(sig.signature.as_slice()) == key.0
+                }),
+                xdr::SignerKey::Ed25519SignedPayload(ref payload) => self.find_signature(|sig| {
+                    sig.hint.0 == signed_payload_hint(payload)
+                        && verify(&payload.ed25519.0, payload.payload.as_slice(), sig)
+                }),
+            };
+            if matched {
+                total += signer.weight.min(u8::MAX as u32);
+                if total >= needed {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Returns true if all signatures were used by some check.
+    pub(super) fn all_signatures_used(&self) -> bool {
+        self.used.iter().all(|used| *used)
+    }
+
+    fn find_signature<F>(&mut self, predicate: F) -> bool
+    where
+        F: Fn(&xdr::DecoratedSignature) -> bool,
+    {
+        for (index, signature) in self.signatures.iter().enumerate() {
+            if predicate(signature) {
+                self.used[index] = true;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Returns the threshold level required by the operation `body`.
+pub(super) fn threshold_level(body: &xdr::OperationBody) -> ThresholdLevel {
+    let changes_signers = match body {
+        xdr::OperationBody::SetOptions(op) => {
+            op.master_weight.is_some()
+                || op.low_threshold.is_some()
+                || op.med_threshold.is_some()
+                || op.high_threshold.is_some()
+                || op.signer.is_some()
+        }
+        _ => false,
+    };
+    operations::threshold_level(body.discriminant(), changes_signers)
+}
+
+/// Returns the signers of `account`, including its master key.
+pub(super) fn account_signers(account: &xdr::AccountEntry) -> Vec<xdr::Signer> {
+    let mut signers = Vec::with_capacity(account.signers.len() + 1);
+    let master_weight = account.thresholds.0[0];
+    if master_weight > 0 {
+        let xdr::PublicKey::PublicKeyTypeEd25519(ref key) = account.account_id.0;
+        signers.push(xdr::Signer {
+            key: xdr::SignerKey::Ed25519(key.clone()),
+            weight: u32::from(master_weight),
+        });
+    }
+    signers.extend(account.signers.iter().cloned());
+    signers
+}
+
+fn needed_threshold(account: &xdr::AccountEntry, level: ThresholdLevel) -> u32 {
+    let thresholds = account.thresholds.0;
+    let threshold = match level {
+        ThresholdLevel::Low => thresholds[1],
+        ThresholdLevel::Medium => thresholds[2],
+        ThresholdLevel::High => thresholds[3],
+    };
+    u32::from(threshold)
+}
+
+fn hint(key: &[u8; 32]) -> [u8; 4] {
+    let mut hint = [0; 4];
+    hint.copy_from_slice(&key[28..]);
+    hint
+}
+
+fn signed_payload_hint(payload: &xdr::SignerKeyEd25519SignedPayload) -> [u8; 4] {
+    let mut payload_hint = [0; 4];
+    let data = payload.payload.as_slice();
+    if data.len() >= 4 {
+        payload_hint.copy_from_slice(&data[data.len() - 4..]);
+    } else {
+        payload_hint[..data.len()].copy_from_slice(data);
+    }
+    let key_hint = hint(&payload.ed25519.0);
+    [
+        key_hint[0] ^ payload_hint[0],
+        key_hint[1] ^ payload_hint[1],
+        key_hint[2] ^ payload_hint[2],
+        key_hint[3] ^ payload_hint[3],
+    ]
+}
+
+fn verify(key: &[u8; 32], message: &[u8], signature: &xdr::DecoratedSignature) -> bool {
+    let Ok(signature) = Signature::from_slice(signature.signature.as_slice()) else {
+        return false;
+    };
+    PublicKey(*key).verify(message, &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::DalekKeyPair;
+
+    fn decorated(keypair: &DalekKeyPair, message: &[u8]) -> xdr::DecoratedSignature {
+        keypair.as_ref().sign_decorated(message).to_xdr().unwrap()
+    }
+
+    fn account(keypair: &DalekKeyPair) -> xdr::AccountEntry {
+        super::super::state::new_account_entry(
+            keypair.public_key().to_xdr_account_id().unwrap(),
+            0,
+            1,
+        )
+    }
+
+    #[test]
+    fn test_master_key_signature() {
+        let keypair = DalekKeyPair::random().unwrap();
+        let message = hash(b"tx");
+        let signatures = vec![decorated(&keypair, &message)];
+        let mut checker = SignatureChecker::new(&message, &signatures);
+        assert!(checker.check_account(&account(&keypair), ThresholdLevel::Low));
+        assert!(checker.all_signatures_used());
+
+        let other = DalekKeyPair::random().unwrap();
+        let mut checker = SignatureChecker::new(&message, &signatures);
+        assert!(!checker.check_account(&account(&other), ThresholdLevel::Low));
+        assert!(!checker.all_signatures_used());
+    }
+
+    #[test]
+    fn test_signer_weights_and_thresholds() {
+        let keypair = DalekKeyPair::random().unwrap();
+        let signer = DalekKeyPair::random().unwrap();
+        let mut entry = account(&keypair);
+        entry.thresholds = xdr::Thresholds([1, 1, 2, 3]);
+        entry.signers = vec![xdr::Signer {
+            key: xdr::SignerKey::Ed25519(signer.public_key().to_xdr_uint256().unwrap()),
+            weight: 1,
+        }]
+        .try_into()
+        .unwrap();
+
+        let message = hash(b"tx");
+        let signatures = vec![decorated(&keypair, &message), decorated(&signer, &message)];
+        let mut checker = SignatureChecker::new(&message, &signatures);
+        assert!(checker.check_account(&entry, ThresholdLevel::Medium));
+        assert!(!checker.check_account(&entry, ThresholdLevel::High));
+    }
+
+    #[test]
+    fn test_hashx_and_pre_auth_signers() {
+        let preimage = b"preimage";
+        let key: [u8; 32] = hash(preimage).try_into().unwrap();
+        let message = hash(b"tx");
+        let signatures = vec![xdr::DecoratedSignature {
+            hint: xdr::SignatureHint(hint(&key)),
+            signature: preimage.to_vec().try_into().unwrap(),
+        }];
+        let hashx = xdr::Signer {
+            key: xdr::SignerKey::HashX(xdr::Uint256(key)),
+            weight: 1,
+        };
+        let mut checker = SignatureChecker::new(&message, &signatures);
+        assert!(checker.check(&[hashx], 1));
+        assert!(checker.all_signatures_used());
+
+        let pre_auth = xdr::Signer {
+            key: xdr::SignerKey::PreAuthTx(xdr::Uint256(message.clone().try_into().unwrap())),
+            weight: 1,
+        };
+        let mut checker = SignatureChecker::new(&message, &[]);
+        assert!(checker.check(&[pre_auth], 1));
+    }
+
+    #[test]
+    fn test_threshold_level() {
+        let bump = xdr::OperationBody::BumpSequence(xdr::BumpSequenceOp {
+            bump_to: xdr::SequenceNumber(1),
+        });
+        assert_eq!(ThresholdLevel::Low, threshold_level(&bump));
+        let set_options = xdr::OperationBody::SetOptions(xdr::SetOptionsOp {
+            inflation_dest: None,
+            clear_flags: None,
+            set_flags: None,
+            master_weight: None,
+            low_threshold: None,
+            med_threshold: None,
+            high_threshold: None,
+            home_domain: None,
+            signer: None,
+        });
+        assert_eq!(ThresholdLevel::Medium, threshold_level(&set_options));
+        let merge =
+            xdr::OperationBody::AccountMerge(xdr::MuxedAccount::Ed25519(xdr::Uint256([0; 32])));
+        assert_eq!(ThresholdLevel::High, threshold_level(&merge));
+    }
+}
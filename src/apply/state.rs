@@ -0,0 +1,798 @@
+use std::collections::BTreeMap;
+
+use crate::amount::Stroops;
+use crate::asset::Asset;
+use crate::claim::ClaimableBalanceId;
+use crate::crypto::PublicKey;
+use crate::error::Result;
+use crate::network::Network;
+use crate::xdr;
+
+/// Default base fee, in stroops.
+pub const DEFAULT_BASE_FEE: u32 = 100;
+
+/// Default base reserve, in stroops.
+pub const DEFAULT_BASE_RESERVE: u32 = 5_000_000;
+
+/// Maximum number of sub entries of an account.
+pub(super) const MAX_SUB_ENTRIES: u32 = 1000;
+
+pub(super) const AUTH_REQUIRED_FLAG: u32 = xdr::AccountFlags::RequiredFlag as u32;
+pub(super) const AUTH_REVOCABLE_FLAG: u32 = xdr::AccountFlags::RevocableFlag as u32;
+pub(super) const AUTH_IMMUTABLE_FLAG: u32 = xdr::AccountFlags::ImmutableFlag as u32;
+pub(super) const AUTH_CLAWBACK_ENABLED_FLAG: u32 = xdr::AccountFlags::ClawbackEnabledFlag as u32;
+pub(super) const ACCOUNT_FLAGS_MASK: u32 = xdr::MASK_ACCOUNT_FLAGS_V17 as u32;
+
+pub(super) const AUTHORIZED_FLAG: u32 = xdr::TrustLineFlags::AuthorizedFlag as u32;
+pub(super) const AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG: u32 =
+    xdr::TrustLineFlags::AuthorizedToMaintainLiabilitiesFlag as u32;
+pub(super) const TRUSTLINE_CLAWBACK_ENABLED_FLAG: u32 =
+    xdr::TrustLineFlags::TrustlineClawbackEnabledFlag as u32;
+
+pub(super) const CLAIMABLE_BALANCE_CLAWBACK_ENABLED_FLAG: u32 =
+    xdr::ClaimableBalanceFlags::ClaimableBalanceClawbackEnabledFlag as u32;
+
+/// In memory ledger state.
+///
+/// The state holds the ledger entries and the ledger header values needed to
+/// apply transactions, such as the ledger sequence and the base reserve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerState {
+    network: Network,
+    ledger_seq: u32,
+    close_time: u64,
+    base_fee: u32,
+    base_reserve: u32,
+    fee_pool: i64,
+    entries: BTreeMap<xdr::LedgerKey, xdr::LedgerEntry>,
+}
+
+/// Reasons a sub entry can't be created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ReserveError {
+    LowReserve,
+    TooManySubEntries,
+    TooManySponsoring,
+}
+
+/// Tracks the changes made to a `LedgerState`, so that they can be
+/// reported as ledger entry changes or rolled back.
+pub(super) struct LedgerTxn<'a> {
+    state: &'a mut LedgerState,
+    original: BTreeMap<xdr::LedgerKey, Option<xdr::LedgerEntry>>,
+}
+
+impl LedgerState {
+    /// Creates a new, empty, `LedgerState` for `network`.
+    pub fn new(network: Network) -> LedgerState {
+        LedgerState {
+            network,
+            ledger_seq: 2,
+            close_time: 0,
+            base_fee: DEFAULT_BASE_FEE,
+            base_reserve: DEFAULT_BASE_RESERVE,
+            fee_pool: 0,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the ledger sequence.
+    pub fn with_ledger_seq(mut self, ledger_seq: u32) -> LedgerState {
+        self.ledger_seq = ledger_seq;
+        self
+    }
+
+    /// Sets the ledger close time, in seconds since the unix epoch.
+    pub fn with_close_time(mut self, close_time: u64) -> LedgerState {
+        self.close_time = close_time;
+        self
+    }
+
+    /// Sets the base fee, in stroops.
+    pub fn with_base_fee(mut self, base_fee: u32) -> LedgerState {
+        self.base_fee = base_fee;
+        self
+    }
+
+    /// Sets the base reserve, in stroops.
+    pub fn with_base_reserve(mut self, base_reserve: u32) -> LedgerState {
+        self.base_reserve = base_reserve;
+        self
+    }
+
+    /// Retrieves the network.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Retrieves the ledger sequence.
+    pub fn ledger_seq(&self) -> u32 {
+        self.ledger_seq
+    }
+
+    /// Retrieves a mutable reference to the ledger sequence.
+    pub fn ledger_seq_mut(&mut self) -> &mut u32 {
+        &mut self.ledger_seq
+    }
+
+    /// Retrieves the ledger close time.
+    pub fn close_time(&self) -> u64 {
+        self.close_time
+    }
+
+    /// Retrieves a mutable reference to the ledger close time.
+    pub fn close_time_mut(&mut self) -> &mut u64 {
+        &mut self.close_time
+    }
+
+    /// Retrieves the base fee.
+    pub fn base_fee(&self) -> u32 {
+        self.base_fee
+    }
+
+    /// Retrieves the base reserve.
+    pub fn base_reserve(&self) -> u32 {
+        self.base_reserve
+    }
+
+    /// Retrieves the fees collected so far.
+    pub fn fee_pool(&self) -> Stroops {
+        Stroops::new(self.fee_pool)
+    }
+
+    /// Retrieves the entry with `key`.
+    pub fn get(&self, key: &xdr::LedgerKey) -> Option<&xdr::LedgerEntry> {
+        self.entries.get(key)
+    }
+
+    /// Inserts `entry`, returning the entry it replaced.
+    pub fn insert(&mut self, entry: xdr::LedgerEntry) -> Option<xdr::LedgerEntry> {
+        self.entries.insert(ledger_entry_key(&entry), entry)
+    }
+
+    /// Removes the entry with `key`, returning it.
+    pub fn remove(&mut self, key: &xdr::LedgerKey) -> Option<xdr::LedgerEntry> {
+        self.entries.remove(key)
+    }
+
+    /// Returns an iterator over the ledger entries, ordered by key.
+    pub fn entries(&self) -> impl Iterator<Item = &xdr::LedgerEntry> {
+        self.entries.values()
+    }
+
+    /// Creates an account with `balance`, as if created in the current ledger.
+    pub fn create_account(&mut self, account: &PublicKey, balance: Stroops) -> Result<()> {
+        let entry = new_account_entry(
+            account.to_xdr_account_id()?,
+            balance.to_i64(),
+            self.ledger_seq,
+        );
+        self.insert(xdr::LedgerEntry {
+            last_modified_ledger_seq: self.ledger_seq,
+            data: xdr::LedgerEntryData::Account(entry),
+            ext: xdr::LedgerEntryExt::V0,
+        });
+        Ok(())
+    }
+
+    /// Retrieves the account entry of `account`.
+    pub fn account(&self, account: &PublicKey) -> Result<Option<&xdr::AccountEntry>> {
+        let key = account_key(&account.to_xdr_account_id()?);
+        match self.get(&key).map(|entry| &entry.data) {
+            Some(xdr::LedgerEntryData::Account(entry)) => Ok(Some(entry)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Retrieves the trust line of `account` for `asset`.
+    pub fn trustline(
+        &self,
+        account: &PublicKey,
+        asset: &Asset,
+    ) -> Result<Option<&xdr::TrustLineEntry>> {
+        let Some(asset) = trustline_asset(&asset.to_xdr()?) else {
+            return Ok(None);
+        };
+        let key = trustline_key(&account.to_xdr_account_id()?, asset);
+        match self.get(&key).map(|entry| &entry.data) {
+            Some(xdr::LedgerEntryData::Trustline(entry)) => Ok(Some(entry)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Retrieves the balance of `account` in `asset`.
+    ///
+    /// Returns `None` if the account or the trust line don't exist.
+    pub fn balance(&self, account: &PublicKey, asset: &Asset) -> Result<Option<Stroops>> {
+        if asset.is_native() {
+            return Ok(self
+                .account(account)?
+                .map(|entry| Stroops::new(entry.balance)));
+        }
+        Ok(self
+            .trustline(account, asset)?
+            .map(|entry| Stroops::new(entry.balance)))
+    }
+
+    /// Retrieves the data entry of `account` named `name`.
+    pub fn data(&self, account: &PublicKey, name: &str) -> Result<Option<&xdr::DataEntry>> {
+        let key = xdr::LedgerKey::Data(xdr::LedgerKeyData {
+            account_id: account.to_xdr_account_id()?,
+            data_name: name.as_bytes().to_vec().try_into()?,
+        });
+        match self.get(&key).map(|entry| &entry.data) {
+            Some(xdr::LedgerEntryData::Data(entry)) => Ok(Some(entry)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Retrieves the claimable balance with `id`.
+    pub fn claimable_balance(
+        &self,
+        id: &ClaimableBalanceId,
+    ) -> Option<&xdr::ClaimableBalanceEntry> {
+        let key = xdr::LedgerKey::ClaimableBalance(xdr::LedgerKeyClaimableBalance {
+            balance_id: id.to_xdr(),
+        });
+        match self.get(&key).map(|entry| &entry.data) {
+            Some(xdr::LedgerEntryData::ClaimableBalance(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub(super) fn load_account(&self, id: &xdr::AccountId) -> Option<xdr::AccountEntry> {
+        match self.get(&account_key(id)).map(|entry| &entry.data) {
+            Some(xdr::LedgerEntryData::Account(entry)) => Some(entry.clone()),
+            _ => None,
+        }
+    }
+
+    pub(super) fn add_fee(&mut self, fee: i64) {
+        self.fee_pool = self.fee_pool.saturating_add(fee);
+    }
+}
+
+impl<'a> LedgerTxn<'a> {
+    pub(super) fn new(state: &'a mut LedgerState) -> LedgerTxn<'a> {
+        LedgerTxn {
+            state,
+            original: BTreeMap::new(),
+        }
+    }
+
+    pub(super) fn state(&self) -> &LedgerState {
+        self.state
+    }
+
+    pub(super) fn state_mut(&mut self) -> &mut LedgerState {
+        self.state
+    }
+
+    pub(super) fn get(&self, key: &xdr::LedgerKey) -> Option<&xdr::LedgerEntry> {
+        self.state.entries.get(key)
+    }
+
+    pub(super) fn load_account(&self, id: &xdr::AccountId) -> Option<xdr::AccountEntry> {
+        self.state.load_account(id)
+    }
+
+    pub(super) fn load_trustline(
+        &self,
+        id: &xdr::AccountId,
+        asset: &xdr::TrustLineAsset,
+    ) -> Option<xdr::TrustLineEntry> {
+        match self
+            .get(&trustline_key(id, asset.clone()))
+            .map(|entry| &entry.data)
+        {
+            Some(xdr::LedgerEntryData::Trustline(entry)) => Some(entry.clone()),
+            _ => None,
+        }
+    }
+
+    /// Stores `data`, keeping the extension of the existing entry, if any.
+    pub(super) fn update(&mut self, data: xdr::LedgerEntryData) {
+        let key = ledger_key(&data);
+        let ext = self
+            .get(&key)
+            .map(|entry| entry.ext.clone())
+            .unwrap_or(xdr::LedgerEntryExt::V0);
+        self.put(xdr::LedgerEntry {
+            last_modified_ledger_seq: self.state.ledger_seq,
+            data,
+            ext,
+        });
+    }
+
+    /// Creates a new entry with `data`, sponsored by `sponsor`.
+    pub(super) fn create(&mut self, data: xdr::LedgerEntryData, sponsor: Option<xdr::AccountId>) {
+        let ext = match sponsor {
+            None => xdr::LedgerEntryExt::V0,
+            Some(sponsor) => xdr::LedgerEntryExt::V1(xdr::LedgerEntryExtensionV1 {
+                sponsoring_id: xdr::SponsorshipDescriptor(Some(sponsor)),
+                ext: xdr::LedgerEntryExtensionV1Ext::V0,
+            }),
+        };
+        self.put(xdr::LedgerEntry {
+            last_modified_ledger_seq: self.state.ledger_seq,
+            data,
+            ext,
+        });
+    }
+
+    pub(super) fn erase(&mut self, key: &xdr::LedgerKey) {
+        self.track(key);
+        self.state.entries.remove(key);
+    }
+
+    /// Returns the sponsor of the entry with `key`.
+    pub(super) fn sponsor_of(&self, key: &xdr::LedgerKey) -> Option<xdr::AccountId> {
+        match self.get(key).map(|entry| &entry.ext) {
+            Some(xdr::LedgerEntryExt::V1(ext)) => ext.sponsoring_id.0.clone(),
+            _ => None,
+        }
+    }
+
+    /// Adds `count` sub entries to `owner`, sponsored by `sponsor` if any.
+    ///
+    /// The account paying the reserve must be able to afford it.
+    pub(super) fn add_sub_entries(
+        &mut self,
+        owner: &xdr::AccountId,
+        sponsor: Option<&xdr::AccountId>,
+        count: u32,
+    ) -> std::result::Result<(), ReserveError> {
+        let base_reserve = self.state.base_reserve;
+        let mut owner_entry = self.load_account(owner).ok_or(ReserveError::LowReserve)?;
+        owner_entry.num_sub_entries = owner_entry
+            .num_sub_entries
+            .checked_add(count)
+            .filter(|n| *n <= MAX_SUB_ENTRIES)
+            .ok_or(ReserveError::TooManySubEntries)?;
+        match sponsor {
+            None => {
+                if available_balance(&owner_entry, base_reserve) < 0 {
+                    return Err(ReserveError::LowReserve);
+                }
+            }
+            Some(sponsor) => {
+                self.add_sponsoring(sponsor, count)?;
+                let ext = account_ext_v2_mut(&mut owner_entry);
+                ext.num_sponsored = ext
+                    .num_sponsored
+                    .checked_add(count)
+                    .ok_or(ReserveError::TooManySponsoring)?;
+            }
+        }
+        self.update(xdr::LedgerEntryData::Account(owner_entry));
+        Ok(())
+    }
+
+    /// Removes `count` sub entries from `owner`, sponsored by `sponsor` if any.
+    pub(super) fn remove_sub_entries(
+        &mut self,
+        owner: &xdr::AccountId,
+        sponsor: Option<&xdr::AccountId>,
+        count: u32,
+    ) {
+        if let Some(mut owner_entry) = self.load_account(owner) {
+            owner_entry.num_sub_entries = owner_entry.num_sub_entries.saturating_sub(count);
+            if sponsor.is_some() {
+                let ext = account_ext_v2_mut(&mut owner_entry);
+                ext.num_sponsored = ext.num_sponsored.saturating_sub(count);
+            }
+            self.update(xdr::LedgerEntryData::Account(owner_entry));
+        }
+        if let Some(sponsor) = sponsor {
+            self.remove_sponsoring(sponsor, count);
+        }
+    }
+
+    /// Makes `sponsor` pay the reserve of `count` more entries.
+    pub(super) fn add_sponsoring(
+        &mut self,
+        sponsor: &xdr::AccountId,
+        count: u32,
+    ) -> std::result::Result<(), ReserveError> {
+        let base_reserve = self.state.base_reserve;
+        let mut sponsor_entry = self.load_account(sponsor).ok_or(ReserveError::LowReserve)?;
+        let ext = account_ext_v2_mut(&mut sponsor_entry);
+        ext.num_sponsoring = ext
+            .num_sponsoring
+            .checked_add(count)
+            .ok_or(ReserveError::TooManySponsoring)?;
+        if available_balance(&sponsor_entry, base_reserve) < 0 {
+            return Err(ReserveError::LowReserve);
+        }
+        self.update(xdr::LedgerEntryData::Account(sponsor_entry));
+        Ok(())
+    }
+
+    /// Releases `sponsor` from paying the reserve of `count` entries.
+    pub(super) fn remove_sponsoring(&mut self, sponsor: &xdr::AccountId, count: u32) {
+        if let Some(mut sponsor_entry) = self.load_account(sponsor) {
+            let ext = account_ext_v2_mut(&mut sponsor_entry);
+            ext.num_sponsoring = ext.num_sponsoring.saturating_sub(count);
+            self.update(xdr::LedgerEntryData::Account(sponsor_entry));
+        }
+    }
+
+    /// Removes the signer at `index` from `account_id`, releasing its reserve.
+    pub(super) fn remove_signer(
+        &mut self,
+        account_id: &xdr::AccountId,
+        index: usize,
+    ) -> Result<()> {
+        let Some(mut account) = self.load_account(account_id) else {
+            return Ok(());
+        };
+        let mut signers = account.signers.to_vec();
+        if index >= signers.len() {
+            return Ok(());
+        }
+        signers.remove(index);
+        let mut sponsor = None;
+        if account_ext_v2(&account).is_some() {
+            let ext = account_ext_v2_mut(&mut account);
+            let mut ids = ext.signer_sponsoring_i_ds.to_vec();
+            if index < ids.len() {
+                sponsor = ids.remove(index).0;
+            }
+            ext.signer_sponsoring_i_ds = ids.try_into()?;
+        }
+        account.signers = signers.try_into()?;
+        self.update(xdr::LedgerEntryData::Account(account));
+        self.remove_sub_entries(account_id, sponsor.as_ref(), 1);
+        Ok(())
+    }
+
+    /// Commits the changes, returning them as ledger entry changes.
+    pub(super) fn commit(self) -> Vec<xdr::LedgerEntryChange> {
+        let mut changes = Vec::new();
+        for (key, original) in self.original {
+            let current = self.state.entries.get(&key);
+            match (original, current) {
+                (None, Some(current)) => {
+                    changes.push(xdr::LedgerEntryChange::Created(current.clone()));
+                }
+                (Some(original), Some(current)) => {
+                    if original != *current {
+                        changes.push(xdr::LedgerEntryChange::State(original));
+                        changes.push(xdr::LedgerEntryChange::Updated(current.clone()));
+                    }
+                }
+                (Some(original), None) => {
+                    changes.push(xdr::LedgerEntryChange::State(original));
+                    changes.push(xdr::LedgerEntryChange::Removed(key));
+                }
+                (None, None) => {}
+            }
+        }
+        changes
+    }
+
+    /// Discards the changes.
+    pub(super) fn rollback(self) {
+        for (key, original) in self.original {
+            match original {
+                Some(entry) => {
+                    self.state.entries.insert(key, entry);
+                }
+                None => {
+                    self.state.entries.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn put(&mut self, entry: xdr::LedgerEntry) {
+        let key = ledger_entry_key(&entry);
+        self.track(&key);
+        self.state.entries.insert(key, entry);
+    }
+
+    fn track(&mut self, key: &xdr::LedgerKey) {
+        if !self.original.contains_key(key) {
+            let original = self.state.entries.get(key).cloned();
+            self.original.insert(key.clone(), original);
+        }
+    }
+}
+
+pub(super) fn new_account_entry(
+    account_id: xdr::AccountId,
+    balance: i64,
+    ledger_seq: u32,
+) -> xdr::AccountEntry {
+    xdr::AccountEntry {
+        account_id,
+        balance,
+        seq_num: xdr::SequenceNumber(i64::from(ledger_seq) << 32),
+        num_sub_entries: 0,
+        inflation_dest: None,
+        flags: 0,
+        home_domain: Default::default(),
+        thresholds: xdr::Thresholds([1, 0, 0, 0]),
+        signers: Default::default(),
+        ext: xdr::AccountEntryExt::V0,
+    }
+}
+
+pub(super) fn account_key(id: &xdr::AccountId) -> xdr::LedgerKey {
+    xdr::LedgerKey::Account(xdr::LedgerKeyAccount {
+        account_id: id.clone(),
+    })
+}
+
+pub(super) fn trustline_key(id: &xdr::AccountId, asset: xdr::TrustLineAsset) -> xdr::LedgerKey {
+    xdr::LedgerKey::Trustline(xdr::LedgerKeyTrustLine {
+        account_id: id.clone(),
+        asset,
+    })
+}
+
+pub(super) fn trustline_asset(asset: &xdr::Asset) -> Option<xdr::TrustLineAsset> {
+    match asset {
+        xdr::Asset::Native => None,
+        xdr::Asset::CreditAlphanum4(code) => {
+            Some(xdr::TrustLineAsset::CreditAlphanum4(code.clone()))
+        }
+        xdr::Asset::CreditAlphanum12(code) => {
+            Some(xdr::TrustLineAsset::CreditAlphanum12(code.clone()))
+        }
+    }
+}
+
+pub(super) fn asset_issuer(asset: &xdr::Asset) -> Option<&xdr::AccountId> {
+    match asset {
+        xdr::Asset::Native => None,
+        xdr::Asset::CreditAlphanum4(code) => Some(&code.issuer),
+        xdr::Asset::CreditAlphanum12(code) => Some(&code.issuer),
+    }
+}
+
+/// Returns the key of the ledger entry `entry`.
+pub fn ledger_entry_key(entry: &xdr::LedgerEntry) -> xdr::LedgerKey {
+    ledger_key(&entry.data)
+}
+
+fn ledger_key(data: &xdr::LedgerEntryData) -> xdr::LedgerKey {
+    match data {
+        xdr::LedgerEntryData::Account(entry) => account_key(&entry.account_id),
+        xdr::LedgerEntryData::Trustline(entry) => {
+            trustline_key(&entry.account_id, entry.asset.clone())
+        }
+        xdr::LedgerEntryData::Offer(entry) => xdr::LedgerKey::Offer(xdr::LedgerKeyOffer {
+            seller_id: entry.seller_id.clone(),
+            offer_id: entry.offer_id,
+        }),
+        xdr::LedgerEntryData::Data(entry) => xdr::LedgerKey::Data(xdr::LedgerKeyData {
+            account_id: entry.account_id.clone(),
+            data_name: entry.data_name.clone(),
+        }),
+        xdr::LedgerEntryData::ClaimableBalance(entry) => {
+            xdr::LedgerKey::ClaimableBalance(xdr::LedgerKeyClaimableBalance {
+                balance_id: entry.balance_id.clone(),
+            })
+        }
+        xdr::LedgerEntryData::LiquidityPool(entry) => {
+            xdr::LedgerKey::LiquidityPool(xdr::LedgerKeyLiquidityPool {
+                liquidity_pool_id: entry.liquidity_pool_id.clone(),
+            })
+        }
+        xdr::LedgerEntryData::ContractData(entry) => {
+            xdr::LedgerKey::ContractData(xdr::LedgerKeyContractData {
+                contract: entry.contract.clone(),
+                key: entry.key.clone(),
+                durability: entry.durability,
+            })
+        }
+        xdr::LedgerEntryData::ContractCode(entry) => {
+            xdr::LedgerKey::ContractCode(xdr::LedgerKeyContractCode {
+                hash: entry.hash.clone(),
+            })
+        }
+        xdr::LedgerEntryData::ConfigSetting(entry) => {
+            xdr::LedgerKey::ConfigSetting(xdr::LedgerKeyConfigSetting {
+                config_setting_id: entry.discriminant(),
+            })
+        }
+        xdr::LedgerEntryData::Ttl(entry) => xdr::LedgerKey::Ttl(xdr::LedgerKeyTtl {
+            key_hash: entry.key_hash.clone(),
+        }),
+    }
+}
+
+/// Returns the buying and selling liabilities of `account`.
+pub(super) fn account_liabilities(account: &xdr::AccountEntry) -> (i64, i64) {
+    match account.ext {
+        xdr::AccountEntryExt::V0 => (0, 0),
+        xdr::AccountEntryExt::V1(ref ext) => (ext.liabilities.buying, ext.liabilities.selling),
+    }
+}
+
+/// Returns the buying and selling liabilities of `trustline`.
+pub(super) fn trustline_liabilities(trustline: &xdr::TrustLineEntry) -> (i64, i64) {
+    match trustline.ext {
+        xdr::TrustLineEntryExt::V0 => (0, 0),
+        xdr::TrustLineEntryExt::V1(ref ext) => (ext.liabilities.buying, ext.liabilities.selling),
+    }
+}
+
+pub(super) fn num_sponsoring(account: &xdr::AccountEntry) -> u32 {
+    account_ext_v2(account).map_or(0, |ext| ext.num_sponsoring)
+}
+
+pub(super) fn num_sponsored(account: &xdr::AccountEntry) -> u32 {
+    account_ext_v2(account).map_or(0, |ext| ext.num_sponsored)
+}
+
+/// Returns the minimum balance of `account`.
+pub(super) fn min_balance(account: &xdr::AccountEntry, base_reserve: u32) -> i64 {
+    let entries = 2 + i64::from(account.num_sub_entries) + i64::from(num_sponsoring(account))
+        - i64::from(num_sponsored(account));
+    entries * i64::from(base_reserve)
+}
+
+/// Returns the balance `account` can spend.
+pub(super) fn available_balance(account: &xdr::AccountEntry, base_reserve: u32) -> i64 {
+    let (_, selling) = account_liabilities(account);
+    account.balance - min_balance(account, base_reserve) - selling
+}
+
+/// Returns the amount `account` can receive.
+pub(super) fn max_receive(account: &xdr::AccountEntry) -> i64 {
+    let (buying, _) = account_liabilities(account);
+    i64::MAX - account.balance - buying
+}
+
+pub(super) fn account_ext_v2(account: &xdr::AccountEntry) -> Option<&xdr::AccountEntryExtensionV2> {
+    match account.ext {
+        xdr::AccountEntryExt::V1(xdr::AccountEntryExtensionV1 {
+            ext: xdr::AccountEntryExtensionV1Ext::V2(ref ext),
+            ..
+        }) => Some(ext),
+        _ => None,
+    }
+}
+
+/// Returns the ledger and close time of the last sequence number update of `account`.
+pub(super) fn account_seq_ledger_and_time(account: &xdr::AccountEntry) -> (u32, u64) {
+    match account_ext_v2(account) {
+        Some(xdr::AccountEntryExtensionV2 {
+            ext: xdr::AccountEntryExtensionV2Ext::V3(ext),
+            ..
+        }) => (ext.seq_ledger, ext.seq_time.0),
+        _ => (0, 0),
+    }
+}
+
+/// Records that the sequence number of `account` was updated in `ledger_seq`
+/// closed at `close_time`, upgrading the account if needed.
+pub(super) fn update_account_seq_ledger_and_time(
+    account: &mut xdr::AccountEntry,
+    ledger_seq: u32,
+    close_time: u64,
+) {
+    account_ext_v2_mut(account).ext =
+        xdr::AccountEntryExtensionV2Ext::V3(xdr::AccountEntryExtensionV3 {
+            ext: xdr::ExtensionPoint::V0,
+            seq_ledger: ledger_seq,
+            seq_time: xdr::TimePoint(close_time),
+        });
+}
+
+/// Returns the version 2 extension of `account`, upgrading the account if needed.
+pub(super) fn account_ext_v2_mut(
+    account: &mut xdr::AccountEntry,
+) -> &mut xdr::AccountEntryExtensionV2 {
+    let signers = account.signers.len();
+    if let xdr::AccountEntryExt::V0 = account.ext {
+        account.ext = xdr::AccountEntryExt::V1(xdr::AccountEntryExtensionV1 {
+            liabilities: xdr::Liabilities {
+                buying: 0,
+                selling: 0,
+            },
+            ext: xdr::AccountEntryExtensionV1Ext::V0,
+        });
+    }
+    let xdr::AccountEntryExt::V1(ref mut ext_v1) = account.ext else {
+        unreachable!()
+    };
+    if let xdr::AccountEntryExtensionV1Ext::V0 = ext_v1.ext {
+        ext_v1.ext = xdr::AccountEntryExtensionV1Ext::V2(xdr::AccountEntryExtensionV2 {
+            num_sponsored: 0,
+            num_sponsoring: 0,
+            signer_sponsoring_i_ds: vec![xdr::SponsorshipDescriptor(None); signers]
+                .try_into()
+                .unwrap_or_default(),
+            ext: xdr::AccountEntryExtensionV2Ext::V0,
+        });
+    }
+    let xdr::AccountEntryExtensionV1Ext::V2(ref mut ext_v2) = ext_v1.ext else {
+        unreachable!()
+    };
+    ext_v2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::tests::*;
+
+    fn new_state() -> LedgerState {
+        let mut state = LedgerState::new(Network::new_test());
+        state
+            .create_account(&keypair0().public_key(), Stroops::new(20_000_000))
+            .unwrap();
+        state
+    }
+
+    #[test]
+    fn test_commit_and_rollback() {
+        let mut state = new_state();
+        let account_id = keypair0().public_key().to_xdr_account_id().unwrap();
+        let original = state.clone();
+
+        let mut ltx = LedgerTxn::new(&mut state);
+        let mut account = ltx.load_account(&account_id).unwrap();
+        account.balance -= 100;
+        ltx.update(xdr::LedgerEntryData::Account(account));
+        ltx.rollback();
+        assert_eq!(original, state);
+
+        let mut ltx = LedgerTxn::new(&mut state);
+        let mut account = ltx.load_account(&account_id).unwrap();
+        account.balance -= 100;
+        ltx.update(xdr::LedgerEntryData::Account(account));
+        let changes = ltx.commit();
+        assert!(matches!(
+            changes[..],
+            [
+                xdr::LedgerEntryChange::State(_),
+                xdr::LedgerEntryChange::Updated(_)
+            ]
+        ));
+        assert_eq!(
+            Some(Stroops::new(19_999_900)),
+            state
+                .balance(&keypair0().public_key(), &Asset::new_native())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sub_entries_reserve() {
+        let mut state = new_state();
+        let account_id = keypair0().public_key().to_xdr_account_id().unwrap();
+        let sponsor = keypair1().public_key();
+        state
+            .create_account(&sponsor, Stroops::new(15_000_000))
+            .unwrap();
+        let sponsor_id = sponsor.to_xdr_account_id().unwrap();
+
+        // 2 XLM covers the base reserve and 2 sub entries.
+        let mut ltx = LedgerTxn::new(&mut state);
+        assert_eq!(Ok(()), ltx.add_sub_entries(&account_id, None, 2));
+        assert_eq!(
+            Err(ReserveError::LowReserve),
+            ltx.add_sub_entries(&account_id, None, 1)
+        );
+        assert_eq!(
+            Ok(()),
+            ltx.add_sub_entries(&account_id, Some(&sponsor_id), 1)
+        );
+        assert_eq!(
+            Err(ReserveError::LowReserve),
+            ltx.add_sub_entries(&account_id, Some(&sponsor_id), 1)
+        );
+        ltx.commit();
+
+        let account = state.account(&keypair0().public_key()).unwrap().unwrap();
+        assert_eq!(3, account.num_sub_entries);
+        assert_eq!(1, num_sponsored(account));
+        assert_eq!(20_000_000, min_balance(account, DEFAULT_BASE_RESERVE));
+        let sponsor = state.account(&sponsor).unwrap().unwrap();
+        assert_eq!(1, num_sponsoring(sponsor));
+    }
+}
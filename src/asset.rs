@@ -1,5 +1,7 @@
 //! Assets on the network.
+use std::fmt;
 use std::io::{Read, Write};
+use std::str::FromStr;
 
 use crate::crypto::PublicKey;
 use crate::error::{Error, Result};
@@ -8,7 +10,12 @@ use crate::xdr;
 
 /// Represent an asset, either the native asset (XLM) or an asset
 /// issued.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Assets are ordered like stellar-core orders them: native first, then
+/// assets with 4 characters codes and then assets with 12 characters codes,
+/// each sorted by code and issuer. Liquidity pools require their asset pair
+/// to be in this order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Asset {
     /// The native asset (XLM).
     Native,
@@ -18,7 +25,9 @@ pub enum Asset {
 
 /// Represent an asset associated with a trustline, either a regular asset or a liquidity pool's
 /// shares
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Trust line assets are ordered like `Asset`, with pool shares last.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TrustLineAsset {
     Native,
     Credit(CreditAsset),
@@ -33,7 +42,7 @@ pub enum CreditAssetType {
 }
 
 /// A non-native asset, identified by asset code/issuer id.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum CreditAsset {
     AlphaNum4 { code: String, issuer: PublicKey },
     AlphaNum12 { code: String, issuer: PublicKey },
@@ -114,12 +123,12 @@ impl Asset {
             xdr::Asset::Native => Ok(Asset::new_native()),
             xdr::Asset::CreditAlphanum4(credit) => {
                 let issuer = PublicKey::from_xdr_account_id(&credit.issuer)?;
-                let code = xdr_code_to_string(&credit.asset_code.0);
+                let code = xdr_code_to_canonical_string(&credit.asset_code.0, 1)?;
                 Asset::new_credit(code, issuer)
             }
             xdr::Asset::CreditAlphanum12(credit) => {
                 let issuer = PublicKey::from_xdr_account_id(&credit.issuer)?;
-                let code = xdr_code_to_string(&credit.asset_code.0);
+                let code = xdr_code_to_canonical_string(&credit.asset_code.0, 5)?;
                 Asset::new_credit(code, issuer)
             }
         }
@@ -129,8 +138,12 @@ impl Asset {
 impl CreditAsset {
     /// Creates new credit asset with `code` and `issuer`.
     ///
-    /// Code must be shorter than 12 characters.
+    /// Code must be between 1 and 12 characters long and only contain
+    /// ascii letters and digits.
     pub fn new(code: String, issuer: PublicKey) -> Result<CreditAsset> {
+        if !code.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(Error::InvalidAssetCode);
+        }
         let code_len = code.len();
         if (1..=4).contains(&code_len) {
             Ok(CreditAsset::AlphaNum4 { code, issuer })
@@ -269,12 +282,12 @@ impl TrustLineAsset {
             xdr::TrustLineAsset::Native => Ok(Self::new_native()),
             xdr::TrustLineAsset::CreditAlphanum4(credit) => {
                 let issuer = PublicKey::from_xdr_account_id(&credit.issuer)?;
-                let code = xdr_code_to_string(&credit.asset_code.0);
+                let code = xdr_code_to_canonical_string(&credit.asset_code.0, 1)?;
                 Ok(Self::new_credit(code, issuer)?)
             }
             xdr::TrustLineAsset::CreditAlphanum12(credit) => {
                 let issuer = PublicKey::from_xdr_account_id(&credit.issuer)?;
-                let code = xdr_code_to_string(&credit.asset_code.0);
+                let code = xdr_code_to_canonical_string(&credit.asset_code.0, 5)?;
                 Ok(Self::new_credit(code, issuer)?)
             }
             xdr::TrustLineAsset::PoolShare(pool_id) => {
@@ -296,6 +309,79 @@ impl From<Asset> for TrustLineAsset {
     }
 }
 
+impl fmt::Display for Asset {
+    /// Formats the asset as `native` or `CODE:ISSUER`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Asset::Native => write!(f, "native"),
+            Asset::Credit(credit) => credit.fmt(f),
+        }
+    }
+}
+
+impl FromStr for Asset {
+    type Err = Error;
+
+    /// Parses an asset in the `native` or `CODE:ISSUER` form.
+    fn from_str(s: &str) -> Result<Asset> {
+        if s == "native" {
+            Ok(Asset::Native)
+        } else {
+            Ok(Asset::Credit(s.parse()?))
+        }
+    }
+}
+
+impl fmt::Display for CreditAsset {
+    /// Formats the asset as `CODE:ISSUER`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code(), self.issuer())
+    }
+}
+
+impl FromStr for CreditAsset {
+    type Err = Error;
+
+    /// Parses an asset in the `CODE:ISSUER` form.
+    fn from_str(s: &str) -> Result<CreditAsset> {
+        let (code, issuer) = s
+            .split_once(':')
+            .ok_or_else(|| Error::InvalidAsset(s.to_string()))?;
+        let issuer =
+            PublicKey::from_account_id(issuer).map_err(|_| Error::InvalidAsset(s.to_string()))?;
+        CreditAsset::new(code.to_string(), issuer)
+    }
+}
+
+impl fmt::Display for TrustLineAsset {
+    /// Formats the asset as `native`, `CODE:ISSUER` or the hex encoded
+    /// liquidity pool id.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrustLineAsset::Native => write!(f, "native"),
+            TrustLineAsset::Credit(credit) => credit.fmt(f),
+            TrustLineAsset::PoolShare(pool_id) => pool_id.fmt(f),
+        }
+    }
+}
+
+impl FromStr for TrustLineAsset {
+    type Err = Error;
+
+    /// Parses an asset in the `native`, `CODE:ISSUER` or hex encoded
+    /// liquidity pool id form.
+    fn from_str(s: &str) -> Result<TrustLineAsset> {
+        if s == "native" {
+            Ok(TrustLineAsset::Native)
+        } else if s.contains(':') {
+            Ok(TrustLineAsset::Credit(s.parse()?))
+        } else {
+            let pool_id = s.parse().map_err(|_| Error::InvalidAsset(s.to_string()))?;
+            Ok(TrustLineAsset::PoolShare(pool_id))
+        }
+    }
+}
+
 impl xdr::WriteXdr for TrustLineAsset {
     fn write_xdr<W: Write>(&self, w: &mut xdr::Limited<W>) -> xdr::Result<()> {
         let xdr_asset = self.to_xdr().map_err(|_| xdr::Error::Invalid)?;
@@ -336,12 +422,32 @@ pub(crate) fn xdr_code_to_string(x: &[u8]) -> String {
     String::from_utf8_lossy(&x[..pos]).into_owned()
 }
 
+/// Create new String from the xdr asset code, checking it is in the canonical
+/// form stellar-core accepts: at least `min_len` ascii letters and digits,
+/// padded with trailing zero bytes only.
+pub(crate) fn xdr_code_to_canonical_string(x: &[u8], min_len: usize) -> Result<String> {
+    let len = x.iter().position(|b| *b == 0).unwrap_or(x.len());
+    let (code, padding) = x.split_at(len);
+    if len < min_len
+        || padding.iter().any(|b| *b != 0)
+        || !code.iter().all(u8::is_ascii_alphanumeric)
+    {
+        return Err(Error::InvalidAssetCode);
+    }
+    Ok(String::from_utf8_lossy(code).into_owned())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Asset, CreditAsset};
+    use super::{Asset, CreditAsset, TrustLineAsset};
     use crate::crypto::PublicKey;
+    use crate::error::Error;
+    use crate::liquidity_pool::{LiquidityPoolConstantFeeParameters, LiquidityPoolId};
+    use crate::xdr;
     use crate::xdr::{XDRDeserialize, XDRSerialize};
 
+    const ISSUER: &str = "GCZHXL5HXQX5ABDM26LHYRCQZ5OJFHLOPLZX47WEBP3V2PF5AVFK2A5D";
+
     #[test]
     fn test_error_code_too_long() {
         let code = "1234567890123".to_string();
@@ -420,4 +526,155 @@ mod tests {
         .unwrap();
         assert_eq!(expected, asset);
     }
+
+    #[test]
+    fn test_error_code_invalid_characters() {
+        let pk = PublicKey::from_account_id(ISSUER).unwrap();
+        for code in ["USD\0", "U$D", "US D", "", "EUR\u{e9}"] {
+            assert!(Asset::new_credit(code, pk).is_err(), "{:?}", code);
+        }
+        let asset = Asset::new_credit("ABCDEFGHIJKL", pk).unwrap();
+        assert!(matches!(
+            asset.as_credit(),
+            Some(CreditAsset::AlphaNum12 { .. })
+        ));
+    }
+
+    #[test]
+    fn test_asset_xdr_de_non_canonical_code() {
+        let issuer = PublicKey::from_account_id(ISSUER)
+            .unwrap()
+            .to_xdr_account_id()
+            .unwrap();
+        let alphanum4 = |code: [u8; 4]| {
+            xdr::Asset::CreditAlphanum4(xdr::AlphaNum4 {
+                asset_code: xdr::AssetCode4(code),
+                issuer: issuer.clone(),
+            })
+        };
+        let alphanum12 = |code: [u8; 12]| {
+            xdr::Asset::CreditAlphanum12(xdr::AlphaNum12 {
+                asset_code: xdr::AssetCode12(code),
+                issuer: issuer.clone(),
+            })
+        };
+
+        let asset = Asset::from_xdr(&alphanum4(*b"USD\0")).unwrap();
+        assert_eq!("USD", asset.as_credit().unwrap().code());
+        assert!(Asset::from_xdr(&alphanum4(*b"US\0D")).is_err());
+        assert!(Asset::from_xdr(&alphanum4([0; 4])).is_err());
+        assert!(Asset::from_xdr(&alphanum4(*b"U-SD")).is_err());
+
+        let asset = Asset::from_xdr(&alphanum12(*b"ABCDEFGHIJKL")).unwrap();
+        assert_eq!("ABCDEFGHIJKL", asset.as_credit().unwrap().code());
+        assert!(Asset::from_xdr(&alphanum12(*b"USD\0\0\0\0\0\0\0\0\0")).is_err());
+        assert!(Asset::from_xdr(&alphanum12(*b"ABCDE\0\0\0\0\0\0F")).is_err());
+    }
+
+    #[test]
+    fn test_asset_string_round_trip() {
+        let native: Asset = "native".parse().unwrap();
+        assert_eq!(Asset::new_native(), native);
+        assert_eq!("native", native.to_string());
+
+        for code in ["USD", "ABCDEFGHIJKL"] {
+            let s = format!("{}:{}", code, ISSUER);
+            let asset: Asset = s.parse().unwrap();
+            assert_eq!(code, asset.as_credit().unwrap().code());
+            assert_eq!(s, asset.to_string());
+        }
+
+        assert!(matches!(
+            "XLM".parse::<Asset>(),
+            Err(Error::InvalidAsset(_))
+        ));
+        assert!(matches!(
+            "USD:GABC".parse::<Asset>(),
+            Err(Error::InvalidAsset(_))
+        ));
+        assert!(matches!(
+            format!("USD\0:{}", ISSUER).parse::<Asset>(),
+            Err(Error::InvalidAssetCode)
+        ));
+        assert!(matches!(
+            format!("ABCDEFGHIJKLM:{}", ISSUER).parse::<Asset>(),
+            Err(Error::InvalidAssetCode)
+        ));
+    }
+
+    #[test]
+    fn test_trust_line_asset_string_round_trip() {
+        let pool_id = LiquidityPoolId::new(vec![0xab; 32]).unwrap();
+        let s = "ab".repeat(32);
+        let asset: TrustLineAsset = s.parse().unwrap();
+        assert_eq!(TrustLineAsset::PoolShare(pool_id), asset);
+        assert_eq!(s, asset.to_string());
+
+        let s = format!("EUR:{}", ISSUER);
+        let asset: TrustLineAsset = s.parse().unwrap();
+        assert!(asset.is_credit());
+        assert_eq!(s, asset.to_string());
+
+        let asset: TrustLineAsset = "native".parse().unwrap();
+        assert!(asset.is_native());
+        assert!("abab".parse::<TrustLineAsset>().is_err());
+
+        assert!(matches!(
+            "zz".repeat(32).parse::<LiquidityPoolId>(),
+            Err(Error::InvalidLiquidityPoolId)
+        ));
+        assert!(matches!(
+            "abab".parse::<LiquidityPoolId>(),
+            Err(Error::InvalidLiquidityPoolIdLength)
+        ));
+    }
+
+    #[test]
+    fn test_asset_ordering() {
+        let pk = PublicKey::from_account_id(ISSUER).unwrap();
+        let other = PublicKey([0; 32]);
+        let mut assets = [
+            Asset::new_credit("ABCDE", pk).unwrap(),
+            Asset::new_credit("USD", pk).unwrap(),
+            Asset::new_credit("USD", other).unwrap(),
+            Asset::new_credit("ZZZZ", other).unwrap(),
+            Asset::new_credit("AAAAA", pk).unwrap(),
+            Asset::new_credit("US", pk).unwrap(),
+            Asset::new_native(),
+        ];
+        assets.sort();
+        let sorted: Vec<String> = assets
+            .iter()
+            .map(|asset| match asset.as_credit() {
+                None => "native".to_string(),
+                Some(credit) => format!("{}-{}", credit.code(), credit.issuer() == &pk),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "native",
+                "US-true",
+                "USD-false",
+                "USD-true",
+                "ZZZZ-false",
+                "AAAAA-true",
+                "ABCDE-true",
+            ],
+            sorted
+        );
+
+        // matches the order of the xdr encoding
+        for pair in assets.windows(2) {
+            assert!(pair[0].to_xdr().unwrap() < pair[1].to_xdr().unwrap());
+        }
+
+        let usd = Asset::new_credit("USD", pk).unwrap();
+        assert!(
+            LiquidityPoolConstantFeeParameters::new(Asset::new_native(), usd.clone(), 30).is_ok()
+        );
+        assert!(
+            LiquidityPoolConstantFeeParameters::new(usd.clone(), Asset::new_native(), 30).is_err()
+        );
+        assert!(LiquidityPoolConstantFeeParameters::new(usd.clone(), usd, 30).is_err());
+    }
 }
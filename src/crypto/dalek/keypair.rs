@@ -5,9 +5,11 @@ use crate::crypto::{Ed25519Signer, Signature};
 use crate::error::{Error, Result};
 use crate::network::Network;
 use crate::PublicKey;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 /// The secret key of the account.
-#[derive(Debug)]
+///
+/// The key material is zeroized when the secret key is dropped.
 pub struct SecretKey {
     signing_key: ed25519_dalek::SigningKey,
 }
@@ -21,9 +23,28 @@ impl Ed25519Signer<Signature> for SecretKey {
     }
 }
 
-#[derive(Debug)]
 pub struct DalekKeyPair(KeyPair<SecretKey, ed25519_dalek::VerifyingKey>);
 
+// The inner `ed25519_dalek::SigningKey` zeroizes itself on drop.
+impl ZeroizeOnDrop for SecretKey {}
+
+impl ZeroizeOnDrop for DalekKeyPair {}
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretKey(<redacted>)")
+    }
+}
+
+impl std::fmt::Debug for DalekKeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DalekKeyPair")
+            .field("public_key", &self.public_key().account_id())
+            .field("secret_key", self.secret_key())
+            .finish()
+    }
+}
+
 impl SecretKey {
     /// Return the inner keypair.
     pub fn inner(&self) -> &ed25519_dalek::SigningKey {
@@ -31,9 +52,21 @@ impl SecretKey {
     }
 
     /// Return the secret key as String, starting with `S`.
+    ///
+    /// Prefer `secret_seed_zeroizing` to make sure the seed is cleared from memory once used.
     pub fn secret_seed(&self) -> String {
         strkey::encode_secret_seed(self.signing_key.as_bytes())
     }
+
+    /// Return the secret key as String, starting with `S`, zeroized when dropped.
+    pub fn secret_seed_zeroizing(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.secret_seed())
+    }
+
+    /// Return the raw secret key bytes, zeroized when dropped.
+    pub fn seed_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.signing_key.to_bytes())
+    }
 }
 
 impl AsRef<KeyPair<SecretKey, ed25519_dalek::VerifyingKey>> for DalekKeyPair {
@@ -45,7 +78,7 @@ impl AsRef<KeyPair<SecretKey, ed25519_dalek::VerifyingKey>> for DalekKeyPair {
 impl DalekKeyPair {
     /// Create the key pair from the secret seed, e.g. `SDAKFNYEIAORZKKCYRILFQKLLOCNPL5SWJ3YY5NM3ZH6GJSZGXHZEPQS`.
     pub fn from_secret_seed(data: &str) -> Result<DalekKeyPair> {
-        let bytes = strkey::decode_secret_seed(data)?;
+        let bytes = Zeroizing::new(strkey::decode_secret_seed(data)?);
         Self::from_seed_bytes(&bytes)
     }
 
@@ -67,9 +100,10 @@ impl DalekKeyPair {
 
     /// Create a key pair from raw bytes.
     pub fn from_seed_bytes(data: &[u8]) -> Result<DalekKeyPair> {
-        let secret_key: ed25519_dalek::SecretKey =
+        let mut secret_key: ed25519_dalek::SecretKey =
             data.try_into().map_err(|_| Error::InvalidSeed)?;
         let signing_key = ed25519_dalek::SigningKey::from_bytes(&secret_key);
+        secret_key.zeroize();
         let verifier = signing_key.verifying_key();
         let signer = SecretKey { signing_key };
         Ok(DalekKeyPair(KeyPair::new(signer, verifier)))
@@ -166,6 +200,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_debug_is_redacted() {
+        let the_secret = "SD7X7LEHBNMUIKQGKPARG5TDJNBHKC346OUARHGZL5ITC6IJPXHILY36";
+        let kp = DalekKeyPair::from_secret_seed(the_secret).unwrap();
+        let debug = format!("{:?}", kp);
+        assert!(!debug.contains(the_secret));
+        assert!(debug.contains("<redacted>"));
+        assert!(debug.contains(&kp.public_key().account_id()));
+        assert_eq!(the_secret, kp.secret_key().secret_seed_zeroizing().as_str());
+    }
+
     #[test]
     fn test_from_network() {
         let network = Network::new_public();
@@ -192,6 +237,8 @@ mod tests {
         ];
         assert_eq!(sign.to_bytes(), expected_sign[..]);
         assert!(kp.verify(&sign, message));
+        assert!(kp.public_key().verify(message, &sign));
+        assert!(!kp.public_key().verify(b"other message", &sign));
     }
 
     #[test]
@@ -5,6 +5,7 @@ mod dalek;
 mod public_key;
 mod signature;
 mod strkey;
+mod transaction_signer;
 
 use crate::error::Error;
 use sha2::Digest;
@@ -13,6 +14,7 @@ use std::convert::TryInto;
 pub use self::public_key::{MuxedAccount, MuxedEd25519PublicKey, PublicKey};
 pub use self::signature::*;
 pub use self::strkey::*;
+pub use self::transaction_signer::*;
 #[cfg(feature = "dalek")]
 pub use dalek::*;
 pub use ed25519::signature::{Signer as Ed25519Signer, Verifier as Ed25519Verifier};
@@ -1,11 +1,16 @@
 use std::convert::TryInto;
 
 use crate::crypto::strkey;
+#[cfg(feature = "dalek")]
+use crate::crypto::Ed25519Verifier;
 use crate::error::{Error, Result};
+use crate::memo::Memo;
 use crate::xdr;
+#[cfg(feature = "dalek")]
+use ed25519::Signature;
 
 /// The public key of the account.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PublicKey(pub [u8; 32]);
 
 impl PublicKey {
@@ -64,6 +69,15 @@ impl PublicKey {
         &self.0
     }
 
+    /// Returns true if `signature` is a valid signature of `message` by the key.
+    #[cfg(feature = "dalek")]
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> bool {
+        let Ok(key) = ed25519_dalek::VerifyingKey::from_bytes(&self.0) else {
+            return false;
+        };
+        key.verify(message, signature).is_ok()
+    }
+
     pub fn to_xdr(&self) -> Result<xdr::MuxedAccount> {
         let uint256 = self.to_xdr_uint256()?;
         Ok(xdr::MuxedAccount::Ed25519(uint256))
@@ -104,6 +118,11 @@ impl MuxedEd25519PublicKey {
         &self.key
     }
 
+    /// Retrieves the muxed id.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
     pub fn account_id(&self) -> String {
         strkey::encode_muxed_account(self.key.as_bytes(), self.id)
     }
@@ -119,6 +138,48 @@ impl MuxedEd25519PublicKey {
 }
 
 impl MuxedAccount {
+    /// Creates the account used by the legacy `key` and `memo` pair.
+    ///
+    /// An id memo gives a muxed account, no memo gives `key` itself. Other
+    /// memos can't be represented as muxed accounts.
+    pub fn from_memo(key: PublicKey, memo: &Memo) -> Result<MuxedAccount> {
+        match memo {
+            Memo::None => Ok(MuxedAccount::Ed25519(key)),
+            Memo::Id(id) => Ok(key.into_muxed_account(*id)),
+            _ => Err(Error::InvalidMuxedMemo),
+        }
+    }
+
+    /// Retrieves the ed25519 public key of the account, without the muxed id.
+    pub fn public_key(&self) -> &PublicKey {
+        match self {
+            MuxedAccount::Ed25519(pk) => pk,
+            MuxedAccount::MuxedEd25519(mx) => mx.public_key(),
+        }
+    }
+
+    /// Retrieves the muxed id, if the account is muxed.
+    pub fn id(&self) -> Option<u64> {
+        match self {
+            MuxedAccount::Ed25519(_) => None,
+            MuxedAccount::MuxedEd25519(mx) => Some(mx.id()),
+        }
+    }
+
+    /// Returns the legacy key and memo pair of the account: the muxed id as
+    /// an id memo, or no memo.
+    pub fn to_memo(&self) -> (PublicKey, Memo) {
+        match self.id() {
+            None => (*self.public_key(), Memo::None),
+            Some(id) => (*self.public_key(), Memo::Id(id)),
+        }
+    }
+
+    /// Returns the account without muxed id.
+    pub fn to_unmuxed(&self) -> MuxedAccount {
+        MuxedAccount::Ed25519(*self.public_key())
+    }
+
     pub fn account_id(&self) -> String {
         match self {
             MuxedAccount::Ed25519(pk) => pk.account_id(),
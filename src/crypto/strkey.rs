@@ -7,6 +7,10 @@ const MUXED_ACCOUNT_VERSION_BYTE: u8 = 12 << 3; // M
 const SECRET_SEED_VERSION_BYTE: u8 = 18 << 3; // S
 const PRE_AUTH_TX_VERSION_BYTE: u8 = 19 << 3; // T
 const SHA256_HASH_VERSION_BYTE: u8 = 23 << 3; // X
+const CONTRACT_VERSION_BYTE: u8 = 2 << 3; // C
+const SIGNED_PAYLOAD_VERSION_BYTE: u8 = 15 << 3; // P
+
+const MAX_SIGNED_PAYLOAD_LEN: usize = 64;
 
 static ALPHABET: base32::Alphabet = base32::Alphabet::Rfc4648 { padding: false };
 
@@ -56,6 +60,40 @@ pub fn decode_sha256_hash(data: &str) -> Result<Vec<u8>> {
     decode_check(SHA256_HASH_VERSION_BYTE, data)
 }
 
+pub fn encode_contract(data: &[u8]) -> String {
+    encode_check(CONTRACT_VERSION_BYTE, data)
+}
+
+pub fn decode_contract(data: &str) -> Result<Vec<u8>> {
+    decode_check(CONTRACT_VERSION_BYTE, data)
+}
+
+pub fn encode_signed_payload(key: &[u8], payload: &[u8]) -> String {
+    let padding = (4 - payload.len() % 4) % 4;
+    let mut data = Vec::with_capacity(key.len() + 4 + payload.len() + padding);
+    data.extend_from_slice(key);
+    data.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    data.extend_from_slice(payload);
+    data.resize(data.len() + padding, 0);
+    encode_check(SIGNED_PAYLOAD_VERSION_BYTE, &data)
+}
+
+pub fn decode_signed_payload(data: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let bytes = decode_check(SIGNED_PAYLOAD_VERSION_BYTE, data)?;
+    let payload_len = BigEndian::read_u32(&bytes[32..36]) as usize;
+    if payload_len == 0 || payload_len > MAX_SIGNED_PAYLOAD_LEN {
+        return Err(Error::InvalidStrKey);
+    }
+    let padded_len = payload_len + (4 - payload_len % 4) % 4;
+    if bytes.len() != 36 + padded_len {
+        return Err(Error::InvalidStrKey);
+    }
+    if bytes[36 + payload_len..].iter().any(|b| *b != 0) {
+        return Err(Error::InvalidStrKey);
+    }
+    Ok((bytes[..32].to_vec(), bytes[36..36 + payload_len].to_vec()))
+}
+
 fn encode_check(version: u8, indata: &[u8]) -> String {
     let mut data = Vec::with_capacity(35);
     data.push(version);
@@ -77,11 +115,15 @@ fn decode_unchecked(data: &str) -> Result<(u8, Vec<u8>)> {
 
     let version_byte = decoded[0];
 
-    if version_byte != MUXED_ACCOUNT_VERSION_BYTE && decoded_len != 35 {
-        return Err(Error::InvalidStrKey);
-    }
-
-    if version_byte == MUXED_ACCOUNT_VERSION_BYTE && decoded_len != 43 {
+    let valid_len = match version_byte {
+        MUXED_ACCOUNT_VERSION_BYTE => decoded_len == 43,
+        // version + key + payload length + payload (4 to 64 bytes, padded) + checksum
+        SIGNED_PAYLOAD_VERSION_BYTE => {
+            (43..=103).contains(&decoded_len) && (decoded_len - 39).is_multiple_of(4)
+        }
+        _ => decoded_len == 35,
+    };
+    if !valid_len {
         return Err(Error::InvalidStrKey);
     }
 
@@ -117,10 +159,12 @@ fn verify_checksum(checksum: u16, bytes: &[u8]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{decode_account_id, encode_account_id};
+    use super::{decode_contract, encode_contract};
     use super::{decode_muxed_account, encode_muxed_account};
     use super::{decode_pre_auth_tx, encode_pre_auth_tx};
     use super::{decode_secret_seed, encode_secret_seed};
     use super::{decode_sha256_hash, encode_sha256_hash};
+    use super::{decode_signed_payload, encode_signed_payload};
     use crate::crypto::DalekKeyPair;
     use crate::network::Network;
 
@@ -227,4 +271,36 @@ mod tests {
         let decoded = decode_sha256_hash(&encoded).unwrap();
         assert_eq!(pk.as_bytes(), &decoded[..]);
     }
+
+    #[test]
+    fn test_contract() {
+        // Test vector from SEP-23.
+        let encoded = "CA3D5KRYM6CB7OWQ6TWYRR3Z4T7GNZLKERYNZGGA5SOAOPIFY6YQGAXE";
+        let decoded = decode_contract(encoded).unwrap();
+        let expected = [
+            0x36, 0x3e, 0xaa, 0x38, 0x67, 0x84, 0x1f, 0xba, 0xd0, 0xf4, 0xed, 0x88, 0xc7, 0x79,
+            0xe4, 0xfe, 0x66, 0xe5, 0x6a, 0x24, 0x70, 0xdc, 0x98, 0xc0, 0xec, 0x9c, 0x07, 0x3d,
+            0x05, 0xc7, 0xb1, 0x03,
+        ];
+        assert_eq!(&expected[..], &decoded[..]);
+        assert_eq!(encoded, encode_contract(&decoded));
+    }
+
+    #[test]
+    fn test_signed_payload() {
+        // Test vector from SEP-23.
+        let encoded = "PA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJUAAAAAQACAQDAQCQMBYIBEFAWDANBYHRAEISCMKBKFQXDAMRUGY4DUPB6IBZGM";
+        let (key, payload) = decode_signed_payload(encoded).unwrap();
+        assert_eq!(
+            "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ",
+            encode_account_id(&key)
+        );
+        assert_eq!((1..=32).collect::<Vec<u8>>(), payload);
+        assert_eq!(encoded, encode_signed_payload(&key, &payload));
+
+        let encoded = "PA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJUAAAAAOQCAQDAQCQMBYIBEFAWDANBYHRAEISCMKBKFQXDAMRUGY4DUAAAAFGBU";
+        let (key, payload) = decode_signed_payload(encoded).unwrap();
+        assert_eq!((1..=29).collect::<Vec<u8>>(), payload);
+        assert_eq!(encoded, encode_signed_payload(&key, &payload));
+    }
 }
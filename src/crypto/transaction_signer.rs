@@ -0,0 +1,230 @@
+//! Pluggable transaction signing backends.
+//!
+//! Implement `TransactionSigner` to sign transactions with keys that are
+//! not available in memory, for example keys stored in a remote key
+//! management service or in an HSM.
+use std::sync::Mutex;
+
+use crate::crypto::{DecoratedSignature, Ed25519Signer, Ed25519Verifier, KeyPair, PublicKey};
+use crate::error::{Error, Result};
+use ed25519::Signature;
+
+/// A backend able to sign transaction hashes.
+pub trait TransactionSigner {
+    /// Signs the transaction `hash` with the key identified by `public_key`.
+    ///
+    /// Returns `Error::UnknownSigner` if the signer doesn't hold the key.
+    fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature>;
+}
+
+/// Asynchronous version of `TransactionSigner`, for signers that need to
+/// perform I/O to produce a signature.
+///
+/// Every `TransactionSigner` is also an `AsyncTransactionSigner`.
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+pub trait AsyncTransactionSigner {
+    /// Signs the transaction `hash` with the key identified by `public_key`.
+    async fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+impl<T> AsyncTransactionSigner for T
+where
+    T: TransactionSigner + Sync + ?Sized,
+{
+    async fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature> {
+        TransactionSigner::sign_hash(self, hash, public_key)
+    }
+}
+
+impl<S, V> TransactionSigner for KeyPair<S, V>
+where
+    S: Ed25519Signer<Signature>,
+    V: Ed25519Verifier<Signature> + AsRef<[u8]>,
+{
+    fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature> {
+        if self.public_key()? != *public_key {
+            return Err(Error::UnknownSigner);
+        }
+        Ok(self.sign_decorated(hash))
+    }
+}
+
+impl<T> TransactionSigner for &T
+where
+    T: TransactionSigner + ?Sized,
+{
+    fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature> {
+        (**self).sign_hash(hash, public_key)
+    }
+}
+
+/// A request received by a `MockSigner`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignRequest {
+    /// The hash to sign.
+    pub hash: Vec<u8>,
+    /// The key requested to sign the hash.
+    pub public_key: PublicKey,
+}
+
+/// A signer that records every request before forwarding it to `inner`.
+///
+/// Useful in tests to check which keys were asked to sign.
+#[derive(Debug)]
+pub struct MockSigner<T> {
+    inner: T,
+    requests: Mutex<Vec<SignRequest>>,
+}
+
+impl<T> MockSigner<T>
+where
+    T: TransactionSigner,
+{
+    /// Creates a new `MockSigner` forwarding requests to `inner`.
+    pub fn new(inner: T) -> MockSigner<T> {
+        MockSigner {
+            inner,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the requests received so far, in order.
+    pub fn requests(&self) -> Vec<SignRequest> {
+        self.requests
+            .lock()
+            .expect("mock signer lock poisoned")
+            .clone()
+    }
+
+    /// Returns the inner signer.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> TransactionSigner for MockSigner<T>
+where
+    T: TransactionSigner,
+{
+    fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature> {
+        self.requests
+            .lock()
+            .expect("mock signer lock poisoned")
+            .push(SignRequest {
+                hash: hash.to_vec(),
+                public_key: *public_key,
+            });
+        self.inner.sign_hash(hash, public_key)
+    }
+}
+
+#[cfg(feature = "dalek")]
+pub use self::in_memory::InMemorySigner;
+
+#[cfg(feature = "dalek")]
+mod in_memory {
+    use std::collections::HashMap;
+
+    use super::TransactionSigner;
+    use crate::crypto::{DalekKeyPair, DecoratedSignature, PublicKey};
+    use crate::error::{Error, Result};
+
+    /// A signer holding a set of key pairs in memory.
+    #[derive(Debug, Default)]
+    pub struct InMemorySigner {
+        keys: HashMap<PublicKey, DalekKeyPair>,
+    }
+
+    impl InMemorySigner {
+        /// Creates a new, empty, `InMemorySigner`.
+        pub fn new() -> InMemorySigner {
+            Default::default()
+        }
+
+        /// Adds `key_pair` to the signer, replacing any key pair with the same public key.
+        pub fn add_key_pair(&mut self, key_pair: DalekKeyPair) {
+            self.keys.insert(key_pair.public_key(), key_pair);
+        }
+
+        /// Adds `key_pair` to the signer.
+        pub fn with_key_pair(mut self, key_pair: DalekKeyPair) -> InMemorySigner {
+            self.add_key_pair(key_pair);
+            self
+        }
+
+        /// Returns true if the signer holds the key for `public_key`.
+        pub fn contains(&self, public_key: &PublicKey) -> bool {
+            self.keys.contains_key(public_key)
+        }
+
+        /// Returns the public keys held by the signer.
+        pub fn public_keys(&self) -> Vec<PublicKey> {
+            self.keys.keys().copied().collect()
+        }
+    }
+
+    impl TransactionSigner for InMemorySigner {
+        fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature> {
+            let key_pair = self.keys.get(public_key).ok_or(Error::UnknownSigner)?;
+            Ok(key_pair.as_ref().sign_decorated(hash))
+        }
+    }
+
+    impl TransactionSigner for DalekKeyPair {
+        fn sign_hash(&self, hash: &[u8], public_key: &PublicKey) -> Result<DecoratedSignature> {
+            self.as_ref().sign_hash(hash, public_key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InMemorySigner, MockSigner, TransactionSigner};
+    use crate::crypto::DalekKeyPair;
+    use crate::error::Error;
+
+    fn keypair() -> DalekKeyPair {
+        DalekKeyPair::from_secret_seed("SBPQUZ6G4FZNWFHKUWC5BEYWF6R52E3SEP7R3GWYSM2XTKGF5LNTWW4R")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_key_pair_signs_only_own_key() {
+        let kp = keypair();
+        let other = DalekKeyPair::random().unwrap();
+        let hash = [1u8; 32];
+        let signature = kp.sign_hash(&hash, &kp.public_key()).unwrap();
+        assert_eq!(signature, kp.as_ref().sign_decorated(&hash));
+        let res = kp.sign_hash(&hash, &other.public_key());
+        assert!(matches!(res, Err(Error::UnknownSigner)));
+    }
+
+    #[test]
+    fn test_in_memory_signer() {
+        let kp = keypair();
+        let pk = kp.public_key();
+        let expected = kp.as_ref().sign_decorated(&[2u8; 32]);
+        let signer = InMemorySigner::new().with_key_pair(kp);
+        assert!(signer.contains(&pk));
+        assert_eq!(expected, signer.sign_hash(&[2u8; 32], &pk).unwrap());
+
+        let other = DalekKeyPair::random().unwrap().public_key();
+        let res = signer.sign_hash(&[2u8; 32], &other);
+        assert!(matches!(res, Err(Error::UnknownSigner)));
+    }
+
+    #[test]
+    fn test_mock_signer_records_requests() {
+        let kp = keypair();
+        let pk = kp.public_key();
+        let signer = MockSigner::new(InMemorySigner::new().with_key_pair(kp));
+        signer.sign_hash(&[3u8; 32], &pk).unwrap();
+        let requests = signer.requests();
+        assert_eq!(1, requests.len());
+        assert_eq!(vec![3u8; 32], requests[0].hash);
+        assert_eq!(pk, requests[0].public_key);
+    }
+}
@@ -24,6 +24,9 @@ pub enum Error {
     /// Invalid signature.
     #[error("invalid signature")]
     InvalidSignature,
+    /// The signer doesn't hold the requested key.
+    #[error("unknown signer")]
+    UnknownSigner,
     /// Invalid signature hint.
     #[error("invalid signature hint")]
     InvalidSignatureHint,
@@ -72,6 +75,9 @@ pub enum Error {
     /// Invalid liquidity pool id length. Length must be 32 bytes.
     #[error("invalid liquidity pool id length")]
     InvalidLiquidityPoolIdLength,
+    /// Liquidity pool id is not hex encoded.
+    #[error("invalid liquidity pool id")]
+    InvalidLiquidityPoolId,
     /// Error that can occur when parsing amounts.
     #[error("error parsing amount")]
     ParseAmountError(#[from] rust_decimal::Error),
@@ -100,14 +106,98 @@ pub enum Error {
     #[error("transaction fee overflow")]
     TransactionFeeOverflow,
     /// Xdr conversion error
-    #[error("xdr conversion error")]
-    XdrError,
+    #[error("xdr conversion error: {0}")]
+    XdrError(#[from] stellar_xdr::curr::Error),
     /// Unsupported feature
     #[error("unsupported feature")]
     UnsupportedFeature,
     /// Invalid xdr claim predicate
     #[error("Invalid xdr claim predicate")]
     XdrClaimPredicateError,
+    /// Malformed or unsupported keystore.
+    #[error("invalid keystore")]
+    InvalidKeystore,
+    /// Keystore decryption failed, usually because of a wrong password.
+    #[error("keystore decryption failed")]
+    KeystoreDecryptionFailed,
+    /// Malformed txrep document.
+    #[error("invalid txrep: {0}")]
+    InvalidTxRep(String),
+    /// Malformed SEP-7 uri.
+    #[error("invalid sep-7 uri: {0}")]
+    InvalidSep7Uri(String),
+    /// Malformed history archive state document.
+    #[error("invalid history archive state: {0}")]
+    InvalidHistoryArchiveState(String),
+    /// Bucket content doesn't match its hash.
+    #[error("bucket hash mismatch: {0}")]
+    BucketHashMismatch(String),
+    /// Bucket list hash doesn't match the expected hash.
+    #[error("bucket list hash mismatch")]
+    BucketListHashMismatch,
+    /// Ledger header hash doesn't match the header.
+    #[error("ledger header hash mismatch for ledger {0}")]
+    LedgerHashMismatch(u32),
+    /// Ledger header doesn't follow the previous ledger header.
+    #[error("broken ledger chain at ledger {0}")]
+    BrokenLedgerChain(u32),
+    /// Transaction set doesn't match the ledger header.
+    #[error("transaction set hash mismatch for ledger {0}")]
+    TransactionSetHashMismatch(u32),
+    /// Transaction results don't match the ledger header.
+    #[error("transaction result set hash mismatch for ledger {0}")]
+    TransactionResultSetHashMismatch(u32),
+    /// Malformed ledger close meta.
+    #[error("invalid ledger close meta: {0}")]
+    InvalidLedgerCloseMeta(String),
+    /// Malformed offer, or offer for a different asset pair.
+    #[error("malformed offer: {0}")]
+    MalformedOffer(String),
+    /// Offer to update or delete doesn't exist.
+    #[error("offer {0} not found")]
+    OfferNotFound(i64),
+    /// Offer would cross an offer from the same seller.
+    #[error("offer crosses an offer from the same seller")]
+    OfferCrossSelf,
+    /// Fee bump transactions can't wrap a fee bump transaction.
+    #[error("invalid fee bump inner transaction")]
+    InvalidInnerTransaction,
+    /// Transaction is malformed.
+    #[error("invalid transaction: {0:?}")]
+    InvalidTransaction(Vec<crate::validation::ValidationError>),
+    /// Amount arithmetic overflowed or the result is out of range.
+    #[error("amount overflow")]
+    AmountOverflow,
+    /// Price with a zero or negative term.
+    #[error("invalid price")]
+    InvalidPrice,
+    /// Malformed token amount.
+    #[error("invalid token amount: {0}")]
+    InvalidTokenAmount(String),
+    /// Token amounts have different decimals.
+    #[error("token amounts have different decimals")]
+    TokenDecimalsMismatch,
+    /// Memo can't be converted to or from muxed account ids.
+    #[error("memo can't be converted to or from muxed account ids")]
+    InvalidMuxedMemo,
+    /// Transaction hash is not 32 bytes.
+    #[error("invalid transaction hash")]
+    InvalidTransactionHash,
+    /// Sequence number out of range.
+    #[error("invalid sequence number")]
+    InvalidSequenceNumber,
+    /// Transaction envelope doesn't match the pre authorized transaction hash.
+    #[error("transaction doesn't match the pre authorized transaction hash")]
+    PreAuthTxMismatch,
+    /// Sponsoring future reserves operation not paired, or nested in a way stellar-core rejects.
+    #[error("invalid sponsorship at operation {0}")]
+    InvalidSponsorship(usize),
+    /// Malformed asset string.
+    #[error("invalid asset: {0}")]
+    InvalidAsset(String),
+    /// I/O error
+    #[error("i/o error")]
+    IoError(#[from] std::io::Error),
     /// Base64 decode error
     #[error("base64 decode error")]
     Base64DecodeError(#[from] base64::DecodeError),
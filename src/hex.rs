@@ -0,0 +1,36 @@
+//! Hex encoding and decoding.
+
+/// Returns the lowercase hex encoding of `bytes`.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes the upper or lowercase hex encoded `s`.
+///
+/// Returns `None` if `s` has an odd length or a non hex character.
+pub(crate) fn decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        assert_eq!("00ff10ab", encode(&[0x00, 0xff, 0x10, 0xab]));
+        assert_eq!(Some(vec![0x00, 0xff, 0x10, 0xab]), decode("00FF10ab"));
+        assert_eq!(Some(vec![]), decode(""));
+        assert_eq!(None, decode("abc"));
+        assert_eq!(None, decode("zz"));
+        // Accepted by `u8::from_str_radix`, but not hex.
+        assert_eq!(None, decode("+f"));
+        assert_eq!(None, decode("éé"));
+    }
+}
@@ -0,0 +1,523 @@
+//! Streaming readers and writers for history archive files.
+//!
+//! History archive checkpoint files (`ledger-*.xdr.gz`, `transactions-*.xdr.gz`,
+//! `results-*.xdr.gz` and buckets) are gzipped streams of record marked xdr
+//! values. The readers in this module accept any `Read`: wrap the file in a gzip
+//! decoder (for example `flate2::read::GzDecoder`) before reading it.
+//!
+//! Records are decoded one at a time, so memory usage is bounded by the
+//! size of the largest record, which is itself bounded by the reader limits.
+use std::io::{ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+use crate::error::{Error, Result};
+use crate::transaction::TransactionEnvelope;
+use crate::transaction_result::TransactionResult;
+use crate::xdr::{self, ReadXdr, WriteXdr, DEFAULT_LIMITS};
+
+/// Bit set in the record mark of the last fragment of a record.
+const LAST_FRAGMENT_BIT: u32 = 0x8000_0000;
+
+/// Maximum length of a single record fragment.
+const MAX_FRAGMENT_LEN: u32 = 0x7fff_ffff;
+
+/// Reader for a stream of record marked xdr values of type `T`.
+///
+/// Iterating the reader yields the values in the stream. Iteration stops
+/// after the first error.
+#[derive(Debug)]
+pub struct RecordReader<R, T> {
+    inner: R,
+    limits: xdr::Limits,
+    done: bool,
+    _record: PhantomData<fn() -> T>,
+}
+
+/// Reader for `ledger-*.xdr` files.
+pub type LedgerHeaderHistoryReader<R> = RecordReader<R, xdr::LedgerHeaderHistoryEntry>;
+
+/// Reader for `transactions-*.xdr` files.
+pub type TransactionHistoryReader<R> = RecordReader<R, xdr::TransactionHistoryEntry>;
+
+/// Reader for `results-*.xdr` files.
+pub type TransactionHistoryResultReader<R> = RecordReader<R, xdr::TransactionHistoryResultEntry>;
+
+/// Reader for bucket files.
+pub type BucketReader<R> = RecordReader<R, xdr::BucketEntry>;
+
+/// Writer for a stream of record marked xdr values.
+#[derive(Debug)]
+pub struct RecordWriter<W> {
+    inner: W,
+    limits: xdr::Limits,
+}
+
+/// Transactions applied in a ledger, as stored in `transactions-*.xdr` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionHistoryEntry {
+    ledger_seq: u32,
+    transactions: Vec<TransactionEnvelope>,
+}
+
+/// Results of the transactions applied in a ledger, as stored in `results-*.xdr` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionHistoryResultEntry {
+    ledger_seq: u32,
+    results: Vec<TransactionResultPair>,
+}
+
+/// A transaction hash together with the transaction result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionResultPair {
+    transaction_hash: Vec<u8>,
+    result: TransactionResult,
+}
+
+impl<R, T> RecordReader<R, T>
+where
+    R: Read,
+    T: ReadXdr,
+{
+    /// Creates a new reader over `inner`, using the default xdr limits.
+    pub fn new(inner: R) -> RecordReader<R, T> {
+        RecordReader::with_limits(inner, DEFAULT_LIMITS)
+    }
+
+    /// Creates a new reader over `inner`.
+    ///
+    /// The length limit bounds the size of each record, the depth limit
+    /// bounds the nesting of each value.
+    pub fn with_limits(inner: R, limits: xdr::Limits) -> RecordReader<R, T> {
+        RecordReader {
+            inner,
+            limits,
+            done: false,
+            _record: PhantomData,
+        }
+    }
+
+    /// Retrieves the reader limits.
+    pub fn limits(&self) -> &xdr::Limits {
+        &self.limits
+    }
+
+    /// Retrieves a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Consumes the record reader, returning the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next value in the stream.
+    ///
+    /// Returns `None` if the stream ended on a record boundary.
+    pub fn read_next(&mut self) -> Result<Option<T>> {
+        match self.read_record()? {
+            None => Ok(None),
+            Some(bytes) => {
+                let value = T::from_xdr(bytes, self.limits.clone())?;
+                Ok(Some(value))
+            }
+        }
+    }
+
+    /// Reads the raw bytes of the next record in the stream.
+    pub fn read_record(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut record = Vec::new();
+        loop {
+            let mark = match self.read_mark()? {
+                None if record.is_empty() => return Ok(None),
+                None => return Err(unexpected_eof()),
+                Some(mark) => mark,
+            };
+            let len = (mark & MAX_FRAGMENT_LEN) as usize;
+            if record.len() + len > self.limits.len {
+                return Err(xdr::Error::LengthLimitExceeded.into());
+            }
+            let read = (&mut self.inner)
+                .take(len as u64)
+                .read_to_end(&mut record)?;
+            if read != len {
+                return Err(unexpected_eof());
+            }
+            if mark & LAST_FRAGMENT_BIT != 0 {
+                return Ok(Some(record));
+            }
+        }
+    }
+
+    fn read_mark(&mut self) -> Result<Option<u32>> {
+        let mut buf = [0u8; 4];
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.inner.read(&mut buf[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(unexpected_eof()),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(Some(u32::from_be_bytes(buf)))
+    }
+}
+
+impl<R, T> Iterator for RecordReader<R, T>
+where
+    R: Read,
+    T: ReadXdr,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_next() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<W> RecordWriter<W>
+where
+    W: Write,
+{
+    /// Creates a new writer over `inner`, without xdr limits.
+    ///
+    /// Records are still bounded by the maximum fragment length.
+    pub fn new(inner: W) -> RecordWriter<W> {
+        RecordWriter::with_limits(inner, xdr::Limits::none())
+    }
+
+    /// Creates a new writer over `inner`.
+    pub fn with_limits(inner: W, limits: xdr::Limits) -> RecordWriter<W> {
+        RecordWriter { inner, limits }
+    }
+
+    /// Writes `value` as a single record.
+    pub fn write_record<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: WriteXdr,
+    {
+        let bytes = value.to_xdr(self.limits.clone())?;
+        let len = u32::try_from(bytes.len())
+            .ok()
+            .filter(|len| *len <= MAX_FRAGMENT_LEN)
+            .ok_or(xdr::Error::LengthLimitExceeded)?;
+        self.inner
+            .write_all(&(len | LAST_FRAGMENT_BIT).to_be_bytes())?;
+        self.inner.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Flushes the inner writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Consumes the record writer, returning the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl TransactionHistoryEntry {
+    /// Retrieves the ledger sequence.
+    pub fn ledger_seq(&self) -> u32 {
+        self.ledger_seq
+    }
+
+    /// Retrieves the ledger transactions.
+    pub fn transactions(&self) -> &Vec<TransactionEnvelope> {
+        &self.transactions
+    }
+
+    /// Creates a `TransactionHistoryEntry` from xdr object.
+    ///
+    /// Transactions in the generalized transaction set are returned after
+    /// the ones in the legacy transaction set, in phase order.
+    pub fn from_xdr(x: &xdr::TransactionHistoryEntry) -> Result<TransactionHistoryEntry> {
+        let mut envelopes: Vec<&xdr::TransactionEnvelope> = x.tx_set.txs.iter().collect();
+        if let xdr::TransactionHistoryEntryExt::V1(ref tx_set) = x.ext {
+            envelopes.extend(generalized_transaction_set_envelopes(tx_set));
+        }
+        let transactions = envelopes
+            .into_iter()
+            .map(TransactionEnvelope::from_xdr)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(TransactionHistoryEntry {
+            ledger_seq: x.ledger_seq,
+            transactions,
+        })
+    }
+}
+
+impl TransactionHistoryResultEntry {
+    /// Retrieves the ledger sequence.
+    pub fn ledger_seq(&self) -> u32 {
+        self.ledger_seq
+    }
+
+    /// Retrieves the transaction results.
+    pub fn results(&self) -> &Vec<TransactionResultPair> {
+        &self.results
+    }
+
+    /// Creates a `TransactionHistoryResultEntry` from xdr object.
+    pub fn from_xdr(
+        x: &xdr::TransactionHistoryResultEntry,
+    ) -> Result<TransactionHistoryResultEntry> {
+        let results = x
+            .tx_result_set
+            .results
+            .iter()
+            .map(TransactionResultPair::from_xdr)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(TransactionHistoryResultEntry {
+            ledger_seq: x.ledger_seq,
+            results,
+        })
+    }
+}
+
+impl TransactionResultPair {
+    /// Retrieves the transaction hash.
+    pub fn transaction_hash(&self) -> &Vec<u8> {
+        &self.transaction_hash
+    }
+
+    /// Retrieves the transaction result.
+    pub fn result(&self) -> &TransactionResult {
+        &self.result
+    }
+
+    /// Creates a `TransactionResultPair` from xdr object.
+    pub fn from_xdr(x: &xdr::TransactionResultPair) -> Result<TransactionResultPair> {
+        let result = TransactionResult::from_xdr(&x.result)?;
+        Ok(TransactionResultPair {
+            transaction_hash: x.transaction_hash.0.to_vec(),
+            result,
+        })
+    }
+}
+
+/// Returns the envelopes of a generalized transaction set, in phase order.
+pub(crate) fn generalized_transaction_set_envelopes(
+    tx_set: &xdr::GeneralizedTransactionSet,
+) -> Vec<&xdr::TransactionEnvelope> {
+    let xdr::GeneralizedTransactionSet::V1(ref tx_set) = tx_set;
+    let mut envelopes = Vec::new();
+    for phase in tx_set.phases.iter() {
+        match phase {
+            xdr::TransactionPhase::V0(components) => {
+                for component in components.iter() {
+                    let xdr::TxSetComponent::TxsetCompTxsMaybeDiscountedFee(inner) = component;
+                    envelopes.extend(inner.txs.iter());
+                }
+            }
+            xdr::TransactionPhase::V1(parallel) => {
+                for stage in parallel.execution_stages.iter() {
+                    for cluster in stage.0.iter() {
+                        envelopes.extend(cluster.0.iter());
+                    }
+                }
+            }
+        }
+    }
+    envelopes
+}
+
+fn unexpected_eof() -> Error {
+    std::io::Error::from(ErrorKind::UnexpectedEof).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BucketReader, RecordReader, RecordWriter, TransactionHistoryEntry,
+        TransactionHistoryReader, TransactionHistoryResultEntry, TransactionHistoryResultReader,
+    };
+    use crate::amount::Stroops;
+    use crate::crypto::DalekKeyPair;
+    use crate::error::Error;
+    use crate::operations::Operation;
+    use crate::transaction::Transaction;
+    use crate::xdr;
+
+    fn envelope(sequence: i64) -> xdr::TransactionEnvelope {
+        let kp = DalekKeyPair::random().unwrap();
+        Transaction::builder(kp.public_key(), sequence, Stroops::new(100))
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap()
+            .into_envelope()
+            .to_xdr()
+            .unwrap()
+    }
+
+    fn history_entry(ledger_seq: u32) -> xdr::TransactionHistoryEntry {
+        xdr::TransactionHistoryEntry {
+            ledger_seq,
+            tx_set: xdr::TransactionSet {
+                previous_ledger_hash: xdr::Hash([0; 32]),
+                txs: vec![envelope(1)].try_into().unwrap(),
+            },
+            ext: xdr::TransactionHistoryEntryExt::V1(xdr::GeneralizedTransactionSet::V1(
+                xdr::TransactionSetV1 {
+                    previous_ledger_hash: xdr::Hash([0; 32]),
+                    phases: vec![
+                        xdr::TransactionPhase::V0(
+                            vec![xdr::TxSetComponent::TxsetCompTxsMaybeDiscountedFee(
+                                xdr::TxSetComponentTxsMaybeDiscountedFee {
+                                    base_fee: Some(100),
+                                    txs: vec![envelope(2)].try_into().unwrap(),
+                                },
+                            )]
+                            .try_into()
+                            .unwrap(),
+                        ),
+                        xdr::TransactionPhase::V1(xdr::ParallelTxsComponent {
+                            base_fee: None,
+                            execution_stages: vec![xdr::ParallelTxExecutionStage(
+                                vec![xdr::DependentTxCluster(
+                                    vec![envelope(3)].try_into().unwrap(),
+                                )]
+                                .try_into()
+                                .unwrap(),
+                            )]
+                            .try_into()
+                            .unwrap(),
+                        }),
+                    ]
+                    .try_into()
+                    .unwrap(),
+                },
+            )),
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_records() {
+        let entries = vec![history_entry(63), history_entry(64)];
+        let mut writer = RecordWriter::new(Vec::new());
+        for entry in &entries {
+            writer.write_record(entry).unwrap();
+        }
+        let bytes = writer.into_inner();
+
+        let reader = TransactionHistoryReader::new(bytes.as_slice());
+        let read = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(entries, read);
+    }
+
+    #[test]
+    fn test_transaction_history_entry_from_xdr() {
+        let entry = TransactionHistoryEntry::from_xdr(&history_entry(127)).unwrap();
+        assert_eq!(127, entry.ledger_seq());
+        let sequences: Vec<i64> = entry
+            .transactions()
+            .iter()
+            .map(|tx| *tx.as_transaction().unwrap().sequence())
+            .collect();
+        assert_eq!(vec![1, 2, 3], sequences);
+    }
+
+    #[test]
+    fn test_transaction_history_result_entry() {
+        let xdr_entry = xdr::TransactionHistoryResultEntry {
+            ledger_seq: 255,
+            tx_result_set: xdr::TransactionResultSet {
+                results: vec![xdr::TransactionResultPair {
+                    transaction_hash: xdr::Hash([7; 32]),
+                    result: xdr::TransactionResult {
+                        fee_charged: 100,
+                        result: xdr::TransactionResultResult::TxBadSeq,
+                        ext: xdr::TransactionResultExt::V0,
+                    },
+                }]
+                .try_into()
+                .unwrap(),
+            },
+            ext: xdr::TransactionHistoryResultEntryExt::V0,
+        };
+        let mut writer = RecordWriter::new(Vec::new());
+        writer.write_record(&xdr_entry).unwrap();
+        let bytes = writer.into_inner();
+
+        let mut reader = TransactionHistoryResultReader::new(bytes.as_slice());
+        let read = reader.read_next().unwrap().unwrap();
+        assert!(reader.read_next().unwrap().is_none());
+
+        let entry = TransactionHistoryResultEntry::from_xdr(&read).unwrap();
+        assert_eq!(255, entry.ledger_seq());
+        assert_eq!(1, entry.results().len());
+        assert_eq!(&vec![7; 32], entry.results()[0].transaction_hash());
+        assert!(entry.results()[0].result().is_bad_sequence());
+    }
+
+    #[test]
+    fn test_read_fragmented_record() {
+        let value = xdr::BucketEntry::Deadentry(xdr::LedgerKey::Account(xdr::LedgerKeyAccount {
+            account_id: xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256([3; 32]))),
+        }));
+        let bytes = xdr::WriteXdr::to_xdr(&value, xdr::Limits::none()).unwrap();
+        let (head, tail) = bytes.split_at(10);
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&(head.len() as u32).to_be_bytes());
+        stream.extend_from_slice(head);
+        stream.extend_from_slice(&(tail.len() as u32 | 0x8000_0000).to_be_bytes());
+        stream.extend_from_slice(tail);
+
+        let read = BucketReader::new(stream.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(vec![value], read);
+    }
+
+    #[test]
+    fn test_read_truncated_stream() {
+        let mut writer = RecordWriter::new(Vec::new());
+        writer.write_record(&history_entry(63)).unwrap();
+        let bytes = writer.into_inner();
+
+        let mut reader = TransactionHistoryReader::new(&bytes[..bytes.len() - 1]);
+        assert!(matches!(reader.next(), Some(Err(Error::IoError(_)))));
+        assert!(reader.next().is_none());
+
+        let mut reader = TransactionHistoryReader::new(&bytes[..2]);
+        assert!(matches!(reader.next(), Some(Err(Error::IoError(_)))));
+    }
+
+    #[test]
+    fn test_record_length_limit() {
+        let mut writer = RecordWriter::new(Vec::new());
+        writer.write_record(&history_entry(63)).unwrap();
+        let bytes = writer.into_inner();
+
+        let mut reader: RecordReader<_, xdr::TransactionHistoryEntry> =
+            RecordReader::with_limits(bytes.as_slice(), xdr::Limits::len(16));
+        let res = reader.read_next();
+        assert!(matches!(
+            res,
+            Err(Error::XdrError(xdr::Error::LengthLimitExceeded))
+        ));
+    }
+}
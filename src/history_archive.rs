@@ -0,0 +1,522 @@
+//! History archive state and layout.
+//!
+//! This module parses and writes History Archive State (HAS) documents,
+//! the `.well-known/stellar-history.json` and `history-*.json` files, and
+//! maps ledgers and buckets to their path in the archive.
+//!
+//! ```rust
+//! use stellar_base::history_archive;
+//!
+//! assert_eq!(127, history_archive::checkpoint_containing(100));
+//! assert_eq!(
+//!     "ledger/00/00/00/ledger-0000007f.xdr.gz",
+//!     history_archive::ledger_path(100)
+//! );
+//! ```
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// Number of ledgers in a checkpoint.
+pub const CHECKPOINT_FREQUENCY: u32 = 64;
+
+/// Path of the archive root HAS document.
+pub const ROOT_HAS_PATH: &str = ".well-known/stellar-history.json";
+
+const HASH_LEN: usize = 32;
+
+const EMPTY_BUCKET_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// History Archive State document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryArchiveState {
+    /// Document format version.
+    pub version: u32,
+    /// Version of the server that published the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Passphrase of the network, present from version 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_passphrase: Option<String>,
+    /// Last ledger of the checkpoint.
+    pub current_ledger: u32,
+    /// Levels of the live bucket list.
+    pub current_buckets: Vec<BucketLevel>,
+    /// Levels of the hot archive bucket list, present from protocol 23.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hot_archive_buckets: Option<Vec<BucketLevel>>,
+}
+
+/// A level of a bucket list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketLevel {
+    /// Hex encoded hash of the `curr` bucket.
+    pub curr: String,
+    /// Merge in progress on the level.
+    pub next: FutureBucket,
+    /// Hex encoded hash of the `snap` bucket.
+    pub snap: String,
+}
+
+/// State of a bucket merge.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FutureBucket {
+    /// Merge state: 0 for no merge, 1 if the output is known, 2 if only the inputs are known.
+    pub state: u32,
+    /// Hex encoded hash of the merge output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Hex encoded hash of the `curr` input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curr: Option<String>,
+    /// Hex encoded hash of the `snap` input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snap: Option<String>,
+    /// Hex encoded hashes of the shadow buckets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<Vec<String>>,
+}
+
+impl HistoryArchiveState {
+    /// Parses a HAS document from JSON.
+    pub fn from_json(json: &str) -> Result<HistoryArchiveState> {
+        let has: HistoryArchiveState = serde_json::from_str(json)
+            .map_err(|err| Error::InvalidHistoryArchiveState(err.to_string()))?;
+        has.check_format()?;
+        Ok(has)
+    }
+
+    /// Serializes the HAS document to JSON, indented like the documents written by stellar-core.
+    pub fn to_json(&self) -> Result<String> {
+        let mut out = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        self.serialize(&mut serializer)
+            .map_err(|err| Error::InvalidHistoryArchiveState(err.to_string()))?;
+        String::from_utf8(out).map_err(|err| Error::InvalidHistoryArchiveState(err.to_string()))
+    }
+
+    /// Returns the hex encoded hashes of the non empty buckets referenced by the document.
+    ///
+    /// These are the buckets needed to mirror the archive state, including the
+    /// outputs and inputs of merges in progress.
+    pub fn bucket_hashes(&self) -> Vec<String> {
+        let mut hashes: Vec<String> = Vec::new();
+        for level in self.levels() {
+            let next = &level.next;
+            let candidates = [&level.curr, &level.snap]
+                .into_iter()
+                .chain(next.output.iter())
+                .chain(next.curr.iter())
+                .chain(next.snap.iter())
+                .chain(next.shadow.iter().flatten());
+            for hash in candidates {
+                if !is_empty_bucket(hash) && !hashes.contains(hash) {
+                    hashes.push(hash.clone());
+                }
+            }
+        }
+        hashes
+    }
+
+    /// Returns the hash of the live bucket list.
+    pub fn live_bucket_list_hash(&self) -> Result<Vec<u8>> {
+        bucket_list_hash(&self.current_buckets)
+    }
+
+    /// Returns the hash of the hot archive bucket list, if present.
+    pub fn hot_archive_bucket_list_hash(&self) -> Result<Option<Vec<u8>>> {
+        self.hot_archive_buckets
+            .as_ref()
+            .map(|levels| bucket_list_hash(levels))
+            .transpose()
+    }
+
+    /// Returns the bucket list hash, as stored in the ledger header.
+    ///
+    /// If the document has a hot archive bucket list, the hash combines the
+    /// live and the hot archive bucket list hashes.
+    pub fn bucket_list_hash(&self) -> Result<Vec<u8>> {
+        let live = self.live_bucket_list_hash()?;
+        match self.hot_archive_bucket_list_hash()? {
+            None => Ok(live),
+            Some(hot_archive) => {
+                let mut hasher = Sha256::new();
+                hasher.update(&live);
+                hasher.update(&hot_archive);
+                Ok(hasher.finalize().to_vec())
+            }
+        }
+    }
+
+    /// Checks the content of the `curr` and `snap` buckets against their hash.
+    ///
+    /// `open` is called with the hex encoded hash of each non empty bucket and
+    /// must return a reader over the uncompressed bucket file.
+    pub fn verify_buckets<F, R>(&self, mut open: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<R>,
+        R: Read,
+    {
+        let mut verified: Vec<&String> = Vec::new();
+        for level in self.levels() {
+            for hash in [&level.curr, &level.snap] {
+                if is_empty_bucket(hash) || verified.contains(&hash) {
+                    continue;
+                }
+                let reader = open(hash)?;
+                if bucket_hash(reader)? != decode_hash(hash)? {
+                    return Err(Error::BucketHashMismatch(hash.clone()));
+                }
+                verified.push(hash);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the content of the buckets and that the bucket list hash is `expected`.
+    ///
+    /// `expected` is usually the `bucket_list_hash` of the checkpoint ledger header.
+    pub fn verify_bucket_list_hash<F, R>(&self, expected: &[u8], open: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<R>,
+        R: Read,
+    {
+        self.verify_buckets(open)?;
+        if self.bucket_list_hash()? != expected {
+            return Err(Error::BucketListHashMismatch);
+        }
+        Ok(())
+    }
+
+    fn levels(&self) -> impl Iterator<Item = &BucketLevel> {
+        self.current_buckets
+            .iter()
+            .chain(self.hot_archive_buckets.iter().flatten())
+    }
+
+    fn check_format(&self) -> Result<()> {
+        if self.version != 1 && self.version != 2 {
+            return Err(Error::InvalidHistoryArchiveState(format!(
+                "unsupported version {}",
+                self.version
+            )));
+        }
+        for level in self.levels() {
+            decode_hash(&level.curr)?;
+            decode_hash(&level.snap)?;
+            let next = &level.next;
+            let hashes = next
+                .output
+                .iter()
+                .chain(next.curr.iter())
+                .chain(next.snap.iter())
+                .chain(next.shadow.iter().flatten());
+            for hash in hashes {
+                decode_hash(hash)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the checkpoint ledger containing `ledger`.
+pub fn checkpoint_containing(ledger: u32) -> u32 {
+    ledger | (CHECKPOINT_FREQUENCY - 1)
+}
+
+/// Returns true if `ledger` is a checkpoint ledger.
+pub fn is_checkpoint(ledger: u32) -> bool {
+    checkpoint_containing(ledger) == ledger
+}
+
+/// Returns the first ledger in the checkpoint containing `ledger`.
+///
+/// The first checkpoint starts at the genesis ledger, 1.
+pub fn first_ledger_in_checkpoint(ledger: u32) -> u32 {
+    (ledger & !(CHECKPOINT_FREQUENCY - 1)).max(1)
+}
+
+/// Returns the checkpoint before the one containing `ledger`, if any.
+pub fn previous_checkpoint(ledger: u32) -> Option<u32> {
+    (ledger & !(CHECKPOINT_FREQUENCY - 1)).checked_sub(1)
+}
+
+/// Returns the checkpoint after the one containing `ledger`, if any.
+pub fn next_checkpoint(ledger: u32) -> Option<u32> {
+    checkpoint_containing(ledger).checked_add(CHECKPOINT_FREQUENCY)
+}
+
+/// Returns the checkpoints containing the ledgers from `first` to `last`, inclusive.
+pub fn checkpoint_range(first: u32, last: u32) -> impl Iterator<Item = u32> {
+    let start = checkpoint_containing(first);
+    let end = checkpoint_containing(last);
+    (start..=end).step_by(CHECKPOINT_FREQUENCY as usize)
+}
+
+/// Returns the path of the `category` file of the checkpoint containing `ledger`.
+///
+/// The path has the form `category/xx/yy/zz/category-xxyyzzww.ext`.
+pub fn checkpoint_path(category: &str, ledger: u32, extension: &str) -> String {
+    let hex = format!("{:08x}", checkpoint_containing(ledger));
+    format!(
+        "{}/{}/{}/{}/{}-{}.{}",
+        category,
+        &hex[0..2],
+        &hex[2..4],
+        &hex[4..6],
+        category,
+        hex,
+        extension
+    )
+}
+
+/// Returns the path of the HAS document of the checkpoint containing `ledger`.
+pub fn history_path(ledger: u32) -> String {
+    checkpoint_path("history", ledger, "json")
+}
+
+/// Returns the path of the ledger headers file of the checkpoint containing `ledger`.
+pub fn ledger_path(ledger: u32) -> String {
+    checkpoint_path("ledger", ledger, "xdr.gz")
+}
+
+/// Returns the path of the transactions file of the checkpoint containing `ledger`.
+pub fn transactions_path(ledger: u32) -> String {
+    checkpoint_path("transactions", ledger, "xdr.gz")
+}
+
+/// Returns the path of the results file of the checkpoint containing `ledger`.
+pub fn results_path(ledger: u32) -> String {
+    checkpoint_path("results", ledger, "xdr.gz")
+}
+
+/// Returns the path of the SCP messages file of the checkpoint containing `ledger`.
+pub fn scp_path(ledger: u32) -> String {
+    checkpoint_path("scp", ledger, "xdr.gz")
+}
+
+/// Returns the path of the bucket with the hex encoded `hash`.
+///
+/// The path has the form `bucket/aa/bb/cc/bucket-aabbcc....xdr.gz`.
+pub fn bucket_path(hash: &str) -> Result<String> {
+    decode_hash(hash)?;
+    let hash = hash.to_ascii_lowercase();
+    Ok(format!(
+        "bucket/{}/{}/{}/bucket-{}.xdr.gz",
+        &hash[0..2],
+        &hash[2..4],
+        &hash[4..6],
+        hash
+    ))
+}
+
+/// Computes the hash of the uncompressed bucket file read from `reader`.
+pub fn bucket_hash<R: Read>(mut reader: R) -> Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+fn bucket_list_hash(levels: &[BucketLevel]) -> Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    for level in levels {
+        let mut level_hasher = Sha256::new();
+        level_hasher.update(decode_hash(&level.curr)?);
+        level_hasher.update(decode_hash(&level.snap)?);
+        hasher.update(level_hasher.finalize());
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+fn is_empty_bucket(hash: &str) -> bool {
+    hash == EMPTY_BUCKET_HASH
+}
+
+fn decode_hash(hash: &str) -> Result<Vec<u8>> {
+    let invalid = || Error::InvalidHistoryArchiveState(format!("invalid hash {}", hash));
+    match crate::hex::decode(hash) {
+        Some(bytes) if bytes.len() == HASH_LEN => Ok(bytes),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    use crate::hex::encode as hex;
+
+    fn level(curr: &str, snap: &str) -> BucketLevel {
+        BucketLevel {
+            curr: curr.to_string(),
+            next: FutureBucket::default(),
+            snap: snap.to_string(),
+        }
+    }
+
+    const HAS_JSON: &str = r#"{
+    "version": 1,
+    "server": "v19.5.0",
+    "currentLedger": 127,
+    "currentBuckets": [
+        {
+            "curr": "5a5a0a8b0fb8e2e9b0d6b5ff1bd5d3f1cf8b4a4f5f1ad0a7e7fa4e57a4c3a1b2",
+            "next": {
+                "state": 0
+            },
+            "snap": "0000000000000000000000000000000000000000000000000000000000000000"
+        },
+        {
+            "curr": "0000000000000000000000000000000000000000000000000000000000000000",
+            "next": {
+                "state": 1,
+                "output": "1111111111111111111111111111111111111111111111111111111111111111"
+            },
+            "snap": "0000000000000000000000000000000000000000000000000000000000000000"
+        }
+    ]
+}"#;
+
+    #[test]
+    fn test_checkpoints() {
+        assert_eq!(63, checkpoint_containing(0));
+        assert_eq!(63, checkpoint_containing(1));
+        assert_eq!(63, checkpoint_containing(63));
+        assert_eq!(127, checkpoint_containing(64));
+        assert_eq!(u32::MAX, checkpoint_containing(u32::MAX));
+        assert!(is_checkpoint(63));
+        assert!(!is_checkpoint(64));
+        assert_eq!(1, first_ledger_in_checkpoint(10));
+        assert_eq!(64, first_ledger_in_checkpoint(127));
+        assert_eq!(None, previous_checkpoint(63));
+        assert_eq!(Some(63), previous_checkpoint(64));
+        assert_eq!(Some(191), next_checkpoint(100));
+        assert_eq!(None, next_checkpoint(u32::MAX));
+        assert_eq!(
+            vec![63, 127, 191],
+            checkpoint_range(5, 130).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(
+            "ledger/00/bc/61/ledger-00bc613f.xdr.gz",
+            ledger_path(12345663)
+        );
+        assert_eq!(
+            "transactions/00/bc/61/transactions-00bc613f.xdr.gz",
+            transactions_path(12345600)
+        );
+        assert_eq!(
+            "results/00/bc/61/results-00bc613f.xdr.gz",
+            results_path(12345663)
+        );
+        assert_eq!(
+            "history/00/bc/61/history-00bc613f.json",
+            history_path(12345663)
+        );
+        assert_eq!("scp/00/00/00/scp-0000003f.xdr.gz", scp_path(1));
+        let hash = "5A5A0A8B0FB8E2E9B0D6B5FF1BD5D3F1CF8B4A4F5F1AD0A7E7FA4E57A4C3A1B2";
+        assert_eq!(
+            "bucket/5a/5a/0a/bucket-5a5a0a8b0fb8e2e9b0d6b5ff1bd5d3f1cf8b4a4f5f1ad0a7e7fa4e57a4c3a1b2.xdr.gz",
+            bucket_path(hash).unwrap()
+        );
+        assert!(bucket_path("5a5a").is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let has = HistoryArchiveState::from_json(HAS_JSON).unwrap();
+        assert_eq!(1, has.version);
+        assert_eq!(127, has.current_ledger);
+        assert_eq!(2, has.current_buckets.len());
+        assert_eq!(
+            Some("1111111111111111111111111111111111111111111111111111111111111111"),
+            has.current_buckets[1].next.output.as_deref()
+        );
+        assert_eq!(HAS_JSON, has.to_json().unwrap());
+        assert_eq!(2, has.bucket_hashes().len());
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let res = HistoryArchiveState::from_json("{}");
+        assert!(matches!(res, Err(Error::InvalidHistoryArchiveState(_))));
+        let res = HistoryArchiveState::from_json(&HAS_JSON.replace("5a5a0a8b", "zz5a0a8b"));
+        assert!(matches!(res, Err(Error::InvalidHistoryArchiveState(_))));
+        let res =
+            HistoryArchiveState::from_json(&HAS_JSON.replace("\"version\": 1", "\"version\": 7"));
+        assert!(matches!(res, Err(Error::InvalidHistoryArchiveState(_))));
+    }
+
+    #[test]
+    fn test_verify_bucket_list_hash() {
+        let bucket0 = b"bucket zero".to_vec();
+        let bucket1 = b"bucket one".to_vec();
+        let hash0 = Sha256::digest(&bucket0).to_vec();
+        let hash1 = Sha256::digest(&bucket1).to_vec();
+        let has = HistoryArchiveState {
+            version: 2,
+            server: None,
+            network_passphrase: Some("Test SDF Network ; September 2015".to_string()),
+            current_ledger: 63,
+            current_buckets: vec![
+                level(&hex(&hash0), EMPTY_BUCKET_HASH),
+                level(&hex(&hash1), &hex(&hash0)),
+            ],
+            hot_archive_buckets: None,
+        };
+
+        let level0 = Sha256::digest([hash0.as_slice(), &[0u8; 32]].concat());
+        let level1 = Sha256::digest([hash1.as_slice(), &hash0].concat());
+        let expected = Sha256::digest([level0, level1].concat()).to_vec();
+        assert_eq!(expected, has.bucket_list_hash().unwrap());
+
+        let buckets = |hash: &str| -> crate::error::Result<&[u8]> {
+            if hash == hex(&hash0) {
+                Ok(&bucket0)
+            } else {
+                Ok(&bucket1)
+            }
+        };
+        has.verify_bucket_list_hash(&expected, buckets).unwrap();
+
+        let res = has.verify_bucket_list_hash(&[0; 32], buckets);
+        assert!(matches!(res, Err(Error::BucketListHashMismatch)));
+
+        let res = has.verify_buckets(|_| Ok(&bucket1[..]));
+        assert!(matches!(res, Err(Error::BucketHashMismatch(_))));
+    }
+
+    #[test]
+    fn test_hot_archive_bucket_list_hash() {
+        let mut has = HistoryArchiveState::from_json(HAS_JSON).unwrap();
+        let live = has.live_bucket_list_hash().unwrap();
+        assert_eq!(live, has.bucket_list_hash().unwrap());
+
+        has.hot_archive_buckets = Some(vec![level(EMPTY_BUCKET_HASH, EMPTY_BUCKET_HASH)]);
+        let hot = has.hot_archive_bucket_list_hash().unwrap().unwrap();
+        let expected = Sha256::digest([live, hot].concat()).to_vec();
+        assert_eq!(expected, has.bucket_list_hash().unwrap());
+
+        let json = has.to_json().unwrap();
+        assert!(json.contains("hotArchiveBuckets"));
+        assert_eq!(has, HistoryArchiveState::from_json(&json).unwrap());
+    }
+}
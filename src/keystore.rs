@@ -0,0 +1,436 @@
+//! Password encrypted secret key storage.
+//!
+//! A keystore is a versioned JSON document containing a secret seed
+//! encrypted with XChaCha20-Poly1305. The encryption key is derived from
+//! a password using scrypt.
+//!
+//! ```rust
+//! use stellar_base::crypto::DalekKeyPair;
+//! use stellar_base::keystore::Keystore;
+//!
+//! # fn run() -> stellar_base::error::Result<()> {
+//! let kp = DalekKeyPair::random()?;
+//! let keystore = Keystore::encrypt(&kp, b"correct horse battery staple")?;
+//! let json = keystore.to_json()?;
+//!
+//! let back = Keystore::from_json(&json)?.decrypt(b"correct horse battery staple")?;
+//! assert_eq!(kp.public_key(), back.public_key());
+//! # Ok(())
+//! # }
+//! ```
+use std::io::Write;
+use std::path::Path;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::crypto::{DalekKeyPair, PublicKey};
+use crate::error::{Error, Result};
+
+/// Current keystore format version.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const CIPHER_XCHACHA20_POLY1305: &str = "xchacha20-poly1305";
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+// Upper bounds of the scrypt cost, so that a crafted keystore can't make key
+// derivation exhaust memory or CPU. Scrypt uses `128 * r * 2^log_n` bytes of
+// memory and its running time grows with `p * r * 2^log_n`.
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_MEMORY: u64 = 256 * 1024 * 1024;
+const MAX_SCRYPT_P_R: u64 = 32;
+
+/// A password encrypted secret key.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    version: u32,
+    public_key: String,
+    crypto: KeystoreCrypto,
+}
+
+/// Encryption parameters and ciphertext of a keystore.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct KeystoreCrypto {
+    cipher: String,
+    #[serde(with = "base64_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "base64_bytes")]
+    ciphertext: Vec<u8>,
+    #[serde(flatten)]
+    kdf: Kdf,
+}
+
+/// Key derivation function used to derive the encryption key from the password.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kdf", content = "kdf_params", rename_all = "lowercase")]
+enum Kdf {
+    Scrypt(ScryptKdf),
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ScryptKdf {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    #[serde(with = "base64_bytes")]
+    salt: Vec<u8>,
+}
+
+/// Cost parameters for the scrypt key derivation function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    /// Log2 of the CPU/memory cost parameter `N`.
+    pub log_n: u8,
+    /// Block size.
+    pub r: u32,
+    /// Parallelization.
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// Returns the parameters recommended for interactive logins.
+    fn default() -> ScryptParams {
+        ScryptParams {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl Keystore {
+    /// Encrypts the secret key of `key_pair` with `password`, using the default scrypt parameters.
+    pub fn encrypt(key_pair: &DalekKeyPair, password: &[u8]) -> Result<Keystore> {
+        Self::encrypt_with_params(key_pair, password, ScryptParams::default())
+    }
+
+    /// Encrypts the secret key of `key_pair` with `password`, using `params` to derive the key.
+    ///
+    /// Returns `Error::InvalidKeystore` if `log_n` is above 20, if scrypt
+    /// needs more than 256 MiB of memory (`128 * r * 2^log_n` bytes) or if
+    /// `p * r` is above 32.
+    pub fn encrypt_with_params(
+        key_pair: &DalekKeyPair,
+        password: &[u8],
+        params: ScryptParams,
+    ) -> Result<Keystore> {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let kdf = ScryptKdf {
+            log_n: params.log_n,
+            r: params.r,
+            p: params.p,
+            salt,
+        };
+        let key = kdf.derive_key(password)?;
+        let public_key = key_pair.public_key();
+        let seed = key_pair.secret_key().seed_bytes();
+        let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: seed.as_ref(),
+                    aad: public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| Error::InvalidKeystore)?;
+
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            public_key: public_key.account_id(),
+            crypto: KeystoreCrypto {
+                cipher: CIPHER_XCHACHA20_POLY1305.to_string(),
+                nonce: nonce.to_vec(),
+                ciphertext,
+                kdf: Kdf::Scrypt(kdf),
+            },
+        })
+    }
+
+    /// Decrypts the secret key with `password`.
+    ///
+    /// Returns `Error::KeystoreDecryptionFailed` if the password is wrong.
+    pub fn decrypt(&self, password: &[u8]) -> Result<DalekKeyPair> {
+        self.check_format()?;
+        let public_key = self.public_key()?;
+        let nonce: [u8; NONCE_LEN] = self
+            .crypto
+            .nonce
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::InvalidKeystore)?;
+        let key = match &self.crypto.kdf {
+            Kdf::Scrypt(kdf) => kdf.derive_key(password)?,
+        };
+        let seed = XChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: &self.crypto.ciphertext,
+                    aad: public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| Error::KeystoreDecryptionFailed)?;
+        let seed = Zeroizing::new(seed);
+        let key_pair = DalekKeyPair::from_seed_bytes(&seed)?;
+        if key_pair.public_key() != public_key {
+            return Err(Error::InvalidKeystore);
+        }
+        Ok(key_pair)
+    }
+
+    /// Returns the keystore format version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the public key of the encrypted secret key.
+    pub fn public_key(&self) -> Result<PublicKey> {
+        PublicKey::from_account_id(&self.public_key)
+    }
+
+    /// Serializes the keystore to JSON.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|_| Error::InvalidKeystore)
+    }
+
+    /// Parses a keystore from JSON.
+    pub fn from_json(json: &str) -> Result<Keystore> {
+        let keystore: Keystore = serde_json::from_str(json).map_err(|_| Error::InvalidKeystore)?;
+        keystore.check_format()?;
+        Ok(keystore)
+    }
+
+    /// Loads a keystore from the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Keystore> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+
+    /// Saves the keystore to the file at `path`, replacing its content.
+    ///
+    /// On unix the file is only readable and writable by its owner.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = self.to_json()?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        // The mode only applies to new files, restrict existing files too.
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(json.as_bytes())?;
+        Ok(())
+    }
+
+    fn check_format(&self) -> Result<()> {
+        if self.version != KEYSTORE_VERSION
+            || self.crypto.cipher != CIPHER_XCHACHA20_POLY1305
+            || self.crypto.nonce.len() != NONCE_LEN
+        {
+            return Err(Error::InvalidKeystore);
+        }
+        match &self.crypto.kdf {
+            Kdf::Scrypt(kdf) => kdf.check_params(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keystore")
+            .field("version", &self.version)
+            .field("public_key", &self.public_key)
+            .field("crypto", &"<redacted>")
+            .finish()
+    }
+}
+
+impl ScryptKdf {
+    fn check_params(&self) -> Result<()> {
+        if self.log_n > MAX_SCRYPT_LOG_N {
+            return Err(Error::InvalidKeystore);
+        }
+        let memory = (128 * u64::from(self.r)) << self.log_n;
+        if memory > MAX_SCRYPT_MEMORY || u64::from(self.p) * u64::from(self.r) > MAX_SCRYPT_P_R {
+            return Err(Error::InvalidKeystore);
+        }
+        Ok(())
+    }
+
+    fn derive_key(&self, password: &[u8]) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+        self.check_params()?;
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, KEY_LEN)
+            .map_err(|_| Error::InvalidKeystore)?;
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        scrypt::scrypt(password, &self.salt, &params, key.as_mut())
+            .map_err(|_| Error::InvalidKeystore)?;
+        Ok(key)
+    }
+}
+
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(d)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keystore, ScryptParams};
+    use crate::crypto::DalekKeyPair;
+    use crate::error::Error;
+
+    const SECRET: &str = "SD7X7LEHBNMUIKQGKPARG5TDJNBHKC346OUARHGZL5ITC6IJPXHILY36";
+
+    fn fast_params() -> ScryptParams {
+        ScryptParams {
+            log_n: 4,
+            r: 8,
+            p: 1,
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        assert_eq!(kp.public_key(), keystore.public_key().unwrap());
+
+        let json = keystore.to_json().unwrap();
+        assert!(!json.contains(SECRET));
+        let back = Keystore::from_json(&json).unwrap();
+        assert_eq!(keystore, back);
+
+        let decrypted = back.decrypt(b"password").unwrap();
+        assert_eq!(
+            SECRET,
+            decrypted.secret_key().secret_seed_zeroizing().as_str()
+        );
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let res = keystore.decrypt(b"not the password");
+        assert!(matches!(res, Err(Error::KeystoreDecryptionFailed)));
+    }
+
+    #[test]
+    fn test_tampered_public_key() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let other = DalekKeyPair::random().unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let json = keystore.to_json().unwrap().replace(
+            &kp.public_key().account_id(),
+            &other.public_key().account_id(),
+        );
+        let tampered = Keystore::from_json(&json).unwrap();
+        assert!(tampered.decrypt(b"password").is_err());
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let json = keystore
+            .to_json()
+            .unwrap()
+            .replace("\"version\": 1", "\"version\": 2");
+        let res = Keystore::from_json(&json);
+        assert!(matches!(res, Err(Error::InvalidKeystore)));
+    }
+
+    #[test]
+    fn test_scrypt_params_out_of_bounds() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let json = keystore.to_json().unwrap();
+        for (from, to) in [("\"log_n\": 4", "\"log_n\": 40"), ("\"p\": 1", "\"p\": 5")] {
+            assert!(json.contains(from));
+            let res = Keystore::from_json(&json.replace(from, to));
+            assert!(matches!(res, Err(Error::InvalidKeystore)));
+        }
+
+        let params = ScryptParams {
+            log_n: 21,
+            ..fast_params()
+        };
+        let res = Keystore::encrypt_with_params(&kp, b"password", params);
+        assert!(matches!(res, Err(Error::InvalidKeystore)));
+    }
+
+    #[test]
+    fn test_scrypt_cost_out_of_bounds() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let json = keystore.to_json().unwrap();
+        // Each parameter is small, but together they need 4 GiB of memory.
+        let json = json
+            .replace("\"log_n\": 4", "\"log_n\": 20")
+            .replace("\"r\": 8", "\"r\": 32");
+        let res = Keystore::from_json(&json);
+        assert!(matches!(res, Err(Error::InvalidKeystore)));
+
+        // The default parameters need 128 MiB.
+        let params = ScryptParams::default();
+        let kdf = super::ScryptKdf {
+            log_n: params.log_n,
+            r: params.r,
+            p: params.p,
+            salt: Vec::new(),
+        };
+        kdf.check_params().unwrap();
+        let kdf = super::ScryptKdf { log_n: 19, ..kdf };
+        assert!(matches!(kdf.check_params(), Err(Error::InvalidKeystore)));
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let debug = format!("{:?}", keystore);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("ciphertext"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let kp = DalekKeyPair::from_secret_seed(SECRET).unwrap();
+        let keystore = Keystore::encrypt_with_params(&kp, b"password", fast_params()).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "stellar-base-keystore-{}.json",
+            kp.public_key().account_id()
+        ));
+        keystore.save(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+        let loaded = Keystore::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(keystore, loaded);
+    }
+}
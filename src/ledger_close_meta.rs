@@ -0,0 +1,672 @@
+//! Ledger close meta decoding.
+//!
+//! `LedgerCloseMeta` is emitted by captive-core and by the ledger exporter
+//! for each closed ledger. It contains the ledger header, the transaction
+//! set and the changes made by each transaction, in application order.
+use std::collections::HashMap;
+
+use crate::amount::Stroops;
+use crate::error::{Error, Result};
+use crate::history::generalized_transaction_set_envelopes;
+use crate::ledger_header::LedgerHeader;
+use crate::network::Network;
+use crate::transaction::TransactionEnvelope;
+use crate::transaction_result::TransactionResult;
+use crate::xdr;
+
+/// Meta of a closed ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerCloseMeta {
+    inner: xdr::LedgerCloseMeta,
+}
+
+/// A transaction applied in a ledger, together with its result and meta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerTransaction {
+    application_order: u32,
+    hash: Vec<u8>,
+    envelope: TransactionEnvelope,
+    result: TransactionResult,
+    fee_charged: Stroops,
+    fee_changes: Vec<xdr::LedgerEntryChange>,
+    changes_before: Vec<xdr::LedgerEntryChange>,
+    operations: Vec<LedgerOperation>,
+    changes_after: Vec<xdr::LedgerEntryChange>,
+    post_apply_fee_changes: Vec<xdr::LedgerEntryChange>,
+    events: Vec<xdr::TransactionEvent>,
+    diagnostic_events: Vec<xdr::DiagnosticEvent>,
+    return_value: Option<xdr::ScVal>,
+}
+
+/// Ledger changes and events of an operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LedgerOperation {
+    changes: Vec<xdr::LedgerEntryChange>,
+    events: Vec<xdr::ContractEvent>,
+}
+
+/// Iterator over the transactions of a `LedgerCloseMeta`, in application order.
+#[derive(Debug)]
+pub struct LedgerTransactions<'a> {
+    envelopes: HashMap<Vec<u8>, TransactionEnvelope>,
+    processing: TransactionProcessing<'a>,
+    application_order: u32,
+}
+
+#[derive(Debug)]
+enum TransactionProcessing<'a> {
+    V0(std::slice::Iter<'a, xdr::TransactionResultMeta>),
+    V1(std::slice::Iter<'a, xdr::TransactionResultMetaV1>),
+}
+
+impl LedgerCloseMeta {
+    /// Retrieves the ledger header entry.
+    pub fn ledger_header(&self) -> &xdr::LedgerHeaderHistoryEntry {
+        match self.inner {
+            xdr::LedgerCloseMeta::V0(ref meta) => &meta.ledger_header,
+            xdr::LedgerCloseMeta::V1(ref meta) => &meta.ledger_header,
+            xdr::LedgerCloseMeta::V2(ref meta) => &meta.ledger_header,
+        }
+    }
+
+    /// Returns the typed ledger header.
+    pub fn header(&self) -> Result<LedgerHeader> {
+        LedgerHeader::from_xdr(&self.ledger_header().header)
+    }
+
+    /// Retrieves the ledger sequence.
+    pub fn ledger_seq(&self) -> u32 {
+        self.ledger_header().header.ledger_seq
+    }
+
+    /// Retrieves the ledger hash.
+    pub fn ledger_hash(&self) -> &[u8] {
+        &self.ledger_header().hash.0
+    }
+
+    /// Retrieves the protocol upgrades applied in the ledger and their changes.
+    pub fn upgrades(&self) -> &[xdr::UpgradeEntryMeta] {
+        match self.inner {
+            xdr::LedgerCloseMeta::V0(ref meta) => &meta.upgrades_processing,
+            xdr::LedgerCloseMeta::V1(ref meta) => &meta.upgrades_processing,
+            xdr::LedgerCloseMeta::V2(ref meta) => &meta.upgrades_processing,
+        }
+    }
+
+    /// Retrieves the keys of the entries evicted from the live state in the ledger.
+    pub fn evicted_keys(&self) -> &[xdr::LedgerKey] {
+        match self.inner {
+            xdr::LedgerCloseMeta::V0(_) => &[],
+            xdr::LedgerCloseMeta::V1(ref meta) => &meta.evicted_keys,
+            xdr::LedgerCloseMeta::V2(ref meta) => &meta.evicted_keys,
+        }
+    }
+
+    /// Retrieves the size of the live Soroban state, if known.
+    pub fn total_byte_size_of_live_soroban_state(&self) -> Option<u64> {
+        match self.inner {
+            xdr::LedgerCloseMeta::V0(_) => None,
+            xdr::LedgerCloseMeta::V1(ref meta) => Some(meta.total_byte_size_of_live_soroban_state),
+            xdr::LedgerCloseMeta::V2(ref meta) => Some(meta.total_byte_size_of_live_soroban_state),
+        }
+    }
+
+    /// Returns the number of transactions applied in the ledger.
+    pub fn transaction_count(&self) -> usize {
+        match self.inner {
+            xdr::LedgerCloseMeta::V0(ref meta) => meta.tx_processing.len(),
+            xdr::LedgerCloseMeta::V1(ref meta) => meta.tx_processing.len(),
+            xdr::LedgerCloseMeta::V2(ref meta) => meta.tx_processing.len(),
+        }
+    }
+
+    /// Returns the transactions applied in the ledger, in application order.
+    ///
+    /// Envelopes are paired with their result by hash, so `network` must be
+    /// the network that closed the ledger.
+    pub fn transactions(&self, network: &Network) -> Result<LedgerTransactions<'_>> {
+        let xdr_envelopes = match self.inner {
+            xdr::LedgerCloseMeta::V0(ref meta) => meta.tx_set.txs.iter().collect(),
+            xdr::LedgerCloseMeta::V1(ref meta) => {
+                generalized_transaction_set_envelopes(&meta.tx_set)
+            }
+            xdr::LedgerCloseMeta::V2(ref meta) => {
+                generalized_transaction_set_envelopes(&meta.tx_set)
+            }
+        };
+        let mut envelopes = HashMap::with_capacity(xdr_envelopes.len());
+        for xdr_envelope in xdr_envelopes {
+            let envelope = TransactionEnvelope::from_xdr(xdr_envelope)?;
+            envelopes.insert(envelope.hash(network)?, envelope);
+        }
+        let processing = match self.inner {
+            xdr::LedgerCloseMeta::V0(ref meta) => {
+                TransactionProcessing::V0(meta.tx_processing.iter())
+            }
+            xdr::LedgerCloseMeta::V1(ref meta) => {
+                TransactionProcessing::V0(meta.tx_processing.iter())
+            }
+            xdr::LedgerCloseMeta::V2(ref meta) => {
+                TransactionProcessing::V1(meta.tx_processing.iter())
+            }
+        };
+        Ok(LedgerTransactions {
+            envelopes,
+            processing,
+            application_order: 0,
+        })
+    }
+
+    /// Retrieves the underlying xdr object.
+    pub fn as_xdr(&self) -> &xdr::LedgerCloseMeta {
+        &self.inner
+    }
+
+    /// Returns the xdr object.
+    pub fn to_xdr(&self) -> xdr::LedgerCloseMeta {
+        self.inner.clone()
+    }
+
+    /// Creates `LedgerCloseMeta` from xdr object.
+    pub fn from_xdr(x: &xdr::LedgerCloseMeta) -> Result<LedgerCloseMeta> {
+        Ok(LedgerCloseMeta { inner: x.clone() })
+    }
+}
+
+impl LedgerTransaction {
+    /// Retrieves the position of the transaction in the ledger application order, starting from 1.
+    pub fn application_order(&self) -> u32 {
+        self.application_order
+    }
+
+    /// Retrieves the transaction hash.
+    pub fn hash(&self) -> &Vec<u8> {
+        &self.hash
+    }
+
+    /// Retrieves the transaction envelope.
+    pub fn envelope(&self) -> &TransactionEnvelope {
+        &self.envelope
+    }
+
+    /// Retrieves the transaction result.
+    pub fn result(&self) -> &TransactionResult {
+        &self.result
+    }
+
+    /// Retrieves the fee charged to the transaction source account.
+    pub fn fee_charged(&self) -> &Stroops {
+        &self.fee_charged
+    }
+
+    /// Returns true if the transaction was successful.
+    pub fn is_successful(&self) -> bool {
+        self.result.is_success() || self.result.is_fee_bump_success()
+    }
+
+    /// Retrieves the ledger changes made when charging the fee.
+    pub fn fee_changes(&self) -> &Vec<xdr::LedgerEntryChange> {
+        &self.fee_changes
+    }
+
+    /// Retrieves the ledger changes made before applying the operations.
+    pub fn changes_before(&self) -> &Vec<xdr::LedgerEntryChange> {
+        &self.changes_before
+    }
+
+    /// Retrieves the operations changes and events.
+    pub fn operations(&self) -> &Vec<LedgerOperation> {
+        &self.operations
+    }
+
+    /// Retrieves the ledger changes made after applying the operations.
+    pub fn changes_after(&self) -> &Vec<xdr::LedgerEntryChange> {
+        &self.changes_after
+    }
+
+    /// Retrieves the ledger changes made when refunding the fee after applying the transaction.
+    pub fn post_apply_fee_changes(&self) -> &Vec<xdr::LedgerEntryChange> {
+        &self.post_apply_fee_changes
+    }
+
+    /// Retrieves the transaction level events.
+    pub fn events(&self) -> &Vec<xdr::TransactionEvent> {
+        &self.events
+    }
+
+    /// Retrieves the diagnostic events.
+    pub fn diagnostic_events(&self) -> &Vec<xdr::DiagnosticEvent> {
+        &self.diagnostic_events
+    }
+
+    /// Retrieves the return value of the Soroban invocation, if any.
+    pub fn return_value(&self) -> Option<&xdr::ScVal> {
+        self.return_value.as_ref()
+    }
+}
+
+impl LedgerOperation {
+    /// Retrieves the ledger changes made by the operation.
+    pub fn changes(&self) -> &Vec<xdr::LedgerEntryChange> {
+        &self.changes
+    }
+
+    /// Retrieves the contract events emitted by the operation.
+    pub fn events(&self) -> &Vec<xdr::ContractEvent> {
+        &self.events
+    }
+}
+
+impl LedgerTransactions<'_> {
+    fn build(
+        &mut self,
+        result: &xdr::TransactionResultPair,
+        fee_processing: &xdr::LedgerEntryChanges,
+        meta: &xdr::TransactionMeta,
+        post_apply_fee_processing: Option<&xdr::LedgerEntryChanges>,
+    ) -> Result<LedgerTransaction> {
+        self.application_order += 1;
+        let hash = result.transaction_hash.0.to_vec();
+        let envelope = self.envelopes.remove(&hash).ok_or_else(|| {
+            Error::InvalidLedgerCloseMeta(format!(
+                "missing envelope for transaction {}",
+                self.application_order
+            ))
+        })?;
+        let mut tx = LedgerTransaction {
+            application_order: self.application_order,
+            hash,
+            envelope,
+            result: TransactionResult::from_xdr(&result.result)?,
+            fee_charged: Stroops::new(result.result.fee_charged),
+            fee_changes: fee_processing.0.to_vec(),
+            changes_before: Vec::new(),
+            operations: Vec::new(),
+            changes_after: Vec::new(),
+            post_apply_fee_changes: post_apply_fee_processing
+                .map(|changes| changes.0.to_vec())
+                .unwrap_or_default(),
+            events: Vec::new(),
+            diagnostic_events: Vec::new(),
+            return_value: None,
+        };
+        match meta {
+            xdr::TransactionMeta::V0(operations) => {
+                tx.operations = operations.iter().map(LedgerOperation::from_v0).collect();
+            }
+            xdr::TransactionMeta::V1(meta) => {
+                tx.changes_before = meta.tx_changes.0.to_vec();
+                tx.operations = meta
+                    .operations
+                    .iter()
+                    .map(LedgerOperation::from_v0)
+                    .collect();
+            }
+            xdr::TransactionMeta::V2(meta) => {
+                tx.changes_before = meta.tx_changes_before.0.to_vec();
+                tx.operations = meta
+                    .operations
+                    .iter()
+                    .map(LedgerOperation::from_v0)
+                    .collect();
+                tx.changes_after = meta.tx_changes_after.0.to_vec();
+            }
+            xdr::TransactionMeta::V3(meta) => {
+                tx.changes_before = meta.tx_changes_before.0.to_vec();
+                tx.operations = meta
+                    .operations
+                    .iter()
+                    .map(LedgerOperation::from_v0)
+                    .collect();
+                tx.changes_after = meta.tx_changes_after.0.to_vec();
+                if let Some(ref soroban_meta) = meta.soroban_meta {
+                    // Soroban transactions have a single operation, which emitted the events.
+                    if let Some(operation) = tx.operations.first_mut() {
+                        operation.events = soroban_meta.events.to_vec();
+                    }
+                    tx.diagnostic_events = soroban_meta.diagnostic_events.to_vec();
+                    tx.return_value = Some(soroban_meta.return_value.clone());
+                }
+            }
+            xdr::TransactionMeta::V4(meta) => {
+                tx.changes_before = meta.tx_changes_before.0.to_vec();
+                tx.operations = meta
+                    .operations
+                    .iter()
+                    .map(LedgerOperation::from_v2)
+                    .collect();
+                tx.changes_after = meta.tx_changes_after.0.to_vec();
+                tx.events = meta.events.to_vec();
+                tx.diagnostic_events = meta.diagnostic_events.to_vec();
+                tx.return_value = meta
+                    .soroban_meta
+                    .as_ref()
+                    .and_then(|soroban_meta| soroban_meta.return_value.clone());
+            }
+        }
+        Ok(tx)
+    }
+}
+
+impl Iterator for LedgerTransactions<'_> {
+    type Item = Result<LedgerTransaction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.processing {
+            TransactionProcessing::V0(ref mut iter) => {
+                let meta = iter.next()?;
+                Some(self.build(
+                    &meta.result,
+                    &meta.fee_processing,
+                    &meta.tx_apply_processing,
+                    None,
+                ))
+            }
+            TransactionProcessing::V1(ref mut iter) => {
+                let meta = iter.next()?;
+                Some(self.build(
+                    &meta.result,
+                    &meta.fee_processing,
+                    &meta.tx_apply_processing,
+                    Some(&meta.post_tx_apply_fee_processing),
+                ))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.processing {
+            TransactionProcessing::V0(ref iter) => iter.size_hint(),
+            TransactionProcessing::V1(ref iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl LedgerOperation {
+    fn from_v0(meta: &xdr::OperationMeta) -> LedgerOperation {
+        LedgerOperation {
+            changes: meta.changes.0.to_vec(),
+            events: Vec::new(),
+        }
+    }
+
+    fn from_v2(meta: &xdr::OperationMetaV2) -> LedgerOperation {
+        LedgerOperation {
+            changes: meta.changes.0.to_vec(),
+            events: meta.events.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LedgerCloseMeta;
+    use crate::amount::Stroops;
+    use crate::crypto::DalekKeyPair;
+    use crate::error::Error;
+    use crate::network::Network;
+    use crate::operations::Operation;
+    use crate::transaction::Transaction;
+    use crate::xdr;
+
+    fn envelope(sequence: i64) -> (xdr::TransactionEnvelope, Vec<u8>) {
+        let kp = DalekKeyPair::random().unwrap();
+        let tx = Transaction::builder(kp.public_key(), sequence, Stroops::new(100))
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap();
+        let hash = tx.hash(&Network::new_test()).unwrap();
+        (tx.into_envelope().to_xdr().unwrap(), hash)
+    }
+
+    fn result_pair(hash: &[u8], fee_charged: i64) -> xdr::TransactionResultPair {
+        xdr::TransactionResultPair {
+            transaction_hash: xdr::Hash(hash.try_into().unwrap()),
+            result: xdr::TransactionResult {
+                fee_charged,
+                result: xdr::TransactionResultResult::TxBadSeq,
+                ext: xdr::TransactionResultExt::V0,
+            },
+        }
+    }
+
+    fn change(balance: i64) -> xdr::LedgerEntryChanges {
+        let entry = xdr::LedgerEntry {
+            last_modified_ledger_seq: 100,
+            data: xdr::LedgerEntryData::Account(xdr::AccountEntry {
+                balance,
+                ..Default::default()
+            }),
+            ext: xdr::LedgerEntryExt::V0,
+        };
+        xdr::LedgerEntryChanges(
+            vec![xdr::LedgerEntryChange::Updated(entry)]
+                .try_into()
+                .unwrap(),
+        )
+    }
+
+    fn event(value: u32) -> xdr::ContractEvent {
+        xdr::ContractEvent {
+            ext: xdr::ExtensionPoint::V0,
+            contract_id: None,
+            type_: xdr::ContractEventType::Contract,
+            body: xdr::ContractEventBody::V0(xdr::ContractEventV0 {
+                topics: Default::default(),
+                data: xdr::ScVal::U32(value),
+            }),
+        }
+    }
+
+    fn generalized_tx_set(txs: Vec<xdr::TransactionEnvelope>) -> xdr::GeneralizedTransactionSet {
+        let (classic, soroban) = txs.split_at(1);
+        xdr::GeneralizedTransactionSet::V1(xdr::TransactionSetV1 {
+            previous_ledger_hash: xdr::Hash([0; 32]),
+            phases: vec![
+                xdr::TransactionPhase::V0(
+                    vec![xdr::TxSetComponent::TxsetCompTxsMaybeDiscountedFee(
+                        xdr::TxSetComponentTxsMaybeDiscountedFee {
+                            base_fee: None,
+                            txs: classic.to_vec().try_into().unwrap(),
+                        },
+                    )]
+                    .try_into()
+                    .unwrap(),
+                ),
+                xdr::TransactionPhase::V1(xdr::ParallelTxsComponent {
+                    base_fee: Some(100),
+                    execution_stages: vec![xdr::ParallelTxExecutionStage(
+                        vec![xdr::DependentTxCluster(
+                            soroban.to_vec().try_into().unwrap(),
+                        )]
+                        .try_into()
+                        .unwrap(),
+                    )]
+                    .try_into()
+                    .unwrap(),
+                }),
+            ]
+            .try_into()
+            .unwrap(),
+        })
+    }
+
+    #[test]
+    fn test_v0_transactions() {
+        let (env0, hash0) = envelope(1);
+        let meta = xdr::LedgerCloseMeta::V0(xdr::LedgerCloseMetaV0 {
+            tx_set: xdr::TransactionSet {
+                previous_ledger_hash: xdr::Hash([0; 32]),
+                txs: vec![env0].try_into().unwrap(),
+            },
+            tx_processing: vec![xdr::TransactionResultMeta {
+                result: result_pair(&hash0, 100),
+                fee_processing: change(900),
+                tx_apply_processing: xdr::TransactionMeta::V2(xdr::TransactionMetaV2 {
+                    tx_changes_before: change(800),
+                    operations: vec![xdr::OperationMeta {
+                        changes: change(700),
+                    }]
+                    .try_into()
+                    .unwrap(),
+                    tx_changes_after: Default::default(),
+                }),
+            }]
+            .try_into()
+            .unwrap(),
+            ..Default::default()
+        });
+        let meta = LedgerCloseMeta::from_xdr(&meta).unwrap();
+        assert!(meta.evicted_keys().is_empty());
+        assert_eq!(None, meta.total_byte_size_of_live_soroban_state());
+        let txs = meta
+            .transactions(&Network::new_test())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(1, txs.len());
+        let tx = &txs[0];
+        assert_eq!(1, tx.application_order());
+        assert_eq!(&hash0, tx.hash());
+        assert_eq!(&Stroops::new(100), tx.fee_charged());
+        assert!(!tx.is_successful());
+        assert!(tx.result().is_bad_sequence());
+        assert_eq!(1, *tx.envelope().as_transaction().unwrap().sequence());
+        assert_eq!(1, tx.fee_changes().len());
+        assert_eq!(1, tx.changes_before().len());
+        assert_eq!(1, tx.operations().len());
+        assert_eq!(1, tx.operations()[0].changes().len());
+        assert!(tx.changes_after().is_empty());
+    }
+
+    #[test]
+    fn test_v1_transactions_in_application_order() {
+        let (env0, hash0) = envelope(1);
+        let (env1, hash1) = envelope(2);
+        let soroban_meta = xdr::SorobanTransactionMeta {
+            ext: xdr::SorobanTransactionMetaExt::V0,
+            events: vec![event(7)].try_into().unwrap(),
+            return_value: xdr::ScVal::Bool(true),
+            diagnostic_events: Default::default(),
+        };
+        let meta = xdr::LedgerCloseMeta::V1(xdr::LedgerCloseMetaV1 {
+            tx_set: generalized_tx_set(vec![env0, env1]),
+            tx_processing: vec![
+                xdr::TransactionResultMeta {
+                    result: result_pair(&hash1, 200),
+                    fee_processing: Default::default(),
+                    tx_apply_processing: xdr::TransactionMeta::V3(xdr::TransactionMetaV3 {
+                        operations: vec![xdr::OperationMeta::default()].try_into().unwrap(),
+                        soroban_meta: Some(soroban_meta),
+                        ..Default::default()
+                    }),
+                },
+                xdr::TransactionResultMeta {
+                    result: result_pair(&hash0, 100),
+                    fee_processing: Default::default(),
+                    tx_apply_processing: xdr::TransactionMeta::V0(Default::default()),
+                },
+            ]
+            .try_into()
+            .unwrap(),
+            total_byte_size_of_live_soroban_state: 1024,
+            evicted_keys: vec![xdr::LedgerKey::Ttl(xdr::LedgerKeyTtl {
+                key_hash: xdr::Hash([1; 32]),
+            })]
+            .try_into()
+            .unwrap(),
+            ..Default::default()
+        });
+        let meta = LedgerCloseMeta::from_xdr(&meta).unwrap();
+        assert_eq!(1, meta.evicted_keys().len());
+        assert_eq!(Some(1024), meta.total_byte_size_of_live_soroban_state());
+        assert_eq!(2, meta.transaction_count());
+        let txs = meta
+            .transactions(&Network::new_test())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(&hash1, txs[0].hash());
+        assert_eq!(1, txs[0].application_order());
+        assert_eq!(&Stroops::new(200), txs[0].fee_charged());
+        assert_eq!(vec![event(7)], *txs[0].operations()[0].events());
+        assert_eq!(Some(&xdr::ScVal::Bool(true)), txs[0].return_value());
+        assert_eq!(&hash0, txs[1].hash());
+        assert_eq!(2, txs[1].application_order());
+        assert_eq!(None, txs[1].return_value());
+    }
+
+    #[test]
+    fn test_v2_transactions() {
+        let (env0, hash0) = envelope(1);
+        let (env1, _) = envelope(2);
+        let tx_event = xdr::TransactionEvent {
+            stage: xdr::TransactionEventStage::BeforeAllTxs,
+            event: event(1),
+        };
+        let meta = xdr::LedgerCloseMeta::V2(xdr::LedgerCloseMetaV2 {
+            tx_set: generalized_tx_set(vec![env0, env1]),
+            tx_processing: vec![xdr::TransactionResultMetaV1 {
+                ext: xdr::ExtensionPoint::V0,
+                result: result_pair(&hash0, 100),
+                fee_processing: change(900),
+                tx_apply_processing: xdr::TransactionMeta::V4(xdr::TransactionMetaV4 {
+                    operations: vec![xdr::OperationMetaV2 {
+                        ext: xdr::ExtensionPoint::V0,
+                        changes: change(800),
+                        events: vec![event(2)].try_into().unwrap(),
+                    }]
+                    .try_into()
+                    .unwrap(),
+                    tx_changes_after: change(700),
+                    soroban_meta: Some(xdr::SorobanTransactionMetaV2 {
+                        ext: xdr::SorobanTransactionMetaExt::V0,
+                        return_value: Some(xdr::ScVal::Void),
+                    }),
+                    events: vec![tx_event.clone()].try_into().unwrap(),
+                    ..Default::default()
+                }),
+                post_tx_apply_fee_processing: change(950),
+            }]
+            .try_into()
+            .unwrap(),
+            ..Default::default()
+        });
+        let meta = LedgerCloseMeta::from_xdr(&meta).unwrap();
+        let txs = meta
+            .transactions(&Network::new_test())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(1, txs.len());
+        let tx = &txs[0];
+        assert_eq!(vec![event(2)], *tx.operations()[0].events());
+        assert_eq!(1, tx.operations()[0].changes().len());
+        assert_eq!(1, tx.changes_after().len());
+        assert_eq!(1, tx.post_apply_fee_changes().len());
+        assert_eq!(vec![tx_event], *tx.events());
+        assert_eq!(Some(&xdr::ScVal::Void), tx.return_value());
+    }
+
+    #[test]
+    fn test_missing_envelope() {
+        let (_, hash0) = envelope(1);
+        let meta = xdr::LedgerCloseMeta::V0(xdr::LedgerCloseMetaV0 {
+            tx_processing: vec![xdr::TransactionResultMeta {
+                result: result_pair(&hash0, 100),
+                fee_processing: Default::default(),
+                tx_apply_processing: xdr::TransactionMeta::V0(Default::default()),
+            }]
+            .try_into()
+            .unwrap(),
+            ..Default::default()
+        });
+        let meta = LedgerCloseMeta::from_xdr(&meta).unwrap();
+        let mut txs = meta.transactions(&Network::new_test()).unwrap();
+        assert!(matches!(
+            txs.next(),
+            Some(Err(Error::InvalidLedgerCloseMeta(_)))
+        ));
+        assert!(txs.next().is_none());
+    }
+}
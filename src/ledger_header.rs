@@ -0,0 +1,400 @@
+//! Ledger headers and hash chain verification.
+//!
+//! Each ledger header commits to the previous ledger header, to the
+//! transaction set applied in the ledger and to the transaction results.
+//! Starting from a trusted ledger hash, for example the hash of a checkpoint
+//! ledger, these commitments prove that transactions and results come from
+//! the trusted ledger history.
+use crate::amount::Stroops;
+use crate::crypto::hash;
+use crate::error::{Error, Result};
+use crate::xdr::{self, XDRSerialize};
+
+/// Header of a closed ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerHeader {
+    inner: xdr::LedgerHeader,
+}
+
+/// Verifies that a sequence of ledger headers forms a valid hash chain.
+///
+/// Headers must be verified in ledger order, without gaps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LedgerChainVerifier {
+    last: Option<(u32, Vec<u8>)>,
+}
+
+impl LedgerHeader {
+    /// Retrieves the ledger protocol version.
+    pub fn ledger_version(&self) -> u32 {
+        self.inner.ledger_version
+    }
+
+    /// Retrieves the ledger sequence.
+    pub fn ledger_seq(&self) -> u32 {
+        self.inner.ledger_seq
+    }
+
+    /// Retrieves the hash of the previous ledger header.
+    pub fn previous_ledger_hash(&self) -> &[u8] {
+        &self.inner.previous_ledger_hash.0
+    }
+
+    /// Retrieves the hash of the transaction set applied in the ledger.
+    pub fn tx_set_hash(&self) -> &[u8] {
+        &self.inner.scp_value.tx_set_hash.0
+    }
+
+    /// Retrieves the hash of the transaction results of the ledger.
+    pub fn tx_set_result_hash(&self) -> &[u8] {
+        &self.inner.tx_set_result_hash.0
+    }
+
+    /// Retrieves the hash of the bucket list after the ledger closed.
+    pub fn bucket_list_hash(&self) -> &[u8] {
+        &self.inner.bucket_list_hash.0
+    }
+
+    /// Retrieves the ledger close time, in seconds since the unix epoch.
+    pub fn close_time(&self) -> u64 {
+        self.inner.scp_value.close_time.0
+    }
+
+    /// Retrieves the total number of stroops in existence.
+    pub fn total_coins(&self) -> Stroops {
+        Stroops::new(self.inner.total_coins)
+    }
+
+    /// Retrieves the fees burned since the last inflation run.
+    pub fn fee_pool(&self) -> Stroops {
+        Stroops::new(self.inner.fee_pool)
+    }
+
+    /// Retrieves the base fee.
+    pub fn base_fee(&self) -> Stroops {
+        Stroops::new(self.inner.base_fee.into())
+    }
+
+    /// Retrieves the base reserve.
+    pub fn base_reserve(&self) -> Stroops {
+        Stroops::new(self.inner.base_reserve.into())
+    }
+
+    /// Retrieves the maximum size of the transaction set.
+    pub fn max_tx_set_size(&self) -> u32 {
+        self.inner.max_tx_set_size
+    }
+
+    /// Retrieves the underlying xdr object.
+    pub fn as_xdr(&self) -> &xdr::LedgerHeader {
+        &self.inner
+    }
+
+    /// Returns the ledger header hash.
+    pub fn hash(&self) -> Result<Vec<u8>> {
+        Ok(hash(&self.inner.xdr_bytes()?))
+    }
+
+    /// Checks that `results` are the transaction results of the ledger.
+    pub fn verify_transaction_results(&self, results: &xdr::TransactionResultSet) -> Result<()> {
+        if transaction_result_set_hash(results)? != self.tx_set_result_hash() {
+            return Err(Error::TransactionResultSetHashMismatch(self.ledger_seq()));
+        }
+        Ok(())
+    }
+
+    /// Checks that `tx_set` is the classic transaction set applied in the ledger.
+    pub fn verify_transaction_set(&self, tx_set: &xdr::TransactionSet) -> Result<()> {
+        if transaction_set_hash(tx_set)? != self.tx_set_hash() {
+            return Err(Error::TransactionSetHashMismatch(self.ledger_seq()));
+        }
+        Ok(())
+    }
+
+    /// Checks that `tx_set` is the generalized transaction set applied in the ledger.
+    pub fn verify_generalized_transaction_set(
+        &self,
+        tx_set: &xdr::GeneralizedTransactionSet,
+    ) -> Result<()> {
+        if generalized_transaction_set_hash(tx_set)? != self.tx_set_hash() {
+            return Err(Error::TransactionSetHashMismatch(self.ledger_seq()));
+        }
+        Ok(())
+    }
+
+    /// Checks that `entry` contains the transactions applied in the ledger.
+    ///
+    /// The generalized transaction set is used if present, the classic one otherwise.
+    pub fn verify_transaction_history_entry(
+        &self,
+        entry: &xdr::TransactionHistoryEntry,
+    ) -> Result<()> {
+        if entry.ledger_seq != self.ledger_seq() {
+            return Err(Error::TransactionSetHashMismatch(self.ledger_seq()));
+        }
+        match entry.ext {
+            xdr::TransactionHistoryEntryExt::V0 => self.verify_transaction_set(&entry.tx_set),
+            xdr::TransactionHistoryEntryExt::V1(ref tx_set) => {
+                self.verify_generalized_transaction_set(tx_set)
+            }
+        }
+    }
+
+    /// Checks that `entry` contains the transaction results of the ledger.
+    pub fn verify_transaction_history_result_entry(
+        &self,
+        entry: &xdr::TransactionHistoryResultEntry,
+    ) -> Result<()> {
+        if entry.ledger_seq != self.ledger_seq() {
+            return Err(Error::TransactionResultSetHashMismatch(self.ledger_seq()));
+        }
+        self.verify_transaction_results(&entry.tx_result_set)
+    }
+
+    /// Returns the xdr object.
+    pub fn to_xdr(&self) -> xdr::LedgerHeader {
+        self.inner.clone()
+    }
+
+    /// Creates `LedgerHeader` from xdr object.
+    pub fn from_xdr(x: &xdr::LedgerHeader) -> Result<LedgerHeader> {
+        Ok(LedgerHeader { inner: x.clone() })
+    }
+}
+
+impl LedgerChainVerifier {
+    /// Creates a new `LedgerChainVerifier`.
+    pub fn new() -> LedgerChainVerifier {
+        Default::default()
+    }
+
+    /// Retrieves the sequence and hash of the last verified ledger.
+    pub fn last(&self) -> Option<(u32, &[u8])> {
+        self.last
+            .as_ref()
+            .map(|(seq, hash)| (*seq, hash.as_slice()))
+    }
+
+    /// Verifies the next ledger header in the chain.
+    ///
+    /// Checks that the entry hash is the hash of its header and that the
+    /// header follows the last verified ledger.
+    pub fn verify(&mut self, entry: &xdr::LedgerHeaderHistoryEntry) -> Result<LedgerHeader> {
+        let header = LedgerHeader::from_xdr(&entry.header)?;
+        let header_hash = header.hash()?;
+        if header_hash != entry.hash.0 {
+            return Err(Error::LedgerHashMismatch(header.ledger_seq()));
+        }
+        if let Some((last_seq, ref last_hash)) = self.last {
+            if last_seq.checked_add(1) != Some(header.ledger_seq())
+                || last_hash.as_slice() != header.previous_ledger_hash()
+            {
+                return Err(Error::BrokenLedgerChain(header.ledger_seq()));
+            }
+        }
+        self.last = Some((header.ledger_seq(), header_hash));
+        Ok(header)
+    }
+}
+
+/// Verifies that `entries` form a valid hash chain and returns the hash of the last ledger.
+///
+/// Compare the returned hash with a trusted ledger hash to trust the whole chain.
+pub fn verify_ledger_chain<'a, I>(entries: I) -> Result<Option<Vec<u8>>>
+where
+    I: IntoIterator<Item = &'a xdr::LedgerHeaderHistoryEntry>,
+{
+    let mut verifier = LedgerChainVerifier::new();
+    for entry in entries {
+        verifier.verify(entry)?;
+    }
+    Ok(verifier.last.map(|(_, hash)| hash))
+}
+
+/// Returns the hash of a classic transaction set.
+pub fn transaction_set_hash(tx_set: &xdr::TransactionSet) -> Result<Vec<u8>> {
+    let mut data = tx_set.previous_ledger_hash.0.to_vec();
+    for tx in tx_set.txs.iter() {
+        data.extend(tx.xdr_bytes()?);
+    }
+    Ok(hash(&data))
+}
+
+/// Returns the hash of a generalized transaction set.
+pub fn generalized_transaction_set_hash(
+    tx_set: &xdr::GeneralizedTransactionSet,
+) -> Result<Vec<u8>> {
+    Ok(hash(&tx_set.xdr_bytes()?))
+}
+
+/// Returns the hash of a transaction result set.
+pub fn transaction_result_set_hash(results: &xdr::TransactionResultSet) -> Result<Vec<u8>> {
+    Ok(hash(&results.xdr_bytes()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(ledger_seq: u32, previous_ledger_hash: Vec<u8>) -> xdr::LedgerHeader {
+        xdr::LedgerHeader {
+            ledger_version: 23,
+            previous_ledger_hash: xdr::Hash(previous_ledger_hash.try_into().unwrap()),
+            scp_value: xdr::StellarValue {
+                tx_set_hash: xdr::Hash([0; 32]),
+                close_time: xdr::TimePoint(1_700_000_000 + u64::from(ledger_seq) * 5),
+                upgrades: Default::default(),
+                ext: xdr::StellarValueExt::Basic,
+            },
+            tx_set_result_hash: xdr::Hash([0; 32]),
+            bucket_list_hash: xdr::Hash([0; 32]),
+            ledger_seq,
+            total_coins: 1_000_000_000_000_000_000,
+            fee_pool: 0,
+            inflation_seq: 0,
+            id_pool: 0,
+            base_fee: 100,
+            base_reserve: 5_000_000,
+            max_tx_set_size: 1000,
+            skip_list: Default::default(),
+            ext: xdr::LedgerHeaderExt::V0,
+        }
+    }
+
+    fn chain(first: u32, len: u32) -> Vec<xdr::LedgerHeaderHistoryEntry> {
+        let mut previous = vec![0; 32];
+        let mut entries = Vec::new();
+        for ledger_seq in first..first + len {
+            let header = header(ledger_seq, previous);
+            let header_hash = LedgerHeader::from_xdr(&header).unwrap().hash().unwrap();
+            entries.push(xdr::LedgerHeaderHistoryEntry {
+                hash: xdr::Hash(header_hash.clone().try_into().unwrap()),
+                header,
+                ext: xdr::LedgerHeaderHistoryEntryExt::V0,
+            });
+            previous = header_hash;
+        }
+        entries
+    }
+
+    fn result_set() -> xdr::TransactionResultSet {
+        xdr::TransactionResultSet {
+            results: vec![xdr::TransactionResultPair {
+                transaction_hash: xdr::Hash([1; 32]),
+                result: xdr::TransactionResult {
+                    fee_charged: 100,
+                    result: xdr::TransactionResultResult::TxBadSeq,
+                    ext: xdr::TransactionResultExt::V0,
+                },
+            }]
+            .try_into()
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_ledger_header_accessors() {
+        let header = LedgerHeader::from_xdr(&header(64, vec![9; 32])).unwrap();
+        assert_eq!(64, header.ledger_seq());
+        assert_eq!(23, header.ledger_version());
+        assert_eq!(&[9; 32], header.previous_ledger_hash());
+        assert_eq!(Stroops::new(100), header.base_fee());
+        assert_eq!(Stroops::new(5_000_000), header.base_reserve());
+        assert_eq!(1_700_000_320, header.close_time());
+        let expected = hash(&header.to_xdr().xdr_bytes().unwrap());
+        assert_eq!(expected, header.hash().unwrap());
+    }
+
+    #[test]
+    fn test_verify_ledger_chain() {
+        let entries = chain(63, 5);
+        let last = verify_ledger_chain(&entries).unwrap();
+        assert_eq!(Some(entries[4].hash.0.to_vec()), last);
+        assert_eq!(None, verify_ledger_chain(&[]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_ledger_chain_errors() {
+        let mut entries = chain(63, 5);
+        entries[2].header.fee_pool = 1;
+        let res = verify_ledger_chain(&entries);
+        assert!(matches!(res, Err(Error::LedgerHashMismatch(65))));
+
+        let mut entries = chain(63, 5);
+        entries.remove(2);
+        let res = verify_ledger_chain(&entries);
+        assert!(matches!(res, Err(Error::BrokenLedgerChain(66))));
+
+        let mut entries = chain(63, 2);
+        entries.extend(chain(65, 2));
+        let res = verify_ledger_chain(&entries);
+        assert!(matches!(res, Err(Error::BrokenLedgerChain(65))));
+    }
+
+    #[test]
+    fn test_verify_transaction_results() {
+        let results = result_set();
+        let mut xdr_header = header(100, vec![0; 32]);
+        xdr_header.tx_set_result_hash = xdr::Hash(
+            transaction_result_set_hash(&results)
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        let header = LedgerHeader::from_xdr(&xdr_header).unwrap();
+        header.verify_transaction_results(&results).unwrap();
+
+        let entry = xdr::TransactionHistoryResultEntry {
+            ledger_seq: 100,
+            tx_result_set: results,
+            ext: xdr::TransactionHistoryResultEntryExt::V0,
+        };
+        header
+            .verify_transaction_history_result_entry(&entry)
+            .unwrap();
+
+        let res = header.verify_transaction_results(&xdr::TransactionResultSet::default());
+        assert!(matches!(
+            res,
+            Err(Error::TransactionResultSetHashMismatch(100))
+        ));
+    }
+
+    #[test]
+    fn test_verify_transaction_sets() {
+        let tx_set = xdr::TransactionSet {
+            previous_ledger_hash: xdr::Hash([3; 32]),
+            txs: Default::default(),
+        };
+        let generalized = xdr::GeneralizedTransactionSet::V1(xdr::TransactionSetV1 {
+            previous_ledger_hash: xdr::Hash([3; 32]),
+            phases: vec![xdr::TransactionPhase::V0(Default::default())]
+                .try_into()
+                .unwrap(),
+        });
+
+        let mut xdr_header = header(100, vec![0; 32]);
+        xdr_header.scp_value.tx_set_hash =
+            xdr::Hash(transaction_set_hash(&tx_set).unwrap().try_into().unwrap());
+        let header = LedgerHeader::from_xdr(&xdr_header).unwrap();
+        assert_eq!(hash(&[3; 32]), header.tx_set_hash());
+        header.verify_transaction_set(&tx_set).unwrap();
+        let res = header.verify_generalized_transaction_set(&generalized);
+        assert!(matches!(res, Err(Error::TransactionSetHashMismatch(100))));
+
+        let mut xdr_header = header.to_xdr();
+        xdr_header.scp_value.tx_set_hash = xdr::Hash(
+            generalized_transaction_set_hash(&generalized)
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        let header = LedgerHeader::from_xdr(&xdr_header).unwrap();
+        let entry = xdr::TransactionHistoryEntry {
+            ledger_seq: 100,
+            tx_set,
+            ext: xdr::TransactionHistoryEntryExt::V1(generalized),
+        };
+        header.verify_transaction_history_entry(&entry).unwrap();
+    }
+}
@@ -73,19 +73,36 @@ extern crate json;
 
 pub mod account;
 pub mod amount;
+#[cfg(feature = "dalek")]
+pub mod apply;
 pub mod asset;
 pub mod claim;
 pub mod crypto;
 pub mod error;
+mod hex;
+pub mod history;
+pub mod history_archive;
+#[cfg(feature = "keystore")]
+pub mod keystore;
 pub mod ledger;
+pub mod ledger_close_meta;
+pub mod ledger_header;
 pub mod liquidity_pool;
 pub mod memo;
 pub mod network;
 pub mod operation_result;
 pub mod operations;
+pub mod order_book;
+pub mod path_finder;
+pub mod pre_auth;
+pub mod sep29;
+pub mod sep7;
+pub mod sponsorship;
 pub mod time_bounds;
 pub mod transaction;
 pub mod transaction_result;
+pub mod txrep;
+pub mod validation;
 pub mod xdr;
 
 pub use self::asset::Asset;
@@ -1,8 +1,18 @@
+use std::fmt;
+use std::str::FromStr;
+
 use crate::asset::Asset;
 use crate::error::{Error, Result};
+use crate::hex;
 use crate::xdr;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Fee of constant fee liquidity pools, in basis points.
+pub const LIQUIDITY_POOL_FEE_V18: i32 = 30;
+
+/// Basis points in a unit. Pool fees must be lower.
+pub const LIQUIDITY_POOL_MAX_FEE: i32 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct LiquidityPoolId(Vec<u8>);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -56,7 +66,53 @@ impl LiquidityPoolId {
     }
 }
 
+impl fmt::Display for LiquidityPoolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(&self.0))
+    }
+}
+
+impl FromStr for LiquidityPoolId {
+    type Err = Error;
+
+    /// Parses the hex encoded liquidity pool id.
+    fn from_str(s: &str) -> Result<Self> {
+        let hash = hex::decode(s).ok_or(Error::InvalidLiquidityPoolId)?;
+        Self::new(hash)
+    }
+}
+
 impl LiquidityPoolConstantFeeParameters {
+    /// Creates the parameters of a constant fee pool of `asset_a` and `asset_b`.
+    ///
+    /// The assets must be different and `asset_a` must sort before `asset_b`.
+    pub fn new(asset_a: Asset, asset_b: Asset, fee: i32) -> Result<Self> {
+        if asset_a >= asset_b {
+            return Err(Error::InvalidOperation(
+                "liquidity pool assets are not in canonical order".to_string(),
+            ));
+        }
+        if !(0..LIQUIDITY_POOL_MAX_FEE).contains(&fee) {
+            return Err(Error::InvalidOperation(
+                "invalid liquidity pool fee".to_string(),
+            ));
+        }
+        Ok(Self {
+            assets: (asset_a, asset_b),
+            fee,
+        })
+    }
+
+    /// Retrieves the pool assets.
+    pub fn assets(&self) -> &(Asset, Asset) {
+        &self.assets
+    }
+
+    /// Retrieves the pool fee, in basis points.
+    pub fn fee(&self) -> i32 {
+        self.fee
+    }
+
     /// Returns the xdr object.
     pub fn to_xdr(&self) -> Result<xdr::LiquidityPoolConstantProductParameters> {
         Ok(xdr::LiquidityPoolConstantProductParameters {
@@ -97,3 +153,156 @@ impl LiquidityPoolParameters {
         }
     }
 }
+
+impl LiquidityPoolConstantFee {
+    /// Creates a constant fee liquidity pool with `reserves` of `assets`.
+    ///
+    /// `fee` is expressed in basis points.
+    pub fn new(
+        assets: (Asset, Asset),
+        fee: i32,
+        reserves: (i64, i64),
+        total_pool_shares: i64,
+        pool_shares_trust_line_count: i64,
+    ) -> Result<Self> {
+        if !(0..LIQUIDITY_POOL_MAX_FEE).contains(&fee) {
+            return Err(Error::InvalidOperation(
+                "invalid liquidity pool fee".to_string(),
+            ));
+        }
+        if reserves.0 < 0 || reserves.1 < 0 {
+            return Err(Error::InvalidOperation(
+                "liquidity pool reserves can't be negative".to_string(),
+            ));
+        }
+        Ok(Self {
+            assets,
+            fee,
+            reserves,
+            total_pool_shares,
+            pool_shares_trust_line_count,
+        })
+    }
+
+    /// Retrieves the pool assets.
+    pub fn assets(&self) -> &(Asset, Asset) {
+        &self.assets
+    }
+
+    /// Retrieves the pool fee, in basis points.
+    pub fn fee(&self) -> i32 {
+        self.fee
+    }
+
+    /// Retrieves the pool reserves, in the same order as the assets.
+    pub fn reserves(&self) -> (i64, i64) {
+        self.reserves
+    }
+
+    /// Retrieves the total number of pool shares.
+    pub fn total_pool_shares(&self) -> i64 {
+        self.total_pool_shares
+    }
+
+    /// Retrieves the number of trust lines holding pool shares.
+    pub fn pool_shares_trust_line_count(&self) -> i64 {
+        self.pool_shares_trust_line_count
+    }
+
+    /// Retrieves the reserve of `asset`, if the pool holds `asset`.
+    pub fn reserve(&self, asset: &Asset) -> Option<i64> {
+        if *asset == self.assets.0 {
+            Some(self.reserves.0)
+        } else if *asset == self.assets.1 {
+            Some(self.reserves.1)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the amount the pool pays when `send_amount` of `send_asset`
+    /// is deposited, rounded down.
+    ///
+    /// Returns `None` if the pool can't take part in the exchange.
+    pub fn strict_send_amount(&self, send_asset: &Asset, send_amount: i64) -> Option<i64> {
+        let (to_pool, from_pool) = self.ordered_reserves(send_asset)?;
+        if send_amount <= 0 || send_amount > i64::MAX - to_pool {
+            return None;
+        }
+        let max_bps = i128::from(LIQUIDITY_POOL_MAX_FEE);
+        let fee_bps = i128::from(self.fee);
+        let numerator = (max_bps - fee_bps)
+            .checked_mul(i128::from(from_pool))?
+            .checked_mul(i128::from(send_amount))?;
+        let denominator =
+            max_bps * i128::from(to_pool) + (max_bps - fee_bps) * i128::from(send_amount);
+        let amount = i64::try_from(numerator / denominator).ok()?;
+        if amount > 0 && amount < from_pool {
+            Some(amount)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the amount of `receive_asset`'s counter asset needed to take
+    /// `receive_amount` of `receive_asset` out of the pool, rounded up.
+    ///
+    /// Returns `None` if the pool can't take part in the exchange.
+    pub fn strict_receive_amount(&self, receive_asset: &Asset, receive_amount: i64) -> Option<i64> {
+        let (from_pool, to_pool) = self.ordered_reserves(receive_asset)?;
+        if receive_amount <= 0 || receive_amount >= from_pool {
+            return None;
+        }
+        let max_bps = i128::from(LIQUIDITY_POOL_MAX_FEE);
+        let fee_bps = i128::from(self.fee);
+        let numerator = max_bps
+            .checked_mul(i128::from(to_pool))?
+            .checked_mul(i128::from(receive_amount))?;
+        let denominator = i128::from(from_pool - receive_amount) * (max_bps - fee_bps);
+        let amount = i64::try_from((numerator + denominator - 1) / denominator).ok()?;
+        if amount <= i64::MAX - to_pool {
+            Some(amount)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the reserves of `asset` and of the other pool asset.
+    fn ordered_reserves(&self, asset: &Asset) -> Option<(i64, i64)> {
+        let reserves = if *asset == self.assets.0 {
+            self.reserves
+        } else if *asset == self.assets.1 {
+            (self.reserves.1, self.reserves.0)
+        } else {
+            return None;
+        };
+        if reserves.0 > 0 && reserves.1 > 0 {
+            Some(reserves)
+        } else {
+            None
+        }
+    }
+
+    /// Creates from the xdr object.
+    pub fn from_xdr(x: &xdr::LiquidityPoolEntryConstantProduct) -> Result<Self> {
+        let params = LiquidityPoolConstantFeeParameters::from_xdr(&x.params)?;
+        Ok(Self {
+            assets: params.assets,
+            fee: params.fee,
+            reserves: (x.reserve_a, x.reserve_b),
+            total_pool_shares: x.total_pool_shares,
+            pool_shares_trust_line_count: x.pool_shares_trust_line_count,
+        })
+    }
+}
+
+impl LiquidityPool {
+    /// Creates from the xdr object.
+    pub fn from_xdr(x: &xdr::LiquidityPoolEntry) -> Result<Self> {
+        match x.body {
+            xdr::LiquidityPoolEntryBody::LiquidityPoolConstantProduct(ref body) => {
+                Ok(Self::ConstantFee(LiquidityPoolConstantFee::from_xdr(body)?))
+            }
+        }
+    }
+}
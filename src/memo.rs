@@ -2,6 +2,7 @@
 use std::io::{Read, Write};
 
 use crate::error::{Error, Result};
+use crate::hex;
 use crate::xdr;
 
 /// Maximum length of text memo.
@@ -11,12 +12,17 @@ pub const MAX_MEMO_TEXT_LEN: usize = 28;
 pub const MAX_HASH_LEN: usize = 32;
 
 /// Memo attached to transactions.
+///
+/// New variants may be added in minor releases, match with a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Memo {
     /// No memo
     None,
     /// Text Memo
     Text(String),
+    /// Text Memo that is not valid UTF-8
+    TextBytes(Vec<u8>),
     /// Id Memo
     Id(u64),
     /// Hash Memo
@@ -46,6 +52,28 @@ impl Memo {
         }
     }
 
+    /// Create new text memo from raw bytes. `text` must be shorter than 28 bytes.
+    ///
+    /// The memo is a `Memo::Text` if `text` is valid UTF-8, and a
+    /// `Memo::TextBytes` otherwise.
+    pub fn new_text_from_bytes(text: &[u8]) -> Result<Memo> {
+        if text.len() > MAX_MEMO_TEXT_LEN {
+            return Err(Error::InvalidMemoText);
+        }
+        match std::str::from_utf8(text) {
+            Ok(text) => Ok(Memo::Text(text.to_string())),
+            Err(_) => Ok(Memo::TextBytes(text.to_vec())),
+        }
+    }
+
+    /// Create new hash memo from the hex encoded 32 bytes `hash`.
+    pub fn new_hash_from_hex(hash: &str) -> Result<Memo> {
+        let memo_hash = hex::decode(hash)
+            .and_then(|hash| hash.try_into().ok())
+            .ok_or(Error::InvalidMemoHash)?;
+        Ok(Memo::Hash(memo_hash))
+    }
+
     /// Create new hash memo.
     pub fn new_hash(hash: &[u8]) -> Result<Memo> {
         if hash.len() > MAX_HASH_LEN {
@@ -110,9 +138,18 @@ impl Memo {
         }
     }
 
-    /// Returns true if memo is Text. Returns false otherwise.
+    /// If the memo is a Text or TextBytes, returns its bytes. Returns None otherwise.
+    pub fn as_text_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Memo::Text(ref text) => Some(text.as_bytes()),
+            Memo::TextBytes(ref text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns true if memo is Text or TextBytes. Returns false otherwise.
     pub fn is_text(&self) -> bool {
-        self.as_text().is_some()
+        self.as_text_bytes().is_some()
     }
 
     /// If the memo is a Hash, returns its value. Returns None otherwise.
@@ -164,9 +201,14 @@ impl Memo {
             Memo::Text(text) => Ok(xdr::Memo::Text(
                 text.try_into().map_err(|_| Error::InvalidMemoText)?,
             )),
+            Memo::TextBytes(text) => Ok(xdr::Memo::Text(
+                text.as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidMemoText)?,
+            )),
             Memo::Id(id) => Ok(xdr::Memo::Id(*id)),
-            Memo::Hash(hash) => Ok(xdr::Memo::Hash(hash.clone().into())),
-            Memo::Return(ret) => Ok(xdr::Memo::Return(ret.clone().into())),
+            Memo::Hash(hash) => Ok(xdr::Memo::Hash((*hash).into())),
+            Memo::Return(ret) => Ok(xdr::Memo::Return((*ret).into())),
         }
     }
 
@@ -174,10 +216,7 @@ impl Memo {
     pub fn from_xdr(x: &xdr::Memo) -> Result<Memo> {
         match x {
             xdr::Memo::None => Ok(Memo::new_none()),
-            xdr::Memo::Text(text) => {
-                let string: String = text.try_into().map_err(|_| Error::InvalidMemoText)?;
-                Memo::new_text(string)
-            }
+            xdr::Memo::Text(text) => Memo::new_text_from_bytes(text.as_slice()),
             xdr::Memo::Id(id) => Ok(Memo::new_id(*id)),
             xdr::Memo::Hash(hash) => Memo::new_hash(&hash.0),
             xdr::Memo::Return(ret) => Memo::new_return(&ret.0),
@@ -325,6 +364,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_memo_text_from_bytes() {
+        let memo = Memo::new_text_from_bytes(b"hello").unwrap();
+        assert_eq!(Some("hello"), memo.as_text());
+
+        let bytes = [0xff, 0xfe, b'a'];
+        let memo = Memo::new_text_from_bytes(&bytes).unwrap();
+        assert!(memo.is_text());
+        assert_eq!(None, memo.as_text());
+        assert_eq!(Some(&bytes[..]), memo.as_text_bytes());
+
+        let xdr = memo.xdr_base64().unwrap();
+        assert_eq!("AAAAAQAAAAP//mEA", xdr);
+        assert_eq!(memo, Memo::from_xdr_base64(&xdr).unwrap());
+
+        assert!(Memo::new_text_from_bytes(&[0xff; 29]).is_err());
+    }
+
+    #[test]
+    fn test_memo_hash_from_hex() {
+        let hex = "000102030405060708090a0b0c0d0e0f101112131415161718191A1B1C1D1E1F";
+        let memo = Memo::new_hash_from_hex(hex).unwrap();
+        let expected: Vec<u8> = (0..32).collect();
+        assert_eq!(expected, memo.as_hash().unwrap());
+
+        assert!(Memo::new_hash_from_hex("0001").is_err());
+        assert!(Memo::new_hash_from_hex(&"zz".repeat(32)).is_err());
+        assert!(Memo::new_hash_from_hex(&"0".repeat(66)).is_err());
+    }
+
     #[test]
     fn test_memo_text_too_long() {
         let result =
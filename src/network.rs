@@ -3,17 +3,31 @@ use crate::crypto;
 
 pub const PUBLIC_PASSPHRASE: &str = "Public Global Stellar Network ; September 2015";
 pub const TEST_PASSPHRASE: &str = "Test SDF Network ; September 2015";
+pub const FUTURENET_PASSPHRASE: &str = "Test SDF Future Network ; October 2022";
+pub const STANDALONE_PASSPHRASE: &str = "Standalone Network ; February 2017";
 
 /// A Stellar Network.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Network {
     passphrase: String,
+    network_id: [u8; 32],
+}
+
+/// A list of known networks, to identify a network from its id or passphrase.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NetworkRegistry {
+    networks: Vec<Network>,
 }
 
 impl Network {
     /// Create new network with `passphrase`.
     pub fn new(passphrase: String) -> Network {
-        Network { passphrase }
+        let mut network_id = [0; 32];
+        network_id.copy_from_slice(&crypto::hash(passphrase.as_bytes()));
+        Network {
+            passphrase,
+            network_id,
+        }
     }
 
     /// Create new network with the same passphrase as SDF public network.
@@ -26,26 +40,80 @@ impl Network {
         Self::new(TEST_PASSPHRASE.to_string())
     }
 
+    /// Create new network with the same passphrase as SDF future network.
+    pub fn new_futurenet() -> Network {
+        Self::new(FUTURENET_PASSPHRASE.to_string())
+    }
+
+    /// Create new network with the same passphrase as the standalone network
+    /// of the quickstart image.
+    pub fn new_standalone() -> Network {
+        Self::new(STANDALONE_PASSPHRASE.to_string())
+    }
+
     /// Retrieves the network passphrase.
     pub fn passphrase(&self) -> &str {
         &self.passphrase
     }
 
     /// Retrieves the network id, which is the hash of the network passphrase.
-    pub fn network_id(&self) -> Vec<u8> {
-        crypto::hash(self.passphrase.as_bytes())
+    pub fn network_id(&self) -> [u8; 32] {
+        self.network_id
+    }
+}
+
+impl NetworkRegistry {
+    /// Create new empty registry.
+    pub fn new() -> NetworkRegistry {
+        NetworkRegistry::default()
+    }
+
+    /// Create new registry with the public, test, future and standalone networks.
+    pub fn known() -> NetworkRegistry {
+        NetworkRegistry::new()
+            .with_network(Network::new_public())
+            .with_network(Network::new_test())
+            .with_network(Network::new_futurenet())
+            .with_network(Network::new_standalone())
+    }
+
+    /// Adds `network` to the registry, if it's not already registered.
+    pub fn with_network(mut self, network: Network) -> NetworkRegistry {
+        if !self.networks.contains(&network) {
+            self.networks.push(network);
+        }
+        self
+    }
+
+    /// Retrieves the registered networks.
+    pub fn networks(&self) -> &Vec<Network> {
+        &self.networks
+    }
+
+    /// Returns the registered network with `network_id`.
+    pub fn find_by_network_id(&self, network_id: &[u8]) -> Option<&Network> {
+        self.networks
+            .iter()
+            .find(|network| network.network_id.as_slice() == network_id)
+    }
+
+    /// Returns the registered network with `passphrase`.
+    pub fn find_by_passphrase(&self, passphrase: &str) -> Option<&Network> {
+        self.networks
+            .iter()
+            .find(|network| network.passphrase == passphrase)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Network;
+    use super::{Network, NetworkRegistry, FUTURENET_PASSPHRASE};
 
     #[test]
     fn test_public_network_id() {
         let network = Network::new_public();
         let id = network.network_id();
-        let expected_id = vec![
+        let expected_id = [
             0x7A, 0xC3, 0x39, 0x97, 0x54, 0x4E, 0x31, 0x75, 0xD2, 0x66, 0xBD, 0x02, 0x24, 0x39,
             0xB2, 0x2C, 0xDB, 0x16, 0x50, 0x8C, 0x01, 0x16, 0x3F, 0x26, 0xE5, 0xCB, 0x2A, 0x3E,
             0x10, 0x45, 0xA9, 0x79,
@@ -57,11 +125,43 @@ mod tests {
     fn test_test_network_id() {
         let network = Network::new_test();
         let id = network.network_id();
-        let expected_id = vec![
+        let expected_id = [
             0xCE, 0xE0, 0x30, 0x2D, 0x59, 0x84, 0x4D, 0x32, 0xBD, 0xCA, 0x91, 0x5C, 0x82, 0x03,
             0xDD, 0x44, 0xB3, 0x3F, 0xBB, 0x7E, 0xDC, 0x19, 0x05, 0x1E, 0xA3, 0x7A, 0xBE, 0xDF,
             0x28, 0xEC, 0xD4, 0x72,
         ];
         assert_eq!(id, expected_id);
     }
+
+    #[test]
+    fn test_network_registry() {
+        let registry = NetworkRegistry::known();
+        assert_eq!(4, registry.networks().len());
+
+        let futurenet = Network::new_futurenet();
+        assert_eq!(
+            Some(&futurenet),
+            registry.find_by_network_id(&futurenet.network_id())
+        );
+        assert_eq!(
+            Some(&futurenet),
+            registry.find_by_passphrase(FUTURENET_PASSPHRASE)
+        );
+        assert_eq!(
+            Some(&Network::new_standalone()),
+            registry.find_by_network_id(&Network::new_standalone().network_id())
+        );
+
+        let custom = Network::new("Custom Network".to_string());
+        assert_eq!(None, registry.find_by_network_id(&custom.network_id()));
+        assert_eq!(None, registry.find_by_passphrase("Custom Network"));
+        let registry = registry
+            .with_network(custom.clone())
+            .with_network(custom.clone());
+        assert_eq!(5, registry.networks().len());
+        assert_eq!(
+            Some(&custom),
+            registry.find_by_network_id(&custom.network_id())
+        );
+    }
 }
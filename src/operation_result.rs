@@ -5,6 +5,7 @@ use crate::crypto::PublicKey;
 use crate::error::Result;
 use crate::xdr;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OperationResult {
     Inner(InnerOperationResult),
@@ -86,6 +87,7 @@ pub enum PathPaymentStrictSendResult {
 
 pub use xdr::SetOptionsResult;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ManageSellOfferResult {
     Success(ManageOfferResultSuccess),
@@ -103,6 +105,7 @@ pub enum ManageSellOfferResult {
     LowReserve,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ManageBuyOfferResult {
     Success(ManageOfferResultSuccess),
@@ -77,7 +77,7 @@ impl AllowTrustOperation {
                 xdr::AssetCode::CreditAlphanum12(asset_code)
             }
         };
-        let authorize = self.authorize.bits() as u32;
+        let authorize = self.authorize.bits();
 
         let inner = xdr::AllowTrustOp {
             trustor,
@@ -1,5 +1,5 @@
 use crate::amount::Stroops;
-use crate::asset::{xdr_code_to_string, CreditAsset};
+use crate::asset::{xdr_code_to_canonical_string, CreditAsset};
 use crate::crypto::MuxedAccount;
 use crate::error::{Error, Result};
 use crate::liquidity_pool::LiquidityPoolParameters;
@@ -143,12 +143,12 @@ impl ChangeTrustAsset {
             xdr::ChangeTrustAsset::Native => Self::new_native(),
             xdr::ChangeTrustAsset::CreditAlphanum4(ref credit) => {
                 let issuer = PublicKey::from_xdr_account_id(&credit.issuer)?;
-                let code = xdr_code_to_string(&credit.asset_code.0);
+                let code = xdr_code_to_canonical_string(&credit.asset_code.0, 1)?;
                 Self::new_credit(code, issuer)
             }
             xdr::ChangeTrustAsset::CreditAlphanum12(ref credit) => {
                 let issuer = PublicKey::from_xdr_account_id(&credit.issuer)?;
-                let code = xdr_code_to_string(&credit.asset_code.0);
+                let code = xdr_code_to_canonical_string(&credit.asset_code.0, 5)?;
                 Self::new_credit(code, issuer)
             }
             xdr::ChangeTrustAsset::PoolShare(ref pool_params_xdr) => {
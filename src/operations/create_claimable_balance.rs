@@ -74,7 +74,7 @@ impl CreateClaimableBalanceOperation {
         let inner = xdr::CreateClaimableBalanceOp {
             asset,
             amount,
-            claimants: claimants.try_into().map_err(|_| Error::XdrError)?,
+            claimants: claimants.try_into().map_err(Error::XdrError)?,
         };
         Ok(xdr::OperationBody::CreateClaimableBalance(inner))
     }
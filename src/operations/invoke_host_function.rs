@@ -7,12 +7,11 @@ use crate::xdr;
 ///
 /// Soroban smart contracts are accessed through host functions that are executed
 /// atomically within the context of the transaction. This operation carries:
-/// - The host function (one of:
+/// - The host function, one of:
 ///     * InvokeContract
 ///     * CreateContract
 ///     * UploadContractWasm
 ///     * CreateContractV2
-///   )
 /// - Authorization entries proving the required signatures / invocation authorizations
 ///
 /// The XDR struct backing this is:
@@ -70,7 +69,7 @@ impl InvokeHostFunctionOperation {
 
     /// Returns the XDR operation body.
     pub fn to_xdr_operation_body(&self) -> Result<xdr::OperationBody> {
-        let auth_xdr = self.auth.clone().try_into().map_err(|_| Error::XdrError)?;
+        let auth_xdr = self.auth.clone().try_into().map_err(Error::XdrError)?;
         let inner = xdr::InvokeHostFunctionOp {
             host_function: self.host_function.clone(),
             auth: auth_xdr,
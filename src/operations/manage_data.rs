@@ -56,7 +56,7 @@ impl ManageDataOperation {
             .as_bytes()
             .to_vec()
             .try_into()
-            .map_err(|_| Error::XdrError)?;
+            .map_err(Error::XdrError)?;
         let data_value = self.data_value.as_ref().map(|d| d.to_xdr()).transpose()?;
         let inner = xdr::ManageDataOp {
             data_name,
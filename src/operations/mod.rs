@@ -54,7 +54,7 @@ pub use begin_sponsoring_future_reserves::{
     BeginSponsoringFutureReservesOperation, BeginSponsoringFutureReservesOperationBuilder,
 };
 pub use bump_sequence::{BumpSequenceOperation, BumpSequenceOperationBuilder};
-pub use change_trust::{ChangeTrustOperation, ChangeTrustOperationBuilder};
+pub use change_trust::{ChangeTrustAsset, ChangeTrustOperation, ChangeTrustOperationBuilder};
 pub use claim_claimable_balance::{
     ClaimClaimableBalanceOperation, ClaimClaimableBalanceOperationBuilder,
 };
@@ -138,6 +138,39 @@ pub enum Operation {
     RestoreFootprint(RestoreFootprintOperation),
 }
 
+/// Threshold of the source account signers needed to authorize an operation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ThresholdLevel {
+    /// Low threshold.
+    Low,
+    /// Medium threshold.
+    Medium,
+    /// High threshold.
+    High,
+}
+
+/// Returns the threshold level needed by an operation of type `op_type`.
+///
+/// `changes_signers` is true for set options operations changing the
+/// signers, the master weight or the thresholds.
+pub(crate) fn threshold_level(
+    op_type: xdr::OperationType,
+    changes_signers: bool,
+) -> ThresholdLevel {
+    match op_type {
+        xdr::OperationType::AllowTrust
+        | xdr::OperationType::SetTrustLineFlags
+        | xdr::OperationType::BumpSequence
+        | xdr::OperationType::ClaimClaimableBalance
+        | xdr::OperationType::Inflation
+        | xdr::OperationType::ExtendFootprintTtl
+        | xdr::OperationType::RestoreFootprint => ThresholdLevel::Low,
+        xdr::OperationType::AccountMerge => ThresholdLevel::High,
+        xdr::OperationType::SetOptions if changes_signers => ThresholdLevel::High,
+        _ => ThresholdLevel::Medium,
+    }
+}
+
 impl Operation {
     /// Creates a new create account operation builder.
     pub fn new_create_account() -> CreateAccountOperationBuilder {
@@ -844,6 +877,87 @@ impl Operation {
         }
     }
 
+    /// Retrieves the destination of payments, path payments and account
+    /// merges. Returns None for other operations.
+    pub fn destination(&self) -> Option<&MuxedAccount> {
+        match self {
+            Operation::Payment(op) => Some(op.destination()),
+            Operation::PathPaymentStrictReceive(op) => Some(op.destination()),
+            Operation::PathPaymentStrictSend(op) => Some(op.destination()),
+            Operation::AccountMerge(op) => Some(op.destination()),
+            _ => None,
+        }
+    }
+
+    /// Retrieves a mutable reference to the destination of payments, path
+    /// payments and account merges. Returns None for other operations.
+    pub fn destination_mut(&mut self) -> Option<&mut MuxedAccount> {
+        match self {
+            Operation::Payment(op) => Some(op.destination_mut()),
+            Operation::PathPaymentStrictReceive(op) => Some(op.destination_mut()),
+            Operation::PathPaymentStrictSend(op) => Some(op.destination_mut()),
+            Operation::AccountMerge(op) => Some(op.destination_mut()),
+            _ => None,
+        }
+    }
+
+    /// Retrieves the threshold level needed to authorize the operation.
+    ///
+    /// Follows stellar-core rules: trust line authorization, sequence bumps,
+    /// claiming balances and footprint management need the low threshold,
+    /// merging an account or changing its signers or thresholds needs the
+    /// high threshold, everything else needs the medium threshold.
+    pub fn threshold_level(&self) -> ThresholdLevel {
+        let changes_signers = match self {
+            Operation::SetOptions(op) => {
+                op.master_weight().is_some()
+                    || op.low_threshold().is_some()
+                    || op.medium_threshold().is_some()
+                    || op.high_threshold().is_some()
+                    || op.signer().is_some()
+            }
+            _ => false,
+        };
+        threshold_level(self.operation_type(), changes_signers)
+    }
+
+    /// Retrieves the xdr operation type.
+    fn operation_type(&self) -> xdr::OperationType {
+        match self {
+            Operation::CreateAccount(_) => xdr::OperationType::CreateAccount,
+            Operation::Payment(_) => xdr::OperationType::Payment,
+            Operation::PathPaymentStrictReceive(_) => xdr::OperationType::PathPaymentStrictReceive,
+            Operation::ManageSellOffer(_) => xdr::OperationType::ManageSellOffer,
+            Operation::CreatePassiveSellOffer(_) => xdr::OperationType::CreatePassiveSellOffer,
+            Operation::SetOptions(_) => xdr::OperationType::SetOptions,
+            Operation::ChangeTrust(_) => xdr::OperationType::ChangeTrust,
+            Operation::AllowTrust(_) => xdr::OperationType::AllowTrust,
+            Operation::AccountMerge(_) => xdr::OperationType::AccountMerge,
+            Operation::Inflation(_) => xdr::OperationType::Inflation,
+            Operation::ManageData(_) => xdr::OperationType::ManageData,
+            Operation::BumpSequence(_) => xdr::OperationType::BumpSequence,
+            Operation::ManageBuyOffer(_) => xdr::OperationType::ManageBuyOffer,
+            Operation::PathPaymentStrictSend(_) => xdr::OperationType::PathPaymentStrictSend,
+            Operation::CreateClaimableBalance(_) => xdr::OperationType::CreateClaimableBalance,
+            Operation::ClaimClaimableBalance(_) => xdr::OperationType::ClaimClaimableBalance,
+            Operation::BeginSponsoringFutureReserves(_) => {
+                xdr::OperationType::BeginSponsoringFutureReserves
+            }
+            Operation::EndSponsoringFutureReserves(_) => {
+                xdr::OperationType::EndSponsoringFutureReserves
+            }
+            Operation::RevokeSponsorship(_) => xdr::OperationType::RevokeSponsorship,
+            Operation::Clawback(_) => xdr::OperationType::Clawback,
+            Operation::ClawbackClaimableBalance(_) => xdr::OperationType::ClawbackClaimableBalance,
+            Operation::SetTrustLineFlags(_) => xdr::OperationType::SetTrustLineFlags,
+            Operation::LiquidityPoolDeposit(_) => xdr::OperationType::LiquidityPoolDeposit,
+            Operation::LiquidityPoolWithdraw(_) => xdr::OperationType::LiquidityPoolWithdraw,
+            Operation::InvokeHostFunction(_) => xdr::OperationType::InvokeHostFunction,
+            Operation::ExtendFootprintTtl(_) => xdr::OperationType::ExtendFootprintTtl,
+            Operation::RestoreFootprint(_) => xdr::OperationType::RestoreFootprint,
+        }
+    }
+
     /// Returns the xdr object.
     pub fn to_xdr(&self) -> Result<xdr::Operation> {
         let source_account = match self.source_account() {
@@ -114,7 +114,7 @@ impl PathPaymentStrictReceiveOperation {
             send_max,
             dest_asset,
             dest_amount,
-            path: path.try_into().map_err(|_| Error::XdrError)?,
+            path: path.try_into().map_err(Error::XdrError)?,
         };
         Ok(xdr::OperationBody::PathPaymentStrictReceive(inner))
     }
@@ -114,7 +114,7 @@ impl PathPaymentStrictSendOperation {
             send_amount,
             dest_asset,
             dest_min,
-            path: path.try_into().map_err(|_| Error::XdrError)?,
+            path: path.try_into().map_err(Error::XdrError)?,
         };
         Ok(xdr::OperationBody::PathPaymentStrictSend(inner))
     }
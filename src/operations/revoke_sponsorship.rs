@@ -155,6 +155,11 @@ impl RevokeSponsorshipLedgerEntry {
     pub fn source_account_mut(&mut self) -> &mut Option<MuxedAccount> {
         &mut self.source_account
     }
+
+    /// Retrieves the ledger key of the sponsored entry.
+    pub fn ledger_key(&self) -> &LedgerKey {
+        &self.ledger_key
+    }
 }
 
 impl RevokeSponsorshipSigner {
@@ -167,6 +172,16 @@ impl RevokeSponsorshipSigner {
     pub fn source_account_mut(&mut self) -> &mut Option<MuxedAccount> {
         &mut self.source_account
     }
+
+    /// Retrieves the account of the sponsored signer.
+    pub fn account_id(&self) -> &PublicKey {
+        &self.account_id
+    }
+
+    /// Retrieves the sponsored signer key.
+    pub fn signer_key(&self) -> &SignerKey {
+        &self.signer_key
+    }
 }
 
 impl LedgerKey {
@@ -198,7 +213,7 @@ impl LedgerKey {
                     .as_bytes()
                     .to_vec()
                     .try_into()
-                    .map_err(|_| Error::XdrError)?;
+                    .map_err(Error::XdrError)?;
                 let inner = xdr::LedgerKeyData {
                     account_id,
                     data_name,
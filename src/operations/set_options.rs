@@ -281,7 +281,7 @@ mod tests {
     use crate::crypto::{Signer, SignerKey};
     use crate::network::Network;
     use crate::operations::tests::*;
-    use crate::operations::Operation;
+    use crate::operations::{Operation, ThresholdLevel};
     use crate::transaction::{Transaction, TransactionEnvelope, MIN_BASE_FEE};
     use crate::xdr::{XDRDeserialize, XDRSerialize};
 
@@ -426,4 +426,26 @@ mod tests {
         let back = TransactionEnvelope::from_xdr_base64(&xdr).unwrap();
         assert_eq!(envelope, back);
     }
+
+    #[test]
+    fn test_set_options_threshold_level() {
+        let op = Operation::new_set_options()
+            .with_set_flags(Some(AccountFlags::AUTH_REQUIRED))
+            .build()
+            .unwrap();
+        assert_eq!(ThresholdLevel::Medium, op.threshold_level());
+
+        let op = Operation::new_set_options()
+            .with_low_threshold(Some(1))
+            .build()
+            .unwrap();
+        assert_eq!(ThresholdLevel::High, op.threshold_level());
+
+        let signer = Signer::new(SignerKey::Ed25519(keypair1().public_key()), 1);
+        let op = Operation::new_set_options()
+            .with_signer(Some(signer))
+            .build()
+            .unwrap();
+        assert_eq!(ThresholdLevel::High, op.threshold_level());
+    }
 }
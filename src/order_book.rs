@@ -0,0 +1,911 @@
+//! Offline order book and offer crossing.
+//!
+//! [`OrderBook`] keeps the offers of one asset pair, in both directions, and
+//! predicts the outcome of `ManageSellOffer`, `ManageBuyOffer` and
+//! `CreatePassiveSellOffer` operations by crossing offers with the same
+//! price and rounding rules used by stellar-core (protocol 10 and later).
+//!
+//! The book doesn't know about account balances, trust line limits or
+//! liabilities: sellers of the offers in the book are assumed to hold the
+//! full offer amount and to be able to receive any amount, and so is the
+//! source of the simulated operation.
+use crate::amount::{Price, Stroops};
+use crate::asset::Asset;
+use crate::crypto::PublicKey;
+use crate::error::{Error, Result};
+use crate::operation_result::{
+    ClaimAtom, ClaimOfferAtom, ManageOfferResultSuccess, OfferEntry, OfferResult,
+};
+use crate::operations::Operation;
+use std::cmp::Ordering;
+
+/// Flag set on passive offers.
+pub const PASSIVE_FLAG: u32 = 1;
+
+/// Maximum number of offers a single operation can cross.
+pub const MAX_OFFERS_TO_CROSS: usize = 1000;
+
+/// Offers of an asset pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBook {
+    base: Asset,
+    counter: Asset,
+    offers: Vec<OfferEntry>,
+    next_offer_id: i64,
+}
+
+/// Rounding used when exchanging assets with an offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rounding {
+    /// Rounding used by offers.
+    Normal,
+    /// Rounding used by path payments with a fixed send amount.
+    PathPaymentStrictSend,
+    /// Rounding used by path payments with a fixed receive amount.
+    PathPaymentStrictReceive,
+}
+
+/// Outcome of crossing offers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Conversion {
+    pub(crate) sheep_sent: i64,
+    pub(crate) wheat_received: i64,
+    pub(crate) offers_claimed: Vec<ClaimAtom>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Exchange {
+    wheat_received: i64,
+    sheep_sent: i64,
+    wheat_stays: bool,
+}
+
+impl OrderBook {
+    /// Creates an empty order book for the `base` and `counter` assets.
+    pub fn new(base: Asset, counter: Asset) -> OrderBook {
+        OrderBook {
+            base,
+            counter,
+            offers: Vec::new(),
+            next_offer_id: 1,
+        }
+    }
+
+    /// Creates an order book for the `base` and `counter` assets with `offers`.
+    pub fn from_offers<I>(base: Asset, counter: Asset, offers: I) -> Result<OrderBook>
+    where
+        I: IntoIterator<Item = OfferEntry>,
+    {
+        let mut book = OrderBook::new(base, counter);
+        for offer in offers {
+            book.insert_offer(offer)?;
+        }
+        Ok(book)
+    }
+
+    /// Sets the id assigned to the next offer created in the book.
+    pub fn with_next_offer_id(mut self, offer_id: i64) -> OrderBook {
+        self.next_offer_id = offer_id;
+        self
+    }
+
+    /// Retrieves the base asset.
+    pub fn base(&self) -> &Asset {
+        &self.base
+    }
+
+    /// Retrieves the counter asset.
+    pub fn counter(&self) -> &Asset {
+        &self.counter
+    }
+
+    /// Retrieves the id assigned to the next offer created in the book.
+    pub fn next_offer_id(&self) -> i64 {
+        self.next_offer_id
+    }
+
+    /// Returns true if the book trades `selling` for `buying`.
+    pub fn is_for_pair(&self, selling: &Asset, buying: &Asset) -> bool {
+        (*selling == self.base && *buying == self.counter)
+            || (*selling == self.counter && *buying == self.base)
+    }
+
+    /// Retrieves all offers in the book.
+    pub fn offers(&self) -> &[OfferEntry] {
+        &self.offers
+    }
+
+    /// Retrieves the offers selling `selling`, best price first.
+    ///
+    /// Offers with the same price are ordered by offer id.
+    pub fn offers_selling(&self, selling: &Asset) -> Vec<&OfferEntry> {
+        let mut offers: Vec<_> = self
+            .offers
+            .iter()
+            .filter(|offer| offer.selling == *selling)
+            .collect();
+        offers.sort_by(|a, b| compare_offers(a, b));
+        offers
+    }
+
+    /// Retrieves the best offer selling `selling`.
+    pub fn best_offer(&self, selling: &Asset) -> Option<&OfferEntry> {
+        self.best_offer_index(selling)
+            .map(|index| &self.offers[index])
+    }
+
+    /// Retrieves the offer `offer_id` of `seller`.
+    pub fn offer(&self, seller: &PublicKey, offer_id: i64) -> Option<&OfferEntry> {
+        self.offers
+            .iter()
+            .find(|offer| offer.seller_id == *seller && offer.offer_id == offer_id)
+    }
+
+    /// Inserts `offer` in the book, replacing the offer with the same seller and id.
+    pub fn insert_offer(&mut self, offer: OfferEntry) -> Result<()> {
+        if !self.is_for_pair(&offer.selling, &offer.buying) {
+            return Err(Error::MalformedOffer(
+                "offer assets don't match the order book".to_string(),
+            ));
+        }
+        if offer.amount.to_i64() <= 0 {
+            return Err(Error::MalformedOffer(
+                "offer amount must be positive".to_string(),
+            ));
+        }
+        check_price(&offer.price)?;
+        self.next_offer_id = self.next_offer_id.max(offer.offer_id + 1);
+        self.remove_offer(&offer.seller_id, offer.offer_id);
+        self.offers.push(offer);
+        Ok(())
+    }
+
+    /// Removes the offer `offer_id` of `seller` from the book.
+    pub fn remove_offer(&mut self, seller: &PublicKey, offer_id: i64) -> Option<OfferEntry> {
+        let index = self
+            .offers
+            .iter()
+            .position(|offer| offer.seller_id == *seller && offer.offer_id == offer_id)?;
+        Some(self.offers.remove(index))
+    }
+
+    /// Simulates a `ManageSellOffer` operation from `seller`.
+    ///
+    /// An `offer_id` of zero creates a new offer, an `amount` of zero deletes
+    /// the existing offer.
+    pub fn manage_sell_offer(
+        &mut self,
+        seller: &PublicKey,
+        offer_id: i64,
+        selling: &Asset,
+        buying: &Asset,
+        amount: Stroops,
+        price: &Price,
+    ) -> Result<ManageOfferResultSuccess> {
+        let request = OfferRequest {
+            seller,
+            offer_id,
+            selling,
+            buying,
+            amount: amount.to_i64(),
+            price: price.clone(),
+            is_buy: false,
+            passive: false,
+        };
+        self.manage_offer(request)
+    }
+
+    /// Simulates a `ManageBuyOffer` operation from `seller`.
+    ///
+    /// `price` is the price of `buying` in terms of `selling`. The offer left
+    /// in the book is a sell offer with the inverse price.
+    pub fn manage_buy_offer(
+        &mut self,
+        seller: &PublicKey,
+        offer_id: i64,
+        selling: &Asset,
+        buying: &Asset,
+        buy_amount: Stroops,
+        price: &Price,
+    ) -> Result<ManageOfferResultSuccess> {
+        check_price(price)?;
+        let request = OfferRequest {
+            seller,
+            offer_id,
+            selling,
+            buying,
+            amount: buy_amount.to_i64(),
+            price: Price::new(price.denominator(), price.numerator()),
+            is_buy: true,
+            passive: false,
+        };
+        self.manage_offer(request)
+    }
+
+    /// Simulates a `CreatePassiveSellOffer` operation from `seller`.
+    ///
+    /// Passive offers don't cross offers with the exact inverse price.
+    pub fn create_passive_sell_offer(
+        &mut self,
+        seller: &PublicKey,
+        selling: &Asset,
+        buying: &Asset,
+        amount: Stroops,
+        price: &Price,
+    ) -> Result<ManageOfferResultSuccess> {
+        if amount.to_i64() <= 0 {
+            return Err(Error::MalformedOffer(
+                "passive offer amount must be positive".to_string(),
+            ));
+        }
+        let request = OfferRequest {
+            seller,
+            offer_id: 0,
+            selling,
+            buying,
+            amount: amount.to_i64(),
+            price: price.clone(),
+            is_buy: false,
+            passive: true,
+        };
+        self.manage_offer(request)
+    }
+
+    /// Simulates the offer operation `operation`.
+    ///
+    /// `source` is used if the operation doesn't have a source account.
+    pub fn apply_operation(
+        &mut self,
+        source: &PublicKey,
+        operation: &Operation,
+    ) -> Result<ManageOfferResultSuccess> {
+        let seller = operation
+            .source_account()
+            .as_ref()
+            .map(|account| *account.public_key())
+            .unwrap_or(*source);
+        match operation {
+            Operation::ManageSellOffer(op) => self.manage_sell_offer(
+                &seller,
+                op.offer_id().unwrap_or(0),
+                op.selling(),
+                op.buying(),
+                *op.amount(),
+                op.price(),
+            ),
+            Operation::ManageBuyOffer(op) => self.manage_buy_offer(
+                &seller,
+                op.offer_id().unwrap_or(0),
+                op.selling(),
+                op.buying(),
+                *op.buy_amount(),
+                op.price(),
+            ),
+            Operation::CreatePassiveSellOffer(op) => self.create_passive_sell_offer(
+                &seller,
+                op.selling(),
+                op.buying(),
+                *op.amount(),
+                op.price(),
+            ),
+            _ => Err(Error::InvalidOperation(
+                "operation is not an offer operation".to_string(),
+            )),
+        }
+    }
+
+    /// Crosses the offers selling `wheat` for `sheep`, best price first.
+    ///
+    /// Crossing stops when `max_sheep_send` is sent, `max_wheat_receive` is
+    /// received, or the next offer is rejected by `filter`. Errors returned by
+    /// `filter` abort the conversion.
+    pub(crate) fn convert<F>(
+        &mut self,
+        sheep: &Asset,
+        wheat: &Asset,
+        max_sheep_send: i64,
+        max_wheat_receive: i64,
+        rounding: Rounding,
+        filter: F,
+    ) -> Result<Conversion>
+    where
+        F: Fn(&OfferEntry) -> Result<bool>,
+    {
+        let mut conversion = Conversion {
+            sheep_sent: 0,
+            wheat_received: 0,
+            offers_claimed: Vec::new(),
+        };
+
+        while conversion.sheep_sent < max_sheep_send
+            && conversion.wheat_received < max_wheat_receive
+        {
+            let Some(index) = self.best_offer_index(wheat) else {
+                break;
+            };
+            if !filter(&self.offers[index])? {
+                break;
+            }
+            if conversion.offers_claimed.len() == MAX_OFFERS_TO_CROSS {
+                return Err(Error::InvalidOperation(
+                    "too many offers crossed".to_string(),
+                ));
+            }
+
+            let offer = &mut self.offers[index];
+            let exchanged = exchange(
+                &offer.price,
+                offer.amount.to_i64(),
+                max_wheat_receive - conversion.wheat_received,
+                max_sheep_send - conversion.sheep_sent,
+                i64::MAX,
+                rounding,
+            );
+
+            let remaining = if exchanged.wheat_stays {
+                adjust_offer(
+                    &offer.price,
+                    offer.amount.to_i64() - exchanged.wheat_received,
+                    i64::MAX,
+                )
+            } else {
+                0
+            };
+            if remaining > 0 && exchanged.wheat_received == 0 && exchanged.sheep_sent == 0 {
+                // The offer can't be crossed with what's left to exchange.
+                break;
+            }
+
+            conversion.sheep_sent += exchanged.sheep_sent;
+            conversion.wheat_received += exchanged.wheat_received;
+            conversion
+                .offers_claimed
+                .push(ClaimAtom::OrderBook(ClaimOfferAtom {
+                    seller_id: offer.seller_id,
+                    offer_id: offer.offer_id,
+                    asset_sold: wheat.clone(),
+                    amount_sold: Stroops::new(exchanged.wheat_received),
+                    asset_bought: sheep.clone(),
+                    amount_bought: Stroops::new(exchanged.sheep_sent),
+                }));
+
+            if remaining > 0 {
+                offer.amount = Stroops::new(remaining);
+                break;
+            }
+            self.offers.remove(index);
+        }
+
+        Ok(conversion)
+    }
+
+    fn manage_offer(&mut self, request: OfferRequest<'_>) -> Result<ManageOfferResultSuccess> {
+        if request.amount < 0 {
+            return Err(Error::MalformedOffer(
+                "offer amount can't be negative".to_string(),
+            ));
+        }
+        if request.offer_id < 0 {
+            return Err(Error::MalformedOffer(
+                "offer id can't be negative".to_string(),
+            ));
+        }
+        check_price(&request.price)?;
+        if request.selling == request.buying {
+            return Err(Error::MalformedOffer(
+                "offer can't sell and buy the same asset".to_string(),
+            ));
+        }
+        if !self.is_for_pair(request.selling, request.buying) {
+            return Err(Error::MalformedOffer(
+                "offer assets don't match the order book".to_string(),
+            ));
+        }
+
+        let existing = if request.offer_id == 0 {
+            if request.amount == 0 {
+                return Err(Error::OfferNotFound(0));
+            }
+            None
+        } else {
+            match self.offer(request.seller, request.offer_id) {
+                Some(offer) => Some(offer.clone()),
+                None => return Err(Error::OfferNotFound(request.offer_id)),
+            }
+        };
+
+        let mut book = self.clone();
+        if let Some(ref existing) = existing {
+            book.remove_offer(&existing.seller_id, existing.offer_id);
+        }
+        if request.amount == 0 {
+            *self = book;
+            return Ok(ManageOfferResultSuccess {
+                offers_claimed: Vec::new(),
+                offer: OfferResult::Deleted,
+            });
+        }
+
+        let (max_sheep_send, max_wheat_receive) = if request.is_buy {
+            (i64::MAX, request.amount)
+        } else {
+            (request.amount, i64::MAX)
+        };
+        // The offer price is the price of sheep in terms of wheat, counter
+        // offers are crossed while their price doesn't exceed its inverse.
+        let price = request.price.clone();
+        let passive = request.passive;
+        let seller = *request.seller;
+        let conversion = book.convert(
+            request.selling,
+            request.buying,
+            max_sheep_send,
+            max_wheat_receive,
+            Rounding::Normal,
+            |offer| {
+                let lhs = i128::from(offer.price.numerator()) * i128::from(price.numerator());
+                let rhs = i128::from(offer.price.denominator()) * i128::from(price.denominator());
+                let crosses = if passive { lhs < rhs } else { lhs <= rhs };
+                if crosses && offer.seller_id == seller {
+                    return Err(Error::OfferCrossSelf);
+                }
+                Ok(crosses)
+            },
+        )?;
+
+        let amount = adjust_offer(
+            &request.price,
+            max_sheep_send - conversion.sheep_sent,
+            max_wheat_receive - conversion.wheat_received,
+        );
+
+        let offer = if amount > 0 {
+            let (offer_id, flags) = match existing {
+                Some(ref existing) => (existing.offer_id, existing.flags),
+                None => {
+                    let offer_id = book.next_offer_id;
+                    book.next_offer_id += 1;
+                    (offer_id, if passive { PASSIVE_FLAG } else { 0 })
+                }
+            };
+            let entry = OfferEntry {
+                seller_id: *request.seller,
+                offer_id,
+                selling: request.selling.clone(),
+                buying: request.buying.clone(),
+                amount: Stroops::new(amount),
+                price: request.price.clone(),
+                flags,
+            };
+            book.offers.push(entry.clone());
+            if existing.is_some() {
+                OfferResult::Updated(entry)
+            } else {
+                OfferResult::Created(entry)
+            }
+        } else {
+            OfferResult::Deleted
+        };
+
+        *self = book;
+        Ok(ManageOfferResultSuccess {
+            offers_claimed: conversion.offers_claimed,
+            offer,
+        })
+    }
+
+    fn best_offer_index(&self, selling: &Asset) -> Option<usize> {
+        self.offers
+            .iter()
+            .enumerate()
+            .filter(|(_, offer)| offer.selling == *selling)
+            .min_by(|(_, a), (_, b)| compare_offers(a, b))
+            .map(|(index, _)| index)
+    }
+}
+
+struct OfferRequest<'a> {
+    seller: &'a PublicKey,
+    offer_id: i64,
+    selling: &'a Asset,
+    buying: &'a Asset,
+    amount: i64,
+    price: Price,
+    is_buy: bool,
+    passive: bool,
+}
+
+fn check_price(price: &Price) -> Result<()> {
+    if price.numerator() <= 0 || price.denominator() <= 0 {
+        return Err(Error::MalformedOffer(
+            "offer price must be positive".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn compare_offers(a: &OfferEntry, b: &OfferEntry) -> Ordering {
+    let lhs = i128::from(a.price.numerator()) * i128::from(b.price.denominator());
+    let rhs = i128::from(b.price.numerator()) * i128::from(a.price.denominator());
+    lhs.cmp(&rhs).then(a.offer_id.cmp(&b.offer_id))
+}
+
+/// Computes the amount an offer can sell so that it can be fully crossed
+/// within the price error bound.
+pub(crate) fn adjust_offer(price: &Price, max_wheat_send: i64, max_sheep_receive: i64) -> i64 {
+    exchange(
+        price,
+        max_wheat_send,
+        i64::MAX,
+        i64::MAX,
+        max_sheep_receive,
+        Rounding::Normal,
+    )
+    .wheat_received
+}
+
+/// Exchanges sheep for the wheat of an offer with `price`, the price of
+/// wheat in terms of sheep.
+fn exchange(
+    price: &Price,
+    max_wheat_send: i64,
+    max_wheat_receive: i64,
+    max_sheep_send: i64,
+    max_sheep_receive: i64,
+    rounding: Rounding,
+) -> Exchange {
+    let n = i128::from(price.numerator());
+    let d = i128::from(price.denominator());
+    let wheat_value = (i128::from(max_wheat_send) * n).min(i128::from(max_sheep_receive) * d);
+    let sheep_value = (i128::from(max_sheep_send) * d).min(i128::from(max_wheat_receive) * n);
+    let wheat_stays = wheat_value > sheep_value;
+
+    let (wheat_received, sheep_sent) = if wheat_stays {
+        if rounding == Rounding::PathPaymentStrictSend {
+            let wheat = big_divide(sheep_value, n, false);
+            (wheat, max_sheep_send.min(max_sheep_receive))
+        } else if n > d || rounding == Rounding::PathPaymentStrictReceive {
+            let wheat = big_divide(sheep_value, n, false);
+            (wheat, big_divide(i128::from(wheat) * n, d, true))
+        } else {
+            let sheep = big_divide(sheep_value, d, false);
+            (big_divide(i128::from(sheep) * d, n, false), sheep)
+        }
+    } else if n > d {
+        let wheat = big_divide(wheat_value, n, false);
+        (wheat, big_divide(i128::from(wheat) * n, d, false))
+    } else {
+        let sheep = big_divide(wheat_value, d, false);
+        (big_divide(i128::from(sheep) * d, n, true), sheep)
+    };
+
+    let (wheat_received, sheep_sent) = if wheat_received > 0 && sheep_sent > 0 {
+        // Path payments accept rounding in favor of the offer.
+        let can_favor_wheat = rounding != Rounding::Normal;
+        if check_price_error_bound(price, wheat_received, sheep_sent, can_favor_wheat) {
+            (wheat_received, sheep_sent)
+        } else {
+            (0, 0)
+        }
+    } else if rounding == Rounding::PathPaymentStrictSend {
+        (0, sheep_sent)
+    } else {
+        (0, 0)
+    };
+
+    Exchange {
+        wheat_received,
+        sheep_sent,
+        wheat_stays,
+    }
+}
+
+/// Returns true if the effective price of the exchange is within 1% of `price`.
+fn check_price_error_bound(
+    price: &Price,
+    wheat_received: i64,
+    sheep_sent: i64,
+    can_favor_wheat: bool,
+) -> bool {
+    let n = i128::from(price.numerator());
+    let d = i128::from(price.denominator());
+    let lhs = 100 * i128::from(sheep_sent) * d;
+    let rhs = 100 * i128::from(wheat_received) * n;
+    if can_favor_wheat && lhs > rhs {
+        return true;
+    }
+    (lhs - rhs).abs() <= i128::from(wheat_received) * n
+}
+
+fn big_divide(value: i128, divisor: i128, round_up: bool) -> i64 {
+    let result = if round_up {
+        (value + divisor - 1) / divisor
+    } else {
+        value / divisor
+    };
+    result.min(i128::from(i64::MAX)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::operations::tests::{keypair0, keypair1, keypair2};
+    use std::str::FromStr;
+
+    fn usd() -> Asset {
+        Asset::new_credit("USD", keypair2().public_key()).unwrap()
+    }
+
+    fn offer(
+        seller: &PublicKey,
+        offer_id: i64,
+        selling: Asset,
+        amount: i64,
+        n: i32,
+        d: i32,
+    ) -> OfferEntry {
+        let buying = if selling == Asset::new_native() {
+            usd()
+        } else {
+            Asset::new_native()
+        };
+        OfferEntry {
+            seller_id: *seller,
+            offer_id,
+            selling,
+            buying,
+            amount: Stroops::new(amount),
+            price: Price::new(n, d),
+            flags: 0,
+        }
+    }
+
+    fn book() -> OrderBook {
+        let maker = keypair1().public_key();
+        OrderBook::from_offers(
+            Asset::new_native(),
+            usd(),
+            vec![
+                offer(&maker, 2, usd(), 100, 2, 1),
+                offer(&maker, 1, usd(), 100, 3, 1),
+                offer(&maker, 3, usd(), 100, 2, 1),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_offers_ordered_by_price_and_id() {
+        let book = book();
+        let ids: Vec<_> = book
+            .offers_selling(&usd())
+            .iter()
+            .map(|offer| offer.offer_id)
+            .collect();
+        assert_eq!(vec![2, 3, 1], ids);
+        assert_eq!(4, book.next_offer_id());
+        assert!(book.best_offer(&Asset::new_native()).is_none());
+    }
+
+    #[test]
+    fn test_manage_sell_offer_crosses_and_rests() {
+        let mut book = book();
+        let taker = keypair0().public_key();
+        // Sell 500 XLM at 1 USD for 2 XLM: crosses the two offers at 2 XLM per USD.
+        let result = book
+            .manage_sell_offer(
+                &taker,
+                0,
+                &Asset::new_native(),
+                &usd(),
+                Stroops::new(500),
+                &Price::new(1, 2),
+            )
+            .unwrap();
+        assert_eq!(2, result.offers_claimed.len());
+        let ClaimAtom::OrderBook(ref first) = result.offers_claimed[0] else {
+            panic!("expected order book claim");
+        };
+        assert_eq!(2, first.offer_id);
+        assert_eq!(Stroops::new(100), first.amount_sold);
+        assert_eq!(Stroops::new(200), first.amount_bought);
+        match result.offer {
+            OfferResult::Created(ref entry) => {
+                assert_eq!(4, entry.offer_id);
+                assert_eq!(Stroops::new(100), entry.amount);
+                assert_eq!(0, entry.flags);
+            }
+            ref other => panic!("unexpected offer result {:?}", other),
+        }
+        assert_eq!(1, book.best_offer(&usd()).unwrap().offer_id);
+        assert_eq!(4, book.best_offer(&Asset::new_native()).unwrap().offer_id);
+    }
+
+    #[test]
+    fn test_partial_cross_updates_offer() {
+        let mut book = book();
+        let taker = keypair0().public_key();
+        let result = book
+            .manage_sell_offer(
+                &taker,
+                0,
+                &Asset::new_native(),
+                &usd(),
+                Stroops::new(51),
+                &Price::new(1, 2),
+            )
+            .unwrap();
+        let ClaimAtom::OrderBook(ref claim) = result.offers_claimed[0] else {
+            panic!("expected order book claim");
+        };
+        assert_eq!(Stroops::new(25), claim.amount_sold);
+        assert_eq!(Stroops::new(50), claim.amount_bought);
+        assert_eq!(OfferResult::Deleted, result.offer);
+        let maker = keypair1().public_key();
+        assert_eq!(Stroops::new(75), book.offer(&maker, 2).unwrap().amount);
+    }
+
+    #[test]
+    fn test_manage_buy_offer() {
+        let mut book = book();
+        let taker = keypair0().public_key();
+        // Buy 250 USD paying at most 2 XLM per USD.
+        let result = book
+            .manage_buy_offer(
+                &taker,
+                0,
+                &Asset::new_native(),
+                &usd(),
+                Stroops::new(250),
+                &Price::new(2, 1),
+            )
+            .unwrap();
+        assert_eq!(2, result.offers_claimed.len());
+        match result.offer {
+            OfferResult::Created(ref entry) => {
+                assert_eq!(Price::new(1, 2), entry.price);
+                assert_eq!(Stroops::new(100), entry.amount);
+            }
+            ref other => panic!("unexpected offer result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_passive_offer_does_not_cross_same_price() {
+        let mut book = book();
+        let taker = keypair0().public_key();
+        let result = book
+            .create_passive_sell_offer(
+                &taker,
+                &Asset::new_native(),
+                &usd(),
+                Stroops::new(100),
+                &Price::new(1, 2),
+            )
+            .unwrap();
+        assert!(result.offers_claimed.is_empty());
+        match result.offer {
+            OfferResult::Created(ref entry) => assert_eq!(PASSIVE_FLAG, entry.flags),
+            ref other => panic!("unexpected offer result {:?}", other),
+        }
+        assert_eq!(4, book.offers().len());
+    }
+
+    #[test]
+    fn test_update_delete_and_errors() {
+        let mut book = book();
+        let maker = keypair1().public_key();
+        let result = book
+            .manage_sell_offer(
+                &maker,
+                1,
+                &usd(),
+                &Asset::new_native(),
+                Stroops::new(50),
+                &Price::new(3, 1),
+            )
+            .unwrap();
+        match result.offer {
+            OfferResult::Updated(ref entry) => assert_eq!(Stroops::new(50), entry.amount),
+            ref other => panic!("unexpected offer result {:?}", other),
+        }
+        let result = book
+            .manage_sell_offer(
+                &maker,
+                1,
+                &usd(),
+                &Asset::new_native(),
+                Stroops::new(0),
+                &Price::new(3, 1),
+            )
+            .unwrap();
+        assert_eq!(OfferResult::Deleted, result.offer);
+        assert!(book.offer(&maker, 1).is_none());
+
+        let err = book
+            .manage_sell_offer(
+                &maker,
+                1,
+                &usd(),
+                &Asset::new_native(),
+                Stroops::new(0),
+                &Price::new(3, 1),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::OfferNotFound(1)));
+
+        let before = book.clone();
+        let err = book
+            .manage_sell_offer(
+                &maker,
+                0,
+                &Asset::new_native(),
+                &usd(),
+                Stroops::new(10),
+                &Price::new(1, 2),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::OfferCrossSelf));
+        assert_eq!(before, book);
+
+        let err = book
+            .manage_sell_offer(
+                &maker,
+                0,
+                &usd(),
+                &usd(),
+                Stroops::new(10),
+                &Price::new(1, 2),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::MalformedOffer(_)));
+    }
+
+    #[test]
+    fn test_apply_operation() {
+        let mut book = book();
+        let op = Operation::new_manage_sell_offer()
+            .with_selling_asset(Asset::new_native())
+            .with_buying_asset(usd())
+            .with_amount(Amount::from_str("0.00002").unwrap())
+            .unwrap()
+            .with_price(Price::new(1, 2))
+            .build()
+            .unwrap();
+        let result = book.apply_operation(&keypair0().public_key(), &op).unwrap();
+        assert_eq!(1, result.offers_claimed.len());
+        assert_eq!(OfferResult::Deleted, result.offer);
+    }
+
+    #[test]
+    fn test_exchange_rounding() {
+        // Offer sells 1 wheat at 3 sheep each, taker sends 2 sheep: nothing happens.
+        let exchanged = exchange(
+            &Price::new(3, 1),
+            1,
+            i64::MAX,
+            2,
+            i64::MAX,
+            Rounding::Normal,
+        );
+        assert_eq!(0, exchanged.wheat_received);
+        assert_eq!(0, exchanged.sheep_sent);
+        assert!(exchanged.wheat_stays);
+
+        let exchanged = exchange(
+            &Price::new(1, 3),
+            10,
+            i64::MAX,
+            3,
+            i64::MAX,
+            Rounding::Normal,
+        );
+        assert_eq!(9, exchanged.wheat_received);
+        assert_eq!(3, exchanged.sheep_sent);
+
+        // Amounts that can't be crossed within 1% of the price are removed.
+        assert_eq!(0, adjust_offer(&Price::new(3, 2), 3, i64::MAX));
+        assert_eq!(101, adjust_offer(&Price::new(3, 2), 101, i64::MAX));
+    }
+}
@@ -0,0 +1,532 @@
+//! Local path finding for path payments.
+//!
+//! [`PathFinder`] searches a snapshot of order books and constant fee
+//! liquidity pools for the paths a path payment can take between two
+//! assets. Each hop of a path is priced against the order book or the
+//! liquidity pool of the asset pair, whichever gives the best rate, with
+//! the rounding rules stellar-core uses for path payments.
+//!
+//! ```
+//! use stellar_base::amount::{Price, Stroops};
+//! use stellar_base::asset::Asset;
+//! use stellar_base::crypto::PublicKey;
+//! use stellar_base::operation_result::OfferEntry;
+//! use stellar_base::order_book::OrderBook;
+//! use stellar_base::path_finder::PathFinder;
+//!
+//! # fn main() -> Result<(), stellar_base::error::Error> {
+//! let issuer = PublicKey::from_account_id("GCZHXL5HXQX5ABDM26LHYRCQZ5OJFHLOPLZX47WEBP3V2PF5AVFK2A5D")?;
+//! let usd = Asset::new_credit("USD", issuer)?;
+//! let maker = PublicKey::from_account_id("GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ")?;
+//! let offer = OfferEntry {
+//!     seller_id: maker,
+//!     offer_id: 1,
+//!     selling: usd.clone(),
+//!     buying: Asset::new_native(),
+//!     amount: Stroops::new(1_000_000),
+//!     price: Price::new(2, 1),
+//!     flags: 0,
+//! };
+//! let book = OrderBook::from_offers(Asset::new_native(), usd.clone(), vec![offer])?;
+//! let paths = PathFinder::new()
+//!     .with_order_book(book)
+//!     .find_strict_send(&Asset::new_native(), Stroops::new(200), &[usd])?;
+//! assert_eq!(Stroops::new(100), *paths[0].destination_amount());
+//! # Ok(())
+//! # }
+//! ```
+use crate::amount::Stroops;
+use crate::asset::Asset;
+use crate::error::{Error, Result};
+use crate::liquidity_pool::LiquidityPoolConstantFee;
+use crate::operations::{
+    PathPaymentStrictReceiveOperationBuilder, PathPaymentStrictSendOperationBuilder,
+};
+use crate::order_book::{OrderBook, Rounding};
+
+/// Maximum number of intermediate assets in a path payment.
+pub const MAX_PATH_LENGTH: usize = 5;
+
+/// Finds payment paths over order books and liquidity pools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathFinder {
+    order_books: Vec<OrderBook>,
+    liquidity_pools: Vec<LiquidityPoolConstantFee>,
+    max_path_length: usize,
+}
+
+/// A payment path with its expected amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentPath {
+    source_asset: Asset,
+    source_amount: Stroops,
+    destination_asset: Asset,
+    destination_amount: Stroops,
+    path: Vec<Asset>,
+}
+
+impl PathFinder {
+    /// Creates a path finder without order books and liquidity pools.
+    pub fn new() -> PathFinder {
+        PathFinder {
+            order_books: Vec::new(),
+            liquidity_pools: Vec::new(),
+            max_path_length: MAX_PATH_LENGTH,
+        }
+    }
+
+    /// Adds `order_book` to the snapshot.
+    pub fn with_order_book(mut self, order_book: OrderBook) -> PathFinder {
+        self.order_books.push(order_book);
+        self
+    }
+
+    /// Adds `liquidity_pool` to the snapshot.
+    pub fn with_liquidity_pool(mut self, liquidity_pool: LiquidityPoolConstantFee) -> PathFinder {
+        self.liquidity_pools.push(liquidity_pool);
+        self
+    }
+
+    /// Sets the maximum number of intermediate assets of the paths.
+    pub fn with_max_path_length(mut self, max_path_length: usize) -> Result<PathFinder> {
+        if max_path_length > MAX_PATH_LENGTH {
+            return Err(Error::InvalidOperation(format!(
+                "path length can't exceed {}",
+                MAX_PATH_LENGTH
+            )));
+        }
+        self.max_path_length = max_path_length;
+        Ok(self)
+    }
+
+    /// Retrieves the order books of the snapshot.
+    pub fn order_books(&self) -> &[OrderBook] {
+        &self.order_books
+    }
+
+    /// Retrieves the liquidity pools of the snapshot.
+    pub fn liquidity_pools(&self) -> &[LiquidityPoolConstantFee] {
+        &self.liquidity_pools
+    }
+
+    /// Finds the paths to send exactly `send_amount` of `send_asset` and
+    /// receive one of `destination_assets`.
+    ///
+    /// Paths are ranked by destination amount, highest first, then by length.
+    pub fn find_strict_send(
+        &self,
+        send_asset: &Asset,
+        send_amount: Stroops,
+        destination_assets: &[Asset],
+    ) -> Result<Vec<PaymentPath>> {
+        if send_amount.to_i64() <= 0 {
+            return Err(Error::InvalidStroopsAmount);
+        }
+        let mut paths = Vec::new();
+        let mut visited = vec![send_asset.clone()];
+        self.search_strict_send(
+            &mut visited,
+            send_amount.to_i64(),
+            send_amount,
+            destination_assets,
+            &mut paths,
+        );
+        paths.sort_by(|a: &PaymentPath, b: &PaymentPath| {
+            b.destination_amount
+                .cmp(&a.destination_amount)
+                .then(a.path.len().cmp(&b.path.len()))
+        });
+        Ok(paths)
+    }
+
+    /// Finds the paths to receive exactly `destination_amount` of
+    /// `destination_asset` sending one of `source_assets`.
+    ///
+    /// Paths are ranked by source amount, lowest first, then by length.
+    pub fn find_strict_receive(
+        &self,
+        source_assets: &[Asset],
+        destination_asset: &Asset,
+        destination_amount: Stroops,
+    ) -> Result<Vec<PaymentPath>> {
+        if destination_amount.to_i64() <= 0 {
+            return Err(Error::InvalidStroopsAmount);
+        }
+        let mut paths = Vec::new();
+        let mut visited = vec![destination_asset.clone()];
+        self.search_strict_receive(
+            &mut visited,
+            destination_amount.to_i64(),
+            destination_amount,
+            source_assets,
+            &mut paths,
+        );
+        paths.sort_by(|a: &PaymentPath, b: &PaymentPath| {
+            a.source_amount
+                .cmp(&b.source_amount)
+                .then(a.path.len().cmp(&b.path.len()))
+        });
+        Ok(paths)
+    }
+
+    /// Extends the path in `visited`, from the send asset to the last asset,
+    /// which can be bought with `amount`.
+    fn search_strict_send(
+        &self,
+        visited: &mut Vec<Asset>,
+        amount: i64,
+        send_amount: Stroops,
+        destination_assets: &[Asset],
+        paths: &mut Vec<PaymentPath>,
+    ) {
+        let last = visited[visited.len() - 1].clone();
+        if destination_assets.contains(&last) {
+            paths.push(PaymentPath {
+                source_asset: visited[0].clone(),
+                source_amount: send_amount,
+                destination_asset: last.clone(),
+                destination_amount: Stroops::new(amount),
+                path: intermediate_assets(visited),
+            });
+        }
+        if visited.len() > self.max_path_length + 1 {
+            return;
+        }
+        for next in self.neighbours(&last) {
+            if visited.contains(&next) {
+                continue;
+            }
+            if let Some(received) = self.strict_send_hop(&last, &next, amount) {
+                visited.push(next);
+                self.search_strict_send(visited, received, send_amount, destination_assets, paths);
+                visited.pop();
+            }
+        }
+    }
+
+    /// Extends the path in `visited`, from the destination asset back to the
+    /// last asset, of which `amount` is needed.
+    fn search_strict_receive(
+        &self,
+        visited: &mut Vec<Asset>,
+        amount: i64,
+        destination_amount: Stroops,
+        source_assets: &[Asset],
+        paths: &mut Vec<PaymentPath>,
+    ) {
+        let last = visited[visited.len() - 1].clone();
+        if source_assets.contains(&last) {
+            let mut path = intermediate_assets(visited);
+            path.reverse();
+            paths.push(PaymentPath {
+                source_asset: last.clone(),
+                source_amount: Stroops::new(amount),
+                destination_asset: visited[0].clone(),
+                destination_amount,
+                path,
+            });
+        }
+        if visited.len() > self.max_path_length + 1 {
+            return;
+        }
+        for previous in self.neighbours(&last) {
+            if visited.contains(&previous) {
+                continue;
+            }
+            if let Some(sent) = self.strict_receive_hop(&previous, &last, amount) {
+                visited.push(previous);
+                self.search_strict_receive(visited, sent, destination_amount, source_assets, paths);
+                visited.pop();
+            }
+        }
+    }
+
+    /// Returns the assets traded against `asset`.
+    fn neighbours(&self, asset: &Asset) -> Vec<Asset> {
+        let books = self
+            .order_books
+            .iter()
+            .map(|book| (book.base(), book.counter()));
+        let pools = self
+            .liquidity_pools
+            .iter()
+            .map(|pool| (&pool.assets().0, &pool.assets().1));
+        let mut neighbours: Vec<Asset> = Vec::new();
+        for (a, b) in books.chain(pools) {
+            let other = if a == asset {
+                b
+            } else if b == asset {
+                a
+            } else {
+                continue;
+            };
+            if !neighbours.contains(other) {
+                neighbours.push(other.clone());
+            }
+        }
+        neighbours
+    }
+
+    /// Returns the best amount of `to` received for `amount` of `from`.
+    fn strict_send_hop(&self, from: &Asset, to: &Asset, amount: i64) -> Option<i64> {
+        let book = self
+            .order_books
+            .iter()
+            .filter(|book| book.is_for_pair(from, to))
+            .filter_map(|book| {
+                let conversion = book
+                    .clone()
+                    .convert(
+                        from,
+                        to,
+                        amount,
+                        i64::MAX,
+                        Rounding::PathPaymentStrictSend,
+                        |_| Ok(true),
+                    )
+                    .ok()?;
+                if conversion.sheep_sent == amount {
+                    Some(conversion.wheat_received)
+                } else {
+                    None
+                }
+            });
+        let pools = self
+            .liquidity_pools
+            .iter()
+            .filter(|pool| pool.reserve(to).is_some())
+            .filter_map(|pool| pool.strict_send_amount(from, amount));
+        book.chain(pools).max().filter(|received| *received > 0)
+    }
+
+    /// Returns the lowest amount of `from` needed to receive `amount` of `to`.
+    fn strict_receive_hop(&self, from: &Asset, to: &Asset, amount: i64) -> Option<i64> {
+        let book = self
+            .order_books
+            .iter()
+            .filter(|book| book.is_for_pair(from, to))
+            .filter_map(|book| {
+                let conversion = book
+                    .clone()
+                    .convert(
+                        from,
+                        to,
+                        i64::MAX,
+                        amount,
+                        Rounding::PathPaymentStrictReceive,
+                        |_| Ok(true),
+                    )
+                    .ok()?;
+                if conversion.wheat_received == amount {
+                    Some(conversion.sheep_sent)
+                } else {
+                    None
+                }
+            });
+        let pools = self
+            .liquidity_pools
+            .iter()
+            .filter(|pool| pool.reserve(from).is_some())
+            .filter_map(|pool| pool.strict_receive_amount(to, amount));
+        book.chain(pools).min()
+    }
+}
+
+/// Returns the assets of `visited` between the first and the last.
+fn intermediate_assets(visited: &[Asset]) -> Vec<Asset> {
+    if visited.len() > 2 {
+        visited[1..visited.len() - 1].to_vec()
+    } else {
+        Vec::new()
+    }
+}
+
+impl Default for PathFinder {
+    fn default() -> Self {
+        PathFinder::new()
+    }
+}
+
+impl PaymentPath {
+    /// Retrieves the asset sent.
+    pub fn source_asset(&self) -> &Asset {
+        &self.source_asset
+    }
+
+    /// Retrieves the amount sent.
+    pub fn source_amount(&self) -> &Stroops {
+        &self.source_amount
+    }
+
+    /// Retrieves the asset received.
+    pub fn destination_asset(&self) -> &Asset {
+        &self.destination_asset
+    }
+
+    /// Retrieves the amount received.
+    pub fn destination_amount(&self) -> &Stroops {
+        &self.destination_amount
+    }
+
+    /// Retrieves the intermediate assets, from source to destination.
+    pub fn path(&self) -> &[Asset] {
+        &self.path
+    }
+
+    /// Returns a strict send path payment builder sending the source amount,
+    /// with the destination amount as minimum.
+    ///
+    /// The destination account still needs to be set.
+    pub fn to_strict_send_builder(&self) -> Result<PathPaymentStrictSendOperationBuilder> {
+        let builder = PathPaymentStrictSendOperationBuilder::new()
+            .with_send_asset(self.source_asset.clone())
+            .with_send_amount(self.source_amount)?
+            .with_destination_asset(self.destination_asset.clone())
+            .with_destination_min(self.destination_amount)?;
+        Ok(self
+            .path
+            .iter()
+            .fold(builder, |builder, asset| builder.add_asset(asset.clone())))
+    }
+
+    /// Returns a strict receive path payment builder receiving the
+    /// destination amount, with the source amount as maximum.
+    ///
+    /// The destination account still needs to be set.
+    pub fn to_strict_receive_builder(&self) -> Result<PathPaymentStrictReceiveOperationBuilder> {
+        let builder = PathPaymentStrictReceiveOperationBuilder::new()
+            .with_send_asset(self.source_asset.clone())
+            .with_send_max(self.source_amount)?
+            .with_destination_asset(self.destination_asset.clone())
+            .with_destination_amount(self.destination_amount)?;
+        Ok(self
+            .path
+            .iter()
+            .fold(builder, |builder, asset| builder.add_asset(asset.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Price;
+    use crate::liquidity_pool::LIQUIDITY_POOL_FEE_V18;
+    use crate::operation_result::OfferEntry;
+    use crate::operations::tests::{keypair0, keypair1, keypair2};
+    use crate::operations::Operation;
+
+    fn credit(code: &str) -> Asset {
+        Asset::new_credit(code, keypair2().public_key()).unwrap()
+    }
+
+    fn book(selling: Asset, buying: Asset, amount: i64, price: Price) -> OrderBook {
+        let offer = OfferEntry {
+            seller_id: keypair1().public_key(),
+            offer_id: 1,
+            selling: selling.clone(),
+            buying: buying.clone(),
+            amount: Stroops::new(amount),
+            price,
+            flags: 0,
+        };
+        OrderBook::from_offers(selling, buying, vec![offer]).unwrap()
+    }
+
+    fn pool(a: Asset, b: Asset, reserves: (i64, i64)) -> LiquidityPoolConstantFee {
+        LiquidityPoolConstantFee::new((a, b), LIQUIDITY_POOL_FEE_V18, reserves, 1000, 1).unwrap()
+    }
+
+    fn finder() -> PathFinder {
+        let xlm = Asset::new_native();
+        PathFinder::new()
+            // 1 USD for 2 XLM.
+            .with_order_book(book(credit("USD"), xlm.clone(), 1_000, Price::new(2, 1)))
+            // 1 EUR for 1 USD.
+            .with_order_book(book(credit("EUR"), credit("USD"), 1_000, Price::new(1, 1)))
+            .with_liquidity_pool(pool(xlm, credit("EUR"), (1_000_000, 1_000_000)))
+    }
+
+    #[test]
+    fn test_pool_amounts() {
+        let pool = pool(Asset::new_native(), credit("EUR"), (1_000, 1_000));
+        // 9970 * 1000 * 100 / (10000 * 1000 + 9970 * 100)
+        assert_eq!(Some(90), pool.strict_send_amount(&Asset::new_native(), 100));
+        // ceil(10000 * 1000 * 90 / (910 * 9970))
+        assert_eq!(Some(100), pool.strict_receive_amount(&credit("EUR"), 90));
+        assert_eq!(None, pool.strict_receive_amount(&credit("EUR"), 1_000));
+        assert_eq!(None, pool.strict_send_amount(&credit("USD"), 100));
+    }
+
+    #[test]
+    fn test_find_strict_send() {
+        let paths = finder()
+            .find_strict_send(&Asset::new_native(), Stroops::new(200), &[credit("EUR")])
+            .unwrap();
+        assert_eq!(2, paths.len());
+        // The pool path pays 199, the path through USD pays 100.
+        assert_eq!(Stroops::new(199), *paths[0].destination_amount());
+        assert!(paths[0].path().is_empty());
+        assert_eq!(Stroops::new(100), *paths[1].destination_amount());
+        assert_eq!(vec![credit("USD")], paths[1].path());
+    }
+
+    #[test]
+    fn test_find_strict_receive() {
+        let paths = finder()
+            .find_strict_receive(&[Asset::new_native()], &credit("EUR"), Stroops::new(100))
+            .unwrap();
+        assert_eq!(2, paths.len());
+        assert_eq!(Stroops::new(101), *paths[0].source_amount());
+        assert!(paths[0].path().is_empty());
+        assert_eq!(Stroops::new(200), *paths[1].source_amount());
+        assert_eq!(vec![credit("USD")], paths[1].path());
+
+        // Not enough offers to receive the amount.
+        let paths = PathFinder::new()
+            .with_order_book(book(
+                credit("USD"),
+                Asset::new_native(),
+                10,
+                Price::new(1, 1),
+            ))
+            .find_strict_receive(&[Asset::new_native()], &credit("USD"), Stroops::new(100))
+            .unwrap();
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn test_max_path_length_and_builders() {
+        let paths = finder()
+            .with_max_path_length(0)
+            .unwrap()
+            .find_strict_send(&Asset::new_native(), Stroops::new(200), &[credit("EUR")])
+            .unwrap();
+        assert_eq!(1, paths.len());
+        assert!(PathFinder::new().with_max_path_length(6).is_err());
+
+        let paths = finder()
+            .find_strict_send(&Asset::new_native(), Stroops::new(200), &[credit("EUR")])
+            .unwrap();
+        let op = paths[1]
+            .to_strict_send_builder()
+            .unwrap()
+            .with_destination(keypair0().public_key())
+            .build()
+            .unwrap();
+        match op {
+            Operation::PathPaymentStrictSend(op) => {
+                assert_eq!(Stroops::new(200), *op.send_amount());
+                assert_eq!(Stroops::new(100), *op.destination_min());
+                assert_eq!(&vec![credit("USD")], op.path());
+            }
+            _ => panic!("expected path payment strict send"),
+        }
+        let op = paths[1]
+            .to_strict_receive_builder()
+            .unwrap()
+            .with_destination(keypair0().public_key())
+            .build()
+            .unwrap();
+        assert!(matches!(op, Operation::PathPaymentStrictReceive(_)));
+    }
+}
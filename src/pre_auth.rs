@@ -0,0 +1,256 @@
+//! Pre authorized transactions.
+//!
+//! A transaction is pre authorized by adding its hash as a signer of its
+//! source account. It can then be submitted without any other signature once
+//! the source account reaches the sequence number preceding the transaction.
+//! The signer is removed from the account when the transaction is applied.
+use crate::crypto::{MuxedAccount, PreAuthTxHash, Signer, SignerKey};
+use crate::error::{Error, Result};
+use crate::network::Network;
+use crate::operations::Operation;
+use crate::transaction::{Transaction, TransactionEnvelope, TransactionHash};
+
+/// A transaction to be pre authorized on a network.
+///
+/// For fee bump transactions the inner transaction is pre authorized, since
+/// stellar-core checks the signers of the inner source account against the
+/// inner transaction hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreAuthorizedTransaction {
+    envelope: TransactionEnvelope,
+    network: Network,
+    hash: TransactionHash,
+}
+
+impl PreAuthorizedTransaction {
+    /// Creates a `PreAuthorizedTransaction` for `envelope` on `network`.
+    pub fn new(
+        envelope: TransactionEnvelope,
+        network: Network,
+    ) -> Result<PreAuthorizedTransaction> {
+        let hash = pre_authorized_hash(&envelope, &network)?;
+        Ok(PreAuthorizedTransaction {
+            envelope,
+            network,
+            hash,
+        })
+    }
+
+    /// Retrieves the transaction envelope.
+    pub fn envelope(&self) -> &TransactionEnvelope {
+        &self.envelope
+    }
+
+    /// Retrieves the network.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Retrieves the hash of the pre authorized transaction, the inner
+    /// transaction for fee bump transactions.
+    pub fn hash(&self) -> &TransactionHash {
+        &self.hash
+    }
+
+    /// Retrieves the source account of the transaction, which is the account
+    /// that needs the pre authorized signer.
+    ///
+    /// For fee bump transactions this is the source account of the inner transaction.
+    pub fn source_account(&self) -> &MuxedAccount {
+        self.transaction().source_account()
+    }
+
+    /// Retrieves the sequence number of the transaction.
+    pub fn sequence(&self) -> i64 {
+        *self.transaction().sequence()
+    }
+
+    /// Returns the sequence number the source account must have for the
+    /// transaction to be valid.
+    pub fn required_account_sequence(&self) -> Result<i64> {
+        self.sequence()
+            .checked_sub(1)
+            .ok_or(Error::InvalidSequenceNumber)
+    }
+
+    /// Returns the pre authorized transaction signer key.
+    pub fn signer_key(&self) -> SignerKey {
+        SignerKey::new_from_pre_authorized_transaction(PreAuthTxHash::from(self.hash))
+    }
+
+    /// Returns the pre authorized transaction signer with `weight`.
+    pub fn signer(&self, weight: u32) -> Signer {
+        Signer::new(self.signer_key(), weight)
+    }
+
+    /// Returns the set options operation that adds the pre authorized
+    /// transaction signer with `weight` to the source account.
+    ///
+    /// A `weight` of 0 removes the signer.
+    pub fn set_options_operation(&self, weight: u32) -> Result<Operation> {
+        Operation::new_set_options()
+            .with_source_account(self.source_account().clone())
+            .with_signer(Some(self.signer(weight)))
+            .build()
+    }
+
+    /// Verifies that `envelope` is the pre authorized transaction, or a fee
+    /// bump transaction wrapping it.
+    ///
+    /// Signatures are not part of the transaction hash, so the envelope can
+    /// have different signatures.
+    pub fn verify(&self, envelope: &TransactionEnvelope) -> Result<()> {
+        if pre_authorized_hash(envelope, &self.network)? != self.hash {
+            return Err(Error::PreAuthTxMismatch);
+        }
+        Ok(())
+    }
+
+    fn transaction(&self) -> &Transaction {
+        match &self.envelope {
+            TransactionEnvelope::Transaction(tx) => tx,
+            TransactionEnvelope::FeeBumpTransaction(tx) => tx.inner_transaction(),
+        }
+    }
+}
+
+/// Returns the hash of the transaction of `envelope` checked against the pre
+/// authorized signers, the inner transaction for fee bump transactions.
+fn pre_authorized_hash(
+    envelope: &TransactionEnvelope,
+    network: &Network,
+) -> Result<TransactionHash> {
+    match envelope {
+        TransactionEnvelope::Transaction(_) => envelope.transaction_hash(network),
+        TransactionEnvelope::FeeBumpTransaction(tx) => {
+            TransactionHash::from_slice(&tx.inner_transaction().hash(network)?)
+        }
+    }
+}
+
+/// Returns the sequence number to give to a transaction pre authorized on an
+/// account with `account_sequence`.
+///
+/// `preceding_transactions` is the number of transactions from the account that
+/// are submitted before, including the one adding the pre authorized signer
+/// when the account is its source.
+pub fn pre_authorized_sequence(account_sequence: i64, preceding_transactions: u32) -> Result<i64> {
+    account_sequence
+        .checked_add(i64::from(preceding_transactions))
+        .and_then(|sequence| sequence.checked_add(1))
+        .ok_or(Error::InvalidSequenceNumber)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pre_authorized_sequence, PreAuthorizedTransaction};
+    use crate::crypto::{DalekKeyPair, MuxedAccount, SignerKey};
+    use crate::error::Error;
+    use crate::network::Network;
+    use crate::operations::Operation;
+    use crate::transaction::{FeeBumpTransaction, Transaction, TransactionHash, MIN_BASE_FEE};
+
+    #[test]
+    fn test_pre_authorized_transaction() {
+        let kp = DalekKeyPair::random().unwrap();
+        let network = Network::new_test();
+        let account_sequence = 100;
+
+        // The account submits the set options transaction first.
+        let sequence = pre_authorized_sequence(account_sequence, 1).unwrap();
+        assert_eq!(102, sequence);
+        let tx = Transaction::builder(kp.public_key(), sequence, MIN_BASE_FEE)
+            .add_operation(
+                Operation::new_bump_sequence()
+                    .with_bump_to(200)
+                    .build()
+                    .unwrap(),
+            )
+            .into_transaction()
+            .unwrap();
+        let envelope = tx.into_envelope();
+        let pre_auth = PreAuthorizedTransaction::new(envelope.clone(), network.clone()).unwrap();
+        assert_eq!(101, pre_auth.required_account_sequence().unwrap());
+        let source = MuxedAccount::from(kp.public_key());
+        assert_eq!(&source, pre_auth.source_account());
+
+        let hash = TransactionHash::new_from_transaction_envelope(&envelope, &network).unwrap();
+        assert_eq!(&hash, pre_auth.hash());
+        assert_eq!(
+            SignerKey::new_from_transaction_envelope(&envelope, &network).unwrap(),
+            pre_auth.signer_key()
+        );
+        match pre_auth.set_options_operation(1).unwrap() {
+            Operation::SetOptions(op) => {
+                assert_eq!(Some(&source), op.source_account().as_ref());
+                let signer = op.signer().as_ref().unwrap();
+                assert_eq!(&pre_auth.signer_key(), signer.key());
+                assert_eq!(&1, signer.weight());
+            }
+            op => panic!("unexpected operation {:?}", op),
+        }
+
+        let mut signed = envelope.clone();
+        signed.sign(kp.as_ref(), &network).unwrap();
+        pre_auth.verify(&signed).unwrap();
+
+        let mut other = envelope;
+        *other.as_transaction_mut().unwrap().sequence_mut() = 103;
+        assert!(matches!(
+            pre_auth.verify(&other),
+            Err(Error::PreAuthTxMismatch)
+        ));
+
+        assert!(matches!(
+            pre_authorized_sequence(i64::MAX, 0),
+            Err(Error::InvalidSequenceNumber)
+        ));
+    }
+
+    #[test]
+    fn test_pre_authorized_fee_bump_transaction() {
+        let kp = DalekKeyPair::random().unwrap();
+        let fee_source = DalekKeyPair::random().unwrap();
+        let network = Network::new_test();
+        let tx = Transaction::builder(kp.public_key(), 102, MIN_BASE_FEE)
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap();
+        let inner = tx.clone().into_envelope();
+        let fee_bump = FeeBumpTransaction::builder(fee_source.public_key(), tx)
+            .into_transaction()
+            .unwrap()
+            .into_envelope();
+
+        let pre_auth = PreAuthorizedTransaction::new(fee_bump.clone(), network.clone()).unwrap();
+        let inner_hash = TransactionHash::new_from_transaction_envelope(&inner, &network).unwrap();
+        assert_eq!(&inner_hash, pre_auth.hash());
+        assert_ne!(
+            &fee_bump.transaction_hash(&network).unwrap(),
+            pre_auth.hash()
+        );
+        assert_eq!(
+            SignerKey::new_from_transaction_envelope(&inner, &network).unwrap(),
+            pre_auth.signer_key()
+        );
+        assert_eq!(
+            &MuxedAccount::from(kp.public_key()),
+            pre_auth.source_account()
+        );
+
+        pre_auth.verify(&fee_bump).unwrap();
+        pre_auth.verify(&inner).unwrap();
+        let other = FeeBumpTransaction::builder(fee_source.public_key(), {
+            let mut tx = inner.as_transaction().unwrap().clone();
+            *tx.sequence_mut() = 103;
+            tx
+        })
+        .into_transaction()
+        .unwrap()
+        .into_envelope();
+        assert!(matches!(
+            pre_auth.verify(&other),
+            Err(Error::PreAuthTxMismatch)
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+//! SEP-29 account memo requirements.
+//!
+//! Accounts that need a memo to credit incoming payments (for example
+//! exchanges) set the `config.memo_required` data entry to `1`. Wallets should
+//! check the destinations of a transaction before submitting it.
+//!
+//! See [SEP-29](https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0029.md).
+use std::collections::HashMap;
+
+use crate::crypto::{MuxedAccount, PublicKey};
+use crate::error::Result;
+use crate::transaction::Transaction;
+
+/// Name of the data entry that flags accounts requiring a memo.
+pub const MEMO_REQUIRED_DATA_NAME: &str = "config.memo_required";
+
+/// Value of the data entry for accounts requiring a memo.
+pub const MEMO_REQUIRED_DATA_VALUE: &[u8] = b"1";
+
+/// Looks up the `config.memo_required` data entry of accounts.
+///
+/// Implementations can query Horizon, a database, or an in-memory map.
+pub trait MemoRequiredLookup {
+    /// Returns the value of the `config.memo_required` data entry of
+    /// `account`, or None if the account doesn't have one.
+    fn memo_required_data(&self, account: &PublicKey) -> Result<Option<Vec<u8>>>;
+}
+
+/// An operation sending funds to an account that requires a memo, in a
+/// transaction without memo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoRequiredViolation {
+    operation: usize,
+    destination: PublicKey,
+}
+
+impl MemoRequiredViolation {
+    /// Retrieves the index of the operation.
+    pub fn operation(&self) -> usize {
+        self.operation
+    }
+
+    /// Retrieves the destination account that requires a memo.
+    pub fn destination(&self) -> &PublicKey {
+        &self.destination
+    }
+}
+
+/// Returns the payments, path payments and account merges of `tx` to
+/// accounts that require a memo.
+///
+/// Transactions with a memo and operations with a muxed destination are
+/// always accepted. Each destination is looked up once.
+pub fn check_memo_required<L>(tx: &Transaction, lookup: &L) -> Result<Vec<MemoRequiredViolation>>
+where
+    L: MemoRequiredLookup + ?Sized,
+{
+    let mut violations = Vec::new();
+    if !tx.memo().is_none() {
+        return Ok(violations);
+    }
+    let mut memo_required = HashMap::new();
+    for (operation, op) in tx.operations().iter().enumerate() {
+        let destination = match op.destination() {
+            Some(MuxedAccount::Ed25519(destination)) => *destination,
+            _ => continue,
+        };
+        let required = match memo_required.get(&destination) {
+            Some(required) => *required,
+            None => {
+                let value = lookup.memo_required_data(&destination)?;
+                let required = value.as_deref() == Some(MEMO_REQUIRED_DATA_VALUE);
+                memo_required.insert(destination, required);
+                required
+            }
+        };
+        if required {
+            violations.push(MemoRequiredViolation {
+                operation,
+                destination,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+impl MemoRequiredLookup for HashMap<PublicKey, Vec<u8>> {
+    fn memo_required_data(&self, account: &PublicKey) -> Result<Option<Vec<u8>>> {
+        Ok(self.get(account).cloned())
+    }
+}
+
+#[cfg(feature = "dalek")]
+impl MemoRequiredLookup for crate::apply::LedgerState {
+    fn memo_required_data(&self, account: &PublicKey) -> Result<Option<Vec<u8>>> {
+        let entry = self.data(account, MEMO_REQUIRED_DATA_NAME)?;
+        Ok(entry.map(|entry| entry.data_value.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use super::{check_memo_required, MemoRequiredLookup, MEMO_REQUIRED_DATA_VALUE};
+    use crate::amount::Stroops;
+    use crate::asset::Asset;
+    use crate::crypto::{MuxedAccount, MuxedEd25519PublicKey, PublicKey};
+    use crate::error::Result;
+    use crate::memo::Memo;
+    use crate::operations::tests::*;
+    use crate::operations::Operation;
+    use crate::transaction::{Transaction, MIN_BASE_FEE};
+
+    struct CountingLookup {
+        inner: HashMap<PublicKey, Vec<u8>>,
+        lookups: RefCell<usize>,
+    }
+
+    impl MemoRequiredLookup for CountingLookup {
+        fn memo_required_data(&self, account: &PublicKey) -> Result<Option<Vec<u8>>> {
+            *self.lookups.borrow_mut() += 1;
+            self.inner.memo_required_data(account)
+        }
+    }
+
+    fn payment<D: Into<MuxedAccount>>(destination: D) -> Operation {
+        Operation::new_payment()
+            .with_destination(destination)
+            .with_amount(Stroops::new(10))
+            .unwrap()
+            .with_asset(Asset::new_native())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_check_memo_required() {
+        let exchange = keypair1().public_key();
+        let other = keypair2().public_key();
+        let mut accounts = HashMap::new();
+        accounts.insert(exchange, MEMO_REQUIRED_DATA_VALUE.to_vec());
+        accounts.insert(other, b"0".to_vec());
+        let lookup = CountingLookup {
+            inner: accounts,
+            lookups: RefCell::new(0),
+        };
+
+        let merge = Operation::new_account_merge()
+            .with_destination(exchange.into())
+            .build()
+            .unwrap();
+        let tx = Transaction::builder(keypair0().public_key(), 1, MIN_BASE_FEE)
+            .add_operation(payment(exchange))
+            .add_operation(payment(other))
+            .add_operation(payment(MuxedEd25519PublicKey::new(exchange, 7)))
+            .add_operation(Operation::new_inflation().build())
+            .add_operation(merge)
+            .into_transaction()
+            .unwrap();
+
+        let violations = check_memo_required(&tx, &lookup).unwrap();
+        let operations: Vec<_> = violations.iter().map(|v| v.operation()).collect();
+        assert_eq!(vec![0, 4], operations);
+        assert!(violations.iter().all(|v| v.destination() == &exchange));
+        assert_eq!(2, *lookup.lookups.borrow());
+
+        let mut tx = tx;
+        *tx.memo_mut() = Memo::new_id(1);
+        assert!(check_memo_required(&tx, &lookup).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ledger_state_lookup() {
+        use crate::apply::LedgerState;
+        use crate::network::Network;
+        use crate::xdr;
+
+        let account = keypair1().public_key();
+        let mut state = LedgerState::new(Network::new_test());
+        assert_eq!(None, state.memo_required_data(&account).unwrap());
+        state.insert(xdr::LedgerEntry {
+            last_modified_ledger_seq: 1,
+            data: xdr::LedgerEntryData::Data(xdr::DataEntry {
+                account_id: account.to_xdr_account_id().unwrap(),
+                data_name: xdr::String64(super::MEMO_REQUIRED_DATA_NAME.try_into().unwrap()),
+                data_value: MEMO_REQUIRED_DATA_VALUE.to_vec().try_into().unwrap(),
+                ext: xdr::DataEntryExt::V0,
+            }),
+            ext: xdr::LedgerEntryExt::V0,
+        });
+        assert_eq!(
+            Some(MEMO_REQUIRED_DATA_VALUE.to_vec()),
+            state.memo_required_data(&account).unwrap()
+        );
+    }
+}
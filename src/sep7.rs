@@ -0,0 +1,947 @@
+//! SEP-7 `web+stellar:` URIs to delegate signing.
+//!
+//! See [SEP-7](https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0007.md).
+use std::str::FromStr;
+
+use base64::Engine;
+
+use crate::amount::Amount;
+use crate::asset::Asset;
+use crate::crypto::{
+    Ed25519Signer, Ed25519Verifier, KeyPair, MuxedAccount, MuxedEd25519PublicKey, PublicKey,
+    Signature,
+};
+use crate::error::{Error, Result};
+use crate::memo::Memo;
+use crate::operations::{Operation, PaymentOperation};
+use crate::transaction::TransactionEnvelope;
+use crate::xdr::{XDRDeserialize, XDRSerialize, DEFAULT_LIMITS};
+
+/// The URI scheme of SEP-7 requests.
+pub const URI_SCHEME: &str = "web+stellar:";
+
+/// Maximum length of the `msg` parameter.
+pub const MAX_MSG_LEN: usize = 300;
+
+const SIGNATURE_PREFIX: &[u8] = b"stellar.sep.7 - URI Scheme";
+
+/// A SEP-7 request, either to sign a transaction or to pay a destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sep7Uri {
+    Transaction(TransactionUri),
+    Pay(PayUri),
+}
+
+/// A `web+stellar:tx` request to sign a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionUri {
+    xdr: String,
+    replace: Vec<Replacement>,
+    callback: Option<String>,
+    pubkey: Option<PublicKey>,
+    chain: Option<String>,
+    options: UriOptions,
+}
+
+/// A `web+stellar:pay` request to pay a destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayUri {
+    destination: MuxedAccount,
+    amount: Option<Amount>,
+    asset: Asset,
+    memo: Memo,
+    callback: Option<String>,
+    options: UriOptions,
+}
+
+/// A field of the transaction that the wallet should replace before signing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// The SEP-11 txrep path of the field, e.g. `sourceAccount`.
+    pub path: String,
+    /// The reference id of the value.
+    pub id: String,
+    /// A hint describing the value to the user.
+    pub hint: String,
+}
+
+/// Parameters shared by all requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct UriOptions {
+    msg: Option<String>,
+    network_passphrase: Option<String>,
+    origin_domain: Option<String>,
+    signature: Option<String>,
+}
+
+impl Sep7Uri {
+    /// Parses the SEP-7 `uri`.
+    pub fn parse(uri: &str) -> Result<Sep7Uri> {
+        let rest = uri
+            .strip_prefix(URI_SCHEME)
+            .ok_or_else(|| invalid("missing web+stellar scheme"))?;
+        let (operation, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let params = Params::parse(query)?;
+        match operation {
+            "tx" => Ok(Sep7Uri::Transaction(TransactionUri::from_params(params)?)),
+            "pay" => Ok(Sep7Uri::Pay(PayUri::from_params(params)?)),
+            _ => Err(invalid("unknown operation")),
+        }
+    }
+
+    /// If the request is a `tx` request, returns its value. Returns None otherwise.
+    pub fn as_transaction(&self) -> Option<&TransactionUri> {
+        match *self {
+            Sep7Uri::Transaction(ref inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the request is a `tx` request.
+    pub fn is_transaction(&self) -> bool {
+        self.as_transaction().is_some()
+    }
+
+    /// If the request is a `pay` request, returns its value. Returns None otherwise.
+    pub fn as_pay(&self) -> Option<&PayUri> {
+        match *self {
+            Sep7Uri::Pay(ref inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the request is a `pay` request.
+    pub fn is_pay(&self) -> bool {
+        self.as_pay().is_some()
+    }
+
+    /// Retrieves the request signature, if any.
+    pub fn signature(&self) -> Option<&str> {
+        match self {
+            Sep7Uri::Transaction(inner) => inner.signature(),
+            Sep7Uri::Pay(inner) => inner.signature(),
+        }
+    }
+
+    /// Returns the uri.
+    ///
+    /// Returns an error if a pay request memo can't be encoded.
+    pub fn to_uri(&self) -> Result<String> {
+        match self {
+            Sep7Uri::Transaction(inner) => Ok(inner.to_uri()),
+            Sep7Uri::Pay(inner) => inner.to_uri(),
+        }
+    }
+}
+
+impl FromStr for Sep7Uri {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Sep7Uri> {
+        Sep7Uri::parse(s)
+    }
+}
+
+impl From<TransactionUri> for Sep7Uri {
+    fn from(uri: TransactionUri) -> Self {
+        Sep7Uri::Transaction(uri)
+    }
+}
+
+impl From<PayUri> for Sep7Uri {
+    fn from(uri: PayUri) -> Self {
+        Sep7Uri::Pay(uri)
+    }
+}
+
+impl TransactionUri {
+    /// Creates a request to sign `envelope`.
+    pub fn new(envelope: &TransactionEnvelope) -> Result<TransactionUri> {
+        Ok(TransactionUri {
+            xdr: envelope.xdr_base64()?,
+            replace: Vec::new(),
+            callback: None,
+            pubkey: None,
+            chain: None,
+            options: UriOptions::default(),
+        })
+    }
+
+    /// Retrieves the base64 encoded transaction envelope.
+    pub fn xdr(&self) -> &str {
+        &self.xdr
+    }
+
+    /// Returns the transaction envelope to sign, decoded with the default
+    /// xdr limits since uris are untrusted.
+    pub fn to_transaction_envelope(&self) -> Result<TransactionEnvelope> {
+        TransactionEnvelope::from_xdr_base64_with_limits(&self.xdr, DEFAULT_LIMITS)
+    }
+
+    /// Retrieves the fields the wallet should replace.
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replace
+    }
+
+    /// Retrieves the url where the signed transaction should be posted.
+    pub fn callback(&self) -> Option<&str> {
+        self.callback.as_deref()
+    }
+
+    /// Retrieves the public key that should sign the transaction.
+    pub fn pubkey(&self) -> Option<&PublicKey> {
+        self.pubkey.as_ref()
+    }
+
+    /// Retrieves the request this request was chained from.
+    pub fn chain(&self) -> Option<&str> {
+        self.chain.as_deref()
+    }
+
+    /// Retrieves the message to show the user.
+    pub fn msg(&self) -> Option<&str> {
+        self.options.msg.as_deref()
+    }
+
+    /// Retrieves the network passphrase, `None` means the public network.
+    pub fn network_passphrase(&self) -> Option<&str> {
+        self.options.network_passphrase.as_deref()
+    }
+
+    /// Retrieves the domain of the request originator.
+    pub fn origin_domain(&self) -> Option<&str> {
+        self.options.origin_domain.as_deref()
+    }
+
+    /// Retrieves the request signature.
+    pub fn signature(&self) -> Option<&str> {
+        self.options.signature.as_deref()
+    }
+
+    /// Adds a field the wallet should replace.
+    pub fn add_replacement<S1, S2, S3>(mut self, path: S1, id: S2, hint: S3) -> TransactionUri
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        self.replace.push(Replacement {
+            path: path.into(),
+            id: id.into(),
+            hint: hint.into(),
+        });
+        self
+    }
+
+    /// Sets the url where the signed transaction should be posted.
+    pub fn with_callback<S: Into<String>>(mut self, callback: S) -> TransactionUri {
+        self.callback = Some(callback.into());
+        self
+    }
+
+    /// Sets the public key that should sign the transaction.
+    pub fn with_pubkey(mut self, pubkey: PublicKey) -> TransactionUri {
+        self.pubkey = Some(pubkey);
+        self
+    }
+
+    /// Sets the request this request was chained from.
+    pub fn with_chain<S: Into<String>>(mut self, chain: S) -> TransactionUri {
+        self.chain = Some(chain.into());
+        self
+    }
+
+    /// Sets the message to show the user.
+    pub fn with_msg<S: Into<String>>(mut self, msg: S) -> Result<TransactionUri> {
+        self.options.msg = Some(check_msg(msg.into())?);
+        Ok(self)
+    }
+
+    /// Sets the network passphrase.
+    pub fn with_network_passphrase<S: Into<String>>(mut self, passphrase: S) -> TransactionUri {
+        self.options.network_passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// Sets the domain of the request originator.
+    pub fn with_origin_domain<S: Into<String>>(mut self, domain: S) -> TransactionUri {
+        self.options.origin_domain = Some(domain.into());
+        self
+    }
+
+    /// Returns the uri.
+    pub fn to_uri(&self) -> String {
+        let mut params = Params::default();
+        params.push("xdr", &self.xdr);
+        if !self.replace.is_empty() {
+            params.push("replace", &encode_replace(&self.replace));
+        }
+        if let Some(callback) = &self.callback {
+            params.push("callback", &format!("url:{}", callback));
+        }
+        if let Some(pubkey) = &self.pubkey {
+            params.push("pubkey", &pubkey.account_id());
+        }
+        if let Some(chain) = &self.chain {
+            params.push("chain", chain);
+        }
+        self.options.push_to(&mut params);
+        params.to_uri("tx")
+    }
+
+    /// Returns the uri, signed with `key_pair`.
+    pub fn to_signed_uri<S, V>(&self, key_pair: &KeyPair<S, V>) -> String
+    where
+        S: Ed25519Signer<Signature>,
+        V: Ed25519Verifier<Signature> + AsRef<[u8]>,
+    {
+        sign_uri(&self.to_uri(), key_pair)
+    }
+
+    fn from_params(mut params: Params) -> Result<TransactionUri> {
+        let xdr = params
+            .take("xdr")
+            .ok_or_else(|| invalid("missing xdr parameter"))?;
+        let replace = match params.take("replace") {
+            None => Vec::new(),
+            Some(replace) => decode_replace(&replace)?,
+        };
+        let pubkey = params
+            .take("pubkey")
+            .map(|pk| PublicKey::from_account_id(&pk))
+            .transpose()?;
+        Ok(TransactionUri {
+            xdr,
+            replace,
+            callback: params.take_callback()?,
+            pubkey,
+            chain: params.take("chain"),
+            options: UriOptions::take_from(&mut params)?,
+        })
+    }
+}
+
+impl PayUri {
+    /// Creates a request to pay `destination`.
+    pub fn new<A: Into<MuxedAccount>>(destination: A) -> PayUri {
+        PayUri {
+            destination: destination.into(),
+            amount: None,
+            asset: Asset::new_native(),
+            memo: Memo::new_none(),
+            callback: None,
+            options: UriOptions::default(),
+        }
+    }
+
+    /// Creates a request for the payment `operation`.
+    pub fn from_payment_operation(operation: &PaymentOperation) -> Result<PayUri> {
+        Ok(PayUri::new(operation.destination().clone())
+            .with_amount(Amount::from_stroops(operation.amount())?)
+            .with_asset(operation.asset().clone()))
+    }
+
+    /// Retrieves the payment destination.
+    pub fn destination(&self) -> &MuxedAccount {
+        &self.destination
+    }
+
+    /// Retrieves the payment amount, if any.
+    pub fn amount(&self) -> Option<&Amount> {
+        self.amount.as_ref()
+    }
+
+    /// Retrieves the payment asset.
+    pub fn asset(&self) -> &Asset {
+        &self.asset
+    }
+
+    /// Retrieves the memo to attach to the payment transaction.
+    pub fn memo(&self) -> &Memo {
+        &self.memo
+    }
+
+    /// Retrieves the url where the signed transaction should be posted.
+    pub fn callback(&self) -> Option<&str> {
+        self.callback.as_deref()
+    }
+
+    /// Retrieves the message to show the user.
+    pub fn msg(&self) -> Option<&str> {
+        self.options.msg.as_deref()
+    }
+
+    /// Retrieves the network passphrase, `None` means the public network.
+    pub fn network_passphrase(&self) -> Option<&str> {
+        self.options.network_passphrase.as_deref()
+    }
+
+    /// Retrieves the domain of the request originator.
+    pub fn origin_domain(&self) -> Option<&str> {
+        self.options.origin_domain.as_deref()
+    }
+
+    /// Retrieves the request signature.
+    pub fn signature(&self) -> Option<&str> {
+        self.options.signature.as_deref()
+    }
+
+    /// Sets the payment amount.
+    pub fn with_amount(mut self, amount: Amount) -> PayUri {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Sets the payment asset.
+    pub fn with_asset(mut self, asset: Asset) -> PayUri {
+        self.asset = asset;
+        self
+    }
+
+    /// Sets the memo to attach to the payment transaction.
+    ///
+    /// Returns an error if `memo` is a text memo that is not valid UTF-8,
+    /// since uri parameters can only carry text.
+    pub fn with_memo(mut self, memo: Memo) -> Result<PayUri> {
+        if let Memo::TextBytes(_) = memo {
+            return Err(invalid("memo text is not valid UTF-8"));
+        }
+        self.memo = memo;
+        Ok(self)
+    }
+
+    /// Sets the url where the signed transaction should be posted.
+    pub fn with_callback<S: Into<String>>(mut self, callback: S) -> PayUri {
+        self.callback = Some(callback.into());
+        self
+    }
+
+    /// Sets the message to show the user.
+    pub fn with_msg<S: Into<String>>(mut self, msg: S) -> Result<PayUri> {
+        self.options.msg = Some(check_msg(msg.into())?);
+        Ok(self)
+    }
+
+    /// Sets the network passphrase.
+    pub fn with_network_passphrase<S: Into<String>>(mut self, passphrase: S) -> PayUri {
+        self.options.network_passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// Sets the domain of the request originator.
+    pub fn with_origin_domain<S: Into<String>>(mut self, domain: S) -> PayUri {
+        self.options.origin_domain = Some(domain.into());
+        self
+    }
+
+    /// Returns the payment operation requested.
+    ///
+    /// Returns an error if the request doesn't specify an amount.
+    pub fn to_payment_operation(&self) -> Result<Operation> {
+        let amount = self
+            .amount
+            .clone()
+            .ok_or_else(|| Error::InvalidOperation("missing payment amount".to_string()))?;
+        Operation::new_payment()
+            .with_destination(self.destination.clone())
+            .with_amount(amount)?
+            .with_asset(self.asset.clone())
+            .build()
+    }
+
+    /// Returns the uri.
+    ///
+    /// Returns an error if the memo is a text memo that is not valid UTF-8.
+    pub fn to_uri(&self) -> Result<String> {
+        let mut params = Params::default();
+        params.push("destination", &self.destination.account_id());
+        if let Some(amount) = &self.amount {
+            params.push("amount", &amount.0.normalize().to_string());
+        }
+        if let Some(credit) = self.asset.as_credit() {
+            params.push("asset_code", credit.code());
+            params.push("asset_issuer", &credit.issuer().account_id());
+        }
+        let base64 = base64::engine::general_purpose::STANDARD;
+        let memo = match &self.memo {
+            Memo::None => None,
+            Memo::Text(text) => Some(("MEMO_TEXT", text.clone())),
+            Memo::TextBytes(_) => return Err(invalid("memo text is not valid UTF-8")),
+            Memo::Id(id) => Some(("MEMO_ID", id.to_string())),
+            Memo::Hash(hash) => Some(("MEMO_HASH", base64.encode(hash))),
+            Memo::Return(hash) => Some(("MEMO_RETURN", base64.encode(hash))),
+        };
+        if let Some((memo_type, memo)) = memo {
+            params.push("memo", &memo);
+            params.push("memo_type", memo_type);
+        }
+        if let Some(callback) = &self.callback {
+            params.push("callback", &format!("url:{}", callback));
+        }
+        self.options.push_to(&mut params);
+        Ok(params.to_uri("pay"))
+    }
+
+    /// Returns the uri, signed with `key_pair`.
+    ///
+    /// Returns an error if the memo is a text memo that is not valid UTF-8.
+    pub fn to_signed_uri<S, V>(&self, key_pair: &KeyPair<S, V>) -> Result<String>
+    where
+        S: Ed25519Signer<Signature>,
+        V: Ed25519Verifier<Signature> + AsRef<[u8]>,
+    {
+        Ok(sign_uri(&self.to_uri()?, key_pair))
+    }
+
+    fn from_params(mut params: Params) -> Result<PayUri> {
+        let destination = params
+            .take("destination")
+            .ok_or_else(|| invalid("missing destination parameter"))?;
+        let destination = if destination.starts_with('M') {
+            MuxedEd25519PublicKey::from_account_id(&destination)?.into()
+        } else {
+            PublicKey::from_account_id(&destination)?.into()
+        };
+        let amount = params
+            .take("amount")
+            .map(|amount| Amount::from_str(&amount))
+            .transpose()?;
+        let asset = match (params.take("asset_code"), params.take("asset_issuer")) {
+            (None, None) => Asset::new_native(),
+            (Some(code), Some(issuer)) => {
+                Asset::new_credit(code, PublicKey::from_account_id(&issuer)?)?
+            }
+            _ => return Err(invalid("asset_code and asset_issuer must be used together")),
+        };
+        let memo = params.take("memo");
+        let memo_type = params.take("memo_type");
+        let memo = match (memo_type.as_deref(), memo) {
+            (None, None) => Memo::new_none(),
+            (Some("MEMO_TEXT") | None, Some(text)) => Memo::new_text(text)?,
+            (Some("MEMO_ID"), Some(id)) => {
+                Memo::new_id(id.parse().map_err(|_| invalid("invalid memo id"))?)
+            }
+            (Some("MEMO_HASH"), Some(hash)) => Memo::new_hash(&decode_memo_hash(&hash)?)?,
+            (Some("MEMO_RETURN"), Some(hash)) => Memo::new_return(&decode_memo_hash(&hash)?)?,
+            _ => return Err(invalid("invalid memo")),
+        };
+        Ok(PayUri {
+            destination,
+            amount,
+            asset,
+            memo,
+            callback: params.take_callback()?,
+            options: UriOptions::take_from(&mut params)?,
+        })
+    }
+}
+
+impl UriOptions {
+    fn push_to(&self, params: &mut Params) {
+        if let Some(msg) = &self.msg {
+            params.push("msg", msg);
+        }
+        if let Some(passphrase) = &self.network_passphrase {
+            params.push("network_passphrase", passphrase);
+        }
+        if let Some(domain) = &self.origin_domain {
+            params.push("origin_domain", domain);
+        }
+    }
+
+    fn take_from(params: &mut Params) -> Result<UriOptions> {
+        let msg = params.take("msg").map(check_msg).transpose()?;
+        Ok(UriOptions {
+            msg,
+            network_passphrase: params.take("network_passphrase"),
+            origin_domain: params.take("origin_domain"),
+            signature: params.take("signature"),
+        })
+    }
+}
+
+/// Signs `uri` with `key_pair`, returning the uri with the `signature` parameter appended.
+///
+/// The key pair is usually the `URI_REQUEST_SIGNING_KEY` of the origin domain.
+pub fn sign_uri<S, V>(uri: &str, key_pair: &KeyPair<S, V>) -> String
+where
+    S: Ed25519Signer<Signature>,
+    V: Ed25519Verifier<Signature> + AsRef<[u8]>,
+{
+    let signature = key_pair.sign(&signature_payload(uri));
+    let signature = base64::engine::general_purpose::STANDARD.encode(signature.to_bytes());
+    format!("{}&signature={}", uri, percent_encode(&signature))
+}
+
+/// Verifies the `signature` parameter of `uri` with `key_pair`.
+///
+/// The signature must be the last parameter of the uri.
+pub fn verify_uri_signature<S, V>(uri: &str, key_pair: &KeyPair<S, V>) -> Result<()>
+where
+    S: Ed25519Signer<Signature>,
+    V: Ed25519Verifier<Signature> + AsRef<[u8]>,
+{
+    let (unsigned, signature) = uri
+        .rsplit_once("&signature=")
+        .ok_or(Error::InvalidSignature)?;
+    let signature = percent_decode(signature).ok_or(Error::InvalidSignature)?;
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(signature)
+        .map_err(|_| Error::InvalidSignature)?;
+    let signature = Signature::from_slice(&signature).map_err(|_| Error::InvalidSignature)?;
+    key_pair
+        .verify(&signature_payload(unsigned), &signature)
+        .map_err(|_| Error::InvalidSignature)
+}
+
+fn signature_payload(uri: &str) -> Vec<u8> {
+    let mut payload = vec![0; 36];
+    payload[35] = 4;
+    payload.extend_from_slice(SIGNATURE_PREFIX);
+    payload.extend_from_slice(uri.as_bytes());
+    payload
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidSep7Uri(reason.to_string())
+}
+
+fn check_msg(msg: String) -> Result<String> {
+    if msg.chars().count() > MAX_MSG_LEN {
+        return Err(invalid("msg too long"));
+    }
+    Ok(msg)
+}
+
+fn decode_memo_hash(hash: &str) -> Result<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(hash)
+        .map_err(|_| invalid("invalid memo hash"))
+}
+
+/// Encodes the replacements as `path:id,path:id;id:hint,id:hint`.
+fn encode_replace(replace: &[Replacement]) -> String {
+    let fields: Vec<String> = replace
+        .iter()
+        .map(|r| format!("{}:{}", r.path, r.id))
+        .collect();
+    let mut hints: Vec<String> = Vec::new();
+    for (i, r) in replace.iter().enumerate() {
+        if !replace[..i].iter().any(|other| other.id == r.id) {
+            hints.push(format!("{}:{}", r.id, r.hint));
+        }
+    }
+    format!("{};{}", fields.join(","), hints.join(","))
+}
+
+fn decode_replace(replace: &str) -> Result<Vec<Replacement>> {
+    let (fields, hints) = replace.split_once(';').unwrap_or((replace, ""));
+    let hints = hints
+        .split(',')
+        .filter(|hint| !hint.is_empty())
+        .map(|hint| {
+            hint.split_once(':')
+                .ok_or_else(|| invalid("invalid replace hint"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    fields
+        .split(',')
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            let (path, id) = field
+                .split_once(':')
+                .ok_or_else(|| invalid("invalid replace field"))?;
+            let hint = hints
+                .iter()
+                .find(|(hint_id, _)| *hint_id == id)
+                .map(|(_, hint)| hint.to_string())
+                .unwrap_or_default();
+            Ok(Replacement {
+                path: path.to_string(),
+                id: id.to_string(),
+                hint,
+            })
+        })
+        .collect()
+}
+
+/// The query parameters of a uri, in order.
+#[derive(Default)]
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn parse(query: &str) -> Result<Params> {
+        let mut params = Params::default();
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let key = percent_decode(key).ok_or_else(|| invalid("invalid parameter name"))?;
+            let value = percent_decode(value)
+                .ok_or_else(|| Error::InvalidSep7Uri(format!("invalid {} parameter", key)))?;
+            if params.0.iter().any(|(k, _)| *k == key) {
+                return Err(Error::InvalidSep7Uri(format!(
+                    "duplicate {} parameter",
+                    key
+                )));
+            }
+            params.0.push((key, value));
+        }
+        Ok(params)
+    }
+
+    fn push(&mut self, key: &str, value: &str) {
+        self.0.push((key.to_string(), value.to_string()));
+    }
+
+    fn take(&mut self, key: &str) -> Option<String> {
+        let index = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(index).1)
+    }
+
+    fn take_callback(&mut self) -> Result<Option<String>> {
+        self.take("callback")
+            .map(|callback| {
+                callback
+                    .strip_prefix("url:")
+                    .map(str::to_string)
+                    .ok_or_else(|| invalid("callback must start with url:"))
+            })
+            .transpose()
+    }
+
+    fn to_uri(&self, operation: &str) -> String {
+        let query: Vec<String> = self
+            .0
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+            .collect();
+        format!("{}{}?{}", URI_SCHEME, operation, query.join("&"))
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = s.bytes();
+    let mut out = Vec::with_capacity(s.len());
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign_uri, verify_uri_signature, PayUri, Sep7Uri, TransactionUri};
+    use crate::amount::Amount;
+    use crate::asset::Asset;
+    use crate::crypto::{DalekKeyPair, MuxedAccount};
+    use crate::error::Error;
+    use crate::memo::Memo;
+    use crate::network::Network;
+    use crate::operations::tests::*;
+    use crate::operations::Operation;
+    use crate::transaction::{Transaction, MIN_BASE_FEE};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_sep7_signature_example() {
+        // Example from SEP-7.
+        let uri = "web+stellar:pay?destination=GCALNQQBXAPZ2WIRSDDBMSTAKCUH5SG6U76YBFLQLIXJTF7FE5AX7AOO&amount=120.1234567&memo=skdjfasf&msg=pay%20me%20with%20lumens&origin_domain=someDomain.com";
+        let kp = DalekKeyPair::from_secret_seed(
+            "SBPOVRVKTTV7W3IOX2FJPSMPCJ5L2WU2YKTP3HCLYPXNI5MDIGREVNYC",
+        )
+        .unwrap();
+        let signed = sign_uri(uri, kp.as_ref());
+        let expected = format!("{}&signature=JTlGMGzxUv90P2SWxUY9xo%2BLlbXaDloend6gkpyylY8X4bUNf6%2F9mFTMJs7JKqSDPRtejlK1kQvrsJfRZSJeAQ%3D%3D", uri);
+        assert_eq!(expected, signed);
+        verify_uri_signature(&signed, kp.as_ref()).unwrap();
+
+        let parsed = Sep7Uri::parse(&signed).unwrap();
+        let pay = parsed.as_pay().unwrap();
+        assert_eq!(
+            "GCALNQQBXAPZ2WIRSDDBMSTAKCUH5SG6U76YBFLQLIXJTF7FE5AX7AOO",
+            pay.destination().account_id()
+        );
+        assert_eq!(
+            &Amount::from_str("120.1234567").unwrap(),
+            pay.amount().unwrap()
+        );
+        assert_eq!(&Memo::new_text("skdjfasf").unwrap(), pay.memo());
+        assert_eq!(Some("pay me with lumens"), pay.msg());
+        assert_eq!(Some("someDomain.com"), pay.origin_domain());
+        assert_eq!(
+            Some("JTlGMGzxUv90P2SWxUY9xo+LlbXaDloend6gkpyylY8X4bUNf6/9mFTMJs7JKqSDPRtejlK1kQvrsJfRZSJeAQ=="),
+            parsed.signature()
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_uri() {
+        let kp = keypair0();
+        let uri = PayUri::new(keypair1().public_key())
+            .with_amount(Amount::from_str("10").unwrap())
+            .with_origin_domain("example.com")
+            .to_signed_uri(kp.as_ref())
+            .unwrap();
+        verify_uri_signature(&uri, kp.as_ref()).unwrap();
+
+        let tampered = uri.replace("amount=10", "amount=100");
+        let res = verify_uri_signature(&tampered, kp.as_ref());
+        assert!(matches!(res, Err(Error::InvalidSignature)));
+
+        let res = verify_uri_signature(&uri, keypair2().as_ref());
+        assert!(matches!(res, Err(Error::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_pay_uri_round_trip() {
+        let issuer = keypair2().public_key();
+        let destination: MuxedAccount = keypair1().public_key().to_muxed_account(42);
+        let uri = PayUri::new(destination.clone())
+            .with_amount(Amount::from_str("12.5").unwrap())
+            .with_asset(Asset::new_credit("USD", issuer).unwrap())
+            .with_memo(Memo::new_hash(&[7; 32]).unwrap())
+            .unwrap()
+            .with_callback("https://example.com/pay?id=1")
+            .with_msg("order #12")
+            .unwrap()
+            .with_network_passphrase(Network::new_test().passphrase());
+        let encoded = uri.to_uri().unwrap();
+        assert!(encoded.starts_with("web+stellar:pay?destination=M"));
+        assert!(encoded.contains("&amount=12.5&asset_code=USD&asset_issuer=GB7BDSZU2Y27LYNLALKKALB52WS2IZWYBDGY6EQBLEED3TJOCVMZRH7H"));
+        assert!(encoded.contains("&memo_type=MEMO_HASH"));
+        assert!(encoded.contains("&callback=url%3Ahttps%3A%2F%2Fexample.com%2Fpay%3Fid%3D1"));
+        let back = Sep7Uri::parse(&encoded).unwrap();
+        assert_eq!(Sep7Uri::Pay(uri), back);
+    }
+
+    #[test]
+    fn test_pay_uri_rejects_invalid_utf8_memo() {
+        let memo = Memo::new_text_from_bytes(&[0xff, 0xfe]).unwrap();
+        assert!(matches!(memo, Memo::TextBytes(_)));
+        let res = PayUri::new(keypair1().public_key()).with_memo(memo.clone());
+        assert!(matches!(res, Err(Error::InvalidSep7Uri(_))));
+        let mut uri = PayUri::new(keypair1().public_key());
+        uri.memo = memo;
+        assert!(matches!(uri.to_uri(), Err(Error::InvalidSep7Uri(_))));
+        assert!(matches!(
+            Sep7Uri::Pay(uri.clone()).to_uri(),
+            Err(Error::InvalidSep7Uri(_))
+        ));
+        assert!(matches!(
+            uri.to_signed_uri(keypair0().as_ref()),
+            Err(Error::InvalidSep7Uri(_))
+        ));
+
+        let uri = PayUri::new(keypair1().public_key())
+            .with_memo(Memo::new_text("héllo").unwrap())
+            .unwrap();
+        assert!(uri.to_uri().unwrap().contains("&memo_type=MEMO_TEXT"));
+    }
+
+    #[test]
+    fn test_pay_uri_to_payment_operation() {
+        let uri = "web+stellar:pay?destination=GAS4V4O2B7DW5T7IQRPEEVCRXMDZESKISR7DVIGKZQYYV3OSQ5SH5LVP&amount=12.301";
+        let pay = Sep7Uri::parse(uri).unwrap().as_pay().unwrap().clone();
+        let op = pay.to_payment_operation().unwrap();
+        let expected = Operation::new_payment()
+            .with_destination(keypair1().public_key())
+            .with_amount(Amount::from_str("12.301").unwrap())
+            .unwrap()
+            .with_asset(Asset::new_native())
+            .build()
+            .unwrap();
+        assert_eq!(expected, op);
+
+        let back = PayUri::from_payment_operation(op.as_payment().unwrap()).unwrap();
+        assert_eq!(uri, back.to_uri().unwrap());
+
+        let no_amount = PayUri::new(keypair1().public_key());
+        assert!(no_amount.to_payment_operation().is_err());
+    }
+
+    #[test]
+    fn test_transaction_uri_round_trip() {
+        let kp = keypair0();
+        let op = Operation::new_bump_sequence()
+            .with_bump_to(100)
+            .build()
+            .unwrap();
+        let tx = Transaction::builder(kp.public_key(), 3556091187167235, MIN_BASE_FEE)
+            .add_operation(op)
+            .into_transaction()
+            .unwrap();
+        let envelope = tx.to_envelope();
+        let uri = TransactionUri::new(&envelope)
+            .unwrap()
+            .add_replacement(
+                "sourceAccount",
+                "X",
+                "account on which to bump the sequence",
+            )
+            .add_replacement("seqNum", "Y", "sequence number")
+            .add_replacement("operations[0].sourceAccount", "X", "")
+            .with_callback("https://example.com/tx")
+            .with_pubkey(kp.public_key())
+            .with_network_passphrase(Network::new_test().passphrase())
+            .with_origin_domain("example.com");
+        let encoded = uri.to_signed_uri(kp.as_ref());
+        assert!(encoded.contains("&replace=sourceAccount%3AX%2CseqNum%3AY%2Coperations%5B0%5D.sourceAccount%3AX%3BX%3Aaccount%20on%20which%20to%20bump%20the%20sequence%2CY%3Asequence%20number&"));
+        verify_uri_signature(&encoded, kp.as_ref()).unwrap();
+
+        let parsed = Sep7Uri::from_str(&encoded).unwrap();
+        let parsed = parsed.as_transaction().unwrap();
+        assert_eq!(envelope, parsed.to_transaction_envelope().unwrap());
+        let replacements = parsed.replacements();
+        assert_eq!(3, replacements.len());
+        assert_eq!("operations[0].sourceAccount", replacements[2].path);
+        assert_eq!("X", replacements[2].id);
+        assert_eq!(
+            "account on which to bump the sequence",
+            replacements[2].hint
+        );
+        assert_eq!(Some("https://example.com/tx"), parsed.callback());
+        assert_eq!(Some(&kp.public_key()), parsed.pubkey());
+        assert!(parsed.signature().is_some());
+    }
+
+    #[test]
+    fn test_invalid_uris() {
+        let destination = "destination=GAS4V4O2B7DW5T7IQRPEEVCRXMDZESKISR7DVIGKZQYYV3OSQ5SH5LVP";
+        let invalid = [
+            format!("stellar:pay?{}", destination),
+            format!("web+stellar:send?{}", destination),
+            "web+stellar:pay?amount=1".to_string(),
+            "web+stellar:tx?msg=hello".to_string(),
+            format!("web+stellar:pay?{}&asset_code=USD", destination),
+            format!(
+                "web+stellar:pay?{}&callback=https://example.com",
+                destination
+            ),
+            format!("web+stellar:pay?{}&memo=abc&memo_type=MEMO_ID", destination),
+            format!("web+stellar:pay?{}&msg={}", destination, "a".repeat(301)),
+            format!("web+stellar:pay?{}&{}", destination, destination),
+        ];
+        for uri in invalid.iter() {
+            assert!(Sep7Uri::parse(uri).is_err(), "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Sponsorship of account reserves.
+//!
+//! Operations between a `BeginSponsoringFutureReserves` operation and the
+//! matching `EndSponsoringFutureReserves` operation have their reserves paid
+//! by the sponsor. The sponsor is the source of the begin operation and the
+//! sponsored account the source of the end operation, so both must sign.
+use crate::crypto::{MuxedAccount, PublicKey, SignerKey};
+use crate::error::{Error, Result};
+use crate::ledger::LedgerKey;
+use crate::operations::Operation;
+use crate::xdr;
+
+/// A pair of begin and end sponsoring future reserves operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sponsorship {
+    sponsor: PublicKey,
+    sponsored: PublicKey,
+    begin: usize,
+    end: usize,
+}
+
+impl Sponsorship {
+    /// Retrieves the sponsor account.
+    pub fn sponsor(&self) -> &PublicKey {
+        &self.sponsor
+    }
+
+    /// Retrieves the sponsored account.
+    pub fn sponsored(&self) -> &PublicKey {
+        &self.sponsored
+    }
+
+    /// Retrieves the index of the begin sponsoring future reserves operation.
+    pub fn begin(&self) -> usize {
+        self.begin
+    }
+
+    /// Retrieves the index of the end sponsoring future reserves operation.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the accounts that must sign the transaction, the sponsor and
+    /// the sponsored account.
+    pub fn signers(&self) -> [PublicKey; 2] {
+        [self.sponsor, self.sponsored]
+    }
+}
+
+/// Pairs the begin and end sponsoring future reserves operations of
+/// `operations`, where `source_account` is the transaction source account.
+///
+/// Returns `Error::InvalidSponsorship` with the index of the first operation
+/// that is not paired, sponsors itself, or nests sponsorships the way
+/// stellar-core rejects. Indexes start at `offset`.
+pub(crate) fn pair_sponsorships(
+    operations: &[Operation],
+    source_account: &MuxedAccount,
+    offset: usize,
+) -> Result<Vec<Sponsorship>> {
+    let mut open: Vec<Sponsorship> = Vec::new();
+    let mut sponsorships = Vec::new();
+    for (index, operation) in operations.iter().enumerate() {
+        let index = index + offset;
+        let source = *operation
+            .source_account()
+            .as_ref()
+            .unwrap_or(source_account)
+            .public_key();
+        match operation {
+            Operation::BeginSponsoringFutureReserves(op) => {
+                let sponsored = *op.sponsored_id();
+                let invalid = sponsored == source
+                    || open.iter().any(|s| {
+                        s.sponsored == sponsored || s.sponsored == source || s.sponsor == sponsored
+                    });
+                if invalid {
+                    return Err(Error::InvalidSponsorship(index));
+                }
+                open.push(Sponsorship {
+                    sponsor: source,
+                    sponsored,
+                    begin: index,
+                    end: index,
+                });
+            }
+            Operation::EndSponsoringFutureReserves(_) => {
+                let position = open
+                    .iter()
+                    .position(|s| s.sponsored == source)
+                    .ok_or(Error::InvalidSponsorship(index))?;
+                let mut sponsorship = open.remove(position);
+                sponsorship.end = index;
+                sponsorships.push(sponsorship);
+            }
+            _ => {}
+        }
+    }
+    if let Some(sponsorship) = open.first() {
+        return Err(Error::InvalidSponsorship(sponsorship.begin));
+    }
+    sponsorships.sort_by_key(|s| s.begin);
+    Ok(sponsorships)
+}
+
+/// Returns the revoke sponsorship operations for the entries and signers
+/// sponsored by `sponsor` in `entries`, with `sponsor` as source account.
+///
+/// `entries` are usually an account entry with its trust lines, offers and
+/// data entries. Signers are revoked from account entries. Claimable balances
+/// are skipped: their sponsorship can only be transferred, inside a begin and
+/// end sponsoring future reserves sandwich, and stellar-core rejects revoking
+/// it otherwise.
+pub fn revoke_sponsorship_operations(
+    sponsor: &PublicKey,
+    entries: &[xdr::LedgerEntry],
+) -> Result<Vec<Operation>> {
+    let sponsor_id = sponsor.to_xdr_account_id()?;
+    let mut operations = Vec::new();
+    for entry in entries {
+        let sponsored = match entry.ext {
+            xdr::LedgerEntryExt::V1(ref ext) => ext.sponsoring_id.0.as_ref() == Some(&sponsor_id),
+            xdr::LedgerEntryExt::V0 => false,
+        };
+        if sponsored {
+            if let Some(key) = sponsorable_ledger_key(&entry.data) {
+                operations.push(
+                    Operation::new_revoke_sponsorship()
+                        .with_source_account(*sponsor)
+                        .with_ledger_key(LedgerKey::from_xdr(&key)?)
+                        .build()?,
+                );
+            }
+        }
+        if let xdr::LedgerEntryData::Account(ref account) = entry.data {
+            let account_id = PublicKey::from_xdr_account_id(&account.account_id)?;
+            for (signer, sponsoring_id) in
+                account.signers.iter().zip(signer_sponsoring_ids(account))
+            {
+                if sponsoring_id.0.as_ref() != Some(&sponsor_id) {
+                    continue;
+                }
+                operations.push(
+                    Operation::new_revoke_sponsorship()
+                        .with_source_account(*sponsor)
+                        .with_signer(account_id, SignerKey::from_xdr(&signer.key)?)
+                        .build()?,
+                );
+            }
+        }
+    }
+    Ok(operations)
+}
+
+fn signer_sponsoring_ids(account: &xdr::AccountEntry) -> &[xdr::SponsorshipDescriptor] {
+    match account.ext {
+        xdr::AccountEntryExt::V1(xdr::AccountEntryExtensionV1 {
+            ext: xdr::AccountEntryExtensionV1Ext::V2(ref ext),
+            ..
+        }) => &ext.signer_sponsoring_i_ds,
+        _ => &[],
+    }
+}
+
+/// Returns the key of the entries whose sponsorship can be revoked.
+fn sponsorable_ledger_key(data: &xdr::LedgerEntryData) -> Option<xdr::LedgerKey> {
+    let key = match data {
+        xdr::LedgerEntryData::Account(entry) => xdr::LedgerKey::Account(xdr::LedgerKeyAccount {
+            account_id: entry.account_id.clone(),
+        }),
+        xdr::LedgerEntryData::Trustline(entry) => {
+            xdr::LedgerKey::Trustline(xdr::LedgerKeyTrustLine {
+                account_id: entry.account_id.clone(),
+                asset: entry.asset.clone(),
+            })
+        }
+        xdr::LedgerEntryData::Offer(entry) => xdr::LedgerKey::Offer(xdr::LedgerKeyOffer {
+            seller_id: entry.seller_id.clone(),
+            offer_id: entry.offer_id,
+        }),
+        xdr::LedgerEntryData::Data(entry) => xdr::LedgerKey::Data(xdr::LedgerKeyData {
+            account_id: entry.account_id.clone(),
+            data_name: entry.data_name.clone(),
+        }),
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::revoke_sponsorship_operations;
+    use crate::amount::Stroops;
+    use crate::crypto::SignerKey;
+    use crate::error::Error;
+    use crate::ledger::LedgerKey;
+    use crate::operations::tests::*;
+    use crate::operations::Operation;
+    use crate::transaction::{Transaction, MIN_BASE_FEE};
+    use crate::xdr;
+
+    fn create_account(destination: crate::PublicKey) -> Operation {
+        Operation::new_create_account()
+            .with_destination(destination)
+            .with_starting_balance(Stroops::new(0))
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sponsor() {
+        let sponsor = keypair0().public_key();
+        let sponsored = keypair1().public_key();
+        let tx = Transaction::builder(sponsor, 1, MIN_BASE_FEE)
+            .add_operation(Operation::new_inflation().build())
+            .sponsor(sponsor, sponsored, |b| {
+                b.add_operation(create_account(sponsored))
+            })
+            .into_transaction()
+            .unwrap();
+
+        assert_eq!(4, tx.operations().len());
+        match &tx.operations()[1] {
+            Operation::BeginSponsoringFutureReserves(op) => {
+                assert_eq!(&Some(sponsor.into()), op.source_account());
+                assert_eq!(&sponsored, op.sponsored_id());
+            }
+            op => panic!("unexpected operation {:?}", op),
+        }
+        assert_eq!(&Some(sponsored.into()), tx.operations()[3].source_account());
+
+        let sponsorships = tx.sponsorships().unwrap();
+        assert_eq!(1, sponsorships.len());
+        assert_eq!((1, 3), (sponsorships[0].begin(), sponsorships[0].end()));
+        assert_eq!([sponsor, sponsored], sponsorships[0].signers());
+        let signers: Vec<_> = tx.required_signers().iter().map(|s| *s.account()).collect();
+        assert_eq!(vec![sponsor, sponsored], signers);
+    }
+
+    #[test]
+    fn test_sponsor_rejects_invalid_pairing() {
+        let sponsor = keypair0().public_key();
+        let sponsored = keypair1().public_key();
+        let other = keypair2().public_key();
+
+        let err = Transaction::builder(sponsor, 1, MIN_BASE_FEE)
+            .sponsor(sponsor, sponsor, |b| b.add_operation(create_account(other)))
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidSponsorship(0)));
+
+        // The sponsored account can't sponsor other accounts in the sandwich.
+        let err = Transaction::builder(sponsor, 1, MIN_BASE_FEE)
+            .sponsor(sponsor, sponsored, |b| {
+                b.sponsor(sponsored, other, |b| b.add_operation(create_account(other)))
+            })
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidSponsorship(1)));
+
+        let tx = Transaction::builder(sponsor, 1, MIN_BASE_FEE)
+            .sponsor(sponsor, sponsored, |b| {
+                b.sponsor(sponsor, other, |b| b.add_operation(create_account(other)))
+            })
+            .into_transaction()
+            .unwrap();
+        assert_eq!(2, tx.sponsorships().unwrap().len());
+
+        let tx = Transaction::builder(sponsor, 1, MIN_BASE_FEE)
+            .add_operation(
+                Operation::new_begin_sponsoring_future_reserves()
+                    .with_sponsored_id(sponsored)
+                    .build()
+                    .unwrap(),
+            )
+            .into_transaction()
+            .unwrap();
+        assert!(matches!(
+            tx.sponsorships(),
+            Err(Error::InvalidSponsorship(0))
+        ));
+    }
+
+    #[test]
+    fn test_revoke_sponsorship_operations() {
+        let sponsor = keypair0().public_key();
+        let account = keypair1().public_key();
+        let signer = keypair2().public_key();
+        let sponsored_by = |sponsor: &crate::PublicKey| {
+            xdr::LedgerEntryExt::V1(xdr::LedgerEntryExtensionV1 {
+                sponsoring_id: xdr::SponsorshipDescriptor(Some(
+                    sponsor.to_xdr_account_id().unwrap(),
+                )),
+                ext: xdr::LedgerEntryExtensionV1Ext::V0,
+            })
+        };
+        let account_entry = xdr::LedgerEntry {
+            last_modified_ledger_seq: 1,
+            data: xdr::LedgerEntryData::Account(xdr::AccountEntry {
+                account_id: account.to_xdr_account_id().unwrap(),
+                balance: 0,
+                seq_num: xdr::SequenceNumber(0),
+                num_sub_entries: 2,
+                inflation_dest: None,
+                flags: 0,
+                home_domain: Default::default(),
+                thresholds: xdr::Thresholds([1, 0, 0, 0]),
+                signers: vec![xdr::Signer {
+                    key: SignerKey::new_from_public_key(signer).to_xdr().unwrap(),
+                    weight: 1,
+                }]
+                .try_into()
+                .unwrap(),
+                ext: xdr::AccountEntryExt::V1(xdr::AccountEntryExtensionV1 {
+                    liabilities: xdr::Liabilities {
+                        buying: 0,
+                        selling: 0,
+                    },
+                    ext: xdr::AccountEntryExtensionV1Ext::V2(xdr::AccountEntryExtensionV2 {
+                        num_sponsored: 3,
+                        num_sponsoring: 0,
+                        signer_sponsoring_i_ds: vec![xdr::SponsorshipDescriptor(Some(
+                            sponsor.to_xdr_account_id().unwrap(),
+                        ))]
+                        .try_into()
+                        .unwrap(),
+                        ext: xdr::AccountEntryExtensionV2Ext::V0,
+                    }),
+                }),
+            }),
+            ext: sponsored_by(&sponsor),
+        };
+        let data_entry = |name: &str, sponsor: &crate::PublicKey| xdr::LedgerEntry {
+            last_modified_ledger_seq: 1,
+            data: xdr::LedgerEntryData::Data(xdr::DataEntry {
+                account_id: account.to_xdr_account_id().unwrap(),
+                data_name: xdr::String64(name.try_into().unwrap()),
+                data_value: b"1".to_vec().try_into().unwrap(),
+                ext: xdr::DataEntryExt::V0,
+            }),
+            ext: sponsored_by(sponsor),
+        };
+        let claimable_balance = xdr::LedgerEntry {
+            last_modified_ledger_seq: 1,
+            data: xdr::LedgerEntryData::ClaimableBalance(xdr::ClaimableBalanceEntry {
+                balance_id: xdr::ClaimableBalanceId::ClaimableBalanceIdTypeV0(xdr::Hash([1; 32])),
+                claimants: Default::default(),
+                asset: xdr::Asset::Native,
+                amount: 1,
+                ext: xdr::ClaimableBalanceEntryExt::V0,
+            }),
+            ext: sponsored_by(&sponsor),
+        };
+        let entries = [
+            account_entry,
+            data_entry("sponsored", &sponsor),
+            data_entry("other", &signer),
+            claimable_balance,
+        ];
+
+        let operations = revoke_sponsorship_operations(&sponsor, &entries).unwrap();
+        assert_eq!(3, operations.len());
+        assert!(operations
+            .iter()
+            .all(|op| op.source_account() == &Some(sponsor.into())));
+        let revoke = |op: &Operation| match op {
+            Operation::RevokeSponsorship(op) => op.clone(),
+            op => panic!("unexpected operation {:?}", op),
+        };
+        assert_eq!(
+            &LedgerKey::Account(account),
+            revoke(&operations[0])
+                .as_ledger_entry()
+                .unwrap()
+                .ledger_key()
+        );
+        assert!(revoke(&operations[1]).is_signer());
+        assert_eq!(
+            &LedgerKey::Data(account, "sponsored".to_string()),
+            revoke(&operations[2])
+                .as_ledger_entry()
+                .unwrap()
+                .ledger_key()
+        );
+
+        let operations = revoke_sponsorship_operations(&signer, &entries).unwrap();
+        assert_eq!(1, operations.len());
+    }
+}
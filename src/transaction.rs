@@ -1,14 +1,26 @@
 //! Transaction that changes the ledger state.
+use std::fmt;
 use std::io::{Read, Write};
+use std::str::FromStr;
 
+use crate::account::Account;
 use crate::amount::Stroops;
+#[cfg(feature = "async")]
+use crate::crypto::AsyncTransactionSigner;
+#[cfg(feature = "dalek")]
+use crate::crypto::SignatureHint;
 use crate::crypto::{
-    hash, DecoratedSignature, Ed25519Signer, Ed25519Verifier, KeyPair, MuxedAccount,
+    hash, DecoratedSignature, Ed25519Signer, Ed25519Verifier, KeyPair, MuxedAccount, PreAuthTxHash,
+    TransactionSigner,
 };
 use crate::error::{Error, Result};
+use crate::hex;
 use crate::memo::Memo;
 use crate::network::Network;
-use crate::operations::Operation;
+#[cfg(feature = "dalek")]
+use crate::network::NetworkRegistry;
+use crate::operations::{Operation, ThresholdLevel};
+use crate::sponsorship::{pair_sponsorships, Sponsorship};
 use crate::time_bounds::TimeBounds;
 use crate::{xdr, PublicKey};
 use ed25519::Signature;
@@ -52,6 +64,34 @@ pub struct TransactionBuilder {
     tx: Result<Transaction>,
 }
 
+/// A builder to construct a `FeeBumpTransaction` wrapping a signed transaction.
+///
+/// Unless an explicit fee is set, the fee is `(operations + 1) * base_fee`
+/// plus the Soroban resource fee of the inner transaction.
+///
+/// `Transaction` doesn't carry Soroban transaction data, so the resource fee
+/// is set with `with_resource_fee`.
+pub struct FeeBumpTransactionBuilder {
+    fee_source: MuxedAccount,
+    base_fee: Stroops,
+    fee: Option<Stroops>,
+    resource_fee: Stroops,
+    inner_tx: Result<Transaction>,
+}
+
+/// An account whose signers need to sign a transaction, together with the
+/// threshold they need to reach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredSigner {
+    account: PublicKey,
+    threshold: ThresholdLevel,
+    operations: Vec<usize>,
+}
+
+/// The hash of a transaction on a network, which identifies the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TransactionHash([u8; 32]);
+
 impl Transaction {
     /// Creates a `TransactionBuilder` to configure a `Transaction`.
     ///
@@ -166,6 +206,38 @@ impl Transaction {
         Ok(())
     }
 
+    /// Sign transaction with the key `public_key` held by `signer` for `network`, and add signature.
+    pub fn sign_with<T>(
+        &mut self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<()>
+    where
+        T: TransactionSigner + ?Sized,
+    {
+        let signature = self.decorated_signature_with(signer, public_key, network)?;
+        self.signatures.push(signature);
+        Ok(())
+    }
+
+    /// Sign transaction with the key `public_key` held by the async `signer` for `network`, and add signature.
+    #[cfg(feature = "async")]
+    pub async fn sign_with_async<T>(
+        &mut self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<()>
+    where
+        T: AsyncTransactionSigner + ?Sized,
+    {
+        let tx_hash = self.hash(network)?;
+        let signature = signer.sign_hash(&tx_hash, public_key).await?;
+        self.signatures.push(signature);
+        Ok(())
+    }
+
     /// Returns the decorated signature of the transaction create with `image`.
     pub fn decorated_signature_from_preimage(&self, preimage: &[u8]) -> Result<DecoratedSignature> {
         DecoratedSignature::new_from_preimage(preimage)
@@ -185,6 +257,20 @@ impl Transaction {
         Ok(key.sign_decorated(&tx_hash))
     }
 
+    /// Returns the decorated signature of the transaction created by `signer` with the key `public_key` for `network`.
+    pub fn decorated_signature_with<T>(
+        &self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<DecoratedSignature>
+    where
+        T: TransactionSigner + ?Sized,
+    {
+        let tx_hash = self.hash(network)?;
+        signer.sign_hash(&tx_hash, public_key)
+    }
+
     /// Returns the transaction hash for the transaction on `network`.
     pub fn hash(&self, network: &Network) -> Result<Vec<u8>> {
         let signature_data = self.signature_data(network)?;
@@ -199,6 +285,187 @@ impl Transaction {
         Ok(base)
     }
 
+    /// Checks the transaction for the malformed conditions stellar-core
+    /// rejects before loading the ledger.
+    ///
+    /// Soroban operations are reported as `MissingSorobanData`, since
+    /// `Transaction` doesn't carry Soroban transaction data.
+    ///
+    /// Returns `Error::InvalidTransaction` with all the problems found.
+    pub fn validate(&self) -> Result<()> {
+        let errors = crate::validation::validate_transaction(self);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidTransaction(errors))
+        }
+    }
+
+    /// Returns the accounts that need to sign the transaction.
+    ///
+    /// Operations are grouped by their effective source account, that is the
+    /// operation source account if set or the transaction source account
+    /// otherwise. Muxed accounts are grouped by their underlying ed25519 key.
+    /// The transaction source account is always the first entry and needs at
+    /// least the low threshold to pay the fee and consume the sequence number.
+    pub fn required_signers(&self) -> Vec<RequiredSigner> {
+        let mut signers = vec![RequiredSigner {
+            account: *self.source_account.public_key(),
+            threshold: ThresholdLevel::Low,
+            operations: Vec::new(),
+        }];
+        for (index, operation) in self.operations.iter().enumerate() {
+            let account = operation
+                .source_account()
+                .as_ref()
+                .unwrap_or(&self.source_account)
+                .public_key();
+            let level = operation.threshold_level();
+            match signers.iter_mut().find(|s| s.account == *account) {
+                Some(signer) => {
+                    signer.threshold = signer.threshold.max(level);
+                    signer.operations.push(index);
+                }
+                None => signers.push(RequiredSigner {
+                    account: *account,
+                    threshold: level,
+                    operations: vec![index],
+                }),
+            }
+        }
+        signers
+    }
+
+    /// Returns the sponsorships of the transaction, pairing the begin and end
+    /// sponsoring future reserves operations.
+    ///
+    /// Returns `Error::InvalidSponsorship` if an operation is not paired.
+    pub fn sponsorships(&self) -> Result<Vec<Sponsorship>> {
+        pair_sponsorships(&self.operations, &self.source_account, 0)
+    }
+
+    /// Moves an id memo into the destinations of the payments, path payments
+    /// and account merges, as muxed accounts.
+    ///
+    /// Does nothing if the transaction has no memo or no destination. Returns
+    /// an error if the memo is not an id memo, if the destinations are
+    /// different accounts or if a destination is already muxed, since the
+    /// memo can't be attributed to a single destination then. This changes
+    /// the transaction hash, sign the transaction afterwards.
+    pub fn mux_destinations(&mut self) -> Result<()> {
+        let id = match self.memo {
+            Memo::None => return Ok(()),
+            Memo::Id(id) => id,
+            _ => return Err(Error::InvalidMuxedMemo),
+        };
+        let destinations: Vec<&MuxedAccount> = self
+            .operations
+            .iter()
+            .filter_map(Operation::destination)
+            .collect();
+        let key = match destinations.first() {
+            None => return Ok(()),
+            Some(destination) => *destination.public_key(),
+        };
+        let ambiguous = destinations
+            .iter()
+            .any(|destination| destination.id().is_some() || destination.public_key() != &key);
+        if ambiguous {
+            return Err(Error::InvalidMuxedMemo);
+        }
+        for operation in self.operations.iter_mut() {
+            if let Some(destination) = operation.destination_mut() {
+                *destination = key.into_muxed_account(id);
+            }
+        }
+        self.memo = Memo::None;
+        Ok(())
+    }
+
+    /// Moves the muxed ids of the payments, path payments and account merges
+    /// destinations into an id memo, for receivers that don't support muxed
+    /// accounts.
+    ///
+    /// Does nothing if no destination is muxed. Returns an error if the
+    /// destinations are different accounts, have different ids, are not all
+    /// muxed, or if the transaction already has a different memo. This
+    /// changes the transaction hash, sign the transaction afterwards.
+    pub fn demux_destinations(&mut self) -> Result<()> {
+        let mut id = self.memo.as_id().copied();
+        if !self.memo.is_none() && id.is_none() {
+            return Err(Error::InvalidMuxedMemo);
+        }
+        let destinations: Vec<&MuxedAccount> = self
+            .operations
+            .iter()
+            .filter_map(Operation::destination)
+            .collect();
+        if destinations
+            .iter()
+            .all(|destination| destination.id().is_none())
+        {
+            return Ok(());
+        }
+        let key = *destinations[0].public_key();
+        for destination in &destinations {
+            if destination.public_key() != &key {
+                return Err(Error::InvalidMuxedMemo);
+            }
+            match (id, destination.id()) {
+                (_, None) => return Err(Error::InvalidMuxedMemo),
+                (None, destination_id) => id = destination_id,
+                (Some(id), Some(destination_id)) if id != destination_id => {
+                    return Err(Error::InvalidMuxedMemo)
+                }
+                _ => {}
+            }
+        }
+        let id = match id {
+            None => return Ok(()),
+            Some(id) => id,
+        };
+        for operation in self.operations.iter_mut() {
+            if let Some(destination) = operation.destination_mut() {
+                *destination = destination.to_unmuxed();
+            }
+        }
+        self.memo = Memo::Id(id);
+        Ok(())
+    }
+
+    /// Replaces all the muxed accounts of the transaction with their ed25519
+    /// public key: the source accounts, the destinations and the clawback
+    /// accounts.
+    ///
+    /// Useful to look up the accounts in the ledger, which doesn't know about
+    /// muxed ids.
+    pub fn normalize_muxed_accounts(&mut self) {
+        self.source_account = self.source_account.to_unmuxed();
+        for operation in self.operations.iter_mut() {
+            if let Some(source) = operation.source_account_mut() {
+                *source = source.to_unmuxed();
+            }
+            if let Some(destination) = operation.destination_mut() {
+                *destination = destination.to_unmuxed();
+            }
+            if let Operation::Clawback(op) = operation {
+                *op.from_mut() = op.from().to_unmuxed();
+            }
+        }
+    }
+
+    /// Returns the account credited by the operation at `index` and the id
+    /// to attribute the deposit to.
+    ///
+    /// The id is the muxed id of the destination if any, the transaction id
+    /// memo otherwise. Returns None if the operation is not a payment, path
+    /// payment or account merge.
+    pub fn deposit_attribution(&self, index: usize) -> Option<(PublicKey, Option<u64>)> {
+        let destination = self.operations.get(index)?.destination()?;
+        let id = destination.id().or_else(|| self.memo.as_id().copied());
+        Some((*destination.public_key(), id))
+    }
+
     /// Returns the xdr object.
     pub fn to_xdr(&self) -> Result<xdr::Transaction> {
         let source_account = self.source_account.to_xdr()?;
@@ -221,7 +488,7 @@ impl Transaction {
             seq_num,
             cond,
             memo,
-            operations: operations.try_into().map_err(|_| Error::XdrError)?,
+            operations: operations.try_into().map_err(Error::XdrError)?,
             ext,
         })
     }
@@ -238,10 +505,7 @@ impl Transaction {
         &self,
         network: &Network,
     ) -> Result<xdr::TransactionSignaturePayload> {
-        let network_id = network
-            .network_id()
-            .try_into()
-            .map_err(|_| Error::XdrError)?;
+        let network_id = xdr::Hash(network.network_id());
         let inner = self.to_xdr()?;
         let tagged_transaction = xdr::TransactionSignaturePayloadTaggedTransaction::Tx(inner);
         Ok(xdr::TransactionSignaturePayload {
@@ -326,7 +590,19 @@ impl Transaction {
 }
 
 impl FeeBumpTransaction {
+    /// Creates a `FeeBumpTransactionBuilder` to wrap `inner_tx`.
+    ///
+    /// This is the same as `FeeBumpTransactionBuilder::new`.
+    pub fn builder<S: Into<MuxedAccount>>(
+        fee_source: S,
+        inner_tx: Transaction,
+    ) -> FeeBumpTransactionBuilder {
+        FeeBumpTransactionBuilder::new(fee_source, inner_tx)
+    }
+
     /// Creates a new fee bump transaction.
+    ///
+    /// The fee is not validated, use `FeeBumpTransaction::builder` to compute it.
     pub fn new(
         fee_source: MuxedAccount,
         fee: Stroops,
@@ -412,6 +688,38 @@ impl FeeBumpTransaction {
         Ok(())
     }
 
+    /// Sign transaction with the key `public_key` held by `signer` for `network`, and add signature.
+    pub fn sign_with<T>(
+        &mut self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<()>
+    where
+        T: TransactionSigner + ?Sized,
+    {
+        let signature = self.decorated_signature_with(signer, public_key, network)?;
+        self.signatures.push(signature);
+        Ok(())
+    }
+
+    /// Sign transaction with the key `public_key` held by the async `signer` for `network`, and add signature.
+    #[cfg(feature = "async")]
+    pub async fn sign_with_async<T>(
+        &mut self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<()>
+    where
+        T: AsyncTransactionSigner + ?Sized,
+    {
+        let tx_hash = self.hash(network)?;
+        let signature = signer.sign_hash(&tx_hash, public_key).await?;
+        self.signatures.push(signature);
+        Ok(())
+    }
+
     /// Returns the decorated signature of the transaction create with `image`.
     pub fn decorated_signature_from_preimage(&self, preimage: &[u8]) -> Result<DecoratedSignature> {
         DecoratedSignature::new_from_preimage(preimage)
@@ -431,6 +739,20 @@ impl FeeBumpTransaction {
         Ok(key.sign_decorated(&tx_hash))
     }
 
+    /// Returns the decorated signature of the transaction created by `signer` with the key `public_key` for `network`.
+    pub fn decorated_signature_with<T>(
+        &self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<DecoratedSignature>
+    where
+        T: TransactionSigner + ?Sized,
+    {
+        let tx_hash = self.hash(network)?;
+        signer.sign_hash(&tx_hash, public_key)
+    }
+
     /// Returns the transaction hash for the transaction on `network`.
     pub fn hash(&self, network: &Network) -> Result<Vec<u8>> {
         let signature_data = self.signature_data(network)?;
@@ -497,10 +819,7 @@ impl FeeBumpTransaction {
         &self,
         network: &Network,
     ) -> Result<xdr::TransactionSignaturePayload> {
-        let network_id = network
-            .network_id()
-            .try_into()
-            .map_err(|_| Error::XdrError)?;
+        let network_id = xdr::Hash(network.network_id());
         let inner = self.to_xdr()?;
         let tagged_transaction =
             xdr::TransactionSignaturePayloadTaggedTransaction::TxFeeBump(inner);
@@ -511,6 +830,23 @@ impl FeeBumpTransaction {
     }
 }
 
+impl RequiredSigner {
+    /// Retrieves the account that needs to sign.
+    pub fn account(&self) -> &PublicKey {
+        &self.account
+    }
+
+    /// Retrieves the threshold the account signers need to reach.
+    pub fn threshold(&self) -> &ThresholdLevel {
+        &self.threshold
+    }
+
+    /// Retrieves the indexes of the operations sourced by the account.
+    pub fn operations(&self) -> &Vec<usize> {
+        &self.operations
+    }
+}
+
 impl TransactionEnvelope {
     /// If the transaction is a Transaction, returns its value. Returns None otherwise.
     pub fn as_transaction(&self) -> Option<&Transaction> {
@@ -576,6 +912,45 @@ impl TransactionEnvelope {
         }
     }
 
+    /// Sign transaction with the key `public_key` held by `signer` for `network`, and add signature.
+    pub fn sign_with<T>(
+        &mut self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<()>
+    where
+        T: TransactionSigner + ?Sized,
+    {
+        match self {
+            TransactionEnvelope::Transaction(tx) => tx.sign_with(signer, public_key, network),
+            TransactionEnvelope::FeeBumpTransaction(tx) => {
+                tx.sign_with(signer, public_key, network)
+            }
+        }
+    }
+
+    /// Sign transaction with the key `public_key` held by the async `signer` for `network`, and add signature.
+    #[cfg(feature = "async")]
+    pub async fn sign_with_async<T>(
+        &mut self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<()>
+    where
+        T: AsyncTransactionSigner + ?Sized,
+    {
+        match self {
+            TransactionEnvelope::Transaction(tx) => {
+                tx.sign_with_async(signer, public_key, network).await
+            }
+            TransactionEnvelope::FeeBumpTransaction(tx) => {
+                tx.sign_with_async(signer, public_key, network).await
+            }
+        }
+    }
+
     /// Returns the decorated signature of the transaction create with `image`.
     pub fn decorated_signature_from_preimage(&self, preimage: &[u8]) -> Result<DecoratedSignature> {
         match self {
@@ -602,6 +977,26 @@ impl TransactionEnvelope {
         }
     }
 
+    /// Returns the decorated signature of the transaction created by `signer` with the key `public_key` for `network`.
+    pub fn decorated_signature_with<T>(
+        &self,
+        signer: &T,
+        public_key: &PublicKey,
+        network: &Network,
+    ) -> Result<DecoratedSignature>
+    where
+        T: TransactionSigner + ?Sized,
+    {
+        match self {
+            TransactionEnvelope::Transaction(tx) => {
+                tx.decorated_signature_with(signer, public_key, network)
+            }
+            TransactionEnvelope::FeeBumpTransaction(tx) => {
+                tx.decorated_signature_with(signer, public_key, network)
+            }
+        }
+    }
+
     /// Returns the transaction hash for the transaction on `network`.
     pub fn hash(&self, network: &Network) -> Result<Vec<u8>> {
         match self {
@@ -610,6 +1005,11 @@ impl TransactionEnvelope {
         }
     }
 
+    /// Returns the `TransactionHash` of the transaction on `network`.
+    pub fn transaction_hash(&self, network: &Network) -> Result<TransactionHash> {
+        TransactionHash::new_from_transaction_envelope(self, network)
+    }
+
     /// Returns the transaction signature data as bytes.
     pub fn signature_data(&self, network: &Network) -> Result<Vec<u8>> {
         match self {
@@ -618,6 +1018,39 @@ impl TransactionEnvelope {
         }
     }
 
+    /// Returns the network of `registry` the envelope was signed for.
+    ///
+    /// Signatures are matched against the `signers` keys by their hint, and
+    /// verified against the transaction hash of each registered network. The
+    /// signatures of the inner transaction of fee bump transactions are
+    /// checked too. Returns None if no signature verifies on any network.
+    #[cfg(feature = "dalek")]
+    pub fn detect_network<'a>(
+        &self,
+        registry: &'a NetworkRegistry,
+        signers: &[PublicKey],
+    ) -> Result<Option<&'a Network>> {
+        for network in registry.networks() {
+            let signed = match self {
+                TransactionEnvelope::Transaction(tx) => {
+                    is_signed_by_any(&tx.hash(network)?, tx.signatures(), signers)
+                }
+                TransactionEnvelope::FeeBumpTransaction(tx) => {
+                    is_signed_by_any(&tx.hash(network)?, tx.signatures(), signers)
+                        || is_signed_by_any(
+                            &tx.inner_transaction().hash(network)?,
+                            tx.inner_transaction().signatures(),
+                            signers,
+                        )
+                }
+            };
+            if signed {
+                return Ok(Some(network));
+            }
+        }
+        Ok(None)
+    }
+
     /// Returns the xdr object.
     pub fn to_xdr(&self) -> Result<xdr::TransactionEnvelope> {
         match self {
@@ -650,6 +1083,16 @@ impl TransactionEnvelope {
         }
     }
 
+    /// Returns the SEP-11 txrep representation of the envelope.
+    pub fn to_txrep(&self) -> Result<String> {
+        Ok(crate::txrep::to_txrep(&self.to_xdr()?))
+    }
+
+    /// Creates from the SEP-11 txrep representation.
+    pub fn from_txrep(txrep: &str) -> Result<TransactionEnvelope> {
+        TransactionEnvelope::from_xdr(&crate::txrep::from_txrep(txrep)?)
+    }
+
     /// Returns the xdr transaction signature payload object.
     pub fn to_xdr_transaction_signature_payload(
         &self,
@@ -689,6 +1132,23 @@ impl TransactionBuilder {
         TransactionBuilder { tx, base_fee }
     }
 
+    /// Creates a builder with `account` as source account, using the next
+    /// sequence number of the account.
+    ///
+    /// The sequence number of `account` is not incremented, this is used by
+    /// `Account::build_transaction` which does it once the build succeeds.
+    pub(crate) fn from_account(account: &Account, base_fee: Stroops) -> TransactionBuilder {
+        let source_account = *account.account_id();
+        match account.next_sequence() {
+            Ok(sequence) => TransactionBuilder::new(source_account, sequence, base_fee),
+            Err(err) => {
+                let mut builder = TransactionBuilder::new(source_account, 0, base_fee);
+                builder.tx = Err(err);
+                builder
+            }
+        }
+    }
+
     pub fn with_time_bounds(mut self, time_bounds: TimeBounds) -> TransactionBuilder {
         if let Ok(ref mut tx) = self.tx {
             *tx.time_bounds_mut() = Some(time_bounds);
@@ -719,6 +1179,48 @@ impl TransactionBuilder {
         self
     }
 
+    /// Adds the operations added by `f` between the begin and end sponsoring
+    /// future reserves operations, so that `sponsor` pays the reserves of
+    /// `sponsored`.
+    ///
+    /// The sponsor is the source of the begin operation and the sponsored
+    /// account the source of the end operation, both need to sign the
+    /// transaction. The builder fails with `Error::InvalidSponsorship` if the
+    /// operations are not paired correctly.
+    pub fn sponsor<F>(self, sponsor: PublicKey, sponsored: PublicKey, f: F) -> TransactionBuilder
+    where
+        F: FnOnce(TransactionBuilder) -> TransactionBuilder,
+    {
+        let begin = match self.tx {
+            Ok(ref tx) => tx.operations.len(),
+            Err(_) => return self,
+        };
+        let begin_op = Operation::new_begin_sponsoring_future_reserves()
+            .with_source_account(sponsor)
+            .with_sponsored_id(sponsored)
+            .build();
+        let mut builder = match begin_op {
+            Ok(op) => f(self.add_operation(op)),
+            Err(err) => {
+                return TransactionBuilder {
+                    base_fee: self.base_fee,
+                    tx: Err(err),
+                }
+            }
+        };
+        let end_op = Operation::new_end_sponsoring_future_reserves()
+            .with_source_account(sponsored)
+            .build();
+        builder = builder.add_operation(end_op);
+        if let Ok(ref tx) = builder.tx {
+            let operations = &tx.operations[begin..];
+            if let Err(err) = pair_sponsorships(operations, &tx.source_account, begin) {
+                builder.tx = Err(err);
+            }
+        }
+        builder
+    }
+
     pub fn into_transaction(mut self) -> Result<Transaction> {
         let mut error = None;
         if let Ok(ref mut tx) = self.tx {
@@ -739,6 +1241,125 @@ impl TransactionBuilder {
     }
 }
 
+impl FeeBumpTransactionBuilder {
+    pub fn new<S: Into<MuxedAccount>>(
+        fee_source: S,
+        inner_tx: Transaction,
+    ) -> FeeBumpTransactionBuilder {
+        FeeBumpTransactionBuilder::from_inner(fee_source.into(), Ok(inner_tx))
+    }
+
+    /// Creates a builder wrapping the transaction in `envelope`.
+    ///
+    /// V0 envelopes are converted to V1, their signatures stay valid since
+    /// both envelope types have the same hash.
+    pub fn from_envelope<S: Into<MuxedAccount>>(
+        fee_source: S,
+        envelope: TransactionEnvelope,
+    ) -> FeeBumpTransactionBuilder {
+        let inner_tx = match envelope {
+            TransactionEnvelope::Transaction(tx) => Ok(tx),
+            TransactionEnvelope::FeeBumpTransaction(_) => Err(Error::InvalidInnerTransaction),
+        };
+        FeeBumpTransactionBuilder::from_inner(fee_source.into(), inner_tx)
+    }
+
+    /// Creates a builder wrapping the transaction in the xdr `envelope`.
+    ///
+    /// Building fails with `Error::UnsupportedFeature` if the transaction
+    /// has Soroban transaction data.
+    pub fn from_xdr_envelope<S: Into<MuxedAccount>>(
+        fee_source: S,
+        envelope: &xdr::TransactionEnvelope,
+    ) -> FeeBumpTransactionBuilder {
+        match TransactionEnvelope::from_xdr(envelope) {
+            Ok(envelope) => FeeBumpTransactionBuilder::from_envelope(fee_source, envelope),
+            Err(error) => FeeBumpTransactionBuilder::from_inner(fee_source.into(), Err(error)),
+        }
+    }
+
+    /// Sets the base fee used to compute the fee.
+    pub fn with_base_fee(mut self, base_fee: Stroops) -> FeeBumpTransactionBuilder {
+        self.base_fee = base_fee;
+        self
+    }
+
+    /// Sets the fee, instead of computing it from the base fee.
+    ///
+    /// The fee includes the resource fee.
+    pub fn with_fee(mut self, fee: Stroops) -> FeeBumpTransactionBuilder {
+        self.fee = Some(fee);
+        self
+    }
+
+    /// Sets the Soroban resource fee of the inner transaction.
+    pub fn with_resource_fee(mut self, resource_fee: Stroops) -> FeeBumpTransactionBuilder {
+        self.resource_fee = resource_fee;
+        self
+    }
+
+    pub fn into_transaction(self) -> Result<FeeBumpTransaction> {
+        let inner_tx = self.inner_tx?;
+        if inner_tx.operations().is_empty() {
+            return Err(Error::MissingOperations);
+        }
+        if self.resource_fee < Stroops::new(0) {
+            return Err(Error::TransactionFeeTooLow);
+        }
+        // The fee bump counts as an additional operation.
+        let operations = Stroops::new(inner_tx.operations().len() as i64 + 1);
+        let min_inclusion_fee = MIN_BASE_FEE
+            .checked_mul(&operations)
+            .ok_or(Error::TransactionFeeOverflow)?;
+        let (fee, inclusion_fee) = match self.fee {
+            Some(fee) => {
+                let inclusion_fee = fee
+                    .checked_sub(&self.resource_fee)
+                    .ok_or(Error::TransactionFeeOverflow)?;
+                (fee, inclusion_fee)
+            }
+            None => {
+                if self.base_fee < MIN_BASE_FEE {
+                    return Err(Error::TransactionFeeTooLow);
+                }
+                let inclusion_fee = self
+                    .base_fee
+                    .checked_mul(&operations)
+                    .ok_or(Error::TransactionFeeOverflow)?;
+                let fee = inclusion_fee
+                    .checked_add(&self.resource_fee)
+                    .ok_or(Error::TransactionFeeOverflow)?;
+                (fee, inclusion_fee)
+            }
+        };
+        if inclusion_fee < min_inclusion_fee {
+            return Err(Error::TransactionFeeTooLow);
+        }
+        // The fee rate can't be lower than the inner transaction fee rate.
+        let inner_inclusion_fee = (inner_tx.fee().to_i64() - self.resource_fee.to_i64()).max(0);
+        let inner_operations = operations.to_i64() - 1;
+        if i128::from(inclusion_fee.to_i64()) * i128::from(inner_operations)
+            < i128::from(inner_inclusion_fee) * i128::from(operations.to_i64())
+        {
+            return Err(Error::TransactionFeeTooLow);
+        }
+        Ok(FeeBumpTransaction::new(self.fee_source, fee, inner_tx))
+    }
+
+    fn from_inner(
+        fee_source: MuxedAccount,
+        inner_tx: Result<Transaction>,
+    ) -> FeeBumpTransactionBuilder {
+        FeeBumpTransactionBuilder {
+            fee_source,
+            base_fee: MIN_BASE_FEE,
+            fee: None,
+            resource_fee: Stroops::new(0),
+            inner_tx,
+        }
+    }
+}
+
 impl xdr::WriteXdr for TransactionEnvelope {
     fn write_xdr<W: Write>(&self, w: &mut xdr::Limited<W>) -> xdr::Result<()> {
         let xdr_tx = self.to_xdr().map_err(|_| xdr::Error::Invalid)?;
@@ -761,7 +1382,7 @@ fn signatures_to_xdr(
         let xdr_signature = signature.to_xdr()?;
         xdr_signatures.push(xdr_signature);
     }
-    xdr_signatures.try_into().map_err(|_| Error::XdrError)
+    xdr_signatures.try_into().map_err(Error::XdrError)
 }
 
 fn signatures_from_xdr(
@@ -775,14 +1396,99 @@ fn signatures_from_xdr(
     Ok(signatures)
 }
 
+impl TransactionHash {
+    /// Creates a `TransactionHash` from the hash bytes.
+    pub fn new(hash: [u8; 32]) -> TransactionHash {
+        TransactionHash(hash)
+    }
+
+    /// Creates a `TransactionHash` from a slice of bytes.
+    ///
+    /// The slice must be exactly 32 bytes.
+    pub fn from_slice(hash: &[u8]) -> Result<TransactionHash> {
+        let hash = hash.try_into().map_err(|_| Error::InvalidTransactionHash)?;
+        Ok(TransactionHash(hash))
+    }
+
+    /// Creates the `TransactionHash` of the transaction envelope on `network`.
+    pub fn new_from_transaction_envelope(
+        tx: &TransactionEnvelope,
+        network: &Network,
+    ) -> Result<TransactionHash> {
+        TransactionHash::from_slice(&tx.hash(network)?)
+    }
+
+    /// Retrieves the hash bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Returns the `PreAuthTxHash` to use the transaction as pre authorized transaction.
+    pub fn to_pre_auth_tx_hash(&self) -> PreAuthTxHash {
+        PreAuthTxHash::from(*self)
+    }
+
+    /// Returns the xdr object.
+    pub fn to_xdr(&self) -> xdr::Hash {
+        xdr::Hash(self.0)
+    }
+
+    /// Creates from xdr object.
+    pub fn from_xdr(x: &xdr::Hash) -> TransactionHash {
+        TransactionHash(x.0)
+    }
+}
+
+impl From<TransactionHash> for PreAuthTxHash {
+    fn from(hash: TransactionHash) -> PreAuthTxHash {
+        // The hash is always 32 bytes long.
+        PreAuthTxHash::new(hash.0.to_vec()).unwrap()
+    }
+}
+
+impl fmt::Display for TransactionHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(&self.0))
+    }
+}
+
+impl FromStr for TransactionHash {
+    type Err = Error;
+
+    /// Parses the hex encoded transaction hash.
+    fn from_str(s: &str) -> Result<TransactionHash> {
+        let hash = hex::decode(s).ok_or(Error::InvalidTransactionHash)?;
+        TransactionHash::from_slice(&hash)
+    }
+}
+
+#[cfg(feature = "dalek")]
+fn is_signed_by_any(
+    tx_hash: &[u8],
+    signatures: &[DecoratedSignature],
+    signers: &[PublicKey],
+) -> bool {
+    signatures.iter().any(|signature| {
+        signers.iter().any(|signer| {
+            let hint = SignatureHint::from_public_key(signer.as_bytes());
+            signature.hint() == &hint && signer.verify(tx_hash, signature.signature())
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Transaction;
+    use super::{FeeBumpTransaction, FeeBumpTransactionBuilder, Transaction};
     use crate::amount::Stroops;
-    use crate::crypto::DalekKeyPair;
+    use crate::crypto::{
+        DalekKeyPair, InMemorySigner, MockSigner, MuxedAccount, MuxedEd25519PublicKey,
+    };
+    use crate::error::Error;
     use crate::memo::Memo;
-    use crate::operations::Operation;
+    use crate::network::Network;
+    use crate::operations::{Operation, ThresholdLevel};
     use crate::time_bounds::TimeBounds;
+    use crate::xdr;
 
     #[test]
     fn test_transaction_builder() {
@@ -799,4 +1505,526 @@ mod tests {
         assert!(tx.time_bounds().is_some());
         assert_eq!(1, tx.operations().len());
     }
+
+    #[test]
+    fn test_required_signers() {
+        let kp0 = DalekKeyPair::random().unwrap();
+        let kp1 = DalekKeyPair::random().unwrap();
+        let kp2 = DalekKeyPair::random().unwrap();
+        let muxed1 = MuxedEd25519PublicKey::new(kp1.public_key(), 42);
+        let tx = Transaction::builder(kp0.public_key(), 123, Stroops::new(400))
+            .add_operation(Operation::new_inflation().build())
+            .add_operation(
+                Operation::new_set_options()
+                    .with_source_account(muxed1)
+                    .with_master_weight(Some(1))
+                    .build()
+                    .unwrap(),
+            )
+            .add_operation(
+                Operation::new_bump_sequence()
+                    .with_source_account(kp1.public_key())
+                    .with_bump_to(1)
+                    .build()
+                    .unwrap(),
+            )
+            .add_operation(
+                Operation::new_set_options()
+                    .with_source_account(kp2.public_key())
+                    .build()
+                    .unwrap(),
+            )
+            .into_transaction()
+            .unwrap();
+
+        let signers = tx.required_signers();
+        assert_eq!(3, signers.len());
+        assert_eq!(&kp0.public_key(), signers[0].account());
+        assert_eq!(&ThresholdLevel::Low, signers[0].threshold());
+        assert_eq!(&vec![0], signers[0].operations());
+        assert_eq!(&kp1.public_key(), signers[1].account());
+        assert_eq!(&ThresholdLevel::High, signers[1].threshold());
+        assert_eq!(&vec![1, 2], signers[1].operations());
+        assert_eq!(&kp2.public_key(), signers[2].account());
+        assert_eq!(&ThresholdLevel::Medium, signers[2].threshold());
+        assert_eq!(&vec![3], signers[2].operations());
+    }
+
+    fn payment<D: Into<MuxedAccount>>(destination: D) -> Operation {
+        Operation::new_payment()
+            .with_destination(destination)
+            .with_amount(Stroops::new(10))
+            .unwrap()
+            .with_asset(crate::Asset::new_native())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mux_and_demux_destinations() {
+        let source = DalekKeyPair::random().unwrap().public_key();
+        let exchange = DalekKeyPair::random().unwrap().public_key();
+        let mut tx = Transaction::builder(source, 1, Stroops::new(200))
+            .with_memo(Memo::new_id(42))
+            .add_operation(payment(exchange))
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap();
+        let original = tx.clone();
+
+        tx.mux_destinations().unwrap();
+        assert!(tx.memo().is_none());
+        assert_eq!(
+            Some(&MuxedAccount::from(MuxedEd25519PublicKey::new(
+                exchange, 42
+            ))),
+            tx.operations()[0].destination()
+        );
+        assert_eq!(Some((exchange, Some(42))), tx.deposit_attribution(0));
+        assert_eq!(None, tx.deposit_attribution(1));
+
+        tx.demux_destinations().unwrap();
+        assert_eq!(original, tx);
+        assert_eq!(Some((exchange, Some(42))), tx.deposit_attribution(0));
+
+        let mut conflicting = Transaction::builder(source, 1, Stroops::new(200))
+            .add_operation(payment(exchange.to_muxed_account(1)))
+            .add_operation(payment(exchange.to_muxed_account(2)))
+            .into_transaction()
+            .unwrap();
+        assert!(matches!(
+            conflicting.demux_destinations(),
+            Err(Error::InvalidMuxedMemo)
+        ));
+        *conflicting.memo_mut() = Memo::new_text("deposit").unwrap();
+        assert!(matches!(
+            conflicting.mux_destinations(),
+            Err(Error::InvalidMuxedMemo)
+        ));
+
+        // Mixed transactions can't be converted without losing attributions.
+        let other = DalekKeyPair::random().unwrap().public_key();
+        let mut different_accounts = Transaction::builder(source, 1, Stroops::new(200))
+            .with_memo(Memo::new_id(42))
+            .add_operation(payment(exchange))
+            .add_operation(payment(other))
+            .into_transaction()
+            .unwrap();
+        assert!(matches!(
+            different_accounts.mux_destinations(),
+            Err(Error::InvalidMuxedMemo)
+        ));
+        assert_eq!(&Memo::new_id(42), different_accounts.memo());
+        assert_eq!(
+            Some(&MuxedAccount::Ed25519(exchange)),
+            different_accounts.operations()[0].destination()
+        );
+
+        let mut partly_muxed = Transaction::builder(source, 1, Stroops::new(200))
+            .with_memo(Memo::new_id(42))
+            .add_operation(payment(exchange))
+            .add_operation(payment(exchange.to_muxed_account(7)))
+            .into_transaction()
+            .unwrap();
+        assert!(matches!(
+            partly_muxed.mux_destinations(),
+            Err(Error::InvalidMuxedMemo)
+        ));
+        *partly_muxed.memo_mut() = Memo::None;
+        assert!(matches!(
+            partly_muxed.demux_destinations(),
+            Err(Error::InvalidMuxedMemo)
+        ));
+        assert_eq!(
+            Some(&exchange.to_muxed_account(7)),
+            partly_muxed.operations()[1].destination()
+        );
+
+        let mut muxed_accounts = Transaction::builder(source, 1, Stroops::new(200))
+            .add_operation(payment(exchange.to_muxed_account(7)))
+            .add_operation(payment(other.to_muxed_account(7)))
+            .into_transaction()
+            .unwrap();
+        assert!(matches!(
+            muxed_accounts.demux_destinations(),
+            Err(Error::InvalidMuxedMemo)
+        ));
+
+        let mut same_id = Transaction::builder(source, 1, Stroops::new(200))
+            .add_operation(payment(exchange.to_muxed_account(7)))
+            .add_operation(payment(exchange.to_muxed_account(7)))
+            .into_transaction()
+            .unwrap();
+        same_id.demux_destinations().unwrap();
+        assert_eq!(&Memo::new_id(7), same_id.memo());
+        same_id.mux_destinations().unwrap();
+        assert!(same_id.memo().is_none());
+        assert_eq!(
+            Some(&exchange.to_muxed_account(7)),
+            same_id.operations()[1].destination()
+        );
+    }
+
+    #[test]
+    fn test_normalize_muxed_accounts() {
+        let source = DalekKeyPair::random().unwrap().public_key();
+        let exchange = DalekKeyPair::random().unwrap().public_key();
+        let mut tx = Transaction::builder(source.to_muxed_account(3), 1, Stroops::new(200))
+            .add_operation(payment(exchange.to_muxed_account(7)))
+            .into_transaction()
+            .unwrap();
+        *tx.operations_mut()[0].source_account_mut() = Some(source.to_muxed_account(4));
+        tx.normalize_muxed_accounts();
+        assert_eq!(&MuxedAccount::Ed25519(source), tx.source_account());
+        assert_eq!(
+            &Some(MuxedAccount::Ed25519(source)),
+            tx.operations()[0].source_account()
+        );
+        assert_eq!(
+            Some(&MuxedAccount::Ed25519(exchange)),
+            tx.operations()[0].destination()
+        );
+        assert_eq!(Some((exchange, None)), tx.deposit_attribution(0));
+
+        let (key, memo) = MuxedAccount::from_memo(exchange, &Memo::new_id(9))
+            .unwrap()
+            .to_memo();
+        assert_eq!((exchange, Memo::new_id(9)), (key, memo));
+        assert!(MuxedAccount::from_memo(exchange, &Memo::new_text("a").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_sign_with_transaction_signer() {
+        let kp = DalekKeyPair::random().unwrap();
+        let public_key = kp.public_key();
+        let network = Network::new_test();
+        let mut tx = Transaction::builder(public_key, 123, Stroops::new(100))
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap();
+        let expected = tx.decorated_signature(kp.as_ref(), &network).unwrap();
+
+        let signer = MockSigner::new(InMemorySigner::new().with_key_pair(kp));
+        tx.sign_with(&signer, &public_key, &network).unwrap();
+        assert_eq!(&vec![expected], tx.signatures());
+
+        let requests = signer.requests();
+        assert_eq!(1, requests.len());
+        assert_eq!(tx.hash(&network).unwrap(), requests[0].hash);
+        assert_eq!(public_key, requests[0].public_key);
+
+        let other = DalekKeyPair::random().unwrap().public_key();
+        assert!(tx.sign_with(&signer, &other, &network).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_sign_with_async_transaction_signer() {
+        use std::future::Future;
+        use std::pin::pin;
+        use std::task::{Context, Poll, Waker};
+
+        let kp = DalekKeyPair::random().unwrap();
+        let public_key = kp.public_key();
+        let network = Network::new_test();
+        let mut tx = Transaction::builder(public_key, 123, Stroops::new(100))
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap()
+            .into_envelope();
+        let expected = tx.decorated_signature(kp.as_ref(), &network).unwrap();
+
+        let signer = InMemorySigner::new().with_key_pair(kp);
+        {
+            let mut fut = pin!(tx.sign_with_async(&signer, &public_key, &network));
+            let mut cx = Context::from_waker(Waker::noop());
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(res) => res.unwrap(),
+                Poll::Pending => panic!("in memory signer should not block"),
+            }
+        }
+        assert_eq!(&vec![expected], tx.as_transaction().unwrap().signatures());
+    }
+
+    fn signed_transaction(kp: &DalekKeyPair, operations: usize, fee: i64) -> Transaction {
+        let mut builder = Transaction::builder(kp.public_key(), 123, Stroops::new(100));
+        for _ in 0..operations {
+            builder = builder.add_operation(Operation::new_inflation().build());
+        }
+        let mut tx = builder.into_transaction().unwrap();
+        *tx.fee_mut() = Stroops::new(fee);
+        tx.sign(kp.as_ref(), &Network::new_test()).unwrap();
+        tx
+    }
+
+    #[test]
+    fn test_fee_bump_builder_fee() {
+        let kp = DalekKeyPair::random().unwrap();
+        let fee_source = DalekKeyPair::random().unwrap().public_key();
+        let tx = signed_transaction(&kp, 2, 200);
+
+        let fee_bump = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&Stroops::new(300), fee_bump.fee());
+        assert_eq!(&tx, fee_bump.inner_transaction());
+
+        let fee_bump = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_base_fee(Stroops::new(1_000))
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&Stroops::new(3_000), fee_bump.fee());
+
+        let fee_bump = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_base_fee(Stroops::new(1_000))
+            .with_resource_fee(Stroops::new(500))
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&Stroops::new(3_500), fee_bump.fee());
+
+        // The base fee is ignored when the fee is set.
+        let fee_bump = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_base_fee(Stroops::new(1))
+            .with_fee(Stroops::new(400))
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&Stroops::new(400), fee_bump.fee());
+
+        let muxed = MuxedAccount::MuxedEd25519(MuxedEd25519PublicKey::new(fee_source, 7));
+        let fee_bump = FeeBumpTransaction::builder(muxed.clone(), tx.clone())
+            .with_fee(Stroops::new(1_000))
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&muxed, fee_bump.fee_source());
+        assert_eq!(&Stroops::new(1_000), fee_bump.fee());
+    }
+
+    #[test]
+    fn test_fee_bump_builder_rejects_low_fees() {
+        let kp = DalekKeyPair::random().unwrap();
+        let fee_source = DalekKeyPair::random().unwrap().public_key();
+
+        let tx = signed_transaction(&kp, 1, 100);
+        let err = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_base_fee(Stroops::new(99))
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionFeeTooLow));
+        let err = FeeBumpTransaction::builder(fee_source, tx)
+            .with_fee(Stroops::new(199))
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionFeeTooLow));
+
+        // The inner transaction pays 1000 per operation.
+        let tx = signed_transaction(&kp, 1, 1_000);
+        let err = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_fee(Stroops::new(1_999))
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionFeeTooLow));
+        let fee_bump = FeeBumpTransaction::builder(fee_source, tx)
+            .with_fee(Stroops::new(2_000))
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&Stroops::new(2_000), fee_bump.fee());
+
+        // The resource fee is not part of the fee rate.
+        let tx = signed_transaction(&kp, 1, 1_500);
+        let err = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_resource_fee(Stroops::new(500))
+            .with_fee(Stroops::new(2_499))
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionFeeTooLow));
+        let fee_bump = FeeBumpTransaction::builder(fee_source, tx.clone())
+            .with_resource_fee(Stroops::new(500))
+            .with_fee(Stroops::new(2_500))
+            .into_transaction()
+            .unwrap();
+        assert_eq!(&Stroops::new(2_500), fee_bump.fee());
+        let err = FeeBumpTransaction::builder(fee_source, tx)
+            .with_resource_fee(Stroops::new(-1))
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionFeeTooLow));
+    }
+
+    #[test]
+    fn test_fee_bump_builder_from_envelope() {
+        let kp = DalekKeyPair::random().unwrap();
+        let fee_source = DalekKeyPair::random().unwrap().public_key();
+        let network = Network::new_test();
+        let tx = signed_transaction(&kp, 1, 100);
+
+        let v1 = tx.to_xdr_envelope().unwrap();
+        let v0 = xdr::TransactionEnvelope::TxV0(xdr::TransactionV0Envelope {
+            tx: xdr::TransactionV0 {
+                source_account_ed25519: kp.public_key().to_xdr_uint256().unwrap(),
+                fee: v1.tx.fee,
+                seq_num: v1.tx.seq_num.clone(),
+                time_bounds: None,
+                memo: v1.tx.memo.clone(),
+                operations: v1.tx.operations.clone(),
+                ext: xdr::TransactionV0Ext::V0,
+            },
+            signatures: v1.signatures.clone(),
+        });
+        let fee_bump = FeeBumpTransactionBuilder::from_xdr_envelope(fee_source, &v0)
+            .into_transaction()
+            .unwrap();
+        let inner = fee_bump.inner_transaction();
+        assert_eq!(tx.hash(&network).unwrap(), inner.hash(&network).unwrap());
+        assert_eq!(tx.signatures(), inner.signatures());
+
+        let envelope = fee_bump.into_envelope();
+        let err = FeeBumpTransactionBuilder::from_envelope(fee_source, envelope)
+            .into_transaction()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInnerTransaction));
+
+        let mut soroban = v1;
+        soroban.tx.ext = xdr::TransactionExt::V1(xdr::SorobanTransactionData {
+            ext: xdr::SorobanTransactionDataExt::V0,
+            resources: xdr::SorobanResources {
+                footprint: xdr::LedgerFootprint {
+                    read_only: Default::default(),
+                    read_write: Default::default(),
+                },
+                instructions: 0,
+                disk_read_bytes: 0,
+                write_bytes: 0,
+            },
+            resource_fee: 500,
+        });
+        let err = FeeBumpTransactionBuilder::from_xdr_envelope(
+            fee_source,
+            &xdr::TransactionEnvelope::Tx(soroban),
+        )
+        .into_transaction()
+        .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature));
+    }
+
+    #[test]
+    fn test_detect_network() {
+        use crate::network::NetworkRegistry;
+
+        let kp = DalekKeyPair::random().unwrap();
+        let other = DalekKeyPair::random().unwrap();
+        let registry = NetworkRegistry::known();
+        let tx = signed_transaction(&kp, 1, 100);
+
+        let envelope = tx.clone().into_envelope();
+        let network = envelope
+            .detect_network(&registry, &[other.public_key(), kp.public_key()])
+            .unwrap();
+        assert_eq!(Some(&Network::new_test()), network);
+        let network = envelope
+            .detect_network(&registry, &[other.public_key()])
+            .unwrap();
+        assert_eq!(None, network);
+        let empty = NetworkRegistry::new();
+        let network = envelope.detect_network(&empty, &[kp.public_key()]).unwrap();
+        assert_eq!(None, network);
+
+        let mut fee_bump = FeeBumpTransaction::builder(other.public_key(), tx)
+            .into_transaction()
+            .unwrap();
+        let futurenet = Network::new_futurenet();
+        assert_eq!(
+            Some(&Network::new_test()),
+            fee_bump
+                .clone()
+                .into_envelope()
+                .detect_network(&registry, &[kp.public_key()])
+                .unwrap()
+        );
+        fee_bump.sign(other.as_ref(), &futurenet).unwrap();
+        assert_eq!(
+            Some(&futurenet),
+            fee_bump
+                .into_envelope()
+                .detect_network(&registry, &[other.public_key()])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_transaction_hash() {
+        use super::TransactionHash;
+        use std::str::FromStr;
+
+        let kp = DalekKeyPair::random().unwrap();
+        let network = Network::new_test();
+        let envelope = signed_transaction(&kp, 1, 100).into_envelope();
+        let hash = envelope.transaction_hash(&network).unwrap();
+        assert_eq!(envelope.hash(&network).unwrap(), hash.as_bytes().to_vec());
+        assert_eq!(hash, TransactionHash::from_xdr(&hash.to_xdr()));
+        assert_eq!(
+            hash.as_bytes().as_slice(),
+            hash.to_pre_auth_tx_hash().as_bytes()
+        );
+
+        let encoded = hash.to_string();
+        assert_eq!(64, encoded.len());
+        assert_eq!(hash, TransactionHash::from_str(&encoded).unwrap());
+        assert_eq!(
+            hash,
+            TransactionHash::from_str(&encoded.to_uppercase()).unwrap()
+        );
+        assert!(matches!(
+            TransactionHash::from_str(&encoded[2..]),
+            Err(Error::InvalidTransactionHash)
+        ));
+        assert!(matches!(
+            TransactionHash::from_slice(&[0; 31]),
+            Err(Error::InvalidTransactionHash)
+        ));
+    }
+
+    #[test]
+    fn test_account_build_transaction() {
+        use crate::account::Account;
+
+        let kp = DalekKeyPair::random().unwrap();
+        let mut account = Account::new(kp.public_key(), 41);
+        let tx = account
+            .build_transaction(Stroops::new(100), |b| {
+                b.add_operation(Operation::new_inflation().build())
+            })
+            .unwrap();
+        assert_eq!(42, *tx.sequence());
+        assert_eq!(42, account.sequence());
+        assert_eq!(&MuxedAccount::from(kp.public_key()), tx.source_account());
+
+        // Failed builds don't use the sequence number.
+        let err = account
+            .build_transaction(Stroops::new(99), |b| {
+                b.add_operation(Operation::new_inflation().build())
+            })
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionFeeTooLow));
+        let err = account
+            .build_transaction(Stroops::new(100), |b| b)
+            .unwrap_err();
+        assert!(matches!(err, Error::MissingOperations));
+        assert_eq!(42, account.sequence());
+        let tx = account
+            .build_transaction(Stroops::new(100), |b| {
+                b.add_operation(Operation::new_inflation().build())
+            })
+            .unwrap();
+        assert_eq!(43, *tx.sequence());
+        assert_eq!(43, account.sequence());
+
+        let mut account = Account::new(kp.public_key(), i64::MAX);
+        let err = account
+            .build_transaction(Stroops::new(100), |b| {
+                b.add_operation(Operation::new_inflation().build())
+            })
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidSequenceNumber));
+        assert_eq!(i64::MAX, account.sequence());
+    }
 }
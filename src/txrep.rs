@@ -0,0 +1,2463 @@
+//! SEP-11 Txrep, a human readable representation of transactions.
+//!
+//! Txrep represents a transaction envelope as a list of `key: value`
+//! lines, one for each field of the xdr structure. Everything after the
+//! value (for example a comment in parentheses) is ignored when parsing.
+//!
+//! See [SEP-11](https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0011.md).
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::crypto::{
+    decode_account_id, decode_contract, decode_muxed_account, decode_pre_auth_tx,
+    decode_sha256_hash, decode_signed_payload, encode_account_id, encode_contract,
+    encode_muxed_account, encode_pre_auth_tx, encode_sha256_hash, encode_signed_payload,
+};
+use crate::error::{Error, Result};
+use crate::hex;
+use crate::xdr;
+use crate::xdr::{Name, Variants};
+
+/// Encodes the xdr transaction envelope as txrep.
+pub fn to_txrep(envelope: &xdr::TransactionEnvelope) -> String {
+    let mut w = Writer::default();
+    write_envelope(&mut w, envelope);
+    w.out
+}
+
+/// Decodes the xdr transaction envelope from txrep.
+pub fn from_txrep(txrep: &str) -> Result<xdr::TransactionEnvelope> {
+    let r = Reader::parse(txrep)?;
+    read_envelope(&r)
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+}
+
+impl Writer {
+    fn put<V: Display>(&mut self, key: &str, value: V) {
+        self.out.push_str(&format!("{}: {}\n", key, value));
+    }
+
+    fn put_enum<T: Name>(&mut self, key: &str, prefix: &str, value: &T) {
+        self.put(key, enum_name(prefix, value));
+    }
+
+    fn put_hex(&mut self, key: &str, value: &[u8]) {
+        self.put(key, hex::encode(value));
+    }
+
+    fn put_string(&mut self, key: &str, value: &[u8]) {
+        self.put(key, quote(value));
+    }
+
+    fn put_optional<T, F>(&mut self, prefix: &str, value: Option<&T>, f: F)
+    where
+        T: ?Sized,
+        F: FnOnce(&mut Writer, &str, &T),
+    {
+        let key = k(prefix, "_present");
+        match value {
+            None => self.put(&key, false),
+            Some(value) => {
+                self.put(&key, true);
+                f(self, prefix, value);
+            }
+        }
+    }
+
+    fn put_array<T, F>(&mut self, prefix: &str, values: &[T], f: F)
+    where
+        F: Fn(&mut Writer, &str, &T),
+    {
+        self.put(&k(prefix, "len"), values.len());
+        for (i, value) in values.iter().enumerate() {
+            f(self, &format!("{}[{}]", prefix, i), value);
+        }
+    }
+}
+
+/// Maximum nesting depth of claim predicates, contract values and
+/// authorized invocations, the default Soroban host depth limit.
+const MAX_NESTING_DEPTH: u32 = 100;
+
+struct Reader {
+    fields: HashMap<String, String>,
+    // Nesting depth of the recursive values being read, bounded by
+    // `MAX_NESTING_DEPTH` so that deeply nested input can't overflow the stack.
+    depth: Cell<u32>,
+}
+
+impl Reader {
+    fn parse(txrep: &str) -> Result<Reader> {
+        let mut fields = HashMap::new();
+        for (i, line) in txrep.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| Error::InvalidTxRep(format!("line {}: missing ':'", i + 1)))?;
+            let key = key.trim().to_string();
+            if fields.contains_key(&key) {
+                return Err(Error::InvalidTxRep(format!("duplicate key {}", key)));
+            }
+            fields.insert(key, value.trim().to_string());
+        }
+        Ok(Reader {
+            fields,
+            depth: Cell::new(0),
+        })
+    }
+
+    fn has(&self, key: &str) -> bool {
+        self.fields.contains_key(key)
+    }
+
+    fn raw(&self, key: &str) -> Result<&str> {
+        self.fields
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| Error::InvalidTxRep(format!("missing {}", key)))
+    }
+
+    fn token(&self, key: &str) -> Result<&str> {
+        Ok(self.raw(key)?.split_whitespace().next().unwrap_or(""))
+    }
+
+    fn get<T: FromStr>(&self, key: &str) -> Result<T> {
+        self.token(key)?.parse().map_err(|_| invalid(key))
+    }
+
+    fn get_enum<T>(&self, key: &str, prefix: &str) -> Result<T>
+    where
+        T: Name + Variants<T> + Copy + 'static,
+    {
+        let token = self.token(key)?;
+        T::variants()
+            .find(|v| enum_name(prefix, *v) == token)
+            .copied()
+            .ok_or_else(|| invalid(key))
+    }
+
+    fn get_hex<T: TryFrom<Vec<u8>>>(&self, key: &str) -> Result<T> {
+        let bytes = hex::decode(self.token(key)?).ok_or_else(|| invalid(key))?;
+        bytes.try_into().map_err(|_| invalid(key))
+    }
+
+    fn get_string<T: TryFrom<Vec<u8>>>(&self, key: &str) -> Result<T> {
+        let bytes = unquote(self.raw(key)?).ok_or_else(|| invalid(key))?;
+        bytes.try_into().map_err(|_| invalid(key))
+    }
+
+    fn nested<T, F>(&self, prefix: &str, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let depth = self.depth.get();
+        if depth >= MAX_NESTING_DEPTH {
+            return Err(Error::InvalidTxRep(format!(
+                "{} is nested too deeply",
+                prefix
+            )));
+        }
+        self.depth.set(depth + 1);
+        let result = f();
+        self.depth.set(depth);
+        result
+    }
+
+    fn get_optional<T, F>(&self, prefix: &str, f: F) -> Result<Option<T>>
+    where
+        F: FnOnce(&Reader, &str) -> Result<T>,
+    {
+        if self.get(&k(prefix, "_present"))? {
+            Ok(Some(f(self, prefix)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_array<T, V, F>(&self, prefix: &str, f: F) -> Result<V>
+    where
+        V: TryFrom<Vec<T>>,
+        F: Fn(&Reader, &str) -> Result<T>,
+    {
+        let len_key = k(prefix, "len");
+        let len: usize = self.get(&len_key)?;
+        // Every element takes at least one line.
+        if len > self.fields.len() {
+            return Err(invalid(&len_key));
+        }
+        let values = (0..len)
+            .map(|i| f(self, &format!("{}[{}]", prefix, i)))
+            .collect::<Result<Vec<_>>>()?;
+        values.try_into().map_err(|_| invalid(&len_key))
+    }
+}
+
+fn k(prefix: &str, field: &str) -> String {
+    format!("{}.{}", prefix, field)
+}
+
+fn invalid(key: &str) -> Error {
+    Error::InvalidTxRep(format!("invalid value for {}", key))
+}
+
+/// Converts the xdr enum variant name to its SCREAMING_SNAKE_CASE constant.
+fn enum_name<T: Name>(prefix: &str, value: &T) -> String {
+    let mut name = prefix.to_string();
+    for (i, c) in value.name().chars().enumerate() {
+        if i > 0 && c.is_ascii_uppercase() {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+fn quote(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    for b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(*b as char),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn unquote(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = s.strip_prefix('"')?.bytes();
+    let mut out = Vec::new();
+    loop {
+        match bytes.next()? {
+            b'"' => return Some(out),
+            b'\\' => match bytes.next()? {
+                b'x' => {
+                    let hex = [bytes.next()?, bytes.next()?];
+                    let hex = std::str::from_utf8(&hex).ok()?;
+                    out.push(u8::from_str_radix(hex, 16).ok()?);
+                }
+                b'n' => out.push(b'\n'),
+                b'r' => out.push(b'\r'),
+                b't' => out.push(b'\t'),
+                b => out.push(b),
+            },
+            b => out.push(b),
+        }
+    }
+}
+
+fn write_envelope(w: &mut Writer, x: &xdr::TransactionEnvelope) {
+    w.put_enum("type", "ENVELOPE_TYPE_", &x.discriminant());
+    match x {
+        xdr::TransactionEnvelope::TxV0(env) => {
+            write_transaction_v0(w, "tx", &env.tx);
+            w.put_array("signatures", &env.signatures, write_signature);
+        }
+        xdr::TransactionEnvelope::Tx(env) => {
+            write_transaction(w, "tx", &env.tx);
+            w.put_array("signatures", &env.signatures, write_signature);
+        }
+        xdr::TransactionEnvelope::TxFeeBump(env) => {
+            write_fee_bump_transaction(w, "feeBump.tx", &env.tx);
+            w.put_array("feeBump.signatures", &env.signatures, write_signature);
+        }
+    }
+}
+
+fn read_envelope(r: &Reader) -> Result<xdr::TransactionEnvelope> {
+    match r.get_enum("type", "ENVELOPE_TYPE_")? {
+        xdr::EnvelopeType::TxV0 => Ok(xdr::TransactionEnvelope::TxV0(xdr::TransactionV0Envelope {
+            tx: read_transaction_v0(r, "tx")?,
+            signatures: r.get_array("signatures", read_signature)?,
+        })),
+        xdr::EnvelopeType::Tx => Ok(xdr::TransactionEnvelope::Tx(xdr::TransactionV1Envelope {
+            tx: read_transaction(r, "tx")?,
+            signatures: r.get_array("signatures", read_signature)?,
+        })),
+        xdr::EnvelopeType::TxFeeBump => Ok(xdr::TransactionEnvelope::TxFeeBump(
+            xdr::FeeBumpTransactionEnvelope {
+                tx: read_fee_bump_transaction(r, "feeBump.tx")?,
+                signatures: r.get_array("feeBump.signatures", read_signature)?,
+            },
+        )),
+        _ => Err(invalid("type")),
+    }
+}
+
+fn write_transaction(w: &mut Writer, p: &str, x: &xdr::Transaction) {
+    write_muxed_account(w, &k(p, "sourceAccount"), &x.source_account);
+    w.put(&k(p, "fee"), x.fee);
+    w.put(&k(p, "seqNum"), x.seq_num.0);
+    write_preconditions(w, &k(p, "cond"), &x.cond);
+    write_memo(w, &k(p, "memo"), &x.memo);
+    w.put_array(&k(p, "operations"), &x.operations, write_operation);
+    match &x.ext {
+        xdr::TransactionExt::V0 => w.put(&k(p, "ext.v"), 0),
+        xdr::TransactionExt::V1(data) => {
+            w.put(&k(p, "ext.v"), 1);
+            write_soroban_transaction_data(w, &k(p, "ext.sorobanData"), data);
+        }
+    }
+}
+
+fn read_transaction(r: &Reader, p: &str) -> Result<xdr::Transaction> {
+    let ext_key = k(p, "ext.v");
+    let ext = match r.get(&ext_key)? {
+        0 => xdr::TransactionExt::V0,
+        1 => xdr::TransactionExt::V1(read_soroban_transaction_data(r, &k(p, "ext.sorobanData"))?),
+        _ => return Err(invalid(&ext_key)),
+    };
+    Ok(xdr::Transaction {
+        source_account: read_muxed_account(r, &k(p, "sourceAccount"))?,
+        fee: r.get(&k(p, "fee"))?,
+        seq_num: xdr::SequenceNumber(r.get(&k(p, "seqNum"))?),
+        cond: read_preconditions(r, &k(p, "cond"))?,
+        memo: read_memo(r, &k(p, "memo"))?,
+        operations: r.get_array(&k(p, "operations"), read_operation)?,
+        ext,
+    })
+}
+
+fn write_transaction_v0(w: &mut Writer, p: &str, x: &xdr::TransactionV0) {
+    w.put(
+        &k(p, "sourceAccountEd25519"),
+        encode_account_id(&x.source_account_ed25519.0),
+    );
+    w.put(&k(p, "fee"), x.fee);
+    w.put(&k(p, "seqNum"), x.seq_num.0);
+    w.put_optional(
+        &k(p, "timeBounds"),
+        x.time_bounds.as_ref(),
+        write_time_bounds,
+    );
+    write_memo(w, &k(p, "memo"), &x.memo);
+    w.put_array(&k(p, "operations"), &x.operations, write_operation);
+    w.put(&k(p, "ext.v"), 0);
+}
+
+fn read_transaction_v0(r: &Reader, p: &str) -> Result<xdr::TransactionV0> {
+    read_ext_v0(r, &k(p, "ext.v"))?;
+    let source_key = k(p, "sourceAccountEd25519");
+    let source = decode_account_id(r.token(&source_key)?).map_err(|_| invalid(&source_key))?;
+    Ok(xdr::TransactionV0 {
+        source_account_ed25519: xdr::Uint256(to_array(&source_key, source)?),
+        fee: r.get(&k(p, "fee"))?,
+        seq_num: xdr::SequenceNumber(r.get(&k(p, "seqNum"))?),
+        time_bounds: r.get_optional(&k(p, "timeBounds"), read_time_bounds)?,
+        memo: read_memo(r, &k(p, "memo"))?,
+        operations: r.get_array(&k(p, "operations"), read_operation)?,
+        ext: xdr::TransactionV0Ext::V0,
+    })
+}
+
+fn write_fee_bump_transaction(w: &mut Writer, p: &str, x: &xdr::FeeBumpTransaction) {
+    write_muxed_account(w, &k(p, "feeSource"), &x.fee_source);
+    w.put(&k(p, "fee"), x.fee);
+    let xdr::FeeBumpTransactionInnerTx::Tx(inner) = &x.inner_tx;
+    let inner_prefix = k(p, "innerTx");
+    w.put_enum(
+        &k(&inner_prefix, "type"),
+        "ENVELOPE_TYPE_",
+        &x.inner_tx.discriminant(),
+    );
+    write_transaction(w, &k(&inner_prefix, "tx"), &inner.tx);
+    w.put_array(
+        &k(&inner_prefix, "signatures"),
+        &inner.signatures,
+        write_signature,
+    );
+    w.put(&k(p, "ext.v"), 0);
+}
+
+fn read_fee_bump_transaction(r: &Reader, p: &str) -> Result<xdr::FeeBumpTransaction> {
+    read_ext_v0(r, &k(p, "ext.v"))?;
+    let inner_prefix = k(p, "innerTx");
+    let type_key = k(&inner_prefix, "type");
+    if r.get_enum::<xdr::EnvelopeType>(&type_key, "ENVELOPE_TYPE_")? != xdr::EnvelopeType::Tx {
+        return Err(invalid(&type_key));
+    }
+    let inner = xdr::TransactionV1Envelope {
+        tx: read_transaction(r, &k(&inner_prefix, "tx"))?,
+        signatures: r.get_array(&k(&inner_prefix, "signatures"), read_signature)?,
+    };
+    Ok(xdr::FeeBumpTransaction {
+        fee_source: read_muxed_account(r, &k(p, "feeSource"))?,
+        fee: r.get(&k(p, "fee"))?,
+        inner_tx: xdr::FeeBumpTransactionInnerTx::Tx(inner),
+        ext: xdr::FeeBumpTransactionExt::V0,
+    })
+}
+
+fn read_ext_v0(r: &Reader, key: &str) -> Result<()> {
+    match r.get(key)? {
+        0 => Ok(()),
+        _ => Err(invalid(key)),
+    }
+}
+
+fn write_signature(w: &mut Writer, p: &str, x: &xdr::DecoratedSignature) {
+    w.put_hex(&k(p, "hint"), &x.hint.0);
+    w.put_hex(&k(p, "signature"), &x.signature.0);
+}
+
+fn read_signature(r: &Reader, p: &str) -> Result<xdr::DecoratedSignature> {
+    Ok(xdr::DecoratedSignature {
+        hint: xdr::SignatureHint(r.get_hex(&k(p, "hint"))?),
+        signature: xdr::Signature(r.get_hex(&k(p, "signature"))?),
+    })
+}
+
+fn write_time_bounds(w: &mut Writer, p: &str, x: &xdr::TimeBounds) {
+    w.put(&k(p, "minTime"), x.min_time.0);
+    w.put(&k(p, "maxTime"), x.max_time.0);
+}
+
+fn read_time_bounds(r: &Reader, p: &str) -> Result<xdr::TimeBounds> {
+    Ok(xdr::TimeBounds {
+        min_time: xdr::TimePoint(r.get(&k(p, "minTime"))?),
+        max_time: xdr::TimePoint(r.get(&k(p, "maxTime"))?),
+    })
+}
+
+fn write_ledger_bounds(w: &mut Writer, p: &str, x: &xdr::LedgerBounds) {
+    w.put(&k(p, "minLedger"), x.min_ledger);
+    w.put(&k(p, "maxLedger"), x.max_ledger);
+}
+
+fn read_ledger_bounds(r: &Reader, p: &str) -> Result<xdr::LedgerBounds> {
+    Ok(xdr::LedgerBounds {
+        min_ledger: r.get(&k(p, "minLedger"))?,
+        max_ledger: r.get(&k(p, "maxLedger"))?,
+    })
+}
+
+fn write_preconditions(w: &mut Writer, p: &str, x: &xdr::Preconditions) {
+    w.put_enum(&k(p, "type"), "PRECOND_", &x.discriminant());
+    match x {
+        xdr::Preconditions::None => {}
+        xdr::Preconditions::Time(tb) => write_time_bounds(w, &k(p, "timeBounds"), tb),
+        xdr::Preconditions::V2(v2) => {
+            let p = k(p, "v2");
+            w.put_optional(
+                &k(&p, "timeBounds"),
+                v2.time_bounds.as_ref(),
+                write_time_bounds,
+            );
+            w.put_optional(
+                &k(&p, "ledgerBounds"),
+                v2.ledger_bounds.as_ref(),
+                write_ledger_bounds,
+            );
+            w.put_optional(&k(&p, "minSeqNum"), v2.min_seq_num.as_ref(), |w, p, x| {
+                w.put(p, x.0)
+            });
+            w.put(&k(&p, "minSeqAge"), v2.min_seq_age.0);
+            w.put(&k(&p, "minSeqLedgerGap"), v2.min_seq_ledger_gap);
+            w.put_array(&k(&p, "extraSigners"), &v2.extra_signers, write_signer_key);
+        }
+    }
+}
+
+fn read_preconditions(r: &Reader, p: &str) -> Result<xdr::Preconditions> {
+    match r.get_enum(&k(p, "type"), "PRECOND_")? {
+        xdr::PreconditionType::None => Ok(xdr::Preconditions::None),
+        xdr::PreconditionType::Time => Ok(xdr::Preconditions::Time(read_time_bounds(
+            r,
+            &k(p, "timeBounds"),
+        )?)),
+        xdr::PreconditionType::V2 => {
+            let p = k(p, "v2");
+            Ok(xdr::Preconditions::V2(xdr::PreconditionsV2 {
+                time_bounds: r.get_optional(&k(&p, "timeBounds"), read_time_bounds)?,
+                ledger_bounds: r.get_optional(&k(&p, "ledgerBounds"), read_ledger_bounds)?,
+                min_seq_num: r.get_optional(&k(&p, "minSeqNum"), |r, p| {
+                    Ok(xdr::SequenceNumber(r.get(p)?))
+                })?,
+                min_seq_age: xdr::Duration(r.get(&k(&p, "minSeqAge"))?),
+                min_seq_ledger_gap: r.get(&k(&p, "minSeqLedgerGap"))?,
+                extra_signers: r.get_array(&k(&p, "extraSigners"), read_signer_key)?,
+            }))
+        }
+    }
+}
+
+fn write_memo(w: &mut Writer, p: &str, x: &xdr::Memo) {
+    w.put_enum(&k(p, "type"), "MEMO_", &x.discriminant());
+    match x {
+        xdr::Memo::None => {}
+        xdr::Memo::Text(text) => w.put_string(&k(p, "text"), text),
+        xdr::Memo::Id(id) => w.put(&k(p, "id"), id),
+        xdr::Memo::Hash(hash) => w.put_hex(&k(p, "hash"), &hash.0),
+        xdr::Memo::Return(hash) => w.put_hex(&k(p, "retHash"), &hash.0),
+    }
+}
+
+fn read_memo(r: &Reader, p: &str) -> Result<xdr::Memo> {
+    Ok(match r.get_enum(&k(p, "type"), "MEMO_")? {
+        xdr::MemoType::None => xdr::Memo::None,
+        xdr::MemoType::Text => xdr::Memo::Text(r.get_string(&k(p, "text"))?),
+        xdr::MemoType::Id => xdr::Memo::Id(r.get(&k(p, "id"))?),
+        xdr::MemoType::Hash => xdr::Memo::Hash(xdr::Hash(r.get_hex(&k(p, "hash"))?)),
+        xdr::MemoType::Return => xdr::Memo::Return(xdr::Hash(r.get_hex(&k(p, "retHash"))?)),
+    })
+}
+
+fn to_array<const N: usize>(key: &str, bytes: Vec<u8>) -> Result<[u8; N]> {
+    bytes.try_into().map_err(|_| invalid(key))
+}
+
+fn write_account_id(w: &mut Writer, key: &str, x: &xdr::AccountId) {
+    let xdr::PublicKey::PublicKeyTypeEd25519(key_bytes) = &x.0;
+    w.put(key, encode_account_id(&key_bytes.0));
+}
+
+fn read_account_id(r: &Reader, key: &str) -> Result<xdr::AccountId> {
+    let bytes = decode_account_id(r.token(key)?).map_err(|_| invalid(key))?;
+    Ok(xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(
+        xdr::Uint256(to_array(key, bytes)?),
+    )))
+}
+
+fn write_muxed_account(w: &mut Writer, key: &str, x: &xdr::MuxedAccount) {
+    match x {
+        xdr::MuxedAccount::Ed25519(key_bytes) => w.put(key, encode_account_id(&key_bytes.0)),
+        xdr::MuxedAccount::MuxedEd25519(muxed) => {
+            w.put(key, encode_muxed_account(&muxed.ed25519.0, muxed.id))
+        }
+    }
+}
+
+fn read_muxed_account(r: &Reader, key: &str) -> Result<xdr::MuxedAccount> {
+    let token = r.token(key)?;
+    if token.starts_with('M') {
+        let (bytes, id) = decode_muxed_account(token).map_err(|_| invalid(key))?;
+        Ok(xdr::MuxedAccount::MuxedEd25519(xdr::MuxedAccountMed25519 {
+            id,
+            ed25519: xdr::Uint256(to_array(key, bytes)?),
+        }))
+    } else {
+        let bytes = decode_account_id(token).map_err(|_| invalid(key))?;
+        Ok(xdr::MuxedAccount::Ed25519(xdr::Uint256(to_array(
+            key, bytes,
+        )?)))
+    }
+}
+
+fn write_signer_key(w: &mut Writer, key: &str, x: &xdr::SignerKey) {
+    let encoded = match x {
+        xdr::SignerKey::Ed25519(k) => encode_account_id(&k.0),
+        xdr::SignerKey::PreAuthTx(k) => encode_pre_auth_tx(&k.0),
+        xdr::SignerKey::HashX(k) => encode_sha256_hash(&k.0),
+        xdr::SignerKey::Ed25519SignedPayload(k) => encode_signed_payload(&k.ed25519.0, &k.payload),
+    };
+    w.put(key, encoded);
+}
+
+fn read_signer_key(r: &Reader, key: &str) -> Result<xdr::SignerKey> {
+    let token = r.token(key)?;
+    let decode_key = |bytes: Result<Vec<u8>>| -> Result<xdr::Uint256> {
+        let bytes = bytes.map_err(|_| invalid(key))?;
+        Ok(xdr::Uint256(to_array(key, bytes)?))
+    };
+    match token.chars().next() {
+        Some('G') => Ok(xdr::SignerKey::Ed25519(decode_key(decode_account_id(
+            token,
+        ))?)),
+        Some('T') => Ok(xdr::SignerKey::PreAuthTx(decode_key(decode_pre_auth_tx(
+            token,
+        ))?)),
+        Some('X') => Ok(xdr::SignerKey::HashX(decode_key(decode_sha256_hash(
+            token,
+        ))?)),
+        Some('P') => {
+            let (ed25519, payload) = decode_signed_payload(token).map_err(|_| invalid(key))?;
+            Ok(xdr::SignerKey::Ed25519SignedPayload(
+                xdr::SignerKeyEd25519SignedPayload {
+                    ed25519: decode_key(Ok(ed25519))?,
+                    payload: payload.try_into().map_err(|_| invalid(key))?,
+                },
+            ))
+        }
+        _ => Err(invalid(key)),
+    }
+}
+
+/// Returns the asset code if it can be written in the compact `CODE:ISSUER` form.
+fn canonical_asset_code(code: &[u8], min_len: usize) -> Option<&str> {
+    let len = code.iter().position(|b| *b == 0).unwrap_or(code.len());
+    let (code, padding) = code.split_at(len);
+    if len < min_len
+        || padding.iter().any(|b| *b != 0)
+        || !code.iter().all(u8::is_ascii_alphanumeric)
+    {
+        return None;
+    }
+    std::str::from_utf8(code).ok()
+}
+
+fn write_asset(w: &mut Writer, p: &str, x: &xdr::Asset) {
+    match x {
+        xdr::Asset::Native => w.put(p, "native"),
+        xdr::Asset::CreditAlphanum4(credit) => {
+            match canonical_asset_code(&credit.asset_code.0, 1) {
+                Some(code) => write_compact_asset(w, p, code, &credit.issuer),
+                None => {
+                    w.put_enum(&k(p, "type"), "ASSET_TYPE_", &x.discriminant());
+                    w.put_hex(&k(p, "alphaNum4.assetCode"), &credit.asset_code.0);
+                    write_account_id(w, &k(p, "alphaNum4.issuer"), &credit.issuer);
+                }
+            }
+        }
+        xdr::Asset::CreditAlphanum12(credit) => {
+            match canonical_asset_code(&credit.asset_code.0, 5) {
+                Some(code) => write_compact_asset(w, p, code, &credit.issuer),
+                None => {
+                    w.put_enum(&k(p, "type"), "ASSET_TYPE_", &x.discriminant());
+                    w.put_hex(&k(p, "alphaNum12.assetCode"), &credit.asset_code.0);
+                    write_account_id(w, &k(p, "alphaNum12.issuer"), &credit.issuer);
+                }
+            }
+        }
+    }
+}
+
+fn write_compact_asset(w: &mut Writer, key: &str, code: &str, issuer: &xdr::AccountId) {
+    let xdr::PublicKey::PublicKeyTypeEd25519(issuer) = &issuer.0;
+    w.put(key, format!("{}:{}", code, encode_account_id(&issuer.0)));
+}
+
+fn read_asset(r: &Reader, p: &str) -> Result<xdr::Asset> {
+    if !r.has(&k(p, "type")) {
+        return read_compact_asset(r, p);
+    }
+    match r.get_enum(&k(p, "type"), "ASSET_TYPE_")? {
+        xdr::AssetType::Native => Ok(xdr::Asset::Native),
+        xdr::AssetType::CreditAlphanum4 => Ok(xdr::Asset::CreditAlphanum4(xdr::AlphaNum4 {
+            asset_code: xdr::AssetCode4(r.get_hex(&k(p, "alphaNum4.assetCode"))?),
+            issuer: read_account_id(r, &k(p, "alphaNum4.issuer"))?,
+        })),
+        xdr::AssetType::CreditAlphanum12 => Ok(xdr::Asset::CreditAlphanum12(xdr::AlphaNum12 {
+            asset_code: xdr::AssetCode12(r.get_hex(&k(p, "alphaNum12.assetCode"))?),
+            issuer: read_account_id(r, &k(p, "alphaNum12.issuer"))?,
+        })),
+        xdr::AssetType::PoolShare => Err(invalid(&k(p, "type"))),
+    }
+}
+
+fn read_compact_asset(r: &Reader, key: &str) -> Result<xdr::Asset> {
+    let token = r.token(key)?;
+    if token == "native" || token == "XLM" {
+        return Ok(xdr::Asset::Native);
+    }
+    let (code, issuer) = token.split_once(':').ok_or_else(|| invalid(key))?;
+    if !code.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(invalid(key));
+    }
+    let issuer = decode_account_id(issuer).map_err(|_| invalid(key))?;
+    let issuer = xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256(
+        to_array(key, issuer)?,
+    )));
+    match code.len() {
+        1..=4 => {
+            let mut asset_code = [0; 4];
+            asset_code[..code.len()].copy_from_slice(code.as_bytes());
+            Ok(xdr::Asset::CreditAlphanum4(xdr::AlphaNum4 {
+                asset_code: xdr::AssetCode4(asset_code),
+                issuer,
+            }))
+        }
+        5..=12 => {
+            let mut asset_code = [0; 12];
+            asset_code[..code.len()].copy_from_slice(code.as_bytes());
+            Ok(xdr::Asset::CreditAlphanum12(xdr::AlphaNum12 {
+                asset_code: xdr::AssetCode12(asset_code),
+                issuer,
+            }))
+        }
+        _ => Err(invalid(key)),
+    }
+}
+
+fn write_asset_code(w: &mut Writer, p: &str, x: &xdr::AssetCode) {
+    let (code, min_len) = match x {
+        xdr::AssetCode::CreditAlphanum4(code) => (&code.0[..], 1),
+        xdr::AssetCode::CreditAlphanum12(code) => (&code.0[..], 5),
+    };
+    match canonical_asset_code(code, min_len) {
+        Some(code) => w.put(p, code),
+        None => {
+            w.put_enum(&k(p, "type"), "ASSET_TYPE_", &x.discriminant());
+            w.put_hex(&k(p, "assetCode"), code);
+        }
+    }
+}
+
+fn read_asset_code(r: &Reader, p: &str) -> Result<xdr::AssetCode> {
+    if r.has(&k(p, "type")) {
+        return match r.get_enum(&k(p, "type"), "ASSET_TYPE_")? {
+            xdr::AssetType::CreditAlphanum4 => Ok(xdr::AssetCode::CreditAlphanum4(
+                xdr::AssetCode4(r.get_hex(&k(p, "assetCode"))?),
+            )),
+            xdr::AssetType::CreditAlphanum12 => Ok(xdr::AssetCode::CreditAlphanum12(
+                xdr::AssetCode12(r.get_hex(&k(p, "assetCode"))?),
+            )),
+            _ => Err(invalid(&k(p, "type"))),
+        };
+    }
+    let code = r.token(p)?;
+    if !code.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(invalid(p));
+    }
+    match code.len() {
+        1..=4 => {
+            let mut asset_code = [0; 4];
+            asset_code[..code.len()].copy_from_slice(code.as_bytes());
+            Ok(xdr::AssetCode::CreditAlphanum4(xdr::AssetCode4(asset_code)))
+        }
+        5..=12 => {
+            let mut asset_code = [0; 12];
+            asset_code[..code.len()].copy_from_slice(code.as_bytes());
+            Ok(xdr::AssetCode::CreditAlphanum12(xdr::AssetCode12(
+                asset_code,
+            )))
+        }
+        _ => Err(invalid(p)),
+    }
+}
+
+fn write_change_trust_asset(w: &mut Writer, p: &str, x: &xdr::ChangeTrustAsset) {
+    match x {
+        xdr::ChangeTrustAsset::Native => write_asset(w, p, &xdr::Asset::Native),
+        xdr::ChangeTrustAsset::CreditAlphanum4(credit) => {
+            write_asset(w, p, &xdr::Asset::CreditAlphanum4(credit.clone()))
+        }
+        xdr::ChangeTrustAsset::CreditAlphanum12(credit) => {
+            write_asset(w, p, &xdr::Asset::CreditAlphanum12(credit.clone()))
+        }
+        xdr::ChangeTrustAsset::PoolShare(params) => {
+            w.put_enum(&k(p, "type"), "ASSET_TYPE_", &x.discriminant());
+            let xdr::LiquidityPoolParameters::LiquidityPoolConstantProduct(cp) = params;
+            w.put_enum(&k(p, "liquidityPool.type"), "", &params.discriminant());
+            let p = k(p, "liquidityPool.constantProduct");
+            write_asset(w, &k(&p, "assetA"), &cp.asset_a);
+            write_asset(w, &k(&p, "assetB"), &cp.asset_b);
+            w.put(&k(&p, "fee"), cp.fee);
+        }
+    }
+}
+
+fn read_change_trust_asset(r: &Reader, p: &str) -> Result<xdr::ChangeTrustAsset> {
+    let type_key = k(p, "type");
+    if r.has(&type_key)
+        && r.get_enum::<xdr::AssetType>(&type_key, "ASSET_TYPE_")? == xdr::AssetType::PoolShare
+    {
+        let pool_type_key = k(p, "liquidityPool.type");
+        match r.get_enum(&pool_type_key, "")? {
+            xdr::LiquidityPoolType::LiquidityPoolConstantProduct => {
+                let p = k(p, "liquidityPool.constantProduct");
+                let cp = xdr::LiquidityPoolConstantProductParameters {
+                    asset_a: read_asset(r, &k(&p, "assetA"))?,
+                    asset_b: read_asset(r, &k(&p, "assetB"))?,
+                    fee: r.get(&k(&p, "fee"))?,
+                };
+                return Ok(xdr::ChangeTrustAsset::PoolShare(
+                    xdr::LiquidityPoolParameters::LiquidityPoolConstantProduct(cp),
+                ));
+            }
+        }
+    }
+    Ok(match read_asset(r, p)? {
+        xdr::Asset::Native => xdr::ChangeTrustAsset::Native,
+        xdr::Asset::CreditAlphanum4(credit) => xdr::ChangeTrustAsset::CreditAlphanum4(credit),
+        xdr::Asset::CreditAlphanum12(credit) => xdr::ChangeTrustAsset::CreditAlphanum12(credit),
+    })
+}
+
+fn write_trust_line_asset(w: &mut Writer, p: &str, x: &xdr::TrustLineAsset) {
+    match x {
+        xdr::TrustLineAsset::Native => write_asset(w, p, &xdr::Asset::Native),
+        xdr::TrustLineAsset::CreditAlphanum4(credit) => {
+            write_asset(w, p, &xdr::Asset::CreditAlphanum4(credit.clone()))
+        }
+        xdr::TrustLineAsset::CreditAlphanum12(credit) => {
+            write_asset(w, p, &xdr::Asset::CreditAlphanum12(credit.clone()))
+        }
+        xdr::TrustLineAsset::PoolShare(pool_id) => {
+            w.put_enum(&k(p, "type"), "ASSET_TYPE_", &x.discriminant());
+            w.put_hex(&k(p, "liquidityPoolID"), &pool_id.0 .0);
+        }
+    }
+}
+
+fn read_trust_line_asset(r: &Reader, p: &str) -> Result<xdr::TrustLineAsset> {
+    let type_key = k(p, "type");
+    if r.has(&type_key)
+        && r.get_enum::<xdr::AssetType>(&type_key, "ASSET_TYPE_")? == xdr::AssetType::PoolShare
+    {
+        let pool_id = r.get_hex(&k(p, "liquidityPoolID"))?;
+        return Ok(xdr::TrustLineAsset::PoolShare(xdr::PoolId(xdr::Hash(
+            pool_id,
+        ))));
+    }
+    Ok(match read_asset(r, p)? {
+        xdr::Asset::Native => xdr::TrustLineAsset::Native,
+        xdr::Asset::CreditAlphanum4(credit) => xdr::TrustLineAsset::CreditAlphanum4(credit),
+        xdr::Asset::CreditAlphanum12(credit) => xdr::TrustLineAsset::CreditAlphanum12(credit),
+    })
+}
+
+fn write_price(w: &mut Writer, p: &str, x: &xdr::Price) {
+    w.put(&k(p, "n"), x.n);
+    w.put(&k(p, "d"), x.d);
+}
+
+fn read_price(r: &Reader, p: &str) -> Result<xdr::Price> {
+    Ok(xdr::Price {
+        n: r.get(&k(p, "n"))?,
+        d: r.get(&k(p, "d"))?,
+    })
+}
+
+fn write_claim_predicate(w: &mut Writer, p: &str, x: &xdr::ClaimPredicate) {
+    w.put_enum(&k(p, "type"), "CLAIM_PREDICATE_", &x.discriminant());
+    match x {
+        xdr::ClaimPredicate::Unconditional => {}
+        xdr::ClaimPredicate::And(predicates) => {
+            w.put_array(&k(p, "andPredicates"), predicates, write_claim_predicate)
+        }
+        xdr::ClaimPredicate::Or(predicates) => {
+            w.put_array(&k(p, "orPredicates"), predicates, write_claim_predicate)
+        }
+        xdr::ClaimPredicate::Not(predicate) => w.put_optional(
+            &k(p, "notPredicate"),
+            predicate.as_deref(),
+            write_claim_predicate,
+        ),
+        xdr::ClaimPredicate::BeforeAbsoluteTime(t) => w.put(&k(p, "absBefore"), t),
+        xdr::ClaimPredicate::BeforeRelativeTime(t) => w.put(&k(p, "relBefore"), t),
+    }
+}
+
+fn read_claim_predicate(r: &Reader, p: &str) -> Result<xdr::ClaimPredicate> {
+    r.nested(p, || read_claim_predicate_body(r, p))
+}
+
+fn read_claim_predicate_body(r: &Reader, p: &str) -> Result<xdr::ClaimPredicate> {
+    Ok(match r.get_enum(&k(p, "type"), "CLAIM_PREDICATE_")? {
+        xdr::ClaimPredicateType::Unconditional => xdr::ClaimPredicate::Unconditional,
+        xdr::ClaimPredicateType::And => {
+            xdr::ClaimPredicate::And(r.get_array(&k(p, "andPredicates"), read_claim_predicate)?)
+        }
+        xdr::ClaimPredicateType::Or => {
+            xdr::ClaimPredicate::Or(r.get_array(&k(p, "orPredicates"), read_claim_predicate)?)
+        }
+        xdr::ClaimPredicateType::Not => xdr::ClaimPredicate::Not(
+            r.get_optional(&k(p, "notPredicate"), read_claim_predicate)?
+                .map(Box::new),
+        ),
+        xdr::ClaimPredicateType::BeforeAbsoluteTime => {
+            xdr::ClaimPredicate::BeforeAbsoluteTime(r.get(&k(p, "absBefore"))?)
+        }
+        xdr::ClaimPredicateType::BeforeRelativeTime => {
+            xdr::ClaimPredicate::BeforeRelativeTime(r.get(&k(p, "relBefore"))?)
+        }
+    })
+}
+
+fn write_claimant(w: &mut Writer, p: &str, x: &xdr::Claimant) {
+    w.put_enum(&k(p, "type"), "", &x.discriminant());
+    let xdr::Claimant::ClaimantTypeV0(claimant) = x;
+    write_account_id(w, &k(p, "v0.destination"), &claimant.destination);
+    write_claim_predicate(w, &k(p, "v0.predicate"), &claimant.predicate);
+}
+
+fn read_claimant(r: &Reader, p: &str) -> Result<xdr::Claimant> {
+    match r.get_enum(&k(p, "type"), "")? {
+        xdr::ClaimantType::ClaimantTypeV0 => Ok(xdr::Claimant::ClaimantTypeV0(xdr::ClaimantV0 {
+            destination: read_account_id(r, &k(p, "v0.destination"))?,
+            predicate: read_claim_predicate(r, &k(p, "v0.predicate"))?,
+        })),
+    }
+}
+
+fn write_claimable_balance_id(w: &mut Writer, p: &str, x: &xdr::ClaimableBalanceId) {
+    w.put_enum(&k(p, "type"), "", &x.discriminant());
+    let xdr::ClaimableBalanceId::ClaimableBalanceIdTypeV0(hash) = x;
+    w.put_hex(&k(p, "v0"), &hash.0);
+}
+
+fn read_claimable_balance_id(r: &Reader, p: &str) -> Result<xdr::ClaimableBalanceId> {
+    match r.get_enum(&k(p, "type"), "")? {
+        xdr::ClaimableBalanceIdType::ClaimableBalanceIdTypeV0 => Ok(
+            xdr::ClaimableBalanceId::ClaimableBalanceIdTypeV0(xdr::Hash(r.get_hex(&k(p, "v0"))?)),
+        ),
+    }
+}
+
+fn write_signer(w: &mut Writer, p: &str, x: &xdr::Signer) {
+    write_signer_key(w, &k(p, "key"), &x.key);
+    w.put(&k(p, "weight"), x.weight);
+}
+
+fn read_signer(r: &Reader, p: &str) -> Result<xdr::Signer> {
+    Ok(xdr::Signer {
+        key: read_signer_key(r, &k(p, "key"))?,
+        weight: r.get(&k(p, "weight"))?,
+    })
+}
+
+fn write_ledger_key(w: &mut Writer, p: &str, x: &xdr::LedgerKey) {
+    w.put_enum(&k(p, "type"), "", &x.discriminant());
+    match x {
+        xdr::LedgerKey::Account(key) => {
+            write_account_id(w, &k(p, "account.accountID"), &key.account_id)
+        }
+        xdr::LedgerKey::Trustline(key) => {
+            write_account_id(w, &k(p, "trustLine.accountID"), &key.account_id);
+            write_trust_line_asset(w, &k(p, "trustLine.asset"), &key.asset);
+        }
+        xdr::LedgerKey::Offer(key) => {
+            write_account_id(w, &k(p, "offer.sellerID"), &key.seller_id);
+            w.put(&k(p, "offer.offerID"), key.offer_id);
+        }
+        xdr::LedgerKey::Data(key) => {
+            write_account_id(w, &k(p, "data.accountID"), &key.account_id);
+            w.put_string(&k(p, "data.dataName"), &key.data_name.0);
+        }
+        xdr::LedgerKey::ClaimableBalance(key) => {
+            write_claimable_balance_id(w, &k(p, "claimableBalance.balanceID"), &key.balance_id)
+        }
+        xdr::LedgerKey::LiquidityPool(key) => w.put_hex(
+            &k(p, "liquidityPool.liquidityPoolID"),
+            &key.liquidity_pool_id.0 .0,
+        ),
+        xdr::LedgerKey::ContractData(key) => {
+            write_sc_address(w, &k(p, "contractData.contract"), &key.contract);
+            write_sc_val(w, &k(p, "contractData.key"), &key.key);
+            w.put_enum(&k(p, "contractData.durability"), "", &key.durability);
+        }
+        xdr::LedgerKey::ContractCode(key) => w.put_hex(&k(p, "contractCode.hash"), &key.hash.0),
+        xdr::LedgerKey::ConfigSetting(key) => w.put_enum(
+            &k(p, "configSetting.configSettingID"),
+            "CONFIG_SETTING_",
+            &key.config_setting_id,
+        ),
+        xdr::LedgerKey::Ttl(key) => w.put_hex(&k(p, "ttl.keyHash"), &key.key_hash.0),
+    }
+}
+
+fn read_ledger_key(r: &Reader, p: &str) -> Result<xdr::LedgerKey> {
+    Ok(match r.get_enum(&k(p, "type"), "")? {
+        xdr::LedgerEntryType::Account => xdr::LedgerKey::Account(xdr::LedgerKeyAccount {
+            account_id: read_account_id(r, &k(p, "account.accountID"))?,
+        }),
+        xdr::LedgerEntryType::Trustline => xdr::LedgerKey::Trustline(xdr::LedgerKeyTrustLine {
+            account_id: read_account_id(r, &k(p, "trustLine.accountID"))?,
+            asset: read_trust_line_asset(r, &k(p, "trustLine.asset"))?,
+        }),
+        xdr::LedgerEntryType::Offer => xdr::LedgerKey::Offer(xdr::LedgerKeyOffer {
+            seller_id: read_account_id(r, &k(p, "offer.sellerID"))?,
+            offer_id: r.get(&k(p, "offer.offerID"))?,
+        }),
+        xdr::LedgerEntryType::Data => xdr::LedgerKey::Data(xdr::LedgerKeyData {
+            account_id: read_account_id(r, &k(p, "data.accountID"))?,
+            data_name: xdr::String64(r.get_string(&k(p, "data.dataName"))?),
+        }),
+        xdr::LedgerEntryType::ClaimableBalance => {
+            xdr::LedgerKey::ClaimableBalance(xdr::LedgerKeyClaimableBalance {
+                balance_id: read_claimable_balance_id(r, &k(p, "claimableBalance.balanceID"))?,
+            })
+        }
+        xdr::LedgerEntryType::LiquidityPool => {
+            xdr::LedgerKey::LiquidityPool(xdr::LedgerKeyLiquidityPool {
+                liquidity_pool_id: xdr::PoolId(xdr::Hash(
+                    r.get_hex(&k(p, "liquidityPool.liquidityPoolID"))?,
+                )),
+            })
+        }
+        xdr::LedgerEntryType::ContractData => {
+            xdr::LedgerKey::ContractData(xdr::LedgerKeyContractData {
+                contract: read_sc_address(r, &k(p, "contractData.contract"))?,
+                key: read_sc_val(r, &k(p, "contractData.key"))?,
+                durability: r.get_enum(&k(p, "contractData.durability"), "")?,
+            })
+        }
+        xdr::LedgerEntryType::ContractCode => {
+            xdr::LedgerKey::ContractCode(xdr::LedgerKeyContractCode {
+                hash: xdr::Hash(r.get_hex(&k(p, "contractCode.hash"))?),
+            })
+        }
+        xdr::LedgerEntryType::ConfigSetting => {
+            xdr::LedgerKey::ConfigSetting(xdr::LedgerKeyConfigSetting {
+                config_setting_id: r
+                    .get_enum(&k(p, "configSetting.configSettingID"), "CONFIG_SETTING_")?,
+            })
+        }
+        xdr::LedgerEntryType::Ttl => xdr::LedgerKey::Ttl(xdr::LedgerKeyTtl {
+            key_hash: xdr::Hash(r.get_hex(&k(p, "ttl.keyHash"))?),
+        }),
+    })
+}
+
+fn write_sc_address(w: &mut Writer, p: &str, x: &xdr::ScAddress) {
+    w.put_enum(&k(p, "type"), "SC_ADDRESS_TYPE_", &x.discriminant());
+    match x {
+        xdr::ScAddress::Account(account_id) => write_account_id(w, &k(p, "accountId"), account_id),
+        xdr::ScAddress::Contract(contract_id) => {
+            w.put(&k(p, "contractId"), encode_contract(&contract_id.0 .0))
+        }
+        xdr::ScAddress::MuxedAccount(muxed) => w.put(
+            &k(p, "muxedAccount"),
+            encode_muxed_account(&muxed.ed25519.0, muxed.id),
+        ),
+        xdr::ScAddress::ClaimableBalance(balance_id) => {
+            write_claimable_balance_id(w, &k(p, "claimableBalanceId"), balance_id)
+        }
+        xdr::ScAddress::LiquidityPool(pool_id) => {
+            w.put_hex(&k(p, "liquidityPoolId"), &pool_id.0 .0)
+        }
+    }
+}
+
+fn read_sc_address(r: &Reader, p: &str) -> Result<xdr::ScAddress> {
+    Ok(match r.get_enum(&k(p, "type"), "SC_ADDRESS_TYPE_")? {
+        xdr::ScAddressType::Account => {
+            xdr::ScAddress::Account(read_account_id(r, &k(p, "accountId"))?)
+        }
+        xdr::ScAddressType::Contract => {
+            let key = k(p, "contractId");
+            let bytes = decode_contract(r.token(&key)?).map_err(|_| invalid(&key))?;
+            xdr::ScAddress::Contract(xdr::ContractId(xdr::Hash(to_array(&key, bytes)?)))
+        }
+        xdr::ScAddressType::MuxedAccount => {
+            let key = k(p, "muxedAccount");
+            let (bytes, id) = decode_muxed_account(r.token(&key)?).map_err(|_| invalid(&key))?;
+            xdr::ScAddress::MuxedAccount(xdr::MuxedEd25519Account {
+                id,
+                ed25519: xdr::Uint256(to_array(&key, bytes)?),
+            })
+        }
+        xdr::ScAddressType::ClaimableBalance => xdr::ScAddress::ClaimableBalance(
+            read_claimable_balance_id(r, &k(p, "claimableBalanceId"))?,
+        ),
+        xdr::ScAddressType::LiquidityPool => xdr::ScAddress::LiquidityPool(xdr::PoolId(xdr::Hash(
+            r.get_hex(&k(p, "liquidityPoolId"))?,
+        ))),
+    })
+}
+
+fn write_sc_error(w: &mut Writer, p: &str, x: &xdr::ScError) {
+    w.put_enum(&k(p, "type"), "SCE_", &x.discriminant());
+    match x {
+        xdr::ScError::Contract(code) => w.put(&k(p, "contractCode"), code),
+        xdr::ScError::WasmVm(code)
+        | xdr::ScError::Context(code)
+        | xdr::ScError::Storage(code)
+        | xdr::ScError::Object(code)
+        | xdr::ScError::Crypto(code)
+        | xdr::ScError::Events(code)
+        | xdr::ScError::Budget(code)
+        | xdr::ScError::Value(code)
+        | xdr::ScError::Auth(code) => w.put_enum(&k(p, "code"), "SCEC_", code),
+    }
+}
+
+fn read_sc_error(r: &Reader, p: &str) -> Result<xdr::ScError> {
+    let error_type = r.get_enum(&k(p, "type"), "SCE_")?;
+    if error_type == xdr::ScErrorType::Contract {
+        return Ok(xdr::ScError::Contract(r.get(&k(p, "contractCode"))?));
+    }
+    let code = r.get_enum(&k(p, "code"), "SCEC_")?;
+    Ok(match error_type {
+        xdr::ScErrorType::Contract => unreachable!(),
+        xdr::ScErrorType::WasmVm => xdr::ScError::WasmVm(code),
+        xdr::ScErrorType::Context => xdr::ScError::Context(code),
+        xdr::ScErrorType::Storage => xdr::ScError::Storage(code),
+        xdr::ScErrorType::Object => xdr::ScError::Object(code),
+        xdr::ScErrorType::Crypto => xdr::ScError::Crypto(code),
+        xdr::ScErrorType::Events => xdr::ScError::Events(code),
+        xdr::ScErrorType::Budget => xdr::ScError::Budget(code),
+        xdr::ScErrorType::Value => xdr::ScError::Value(code),
+        xdr::ScErrorType::Auth => xdr::ScError::Auth(code),
+    })
+}
+
+fn write_sc_map(w: &mut Writer, p: &str, x: &xdr::ScMap) {
+    w.put_array(p, &x.0, |w, p, entry| {
+        write_sc_val(w, &k(p, "key"), &entry.key);
+        write_sc_val(w, &k(p, "val"), &entry.val);
+    });
+}
+
+fn read_sc_map(r: &Reader, p: &str) -> Result<xdr::ScMap> {
+    Ok(xdr::ScMap(r.get_array(p, |r, p| {
+        Ok(xdr::ScMapEntry {
+            key: read_sc_val(r, &k(p, "key"))?,
+            val: read_sc_val(r, &k(p, "val"))?,
+        })
+    })?))
+}
+
+fn write_sc_val(w: &mut Writer, p: &str, x: &xdr::ScVal) {
+    w.put_enum(&k(p, "type"), "SCV_", &x.discriminant());
+    match x {
+        xdr::ScVal::Bool(b) => w.put(&k(p, "b"), b),
+        xdr::ScVal::Void | xdr::ScVal::LedgerKeyContractInstance => {}
+        xdr::ScVal::Error(error) => write_sc_error(w, &k(p, "error"), error),
+        xdr::ScVal::U32(n) => w.put(&k(p, "u32"), n),
+        xdr::ScVal::I32(n) => w.put(&k(p, "i32"), n),
+        xdr::ScVal::U64(n) => w.put(&k(p, "u64"), n),
+        xdr::ScVal::I64(n) => w.put(&k(p, "i64"), n),
+        xdr::ScVal::Timepoint(t) => w.put(&k(p, "timepoint"), t.0),
+        xdr::ScVal::Duration(d) => w.put(&k(p, "duration"), d.0),
+        xdr::ScVal::U128(n) => {
+            w.put(&k(p, "u128.hi"), n.hi);
+            w.put(&k(p, "u128.lo"), n.lo);
+        }
+        xdr::ScVal::I128(n) => {
+            w.put(&k(p, "i128.hi"), n.hi);
+            w.put(&k(p, "i128.lo"), n.lo);
+        }
+        xdr::ScVal::U256(n) => {
+            w.put(&k(p, "u256.hiHi"), n.hi_hi);
+            w.put(&k(p, "u256.hiLo"), n.hi_lo);
+            w.put(&k(p, "u256.loHi"), n.lo_hi);
+            w.put(&k(p, "u256.loLo"), n.lo_lo);
+        }
+        xdr::ScVal::I256(n) => {
+            w.put(&k(p, "i256.hiHi"), n.hi_hi);
+            w.put(&k(p, "i256.hiLo"), n.hi_lo);
+            w.put(&k(p, "i256.loHi"), n.lo_hi);
+            w.put(&k(p, "i256.loLo"), n.lo_lo);
+        }
+        xdr::ScVal::Bytes(bytes) => w.put_hex(&k(p, "bytes"), &bytes.0),
+        xdr::ScVal::String(s) => w.put_string(&k(p, "str"), &s.0),
+        xdr::ScVal::Symbol(s) => w.put_string(&k(p, "sym"), &s.0),
+        xdr::ScVal::Vec(vec) => w.put_optional(&k(p, "vec"), vec.as_ref(), |w, p, vec| {
+            w.put_array(p, &vec.0, write_sc_val)
+        }),
+        xdr::ScVal::Map(map) => w.put_optional(&k(p, "map"), map.as_ref(), write_sc_map),
+        xdr::ScVal::Address(address) => write_sc_address(w, &k(p, "address"), address),
+        xdr::ScVal::ContractInstance(instance) => {
+            write_contract_executable(w, &k(p, "instance.executable"), &instance.executable);
+            w.put_optional(
+                &k(p, "instance.storage"),
+                instance.storage.as_ref(),
+                write_sc_map,
+            );
+        }
+        xdr::ScVal::LedgerKeyNonce(key) => w.put(&k(p, "nonce_key.nonce"), key.nonce),
+    }
+}
+
+fn read_sc_val(r: &Reader, p: &str) -> Result<xdr::ScVal> {
+    r.nested(p, || read_sc_val_body(r, p))
+}
+
+fn read_sc_val_body(r: &Reader, p: &str) -> Result<xdr::ScVal> {
+    Ok(match r.get_enum(&k(p, "type"), "SCV_")? {
+        xdr::ScValType::Bool => xdr::ScVal::Bool(r.get(&k(p, "b"))?),
+        xdr::ScValType::Void => xdr::ScVal::Void,
+        xdr::ScValType::Error => xdr::ScVal::Error(read_sc_error(r, &k(p, "error"))?),
+        xdr::ScValType::U32 => xdr::ScVal::U32(r.get(&k(p, "u32"))?),
+        xdr::ScValType::I32 => xdr::ScVal::I32(r.get(&k(p, "i32"))?),
+        xdr::ScValType::U64 => xdr::ScVal::U64(r.get(&k(p, "u64"))?),
+        xdr::ScValType::I64 => xdr::ScVal::I64(r.get(&k(p, "i64"))?),
+        xdr::ScValType::Timepoint => {
+            xdr::ScVal::Timepoint(xdr::TimePoint(r.get(&k(p, "timepoint"))?))
+        }
+        xdr::ScValType::Duration => xdr::ScVal::Duration(xdr::Duration(r.get(&k(p, "duration"))?)),
+        xdr::ScValType::U128 => xdr::ScVal::U128(xdr::UInt128Parts {
+            hi: r.get(&k(p, "u128.hi"))?,
+            lo: r.get(&k(p, "u128.lo"))?,
+        }),
+        xdr::ScValType::I128 => xdr::ScVal::I128(xdr::Int128Parts {
+            hi: r.get(&k(p, "i128.hi"))?,
+            lo: r.get(&k(p, "i128.lo"))?,
+        }),
+        xdr::ScValType::U256 => xdr::ScVal::U256(xdr::UInt256Parts {
+            hi_hi: r.get(&k(p, "u256.hiHi"))?,
+            hi_lo: r.get(&k(p, "u256.hiLo"))?,
+            lo_hi: r.get(&k(p, "u256.loHi"))?,
+            lo_lo: r.get(&k(p, "u256.loLo"))?,
+        }),
+        xdr::ScValType::I256 => xdr::ScVal::I256(xdr::Int256Parts {
+            hi_hi: r.get(&k(p, "i256.hiHi"))?,
+            hi_lo: r.get(&k(p, "i256.hiLo"))?,
+            lo_hi: r.get(&k(p, "i256.loHi"))?,
+            lo_lo: r.get(&k(p, "i256.loLo"))?,
+        }),
+        xdr::ScValType::Bytes => xdr::ScVal::Bytes(xdr::ScBytes(r.get_hex(&k(p, "bytes"))?)),
+        xdr::ScValType::String => xdr::ScVal::String(xdr::ScString(r.get_string(&k(p, "str"))?)),
+        xdr::ScValType::Symbol => xdr::ScVal::Symbol(xdr::ScSymbol(r.get_string(&k(p, "sym"))?)),
+        xdr::ScValType::Vec => xdr::ScVal::Vec(r.get_optional(&k(p, "vec"), |r, p| {
+            Ok(xdr::ScVec(r.get_array(p, read_sc_val)?))
+        })?),
+        xdr::ScValType::Map => xdr::ScVal::Map(r.get_optional(&k(p, "map"), read_sc_map)?),
+        xdr::ScValType::Address => xdr::ScVal::Address(read_sc_address(r, &k(p, "address"))?),
+        xdr::ScValType::ContractInstance => xdr::ScVal::ContractInstance(xdr::ScContractInstance {
+            executable: read_contract_executable(r, &k(p, "instance.executable"))?,
+            storage: r.get_optional(&k(p, "instance.storage"), read_sc_map)?,
+        }),
+        xdr::ScValType::LedgerKeyContractInstance => xdr::ScVal::LedgerKeyContractInstance,
+        xdr::ScValType::LedgerKeyNonce => xdr::ScVal::LedgerKeyNonce(xdr::ScNonceKey {
+            nonce: r.get(&k(p, "nonce_key.nonce"))?,
+        }),
+    })
+}
+
+fn write_contract_executable(w: &mut Writer, p: &str, x: &xdr::ContractExecutable) {
+    w.put_enum(&k(p, "type"), "CONTRACT_EXECUTABLE_", &x.discriminant());
+    match x {
+        xdr::ContractExecutable::Wasm(hash) => w.put_hex(&k(p, "wasm_hash"), &hash.0),
+        xdr::ContractExecutable::StellarAsset => {}
+    }
+}
+
+fn read_contract_executable(r: &Reader, p: &str) -> Result<xdr::ContractExecutable> {
+    Ok(match r.get_enum(&k(p, "type"), "CONTRACT_EXECUTABLE_")? {
+        xdr::ContractExecutableType::Wasm => {
+            xdr::ContractExecutable::Wasm(xdr::Hash(r.get_hex(&k(p, "wasm_hash"))?))
+        }
+        xdr::ContractExecutableType::StellarAsset => xdr::ContractExecutable::StellarAsset,
+    })
+}
+
+fn write_contract_id_preimage(w: &mut Writer, p: &str, x: &xdr::ContractIdPreimage) {
+    w.put_enum(
+        &k(p, "type"),
+        "CONTRACT_ID_PREIMAGE_FROM_",
+        &x.discriminant(),
+    );
+    match x {
+        xdr::ContractIdPreimage::Address(from) => {
+            write_sc_address(w, &k(p, "fromAddress.address"), &from.address);
+            w.put_hex(&k(p, "fromAddress.salt"), &from.salt.0);
+        }
+        xdr::ContractIdPreimage::Asset(asset) => write_asset(w, &k(p, "fromAsset"), asset),
+    }
+}
+
+fn read_contract_id_preimage(r: &Reader, p: &str) -> Result<xdr::ContractIdPreimage> {
+    Ok(
+        match r.get_enum(&k(p, "type"), "CONTRACT_ID_PREIMAGE_FROM_")? {
+            xdr::ContractIdPreimageType::Address => {
+                xdr::ContractIdPreimage::Address(xdr::ContractIdPreimageFromAddress {
+                    address: read_sc_address(r, &k(p, "fromAddress.address"))?,
+                    salt: xdr::Uint256(r.get_hex(&k(p, "fromAddress.salt"))?),
+                })
+            }
+            xdr::ContractIdPreimageType::Asset => {
+                xdr::ContractIdPreimage::Asset(read_asset(r, &k(p, "fromAsset"))?)
+            }
+        },
+    )
+}
+
+fn write_invoke_contract_args(w: &mut Writer, p: &str, x: &xdr::InvokeContractArgs) {
+    write_sc_address(w, &k(p, "contractAddress"), &x.contract_address);
+    w.put_string(&k(p, "functionName"), &x.function_name.0);
+    w.put_array(&k(p, "args"), &x.args, write_sc_val);
+}
+
+fn read_invoke_contract_args(r: &Reader, p: &str) -> Result<xdr::InvokeContractArgs> {
+    Ok(xdr::InvokeContractArgs {
+        contract_address: read_sc_address(r, &k(p, "contractAddress"))?,
+        function_name: xdr::ScSymbol(r.get_string(&k(p, "functionName"))?),
+        args: r.get_array(&k(p, "args"), read_sc_val)?,
+    })
+}
+
+fn write_create_contract_args(w: &mut Writer, p: &str, x: &xdr::CreateContractArgs) {
+    write_contract_id_preimage(w, &k(p, "contractIDPreimage"), &x.contract_id_preimage);
+    write_contract_executable(w, &k(p, "executable"), &x.executable);
+}
+
+fn read_create_contract_args(r: &Reader, p: &str) -> Result<xdr::CreateContractArgs> {
+    Ok(xdr::CreateContractArgs {
+        contract_id_preimage: read_contract_id_preimage(r, &k(p, "contractIDPreimage"))?,
+        executable: read_contract_executable(r, &k(p, "executable"))?,
+    })
+}
+
+fn write_create_contract_args_v2(w: &mut Writer, p: &str, x: &xdr::CreateContractArgsV2) {
+    write_contract_id_preimage(w, &k(p, "contractIDPreimage"), &x.contract_id_preimage);
+    write_contract_executable(w, &k(p, "executable"), &x.executable);
+    w.put_array(&k(p, "constructorArgs"), &x.constructor_args, write_sc_val);
+}
+
+fn read_create_contract_args_v2(r: &Reader, p: &str) -> Result<xdr::CreateContractArgsV2> {
+    Ok(xdr::CreateContractArgsV2 {
+        contract_id_preimage: read_contract_id_preimage(r, &k(p, "contractIDPreimage"))?,
+        executable: read_contract_executable(r, &k(p, "executable"))?,
+        constructor_args: r.get_array(&k(p, "constructorArgs"), read_sc_val)?,
+    })
+}
+
+fn write_host_function(w: &mut Writer, p: &str, x: &xdr::HostFunction) {
+    w.put_enum(&k(p, "type"), "HOST_FUNCTION_TYPE_", &x.discriminant());
+    match x {
+        xdr::HostFunction::InvokeContract(args) => {
+            write_invoke_contract_args(w, &k(p, "invokeContract"), args)
+        }
+        xdr::HostFunction::CreateContract(args) => {
+            write_create_contract_args(w, &k(p, "createContract"), args)
+        }
+        xdr::HostFunction::UploadContractWasm(wasm) => w.put_hex(&k(p, "wasm"), wasm),
+        xdr::HostFunction::CreateContractV2(args) => {
+            write_create_contract_args_v2(w, &k(p, "createContractV2"), args)
+        }
+    }
+}
+
+fn read_host_function(r: &Reader, p: &str) -> Result<xdr::HostFunction> {
+    Ok(match r.get_enum(&k(p, "type"), "HOST_FUNCTION_TYPE_")? {
+        xdr::HostFunctionType::InvokeContract => xdr::HostFunction::InvokeContract(
+            read_invoke_contract_args(r, &k(p, "invokeContract"))?,
+        ),
+        xdr::HostFunctionType::CreateContract => xdr::HostFunction::CreateContract(
+            read_create_contract_args(r, &k(p, "createContract"))?,
+        ),
+        xdr::HostFunctionType::UploadContractWasm => {
+            xdr::HostFunction::UploadContractWasm(r.get_hex(&k(p, "wasm"))?)
+        }
+        xdr::HostFunctionType::CreateContractV2 => xdr::HostFunction::CreateContractV2(
+            read_create_contract_args_v2(r, &k(p, "createContractV2"))?,
+        ),
+    })
+}
+
+fn write_authorized_invocation(w: &mut Writer, p: &str, x: &xdr::SorobanAuthorizedInvocation) {
+    let fp = k(p, "function");
+    w.put_enum(
+        &k(&fp, "type"),
+        "SOROBAN_AUTHORIZED_FUNCTION_TYPE_",
+        &x.function.discriminant(),
+    );
+    match &x.function {
+        xdr::SorobanAuthorizedFunction::ContractFn(args) => {
+            write_invoke_contract_args(w, &k(&fp, "contractFn"), args)
+        }
+        xdr::SorobanAuthorizedFunction::CreateContractHostFn(args) => {
+            write_create_contract_args(w, &k(&fp, "createContractHostFn"), args)
+        }
+        xdr::SorobanAuthorizedFunction::CreateContractV2HostFn(args) => {
+            write_create_contract_args_v2(w, &k(&fp, "createContractV2HostFn"), args)
+        }
+    }
+    w.put_array(
+        &k(p, "subInvocations"),
+        &x.sub_invocations,
+        write_authorized_invocation,
+    );
+}
+
+fn read_authorized_invocation(r: &Reader, p: &str) -> Result<xdr::SorobanAuthorizedInvocation> {
+    r.nested(p, || read_authorized_invocation_body(r, p))
+}
+
+fn read_authorized_invocation_body(
+    r: &Reader,
+    p: &str,
+) -> Result<xdr::SorobanAuthorizedInvocation> {
+    let fp = k(p, "function");
+    let function = match r.get_enum(&k(&fp, "type"), "SOROBAN_AUTHORIZED_FUNCTION_TYPE_")? {
+        xdr::SorobanAuthorizedFunctionType::ContractFn => {
+            xdr::SorobanAuthorizedFunction::ContractFn(read_invoke_contract_args(
+                r,
+                &k(&fp, "contractFn"),
+            )?)
+        }
+        xdr::SorobanAuthorizedFunctionType::CreateContractHostFn => {
+            xdr::SorobanAuthorizedFunction::CreateContractHostFn(read_create_contract_args(
+                r,
+                &k(&fp, "createContractHostFn"),
+            )?)
+        }
+        xdr::SorobanAuthorizedFunctionType::CreateContractV2HostFn => {
+            xdr::SorobanAuthorizedFunction::CreateContractV2HostFn(read_create_contract_args_v2(
+                r,
+                &k(&fp, "createContractV2HostFn"),
+            )?)
+        }
+    };
+    Ok(xdr::SorobanAuthorizedInvocation {
+        function,
+        sub_invocations: r.get_array(&k(p, "subInvocations"), read_authorized_invocation)?,
+    })
+}
+
+fn write_authorization_entry(w: &mut Writer, p: &str, x: &xdr::SorobanAuthorizationEntry) {
+    let cp = k(p, "credentials");
+    w.put_enum(
+        &k(&cp, "type"),
+        "SOROBAN_CREDENTIALS_",
+        &x.credentials.discriminant(),
+    );
+    match &x.credentials {
+        xdr::SorobanCredentials::SourceAccount => {}
+        xdr::SorobanCredentials::Address(credentials) => {
+            let cp = k(&cp, "address");
+            write_sc_address(w, &k(&cp, "address"), &credentials.address);
+            w.put(&k(&cp, "nonce"), credentials.nonce);
+            w.put(
+                &k(&cp, "signatureExpirationLedger"),
+                credentials.signature_expiration_ledger,
+            );
+            write_sc_val(w, &k(&cp, "signature"), &credentials.signature);
+        }
+    }
+    write_authorized_invocation(w, &k(p, "rootInvocation"), &x.root_invocation);
+}
+
+fn read_authorization_entry(r: &Reader, p: &str) -> Result<xdr::SorobanAuthorizationEntry> {
+    let cp = k(p, "credentials");
+    let credentials = match r.get_enum(&k(&cp, "type"), "SOROBAN_CREDENTIALS_")? {
+        xdr::SorobanCredentialsType::SourceAccount => xdr::SorobanCredentials::SourceAccount,
+        xdr::SorobanCredentialsType::Address => {
+            let cp = k(&cp, "address");
+            xdr::SorobanCredentials::Address(xdr::SorobanAddressCredentials {
+                address: read_sc_address(r, &k(&cp, "address"))?,
+                nonce: r.get(&k(&cp, "nonce"))?,
+                signature_expiration_ledger: r.get(&k(&cp, "signatureExpirationLedger"))?,
+                signature: read_sc_val(r, &k(&cp, "signature"))?,
+            })
+        }
+    };
+    Ok(xdr::SorobanAuthorizationEntry {
+        credentials,
+        root_invocation: read_authorized_invocation(r, &k(p, "rootInvocation"))?,
+    })
+}
+
+fn write_soroban_transaction_data(w: &mut Writer, p: &str, x: &xdr::SorobanTransactionData) {
+    match &x.ext {
+        xdr::SorobanTransactionDataExt::V0 => w.put(&k(p, "ext.v"), 0),
+        xdr::SorobanTransactionDataExt::V1(ext) => {
+            w.put(&k(p, "ext.v"), 1);
+            w.put_array(
+                &k(p, "ext.v1.archivedSorobanEntries"),
+                &ext.archived_soroban_entries,
+                |w, p, index| w.put(p, index),
+            );
+        }
+    }
+    let rp = k(p, "resources");
+    let footprint = &x.resources.footprint;
+    w.put_array(
+        &k(&rp, "footprint.readOnly"),
+        &footprint.read_only,
+        write_ledger_key,
+    );
+    w.put_array(
+        &k(&rp, "footprint.readWrite"),
+        &footprint.read_write,
+        write_ledger_key,
+    );
+    w.put(&k(&rp, "instructions"), x.resources.instructions);
+    w.put(&k(&rp, "diskReadBytes"), x.resources.disk_read_bytes);
+    w.put(&k(&rp, "writeBytes"), x.resources.write_bytes);
+    w.put(&k(p, "resourceFee"), x.resource_fee);
+}
+
+fn read_soroban_transaction_data(r: &Reader, p: &str) -> Result<xdr::SorobanTransactionData> {
+    let ext_key = k(p, "ext.v");
+    let ext = match r.get(&ext_key)? {
+        0 => xdr::SorobanTransactionDataExt::V0,
+        1 => xdr::SorobanTransactionDataExt::V1(xdr::SorobanResourcesExtV0 {
+            archived_soroban_entries: r
+                .get_array(&k(p, "ext.v1.archivedSorobanEntries"), |r, p| r.get(p))?,
+        }),
+        _ => return Err(invalid(&ext_key)),
+    };
+    let rp = k(p, "resources");
+    Ok(xdr::SorobanTransactionData {
+        ext,
+        resources: xdr::SorobanResources {
+            footprint: xdr::LedgerFootprint {
+                read_only: r.get_array(&k(&rp, "footprint.readOnly"), read_ledger_key)?,
+                read_write: r.get_array(&k(&rp, "footprint.readWrite"), read_ledger_key)?,
+            },
+            instructions: r.get(&k(&rp, "instructions"))?,
+            disk_read_bytes: r.get(&k(&rp, "diskReadBytes"))?,
+            write_bytes: r.get(&k(&rp, "writeBytes"))?,
+        },
+        resource_fee: r.get(&k(p, "resourceFee"))?,
+    })
+}
+
+fn write_operation(w: &mut Writer, p: &str, x: &xdr::Operation) {
+    w.put_optional(
+        &k(p, "sourceAccount"),
+        x.source_account.as_ref(),
+        write_muxed_account,
+    );
+    let p = &k(p, "body");
+    w.put_enum(&k(p, "type"), "", &x.body.discriminant());
+    match &x.body {
+        xdr::OperationBody::CreateAccount(op) => {
+            let p = k(p, "createAccountOp");
+            write_account_id(w, &k(&p, "destination"), &op.destination);
+            w.put(&k(&p, "startingBalance"), op.starting_balance);
+        }
+        xdr::OperationBody::Payment(op) => {
+            let p = k(p, "paymentOp");
+            write_muxed_account(w, &k(&p, "destination"), &op.destination);
+            write_asset(w, &k(&p, "asset"), &op.asset);
+            w.put(&k(&p, "amount"), op.amount);
+        }
+        xdr::OperationBody::PathPaymentStrictReceive(op) => {
+            let p = k(p, "pathPaymentStrictReceiveOp");
+            write_asset(w, &k(&p, "sendAsset"), &op.send_asset);
+            w.put(&k(&p, "sendMax"), op.send_max);
+            write_muxed_account(w, &k(&p, "destination"), &op.destination);
+            write_asset(w, &k(&p, "destAsset"), &op.dest_asset);
+            w.put(&k(&p, "destAmount"), op.dest_amount);
+            w.put_array(&k(&p, "path"), &op.path, write_asset);
+        }
+        xdr::OperationBody::ManageSellOffer(op) => {
+            let p = k(p, "manageSellOfferOp");
+            write_asset(w, &k(&p, "selling"), &op.selling);
+            write_asset(w, &k(&p, "buying"), &op.buying);
+            w.put(&k(&p, "amount"), op.amount);
+            write_price(w, &k(&p, "price"), &op.price);
+            w.put(&k(&p, "offerID"), op.offer_id);
+        }
+        xdr::OperationBody::CreatePassiveSellOffer(op) => {
+            let p = k(p, "createPassiveSellOfferOp");
+            write_asset(w, &k(&p, "selling"), &op.selling);
+            write_asset(w, &k(&p, "buying"), &op.buying);
+            w.put(&k(&p, "amount"), op.amount);
+            write_price(w, &k(&p, "price"), &op.price);
+        }
+        xdr::OperationBody::SetOptions(op) => {
+            let p = k(p, "setOptionsOp");
+            let put_u32 = |w: &mut Writer, p: &str, x: &u32| w.put(p, x);
+            w.put_optional(
+                &k(&p, "inflationDest"),
+                op.inflation_dest.as_ref(),
+                write_account_id,
+            );
+            w.put_optional(&k(&p, "clearFlags"), op.clear_flags.as_ref(), put_u32);
+            w.put_optional(&k(&p, "setFlags"), op.set_flags.as_ref(), put_u32);
+            w.put_optional(&k(&p, "masterWeight"), op.master_weight.as_ref(), put_u32);
+            w.put_optional(&k(&p, "lowThreshold"), op.low_threshold.as_ref(), put_u32);
+            w.put_optional(&k(&p, "medThreshold"), op.med_threshold.as_ref(), put_u32);
+            w.put_optional(&k(&p, "highThreshold"), op.high_threshold.as_ref(), put_u32);
+            w.put_optional(&k(&p, "homeDomain"), op.home_domain.as_ref(), |w, p, x| {
+                w.put_string(p, &x.0)
+            });
+            w.put_optional(&k(&p, "signer"), op.signer.as_ref(), write_signer);
+        }
+        xdr::OperationBody::ChangeTrust(op) => {
+            let p = k(p, "changeTrustOp");
+            write_change_trust_asset(w, &k(&p, "line"), &op.line);
+            w.put(&k(&p, "limit"), op.limit);
+        }
+        xdr::OperationBody::AllowTrust(op) => {
+            let p = k(p, "allowTrustOp");
+            write_account_id(w, &k(&p, "trustor"), &op.trustor);
+            write_asset_code(w, &k(&p, "asset"), &op.asset);
+            w.put(&k(&p, "authorize"), op.authorize);
+        }
+        xdr::OperationBody::AccountMerge(destination) => {
+            write_muxed_account(w, &k(p, "destination"), destination)
+        }
+        xdr::OperationBody::Inflation | xdr::OperationBody::EndSponsoringFutureReserves => {}
+        xdr::OperationBody::ManageData(op) => {
+            let p = k(p, "manageDataOp");
+            w.put_string(&k(&p, "dataName"), &op.data_name.0);
+            w.put_optional(&k(&p, "dataValue"), op.data_value.as_ref(), |w, p, x| {
+                w.put_hex(p, &x.0)
+            });
+        }
+        xdr::OperationBody::BumpSequence(op) => w.put(&k(p, "bumpSequenceOp.bumpTo"), op.bump_to.0),
+        xdr::OperationBody::ManageBuyOffer(op) => {
+            let p = k(p, "manageBuyOfferOp");
+            write_asset(w, &k(&p, "selling"), &op.selling);
+            write_asset(w, &k(&p, "buying"), &op.buying);
+            w.put(&k(&p, "buyAmount"), op.buy_amount);
+            write_price(w, &k(&p, "price"), &op.price);
+            w.put(&k(&p, "offerID"), op.offer_id);
+        }
+        xdr::OperationBody::PathPaymentStrictSend(op) => {
+            let p = k(p, "pathPaymentStrictSendOp");
+            write_asset(w, &k(&p, "sendAsset"), &op.send_asset);
+            w.put(&k(&p, "sendAmount"), op.send_amount);
+            write_muxed_account(w, &k(&p, "destination"), &op.destination);
+            write_asset(w, &k(&p, "destAsset"), &op.dest_asset);
+            w.put(&k(&p, "destMin"), op.dest_min);
+            w.put_array(&k(&p, "path"), &op.path, write_asset);
+        }
+        xdr::OperationBody::CreateClaimableBalance(op) => {
+            let p = k(p, "createClaimableBalanceOp");
+            write_asset(w, &k(&p, "asset"), &op.asset);
+            w.put(&k(&p, "amount"), op.amount);
+            w.put_array(&k(&p, "claimants"), &op.claimants, write_claimant);
+        }
+        xdr::OperationBody::ClaimClaimableBalance(op) => write_claimable_balance_id(
+            w,
+            &k(p, "claimClaimableBalanceOp.balanceID"),
+            &op.balance_id,
+        ),
+        xdr::OperationBody::BeginSponsoringFutureReserves(op) => write_account_id(
+            w,
+            &k(p, "beginSponsoringFutureReservesOp.sponsoredID"),
+            &op.sponsored_id,
+        ),
+        xdr::OperationBody::RevokeSponsorship(op) => {
+            let p = k(p, "revokeSponsorshipOp");
+            w.put_enum(&k(&p, "type"), "REVOKE_SPONSORSHIP_", &op.discriminant());
+            match op {
+                xdr::RevokeSponsorshipOp::LedgerEntry(key) => {
+                    write_ledger_key(w, &k(&p, "ledgerKey"), key)
+                }
+                xdr::RevokeSponsorshipOp::Signer(signer) => {
+                    write_account_id(w, &k(&p, "signer.accountID"), &signer.account_id);
+                    write_signer_key(w, &k(&p, "signer.signerKey"), &signer.signer_key);
+                }
+            }
+        }
+        xdr::OperationBody::Clawback(op) => {
+            let p = k(p, "clawbackOp");
+            write_asset(w, &k(&p, "asset"), &op.asset);
+            write_muxed_account(w, &k(&p, "from"), &op.from);
+            w.put(&k(&p, "amount"), op.amount);
+        }
+        xdr::OperationBody::ClawbackClaimableBalance(op) => write_claimable_balance_id(
+            w,
+            &k(p, "clawbackClaimableBalanceOp.balanceID"),
+            &op.balance_id,
+        ),
+        xdr::OperationBody::SetTrustLineFlags(op) => {
+            let p = k(p, "setTrustLineFlagsOp");
+            write_account_id(w, &k(&p, "trustor"), &op.trustor);
+            write_asset(w, &k(&p, "asset"), &op.asset);
+            w.put(&k(&p, "clearFlags"), op.clear_flags);
+            w.put(&k(&p, "setFlags"), op.set_flags);
+        }
+        xdr::OperationBody::LiquidityPoolDeposit(op) => {
+            let p = k(p, "liquidityPoolDepositOp");
+            w.put_hex(&k(&p, "liquidityPoolID"), &op.liquidity_pool_id.0 .0);
+            w.put(&k(&p, "maxAmountA"), op.max_amount_a);
+            w.put(&k(&p, "maxAmountB"), op.max_amount_b);
+            write_price(w, &k(&p, "minPrice"), &op.min_price);
+            write_price(w, &k(&p, "maxPrice"), &op.max_price);
+        }
+        xdr::OperationBody::LiquidityPoolWithdraw(op) => {
+            let p = k(p, "liquidityPoolWithdrawOp");
+            w.put_hex(&k(&p, "liquidityPoolID"), &op.liquidity_pool_id.0 .0);
+            w.put(&k(&p, "amount"), op.amount);
+            w.put(&k(&p, "minAmountA"), op.min_amount_a);
+            w.put(&k(&p, "minAmountB"), op.min_amount_b);
+        }
+        xdr::OperationBody::InvokeHostFunction(op) => {
+            let p = k(p, "invokeHostFunctionOp");
+            write_host_function(w, &k(&p, "hostFunction"), &op.host_function);
+            w.put_array(&k(&p, "auth"), &op.auth, write_authorization_entry);
+        }
+        xdr::OperationBody::ExtendFootprintTtl(op) => {
+            let p = k(p, "extendFootprintTTLOp");
+            w.put(&k(&p, "ext.v"), 0);
+            w.put(&k(&p, "extendTo"), op.extend_to);
+        }
+        xdr::OperationBody::RestoreFootprint(_) => w.put(&k(p, "restoreFootprintOp.ext.v"), 0),
+    }
+}
+
+fn read_operation(r: &Reader, p: &str) -> Result<xdr::Operation> {
+    let source_account = r.get_optional(&k(p, "sourceAccount"), read_muxed_account)?;
+    let p = &k(p, "body");
+    let body = match r.get_enum(&k(p, "type"), "")? {
+        xdr::OperationType::CreateAccount => {
+            let p = k(p, "createAccountOp");
+            xdr::OperationBody::CreateAccount(xdr::CreateAccountOp {
+                destination: read_account_id(r, &k(&p, "destination"))?,
+                starting_balance: r.get(&k(&p, "startingBalance"))?,
+            })
+        }
+        xdr::OperationType::Payment => {
+            let p = k(p, "paymentOp");
+            xdr::OperationBody::Payment(xdr::PaymentOp {
+                destination: read_muxed_account(r, &k(&p, "destination"))?,
+                asset: read_asset(r, &k(&p, "asset"))?,
+                amount: r.get(&k(&p, "amount"))?,
+            })
+        }
+        xdr::OperationType::PathPaymentStrictReceive => {
+            let p = k(p, "pathPaymentStrictReceiveOp");
+            xdr::OperationBody::PathPaymentStrictReceive(xdr::PathPaymentStrictReceiveOp {
+                send_asset: read_asset(r, &k(&p, "sendAsset"))?,
+                send_max: r.get(&k(&p, "sendMax"))?,
+                destination: read_muxed_account(r, &k(&p, "destination"))?,
+                dest_asset: read_asset(r, &k(&p, "destAsset"))?,
+                dest_amount: r.get(&k(&p, "destAmount"))?,
+                path: r.get_array(&k(&p, "path"), read_asset)?,
+            })
+        }
+        xdr::OperationType::ManageSellOffer => {
+            let p = k(p, "manageSellOfferOp");
+            xdr::OperationBody::ManageSellOffer(xdr::ManageSellOfferOp {
+                selling: read_asset(r, &k(&p, "selling"))?,
+                buying: read_asset(r, &k(&p, "buying"))?,
+                amount: r.get(&k(&p, "amount"))?,
+                price: read_price(r, &k(&p, "price"))?,
+                offer_id: r.get(&k(&p, "offerID"))?,
+            })
+        }
+        xdr::OperationType::CreatePassiveSellOffer => {
+            let p = k(p, "createPassiveSellOfferOp");
+            xdr::OperationBody::CreatePassiveSellOffer(xdr::CreatePassiveSellOfferOp {
+                selling: read_asset(r, &k(&p, "selling"))?,
+                buying: read_asset(r, &k(&p, "buying"))?,
+                amount: r.get(&k(&p, "amount"))?,
+                price: read_price(r, &k(&p, "price"))?,
+            })
+        }
+        xdr::OperationType::SetOptions => {
+            let p = k(p, "setOptionsOp");
+            let get_u32 = |r: &Reader, p: &str| r.get::<u32>(p);
+            xdr::OperationBody::SetOptions(xdr::SetOptionsOp {
+                inflation_dest: r.get_optional(&k(&p, "inflationDest"), read_account_id)?,
+                clear_flags: r.get_optional(&k(&p, "clearFlags"), get_u32)?,
+                set_flags: r.get_optional(&k(&p, "setFlags"), get_u32)?,
+                master_weight: r.get_optional(&k(&p, "masterWeight"), get_u32)?,
+                low_threshold: r.get_optional(&k(&p, "lowThreshold"), get_u32)?,
+                med_threshold: r.get_optional(&k(&p, "medThreshold"), get_u32)?,
+                high_threshold: r.get_optional(&k(&p, "highThreshold"), get_u32)?,
+                home_domain: r.get_optional(&k(&p, "homeDomain"), |r, p| {
+                    Ok(xdr::String32(r.get_string(p)?))
+                })?,
+                signer: r.get_optional(&k(&p, "signer"), read_signer)?,
+            })
+        }
+        xdr::OperationType::ChangeTrust => {
+            let p = k(p, "changeTrustOp");
+            xdr::OperationBody::ChangeTrust(xdr::ChangeTrustOp {
+                line: read_change_trust_asset(r, &k(&p, "line"))?,
+                limit: r.get(&k(&p, "limit"))?,
+            })
+        }
+        xdr::OperationType::AllowTrust => {
+            let p = k(p, "allowTrustOp");
+            xdr::OperationBody::AllowTrust(xdr::AllowTrustOp {
+                trustor: read_account_id(r, &k(&p, "trustor"))?,
+                asset: read_asset_code(r, &k(&p, "asset"))?,
+                authorize: r.get(&k(&p, "authorize"))?,
+            })
+        }
+        xdr::OperationType::AccountMerge => {
+            xdr::OperationBody::AccountMerge(read_muxed_account(r, &k(p, "destination"))?)
+        }
+        xdr::OperationType::Inflation => xdr::OperationBody::Inflation,
+        xdr::OperationType::ManageData => {
+            let p = k(p, "manageDataOp");
+            xdr::OperationBody::ManageData(xdr::ManageDataOp {
+                data_name: xdr::String64(r.get_string(&k(&p, "dataName"))?),
+                data_value: r.get_optional(&k(&p, "dataValue"), |r, p| {
+                    Ok(xdr::DataValue(r.get_hex(p)?))
+                })?,
+            })
+        }
+        xdr::OperationType::BumpSequence => xdr::OperationBody::BumpSequence(xdr::BumpSequenceOp {
+            bump_to: xdr::SequenceNumber(r.get(&k(p, "bumpSequenceOp.bumpTo"))?),
+        }),
+        xdr::OperationType::ManageBuyOffer => {
+            let p = k(p, "manageBuyOfferOp");
+            xdr::OperationBody::ManageBuyOffer(xdr::ManageBuyOfferOp {
+                selling: read_asset(r, &k(&p, "selling"))?,
+                buying: read_asset(r, &k(&p, "buying"))?,
+                buy_amount: r.get(&k(&p, "buyAmount"))?,
+                price: read_price(r, &k(&p, "price"))?,
+                offer_id: r.get(&k(&p, "offerID"))?,
+            })
+        }
+        xdr::OperationType::PathPaymentStrictSend => {
+            let p = k(p, "pathPaymentStrictSendOp");
+            xdr::OperationBody::PathPaymentStrictSend(xdr::PathPaymentStrictSendOp {
+                send_asset: read_asset(r, &k(&p, "sendAsset"))?,
+                send_amount: r.get(&k(&p, "sendAmount"))?,
+                destination: read_muxed_account(r, &k(&p, "destination"))?,
+                dest_asset: read_asset(r, &k(&p, "destAsset"))?,
+                dest_min: r.get(&k(&p, "destMin"))?,
+                path: r.get_array(&k(&p, "path"), read_asset)?,
+            })
+        }
+        xdr::OperationType::CreateClaimableBalance => {
+            let p = k(p, "createClaimableBalanceOp");
+            xdr::OperationBody::CreateClaimableBalance(xdr::CreateClaimableBalanceOp {
+                asset: read_asset(r, &k(&p, "asset"))?,
+                amount: r.get(&k(&p, "amount"))?,
+                claimants: r.get_array(&k(&p, "claimants"), read_claimant)?,
+            })
+        }
+        xdr::OperationType::ClaimClaimableBalance => {
+            xdr::OperationBody::ClaimClaimableBalance(xdr::ClaimClaimableBalanceOp {
+                balance_id: read_claimable_balance_id(
+                    r,
+                    &k(p, "claimClaimableBalanceOp.balanceID"),
+                )?,
+            })
+        }
+        xdr::OperationType::BeginSponsoringFutureReserves => {
+            xdr::OperationBody::BeginSponsoringFutureReserves(
+                xdr::BeginSponsoringFutureReservesOp {
+                    sponsored_id: read_account_id(
+                        r,
+                        &k(p, "beginSponsoringFutureReservesOp.sponsoredID"),
+                    )?,
+                },
+            )
+        }
+        xdr::OperationType::EndSponsoringFutureReserves => {
+            xdr::OperationBody::EndSponsoringFutureReserves
+        }
+        xdr::OperationType::RevokeSponsorship => {
+            let p = k(p, "revokeSponsorshipOp");
+            let op = match r.get_enum(&k(&p, "type"), "REVOKE_SPONSORSHIP_")? {
+                xdr::RevokeSponsorshipType::LedgerEntry => {
+                    xdr::RevokeSponsorshipOp::LedgerEntry(read_ledger_key(r, &k(&p, "ledgerKey"))?)
+                }
+                xdr::RevokeSponsorshipType::Signer => {
+                    xdr::RevokeSponsorshipOp::Signer(xdr::RevokeSponsorshipOpSigner {
+                        account_id: read_account_id(r, &k(&p, "signer.accountID"))?,
+                        signer_key: read_signer_key(r, &k(&p, "signer.signerKey"))?,
+                    })
+                }
+            };
+            xdr::OperationBody::RevokeSponsorship(op)
+        }
+        xdr::OperationType::Clawback => {
+            let p = k(p, "clawbackOp");
+            xdr::OperationBody::Clawback(xdr::ClawbackOp {
+                asset: read_asset(r, &k(&p, "asset"))?,
+                from: read_muxed_account(r, &k(&p, "from"))?,
+                amount: r.get(&k(&p, "amount"))?,
+            })
+        }
+        xdr::OperationType::ClawbackClaimableBalance => {
+            xdr::OperationBody::ClawbackClaimableBalance(xdr::ClawbackClaimableBalanceOp {
+                balance_id: read_claimable_balance_id(
+                    r,
+                    &k(p, "clawbackClaimableBalanceOp.balanceID"),
+                )?,
+            })
+        }
+        xdr::OperationType::SetTrustLineFlags => {
+            let p = k(p, "setTrustLineFlagsOp");
+            xdr::OperationBody::SetTrustLineFlags(xdr::SetTrustLineFlagsOp {
+                trustor: read_account_id(r, &k(&p, "trustor"))?,
+                asset: read_asset(r, &k(&p, "asset"))?,
+                clear_flags: r.get(&k(&p, "clearFlags"))?,
+                set_flags: r.get(&k(&p, "setFlags"))?,
+            })
+        }
+        xdr::OperationType::LiquidityPoolDeposit => {
+            let p = k(p, "liquidityPoolDepositOp");
+            xdr::OperationBody::LiquidityPoolDeposit(xdr::LiquidityPoolDepositOp {
+                liquidity_pool_id: xdr::PoolId(xdr::Hash(r.get_hex(&k(&p, "liquidityPoolID"))?)),
+                max_amount_a: r.get(&k(&p, "maxAmountA"))?,
+                max_amount_b: r.get(&k(&p, "maxAmountB"))?,
+                min_price: read_price(r, &k(&p, "minPrice"))?,
+                max_price: read_price(r, &k(&p, "maxPrice"))?,
+            })
+        }
+        xdr::OperationType::LiquidityPoolWithdraw => {
+            let p = k(p, "liquidityPoolWithdrawOp");
+            xdr::OperationBody::LiquidityPoolWithdraw(xdr::LiquidityPoolWithdrawOp {
+                liquidity_pool_id: xdr::PoolId(xdr::Hash(r.get_hex(&k(&p, "liquidityPoolID"))?)),
+                amount: r.get(&k(&p, "amount"))?,
+                min_amount_a: r.get(&k(&p, "minAmountA"))?,
+                min_amount_b: r.get(&k(&p, "minAmountB"))?,
+            })
+        }
+        xdr::OperationType::InvokeHostFunction => {
+            let p = k(p, "invokeHostFunctionOp");
+            xdr::OperationBody::InvokeHostFunction(xdr::InvokeHostFunctionOp {
+                host_function: read_host_function(r, &k(&p, "hostFunction"))?,
+                auth: r.get_array(&k(&p, "auth"), read_authorization_entry)?,
+            })
+        }
+        xdr::OperationType::ExtendFootprintTtl => {
+            let p = k(p, "extendFootprintTTLOp");
+            read_ext_v0(r, &k(&p, "ext.v"))?;
+            xdr::OperationBody::ExtendFootprintTtl(xdr::ExtendFootprintTtlOp {
+                ext: xdr::ExtensionPoint::V0,
+                extend_to: r.get(&k(&p, "extendTo"))?,
+            })
+        }
+        xdr::OperationType::RestoreFootprint => {
+            read_ext_v0(r, &k(p, "restoreFootprintOp.ext.v"))?;
+            xdr::OperationBody::RestoreFootprint(xdr::RestoreFootprintOp {
+                ext: xdr::ExtensionPoint::V0,
+            })
+        }
+    };
+    Ok(xdr::Operation {
+        source_account,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_txrep, read_sc_val, to_txrep, Reader, MAX_NESTING_DEPTH};
+    use crate::amount::{Amount, Stroops};
+    use crate::asset::Asset;
+    use crate::crypto::PublicKey;
+    use crate::error::Error;
+    use crate::memo::Memo;
+    use crate::network::Network;
+    use crate::operations::tests::*;
+    use crate::operations::Operation;
+    use crate::time_bounds::TimeBounds;
+    use crate::transaction::{FeeBumpTransaction, Transaction, TransactionEnvelope, MIN_BASE_FEE};
+    use crate::xdr;
+    use chrono::DateTime;
+    use std::convert::TryInto;
+    use std::str::FromStr;
+
+    fn account_id(pk: &PublicKey) -> xdr::AccountId {
+        pk.to_xdr_account_id().unwrap()
+    }
+
+    fn key(pk: &PublicKey) -> xdr::Uint256 {
+        xdr::Uint256(pk.as_bytes().try_into().unwrap())
+    }
+
+    fn muxed(pk: &PublicKey) -> xdr::MuxedAccount {
+        xdr::MuxedAccount::Ed25519(key(pk))
+    }
+
+    fn payment_envelope() -> TransactionEnvelope {
+        let kp = keypair0();
+        let op = Operation::new_payment()
+            .with_destination(keypair1().public_key())
+            .with_amount(Amount::from_str("12.301").unwrap())
+            .unwrap()
+            .with_asset(Asset::new_credit("USD", keypair2().public_key()).unwrap())
+            .build()
+            .unwrap();
+        let time_bounds = TimeBounds::always_valid()
+            .with_upper(DateTime::from_timestamp(1_700_000_000, 0).unwrap())
+            .unwrap();
+        let mut tx = Transaction::builder(kp.public_key(), 3556091187167235, MIN_BASE_FEE)
+            .with_memo(Memo::new_text("Hello \"txrep\"").unwrap())
+            .with_time_bounds(time_bounds)
+            .add_operation(op)
+            .into_transaction()
+            .unwrap();
+        tx.sign(kp.as_ref(), &Network::new_test()).unwrap();
+        tx.to_envelope()
+    }
+
+    #[test]
+    fn test_payment_round_trip() {
+        let envelope = payment_envelope();
+        let txrep = envelope.to_txrep().unwrap();
+        assert!(txrep.starts_with("type: ENVELOPE_TYPE_TX\n"));
+        assert!(txrep.contains(
+            "tx.sourceAccount: GDQNY3PBOJOKYZSRMK2S7LHHGWZIUISD4QORETLMXEWXBI7KFZZMKTL3\n"
+        ));
+        assert!(txrep.contains("tx.memo.type: MEMO_TEXT\n"));
+        assert!(txrep.contains("tx.memo.text: \"Hello \\\"txrep\\\"\"\n"));
+        assert!(txrep.contains("tx.cond.type: PRECOND_TIME\n"));
+        assert!(txrep.contains("tx.operations[0].body.type: PAYMENT\n"));
+        assert!(txrep.contains(
+            "tx.operations[0].body.paymentOp.asset: USD:GB7BDSZU2Y27LYNLALKKALB52WS2IZWYBDGY6EQBLEED3TJOCVMZRH7H\n"
+        ));
+        assert!(txrep.contains("tx.operations[0].body.paymentOp.amount: 123010000\n"));
+        assert!(txrep.contains("signatures.len: 1\n"));
+        let back = TransactionEnvelope::from_txrep(&txrep).unwrap();
+        assert_eq!(envelope, back);
+    }
+
+    #[test]
+    fn test_fee_bump_round_trip() {
+        let kp = keypair1();
+        let inner = payment_envelope();
+        let inner = inner.as_transaction().unwrap().clone();
+        let mut fee_bump =
+            FeeBumpTransaction::new(kp.public_key().into(), Stroops::new(200), inner);
+        fee_bump.sign(kp.as_ref(), &Network::new_test()).unwrap();
+        let envelope = fee_bump.to_envelope();
+        let txrep = envelope.to_txrep().unwrap();
+        assert!(txrep.starts_with("type: ENVELOPE_TYPE_TX_FEE_BUMP\n"));
+        assert!(txrep.contains("feeBump.tx.fee: 200\n"));
+        assert!(txrep.contains("feeBump.tx.innerTx.type: ENVELOPE_TYPE_TX\n"));
+        assert!(txrep.contains("feeBump.tx.innerTx.signatures.len: 1\n"));
+        assert!(txrep.contains("feeBump.signatures.len: 1\n"));
+        let back = TransactionEnvelope::from_txrep(&txrep).unwrap();
+        assert_eq!(envelope, back);
+    }
+
+    #[test]
+    fn test_parse_ignores_comments_and_blank_lines() {
+        let envelope = payment_envelope();
+        let txrep = envelope
+            .to_txrep()
+            .unwrap()
+            .replace(
+                "paymentOp.amount: 123010000\n",
+                "paymentOp.amount: 123010000 (12.301e7)\n\n",
+            )
+            .replace("tx.fee: 100\n", "  tx.fee:100   \n");
+        let back = TransactionEnvelope::from_txrep(&txrep).unwrap();
+        assert_eq!(envelope, back);
+    }
+
+    fn nested_sc_vec(depth: usize) -> String {
+        let mut txrep = String::new();
+        let mut p = "val".to_string();
+        for _ in 0..depth {
+            txrep.push_str(&format!(
+                "{p}.type: SCV_VEC\n{p}.vec._present: true\n{p}.vec.len: 1\n"
+            ));
+            p = format!("{}.vec[0]", p);
+        }
+        txrep.push_str(&format!("{}.type: SCV_VOID\n", p));
+        txrep
+    }
+
+    #[test]
+    fn test_nesting_depth_is_bounded() {
+        let r = Reader::parse(&nested_sc_vec(MAX_NESTING_DEPTH as usize - 1)).unwrap();
+        assert!(read_sc_val(&r, "val").is_ok());
+        assert_eq!(0, r.depth.get());
+
+        let too_deep = MAX_NESTING_DEPTH as usize + 1;
+        let r = Reader::parse(&nested_sc_vec(too_deep)).unwrap();
+        let res = read_sc_val(&r, "val");
+        assert!(
+            matches!(res, Err(Error::InvalidTxRep(msg)) if msg.ends_with("is nested too deeply"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_txrep() {
+        let txrep = payment_envelope().to_txrep().unwrap();
+
+        let missing = txrep.replace("tx.fee: 100\n", "");
+        let res = TransactionEnvelope::from_txrep(&missing);
+        assert!(matches!(res, Err(Error::InvalidTxRep(msg)) if msg == "missing tx.fee"));
+
+        let bad_type = txrep.replace("body.type: PAYMENT", "body.type: PAYMENTS");
+        let res = TransactionEnvelope::from_txrep(&bad_type);
+        assert!(matches!(res, Err(Error::InvalidTxRep(_))));
+
+        let duplicate = format!("{}tx.fee: 100\n", txrep);
+        let res = TransactionEnvelope::from_txrep(&duplicate);
+        assert!(matches!(res, Err(Error::InvalidTxRep(_))));
+
+        let huge_len = txrep.replace("signatures.len: 1", "signatures.len: 4294967295");
+        let res = TransactionEnvelope::from_txrep(&huge_len);
+        assert!(matches!(res, Err(Error::InvalidTxRep(_))));
+    }
+
+    fn sc_address(pk: &PublicKey) -> xdr::ScAddress {
+        xdr::ScAddress::Account(account_id(pk))
+    }
+
+    fn contract_key() -> xdr::LedgerKey {
+        xdr::LedgerKey::ContractData(xdr::LedgerKeyContractData {
+            contract: xdr::ScAddress::Contract(xdr::ContractId(xdr::Hash([7; 32]))),
+            key: xdr::ScVal::Vec(Some(xdr::ScVec(
+                vec![
+                    xdr::ScVal::Symbol("balance".try_into().unwrap()),
+                    xdr::ScVal::I128(xdr::Int128Parts { hi: -1, lo: 42 }),
+                    xdr::ScVal::Map(Some(xdr::ScMap(
+                        vec![xdr::ScMapEntry {
+                            key: xdr::ScVal::String(xdr::ScString("k\n\u{e9}".try_into().unwrap())),
+                            val: xdr::ScVal::Error(xdr::ScError::Budget(
+                                xdr::ScErrorCode::ExceededLimit,
+                            )),
+                        }]
+                        .try_into()
+                        .unwrap(),
+                    ))),
+                    xdr::ScVal::Vec(None),
+                    xdr::ScVal::U256(xdr::UInt256Parts {
+                        hi_hi: 1,
+                        hi_lo: 2,
+                        lo_hi: 3,
+                        lo_lo: 4,
+                    }),
+                    xdr::ScVal::LedgerKeyContractInstance,
+                ]
+                .try_into()
+                .unwrap(),
+            ))),
+            durability: xdr::ContractDataDurability::Persistent,
+        })
+    }
+
+    fn all_operations() -> Vec<xdr::OperationBody> {
+        let pk0 = keypair0().public_key();
+        let pk1 = keypair1().public_key();
+        let usd = xdr::Asset::CreditAlphanum4(xdr::AlphaNum4 {
+            asset_code: xdr::AssetCode4(*b"USD\0"),
+            issuer: account_id(&pk1),
+        });
+        let long = xdr::Asset::CreditAlphanum12(xdr::AlphaNum12 {
+            asset_code: xdr::AssetCode12(*b"LONGCODE\0\0\0\0"),
+            issuer: account_id(&pk1),
+        });
+        // Not representable as CODE:ISSUER.
+        let odd = xdr::Asset::CreditAlphanum12(xdr::AlphaNum12 {
+            asset_code: xdr::AssetCode12(*b"AB\0\0\0\0\0\0\0\0\0\0"),
+            issuer: account_id(&pk1),
+        });
+        let price = xdr::Price { n: 3, d: 7 };
+        let balance_id = xdr::ClaimableBalanceId::ClaimableBalanceIdTypeV0(xdr::Hash([9; 32]));
+        let pool_id = xdr::PoolId(xdr::Hash([5; 32]));
+        let invoke_args = xdr::InvokeContractArgs {
+            contract_address: xdr::ScAddress::Contract(xdr::ContractId(xdr::Hash([7; 32]))),
+            function_name: xdr::ScSymbol("transfer".try_into().unwrap()),
+            args: vec![
+                xdr::ScVal::Address(sc_address(&pk0)),
+                xdr::ScVal::Bool(true),
+                xdr::ScVal::Bytes(xdr::ScBytes(vec![1, 2, 3].try_into().unwrap())),
+            ]
+            .try_into()
+            .unwrap(),
+        };
+        let create_args = xdr::CreateContractArgs {
+            contract_id_preimage: xdr::ContractIdPreimage::Address(
+                xdr::ContractIdPreimageFromAddress {
+                    address: sc_address(&pk0),
+                    salt: xdr::Uint256([3; 32]),
+                },
+            ),
+            executable: xdr::ContractExecutable::Wasm(xdr::Hash([4; 32])),
+        };
+        let auth = xdr::SorobanAuthorizationEntry {
+            credentials: xdr::SorobanCredentials::Address(xdr::SorobanAddressCredentials {
+                address: sc_address(&pk1),
+                nonce: 99,
+                signature_expiration_ledger: 1000,
+                signature: xdr::ScVal::Void,
+            }),
+            root_invocation: xdr::SorobanAuthorizedInvocation {
+                function: xdr::SorobanAuthorizedFunction::ContractFn(invoke_args.clone()),
+                sub_invocations: vec![xdr::SorobanAuthorizedInvocation {
+                    function: xdr::SorobanAuthorizedFunction::CreateContractHostFn(
+                        create_args.clone(),
+                    ),
+                    sub_invocations: Default::default(),
+                }]
+                .try_into()
+                .unwrap(),
+            },
+        };
+
+        vec![
+            xdr::OperationBody::CreateAccount(xdr::CreateAccountOp {
+                destination: account_id(&pk1),
+                starting_balance: 1_000_000,
+            }),
+            xdr::OperationBody::Payment(xdr::PaymentOp {
+                destination: xdr::MuxedAccount::MuxedEd25519(xdr::MuxedAccountMed25519 {
+                    id: 123,
+                    ed25519: key(&pk1),
+                }),
+                asset: odd.clone(),
+                amount: 10,
+            }),
+            xdr::OperationBody::PathPaymentStrictReceive(xdr::PathPaymentStrictReceiveOp {
+                send_asset: xdr::Asset::Native,
+                send_max: 100,
+                destination: muxed(&pk1),
+                dest_asset: usd.clone(),
+                dest_amount: 50,
+                path: vec![long.clone(), odd.clone()].try_into().unwrap(),
+            }),
+            xdr::OperationBody::ManageSellOffer(xdr::ManageSellOfferOp {
+                selling: usd.clone(),
+                buying: xdr::Asset::Native,
+                amount: 1,
+                price: price.clone(),
+                offer_id: 5,
+            }),
+            xdr::OperationBody::CreatePassiveSellOffer(xdr::CreatePassiveSellOfferOp {
+                selling: long.clone(),
+                buying: usd.clone(),
+                amount: 2,
+                price: price.clone(),
+            }),
+            xdr::OperationBody::SetOptions(xdr::SetOptionsOp {
+                inflation_dest: Some(account_id(&pk1)),
+                clear_flags: None,
+                set_flags: Some(3),
+                master_weight: Some(1),
+                low_threshold: None,
+                med_threshold: Some(2),
+                high_threshold: None,
+                home_domain: Some(xdr::String32("example.com".try_into().unwrap())),
+                signer: Some(xdr::Signer {
+                    key: xdr::SignerKey::Ed25519SignedPayload(xdr::SignerKeyEd25519SignedPayload {
+                        ed25519: key(&pk0),
+                        payload: vec![1, 2, 3, 4, 5].try_into().unwrap(),
+                    }),
+                    weight: 1,
+                }),
+            }),
+            xdr::OperationBody::ChangeTrust(xdr::ChangeTrustOp {
+                line: xdr::ChangeTrustAsset::PoolShare(
+                    xdr::LiquidityPoolParameters::LiquidityPoolConstantProduct(
+                        xdr::LiquidityPoolConstantProductParameters {
+                            asset_a: xdr::Asset::Native,
+                            asset_b: usd.clone(),
+                            fee: 30,
+                        },
+                    ),
+                ),
+                limit: i64::MAX,
+            }),
+            xdr::OperationBody::AllowTrust(xdr::AllowTrustOp {
+                trustor: account_id(&pk1),
+                asset: xdr::AssetCode::CreditAlphanum4(xdr::AssetCode4(*b"USD\0")),
+                authorize: 1,
+            }),
+            xdr::OperationBody::AccountMerge(muxed(&pk1)),
+            xdr::OperationBody::Inflation,
+            xdr::OperationBody::ManageData(xdr::ManageDataOp {
+                data_name: xdr::String64("name".try_into().unwrap()),
+                data_value: Some(xdr::DataValue(vec![0, 255].try_into().unwrap())),
+            }),
+            xdr::OperationBody::BumpSequence(xdr::BumpSequenceOp {
+                bump_to: xdr::SequenceNumber(12345),
+            }),
+            xdr::OperationBody::ManageBuyOffer(xdr::ManageBuyOfferOp {
+                selling: xdr::Asset::Native,
+                buying: usd.clone(),
+                buy_amount: 3,
+                price: price.clone(),
+                offer_id: 0,
+            }),
+            xdr::OperationBody::PathPaymentStrictSend(xdr::PathPaymentStrictSendOp {
+                send_asset: usd.clone(),
+                send_amount: 100,
+                destination: muxed(&pk0),
+                dest_asset: xdr::Asset::Native,
+                dest_min: 1,
+                path: Default::default(),
+            }),
+            xdr::OperationBody::CreateClaimableBalance(xdr::CreateClaimableBalanceOp {
+                asset: xdr::Asset::Native,
+                amount: 10,
+                claimants: vec![xdr::Claimant::ClaimantTypeV0(xdr::ClaimantV0 {
+                    destination: account_id(&pk1),
+                    predicate: xdr::ClaimPredicate::And(
+                        vec![
+                            xdr::ClaimPredicate::Not(Some(Box::new(
+                                xdr::ClaimPredicate::BeforeRelativeTime(60),
+                            ))),
+                            xdr::ClaimPredicate::Or(
+                                vec![
+                                    xdr::ClaimPredicate::BeforeAbsoluteTime(1_000),
+                                    xdr::ClaimPredicate::Unconditional,
+                                ]
+                                .try_into()
+                                .unwrap(),
+                            ),
+                        ]
+                        .try_into()
+                        .unwrap(),
+                    ),
+                })]
+                .try_into()
+                .unwrap(),
+            }),
+            xdr::OperationBody::ClaimClaimableBalance(xdr::ClaimClaimableBalanceOp {
+                balance_id: balance_id.clone(),
+            }),
+            xdr::OperationBody::BeginSponsoringFutureReserves(
+                xdr::BeginSponsoringFutureReservesOp {
+                    sponsored_id: account_id(&pk1),
+                },
+            ),
+            xdr::OperationBody::EndSponsoringFutureReserves,
+            xdr::OperationBody::RevokeSponsorship(xdr::RevokeSponsorshipOp::LedgerEntry(
+                xdr::LedgerKey::Trustline(xdr::LedgerKeyTrustLine {
+                    account_id: account_id(&pk1),
+                    asset: xdr::TrustLineAsset::PoolShare(pool_id.clone()),
+                }),
+            )),
+            xdr::OperationBody::RevokeSponsorship(xdr::RevokeSponsorshipOp::Signer(
+                xdr::RevokeSponsorshipOpSigner {
+                    account_id: account_id(&pk1),
+                    signer_key: xdr::SignerKey::HashX(xdr::Uint256([8; 32])),
+                },
+            )),
+            xdr::OperationBody::Clawback(xdr::ClawbackOp {
+                asset: usd.clone(),
+                from: muxed(&pk1),
+                amount: 7,
+            }),
+            xdr::OperationBody::ClawbackClaimableBalance(xdr::ClawbackClaimableBalanceOp {
+                balance_id,
+            }),
+            xdr::OperationBody::SetTrustLineFlags(xdr::SetTrustLineFlagsOp {
+                trustor: account_id(&pk1),
+                asset: usd,
+                clear_flags: 1,
+                set_flags: 2,
+            }),
+            xdr::OperationBody::LiquidityPoolDeposit(xdr::LiquidityPoolDepositOp {
+                liquidity_pool_id: pool_id.clone(),
+                max_amount_a: 10,
+                max_amount_b: 20,
+                min_price: price.clone(),
+                max_price: price,
+            }),
+            xdr::OperationBody::LiquidityPoolWithdraw(xdr::LiquidityPoolWithdrawOp {
+                liquidity_pool_id: pool_id,
+                amount: 10,
+                min_amount_a: 1,
+                min_amount_b: 2,
+            }),
+            xdr::OperationBody::InvokeHostFunction(xdr::InvokeHostFunctionOp {
+                host_function: xdr::HostFunction::InvokeContract(invoke_args),
+                auth: vec![auth].try_into().unwrap(),
+            }),
+            xdr::OperationBody::InvokeHostFunction(xdr::InvokeHostFunctionOp {
+                host_function: xdr::HostFunction::CreateContract(xdr::CreateContractArgs {
+                    contract_id_preimage: xdr::ContractIdPreimage::Asset(long),
+                    executable: xdr::ContractExecutable::StellarAsset,
+                }),
+                auth: Default::default(),
+            }),
+            xdr::OperationBody::InvokeHostFunction(xdr::InvokeHostFunctionOp {
+                host_function: xdr::HostFunction::UploadContractWasm(
+                    vec![0, 97, 115, 109].try_into().unwrap(),
+                ),
+                auth: Default::default(),
+            }),
+            xdr::OperationBody::ExtendFootprintTtl(xdr::ExtendFootprintTtlOp {
+                ext: xdr::ExtensionPoint::V0,
+                extend_to: 500,
+            }),
+            xdr::OperationBody::RestoreFootprint(xdr::RestoreFootprintOp {
+                ext: xdr::ExtensionPoint::V0,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_all_operations_round_trip() {
+        let pk0 = keypair0().public_key();
+        let operations: Vec<xdr::Operation> = all_operations()
+            .into_iter()
+            .enumerate()
+            .map(|(i, body)| xdr::Operation {
+                source_account: if i % 2 == 0 { Some(muxed(&pk0)) } else { None },
+                body,
+            })
+            .collect();
+        let tx = xdr::Transaction {
+            source_account: muxed(&pk0),
+            fee: 10_000,
+            seq_num: xdr::SequenceNumber(42),
+            cond: xdr::Preconditions::V2(xdr::PreconditionsV2 {
+                time_bounds: None,
+                ledger_bounds: Some(xdr::LedgerBounds {
+                    min_ledger: 1,
+                    max_ledger: 0,
+                }),
+                min_seq_num: Some(xdr::SequenceNumber(40)),
+                min_seq_age: xdr::Duration(10),
+                min_seq_ledger_gap: 2,
+                extra_signers: vec![xdr::SignerKey::PreAuthTx(xdr::Uint256([1; 32]))]
+                    .try_into()
+                    .unwrap(),
+            }),
+            memo: xdr::Memo::Return(xdr::Hash([2; 32])),
+            operations: operations.try_into().unwrap(),
+            ext: xdr::TransactionExt::V1(xdr::SorobanTransactionData {
+                ext: xdr::SorobanTransactionDataExt::V1(xdr::SorobanResourcesExtV0 {
+                    archived_soroban_entries: vec![0, 3].try_into().unwrap(),
+                }),
+                resources: xdr::SorobanResources {
+                    footprint: xdr::LedgerFootprint {
+                        read_only: vec![
+                            contract_key(),
+                            xdr::LedgerKey::ConfigSetting(xdr::LedgerKeyConfigSetting {
+                                config_setting_id: xdr::ConfigSettingId::ContractComputeV0,
+                            }),
+                        ]
+                        .try_into()
+                        .unwrap(),
+                        read_write: vec![xdr::LedgerKey::Ttl(xdr::LedgerKeyTtl {
+                            key_hash: xdr::Hash([6; 32]),
+                        })]
+                        .try_into()
+                        .unwrap(),
+                    },
+                    instructions: 1_000,
+                    disk_read_bytes: 2_000,
+                    write_bytes: 3_000,
+                },
+                resource_fee: 4_000,
+            }),
+        };
+        let envelope = xdr::TransactionEnvelope::Tx(xdr::TransactionV1Envelope {
+            tx,
+            signatures: vec![xdr::DecoratedSignature {
+                hint: xdr::SignatureHint([1, 2, 3, 4]),
+                signature: xdr::Signature(vec![5; 64].try_into().unwrap()),
+            }]
+            .try_into()
+            .unwrap(),
+        });
+        let txrep = to_txrep(&envelope);
+        assert!(txrep.contains(
+            "tx.operations[1].body.paymentOp.asset.type: ASSET_TYPE_CREDIT_ALPHANUM12\n"
+        ));
+        assert!(txrep.contains("tx.operations[1].body.paymentOp.asset.alphaNum12.assetCode: 414200000000000000000000\n"));
+        assert!(txrep.contains("tx.operations[7].body.allowTrustOp.asset: USD\n"));
+        assert!(txrep.contains("tx.cond.v2.timeBounds._present: false\n"));
+        assert!(txrep.contains("tx.ext.sorobanData.resources.footprint.readOnly[1].configSetting.configSettingID: CONFIG_SETTING_CONTRACT_COMPUTE_V0\n"));
+        assert_eq!(envelope, from_txrep(&txrep).unwrap());
+    }
+
+    #[test]
+    fn test_v0_envelope_round_trip() {
+        let pk0 = keypair0().public_key();
+        let envelope = xdr::TransactionEnvelope::TxV0(xdr::TransactionV0Envelope {
+            tx: xdr::TransactionV0 {
+                source_account_ed25519: key(&pk0),
+                fee: 100,
+                seq_num: xdr::SequenceNumber(1),
+                time_bounds: Some(xdr::TimeBounds {
+                    min_time: xdr::TimePoint(0),
+                    max_time: xdr::TimePoint(u64::MAX),
+                }),
+                memo: xdr::Memo::Id(u64::MAX),
+                operations: vec![xdr::Operation {
+                    source_account: None,
+                    body: xdr::OperationBody::Inflation,
+                }]
+                .try_into()
+                .unwrap(),
+                ext: xdr::TransactionV0Ext::V0,
+            },
+            signatures: Default::default(),
+        });
+        let txrep = to_txrep(&envelope);
+        assert!(txrep.starts_with("type: ENVELOPE_TYPE_TX_V0\n"));
+        assert_eq!(envelope, from_txrep(&txrep).unwrap());
+    }
+}
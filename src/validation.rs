@@ -0,0 +1,590 @@
+//! Static transaction validity checks.
+//!
+//! These checks mirror the checks stellar-core runs on a transaction before
+//! loading any ledger entry, so that malformed transactions are caught
+//! before they are submitted.
+//!
+//! `Transaction` doesn't carry Soroban transaction data, so transactions with
+//! Soroban operations are always reported as malformed.
+use crate::amount::{Price, Stroops};
+use crate::asset::{Asset, CreditAsset, CreditAssetType};
+use crate::crypto::MuxedAccount;
+use crate::liquidity_pool::LiquidityPoolParameters;
+use crate::operations::{ChangeTrustAsset, Operation};
+use crate::transaction::Transaction;
+use crate::xdr;
+
+/// Maximum length of the path of a path payment.
+pub const MAX_PATH_PAYMENT_PATH_LENGTH: usize = 5;
+
+/// Maximum length of a data entry name.
+pub const MAX_DATA_NAME_LENGTH: usize = 64;
+
+/// Maximum length of an account home domain.
+pub const MAX_HOME_DOMAIN_LENGTH: usize = 32;
+
+/// Maximum signer weight and threshold.
+pub const MAX_WEIGHT: u32 = 255;
+
+/// Reason a transaction is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The transaction has no operations.
+    MissingOperations,
+    /// The transaction has too many operations.
+    TooManyOperations,
+    /// The lower time bound is after the upper time bound.
+    InvalidTimeBounds,
+    /// The signature at `index` is a duplicate of a previous signature.
+    DuplicateSignature { index: usize },
+    /// An asset code is empty, too long, or not alphanumeric.
+    InvalidAssetCode { operation: usize },
+    /// An amount is zero or negative, or a new offer has a zero amount.
+    InvalidAmount { operation: usize },
+    /// A native payment to the source account itself.
+    SelfPayment { operation: usize },
+    /// An offer sells and buys the same asset, a trust line is for the
+    /// native asset or a liquidity pool assets are not in canonical order.
+    InvalidAssetPair { operation: usize },
+    /// A price numerator or denominator is zero or negative, or a minimum
+    /// price is above the maximum price.
+    InvalidPrice { operation: usize },
+    /// A data entry name is empty, too long, or not printable ascii.
+    InvalidDataName { operation: usize },
+    /// A signer weight or threshold is above 255.
+    InvalidWeight { operation: usize },
+    /// A home domain is too long or not printable ascii.
+    InvalidHomeDomain { operation: usize },
+    /// A path payment path is longer than 5 assets.
+    PathTooLong { operation: usize },
+    /// A clawback of the native asset.
+    InvalidNativeAsset { operation: usize },
+    /// A claimable balance without claimants.
+    MissingClaimants { operation: usize },
+    /// A Soroban operation without Soroban transaction data.
+    MissingSorobanData { operation: usize },
+}
+
+impl ValidationError {
+    /// Retrieves the index of the operation with the error, if any.
+    pub fn operation(&self) -> Option<usize> {
+        match *self {
+            ValidationError::MissingOperations
+            | ValidationError::TooManyOperations
+            | ValidationError::InvalidTimeBounds
+            | ValidationError::DuplicateSignature { .. } => None,
+            ValidationError::InvalidAssetCode { operation }
+            | ValidationError::InvalidAmount { operation }
+            | ValidationError::SelfPayment { operation }
+            | ValidationError::InvalidAssetPair { operation }
+            | ValidationError::InvalidPrice { operation }
+            | ValidationError::InvalidDataName { operation }
+            | ValidationError::InvalidWeight { operation }
+            | ValidationError::InvalidHomeDomain { operation }
+            | ValidationError::PathTooLong { operation }
+            | ValidationError::InvalidNativeAsset { operation }
+            | ValidationError::MissingClaimants { operation }
+            | ValidationError::MissingSorobanData { operation } => Some(operation),
+        }
+    }
+}
+
+/// Returns all the reasons `tx` is malformed.
+pub(crate) fn validate_transaction(tx: &Transaction) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    if tx.operations().is_empty() {
+        errors.push(ValidationError::MissingOperations);
+    } else if tx.operations().len() > xdr::MAX_OPS_PER_TX as usize {
+        errors.push(ValidationError::TooManyOperations);
+    }
+
+    if let Some(time_bounds) = tx.time_bounds() {
+        if let (Some(lower), Some(upper)) = (time_bounds.lower(), time_bounds.upper()) {
+            if lower > upper {
+                errors.push(ValidationError::InvalidTimeBounds);
+            }
+        }
+    }
+
+    for (index, signature) in tx.signatures().iter().enumerate() {
+        if tx.signatures()[..index].contains(signature) {
+            errors.push(ValidationError::DuplicateSignature { index });
+        }
+    }
+
+    for (index, operation) in tx.operations().iter().enumerate() {
+        let mut checker = OperationChecker {
+            operation: index,
+            errors: &mut errors,
+        };
+        checker.check(tx.source_account(), operation);
+    }
+
+    errors
+}
+
+struct OperationChecker<'a> {
+    operation: usize,
+    errors: &'a mut Vec<ValidationError>,
+}
+
+impl OperationChecker<'_> {
+    fn check(&mut self, tx_source: &MuxedAccount, operation: &Operation) {
+        let operation_source = operation.source_account().as_ref().unwrap_or(tx_source);
+        match operation {
+            Operation::CreateAccount(op) => {
+                self.non_negative(op.starting_balance());
+            }
+            Operation::Payment(op) => {
+                self.asset(op.asset());
+                self.positive(op.amount());
+                if op.asset().is_native()
+                    && op.destination().public_key() == operation_source.public_key()
+                {
+                    self.push(ValidationError::SelfPayment {
+                        operation: self.operation,
+                    });
+                }
+            }
+            Operation::PathPaymentStrictReceive(op) => {
+                self.path_payment(op.send_asset(), op.destination_asset(), op.path());
+                self.positive(op.send_max());
+                self.positive(op.destination_amount());
+            }
+            Operation::PathPaymentStrictSend(op) => {
+                self.path_payment(op.send_asset(), op.destination_asset(), op.path());
+                self.positive(op.send_amount());
+                self.positive(op.destination_min());
+            }
+            Operation::ManageSellOffer(op) => {
+                self.offer(op.selling(), op.buying(), op.price());
+                self.offer_amount(op.amount(), op.offer_id());
+            }
+            Operation::ManageBuyOffer(op) => {
+                self.offer(op.selling(), op.buying(), op.price());
+                self.offer_amount(op.buy_amount(), op.offer_id());
+            }
+            Operation::CreatePassiveSellOffer(op) => {
+                self.offer(op.selling(), op.buying(), op.price());
+                self.positive(op.amount());
+            }
+            Operation::SetOptions(op) => {
+                let weights = [
+                    op.master_weight(),
+                    op.low_threshold(),
+                    op.medium_threshold(),
+                    op.high_threshold(),
+                ];
+                let signer_weight = op.signer().as_ref().map(|signer| *signer.weight());
+                if weights
+                    .iter()
+                    .filter_map(|weight| **weight)
+                    .chain(signer_weight)
+                    .any(|weight| weight > MAX_WEIGHT)
+                {
+                    self.push(ValidationError::InvalidWeight {
+                        operation: self.operation,
+                    });
+                }
+                if let Some(home_domain) = op.home_domain() {
+                    if home_domain.len() > MAX_HOME_DOMAIN_LENGTH || !is_string_valid(home_domain) {
+                        self.push(ValidationError::InvalidHomeDomain {
+                            operation: self.operation,
+                        });
+                    }
+                }
+            }
+            Operation::ChangeTrust(op) => {
+                match op.asset() {
+                    ChangeTrustAsset::Native => self.push(ValidationError::InvalidAssetPair {
+                        operation: self.operation,
+                    }),
+                    ChangeTrustAsset::Credit(credit) => self.credit_asset(credit),
+                    ChangeTrustAsset::PoolShare(LiquidityPoolParameters::ConstantFee(params)) => {
+                        let (asset_a, asset_b) = params.assets();
+                        self.asset(asset_a);
+                        self.asset(asset_b);
+                        if asset_a >= asset_b {
+                            self.push(ValidationError::InvalidAssetPair {
+                                operation: self.operation,
+                            });
+                        }
+                    }
+                }
+                if let Some(limit) = op.limit() {
+                    self.non_negative(limit);
+                }
+            }
+            Operation::AllowTrust(op) => {
+                let valid = match op.asset() {
+                    CreditAssetType::CreditAlphaNum4(code) => is_asset_code_valid(code, 1, 4),
+                    CreditAssetType::CreditAlphaNum12(code) => is_asset_code_valid(code, 5, 12),
+                };
+                if !valid {
+                    self.push(ValidationError::InvalidAssetCode {
+                        operation: self.operation,
+                    });
+                }
+            }
+            Operation::ManageData(op) => {
+                let name = op.data_name();
+                if name.is_empty() || name.len() > MAX_DATA_NAME_LENGTH || !is_string_valid(name) {
+                    self.push(ValidationError::InvalidDataName {
+                        operation: self.operation,
+                    });
+                }
+            }
+            Operation::CreateClaimableBalance(op) => {
+                self.asset(op.asset());
+                self.positive(op.amount());
+                if op.claimants().is_empty() {
+                    self.push(ValidationError::MissingClaimants {
+                        operation: self.operation,
+                    });
+                }
+            }
+            Operation::Clawback(op) => {
+                self.asset(op.asset());
+                self.positive(op.amount());
+                if op.asset().is_native() {
+                    self.push(ValidationError::InvalidNativeAsset {
+                        operation: self.operation,
+                    });
+                }
+            }
+            Operation::SetTrustLineFlags(op) => {
+                self.asset(op.asset());
+            }
+            Operation::LiquidityPoolDeposit(op) => {
+                self.positive(op.max_amount_a());
+                self.positive(op.max_amount_b());
+                self.price(op.min_price());
+                self.price(op.max_price());
+                let (min, max) = (op.min_price(), op.max_price());
+                if i128::from(min.numerator()) * i128::from(max.denominator())
+                    > i128::from(min.denominator()) * i128::from(max.numerator())
+                {
+                    self.push(ValidationError::InvalidPrice {
+                        operation: self.operation,
+                    });
+                }
+            }
+            Operation::LiquidityPoolWithdraw(op) => {
+                self.positive(op.amount());
+                self.non_negative(op.min_amount_a());
+                self.non_negative(op.min_amount_b());
+            }
+            // Transactions don't carry Soroban data, which Soroban operations require.
+            Operation::InvokeHostFunction(_)
+            | Operation::ExtendFootprintTtl(_)
+            | Operation::RestoreFootprint(_) => self.push(ValidationError::MissingSorobanData {
+                operation: self.operation,
+            }),
+            _ => {}
+        }
+    }
+
+    fn push(&mut self, error: ValidationError) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    fn positive(&mut self, amount: &Stroops) {
+        if amount.to_i64() <= 0 {
+            self.push(ValidationError::InvalidAmount {
+                operation: self.operation,
+            });
+        }
+    }
+
+    fn non_negative(&mut self, amount: &Stroops) {
+        if amount.to_i64() < 0 {
+            self.push(ValidationError::InvalidAmount {
+                operation: self.operation,
+            });
+        }
+    }
+
+    /// Offers can be deleted with a zero amount, but not created.
+    fn offer_amount(&mut self, amount: &Stroops, offer_id: &Option<i64>) {
+        self.non_negative(amount);
+        if amount.to_i64() == 0 && offer_id.unwrap_or(0) == 0 {
+            self.push(ValidationError::InvalidAmount {
+                operation: self.operation,
+            });
+        }
+    }
+
+    fn price(&mut self, price: &Price) {
+        if price.numerator() <= 0 || price.denominator() <= 0 {
+            self.push(ValidationError::InvalidPrice {
+                operation: self.operation,
+            });
+        }
+    }
+
+    fn asset(&mut self, asset: &Asset) {
+        if let Asset::Credit(credit) = asset {
+            self.credit_asset(credit);
+        }
+    }
+
+    fn credit_asset(&mut self, credit: &CreditAsset) {
+        let valid = match credit {
+            CreditAsset::AlphaNum4 { code, .. } => is_asset_code_valid(code, 1, 4),
+            CreditAsset::AlphaNum12 { code, .. } => is_asset_code_valid(code, 5, 12),
+        };
+        if !valid {
+            self.push(ValidationError::InvalidAssetCode {
+                operation: self.operation,
+            });
+        }
+    }
+
+    fn offer(&mut self, selling: &Asset, buying: &Asset, price: &Price) {
+        self.asset(selling);
+        self.asset(buying);
+        self.price(price);
+        if selling == buying {
+            self.push(ValidationError::InvalidAssetPair {
+                operation: self.operation,
+            });
+        }
+    }
+
+    fn path_payment(&mut self, send_asset: &Asset, destination_asset: &Asset, path: &[Asset]) {
+        self.asset(send_asset);
+        self.asset(destination_asset);
+        for asset in path {
+            self.asset(asset);
+        }
+        if path.len() > MAX_PATH_PAYMENT_PATH_LENGTH {
+            self.push(ValidationError::PathTooLong {
+                operation: self.operation,
+            });
+        }
+    }
+}
+
+/// Returns true if `code` only has ascii letters and digits and its length
+/// is between `min` and `max`.
+fn is_asset_code_valid(code: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&code.len()) && code.bytes().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Returns true if `value` only has printable ascii characters.
+fn is_string_valid(value: &str) -> bool {
+    value.bytes().all(|c| c.is_ascii() && !c.is_ascii_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claim::{ClaimPredicate, Claimant};
+    use crate::crypto::{DalekKeyPair, DecoratedSignature};
+    use crate::error::Error;
+    use crate::liquidity_pool::LiquidityPoolId;
+    use crate::network::Network;
+    use crate::operations::tests::{keypair0, keypair1};
+    use crate::time_bounds::TimeBounds;
+    use crate::transaction::MIN_BASE_FEE;
+    use chrono::{TimeZone, Utc};
+
+    fn transaction(operations: Vec<Operation>) -> Transaction {
+        let mut tx = Transaction::builder(keypair0().public_key(), 1, MIN_BASE_FEE)
+            .add_operation(Operation::new_inflation().build())
+            .into_transaction()
+            .unwrap();
+        *tx.operations_mut() = operations;
+        tx
+    }
+
+    fn credit(code: &str) -> Asset {
+        Asset::Credit(CreditAsset::AlphaNum4 {
+            code: code.to_string(),
+            issuer: keypair1().public_key(),
+        })
+    }
+
+    #[test]
+    fn test_valid_transaction() {
+        let tx = transaction(vec![Operation::new_payment()
+            .with_destination(keypair1().public_key())
+            .with_amount(Stroops::new(10))
+            .unwrap()
+            .with_asset(Asset::new_native())
+            .build()
+            .unwrap()]);
+        assert!(validate_transaction(&tx).is_empty());
+        assert!(tx.validate().is_ok());
+    }
+
+    #[test]
+    fn test_operation_errors() {
+        let mut payment = Operation::new_payment()
+            .with_destination(keypair0().public_key())
+            .with_amount(Stroops::new(10))
+            .unwrap()
+            .with_asset(Asset::new_native())
+            .build()
+            .unwrap();
+        let mut bad_code = payment.clone();
+        if let Operation::Payment(ref mut op) = bad_code {
+            *op.asset_mut() = credit("U$D");
+        }
+        if let Operation::Payment(ref mut op) = payment {
+            *op.amount_mut() = Stroops::new(0);
+        }
+        let offer = Operation::new_manage_sell_offer()
+            .with_selling_asset(credit("USD"))
+            .with_buying_asset(Asset::new_native())
+            .with_amount(crate::amount::Amount::from_stroops(&Stroops::new(10)).unwrap())
+            .unwrap()
+            .with_price(Price::new(0, 1))
+            .build()
+            .unwrap();
+        let data = Operation::new_manage_data()
+            .with_data_name("a\nb".to_string())
+            .build()
+            .unwrap();
+        let mut options = Operation::new_set_options()
+            .with_master_weight(Some(256))
+            .build()
+            .unwrap();
+        if let Operation::SetOptions(ref mut op) = options {
+            *op.home_domain_mut() = Some("d\u{e9}j\u{e0}.example".to_string());
+        }
+        let mut path_payment = Operation::new_path_payment_strict_send()
+            .with_destination(keypair1().public_key())
+            .with_send_asset(Asset::new_native())
+            .with_send_amount(Stroops::new(10))
+            .unwrap()
+            .with_destination_asset(credit("USD"))
+            .with_destination_min(Stroops::new(1))
+            .unwrap()
+            .build()
+            .unwrap();
+        if let Operation::PathPaymentStrictSend(ref mut op) = path_payment {
+            *op.path_mut() = vec![credit("EUR"); 6];
+        }
+
+        let tx = transaction(vec![payment, bad_code, offer, data, options, path_payment]);
+        let errors = validate_transaction(&tx);
+        assert_eq!(
+            vec![
+                ValidationError::InvalidAmount { operation: 0 },
+                ValidationError::SelfPayment { operation: 0 },
+                ValidationError::InvalidAssetCode { operation: 1 },
+                ValidationError::InvalidPrice { operation: 2 },
+                ValidationError::InvalidDataName { operation: 3 },
+                ValidationError::InvalidWeight { operation: 4 },
+                ValidationError::InvalidHomeDomain { operation: 4 },
+                ValidationError::PathTooLong { operation: 5 },
+            ],
+            errors
+        );
+        assert_eq!(Some(4), errors[6].operation());
+        match tx.validate() {
+            Err(Error::InvalidTransaction(reported)) => assert_eq!(errors, reported),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_malformed_operations() {
+        let offer = Operation::new_manage_buy_offer()
+            .with_selling_asset(credit("USD"))
+            .with_buying_asset(Asset::new_native())
+            .with_buy_amount(crate::amount::Amount::from_stroops(&Stroops::new(0)).unwrap())
+            .unwrap()
+            .with_price(Price::new(1, 1))
+            .build()
+            .unwrap();
+        let delete_offer = Operation::new_manage_buy_offer()
+            .with_selling_asset(credit("USD"))
+            .with_buying_asset(Asset::new_native())
+            .with_buy_amount(crate::amount::Amount::from_stroops(&Stroops::new(0)).unwrap())
+            .unwrap()
+            .with_price(Price::new(1, 1))
+            .with_offer_id(Some(7))
+            .build()
+            .unwrap();
+        let clawback = Operation::new_clawback()
+            .with_asset(Asset::new_native())
+            .with_from(keypair1().public_key())
+            .with_amount(Stroops::new(10))
+            .unwrap()
+            .build()
+            .unwrap();
+        let deposit = Operation::new_liquidity_pool_deposit()
+            .with_liquidity_pool_id(LiquidityPoolId::new(vec![7; 32]).unwrap())
+            .with_max_amount_a(Stroops::new(10))
+            .unwrap()
+            .with_max_amount_b(Stroops::new(10))
+            .unwrap()
+            .with_min_price(Price::new(2, 1))
+            .with_max_price(Price::new(3, 2))
+            .build()
+            .unwrap();
+        let mut claimable_balance = Operation::new_create_claimable_balance()
+            .with_asset(Asset::new_native())
+            .with_amount(Stroops::new(10))
+            .unwrap()
+            .add_claimant(Claimant::new(
+                keypair1().public_key(),
+                ClaimPredicate::new_unconditional(),
+            ))
+            .build()
+            .unwrap();
+        if let Operation::CreateClaimableBalance(ref mut op) = claimable_balance {
+            op.claimants_mut().clear();
+        }
+        let restore = Operation::new_restore_footprint().build();
+
+        let tx = transaction(vec![
+            offer,
+            delete_offer,
+            clawback,
+            deposit,
+            claimable_balance,
+            restore,
+        ]);
+        assert_eq!(
+            vec![
+                ValidationError::InvalidAmount { operation: 0 },
+                ValidationError::InvalidNativeAsset { operation: 2 },
+                ValidationError::InvalidPrice { operation: 3 },
+                ValidationError::MissingClaimants { operation: 4 },
+                ValidationError::MissingSorobanData { operation: 5 },
+            ],
+            validate_transaction(&tx)
+        );
+    }
+
+    #[test]
+    fn test_transaction_errors() {
+        let keypair = DalekKeyPair::random().unwrap();
+        let mut tx = transaction(Vec::new());
+        let lower = Utc.timestamp_opt(2_000, 0).unwrap();
+        let upper = Utc.timestamp_opt(1_000, 0).unwrap();
+        *tx.time_bounds_mut() = Some(TimeBounds::always_valid());
+        if let Some(ref mut time_bounds) = tx.time_bounds_mut() {
+            *time_bounds.lower_mut() = Some(lower);
+            *time_bounds.upper_mut() = Some(upper);
+        }
+        let network = Network::new_test();
+        let signature: DecoratedSignature =
+            tx.decorated_signature(keypair.as_ref(), &network).unwrap();
+        *tx.signatures_mut() = vec![signature.clone(), signature];
+        assert_eq!(
+            vec![
+                ValidationError::MissingOperations,
+                ValidationError::InvalidTimeBounds,
+                ValidationError::DuplicateSignature { index: 1 },
+            ],
+            validate_transaction(&tx)
+        );
+    }
+}
@@ -5,50 +5,188 @@ pub use stellar_xdr::curr::*;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Maximum nesting depth of xdr values, the same used by stellar-core.
+pub const MARSHALLING_STACK_LIMIT: u32 = 1000;
+
+/// Maximum size of a network message accepted by stellar-core.
+pub const MAX_MESSAGE_SIZE: usize = 0x1000000;
+
+/// Limits safe to use when decoding untrusted input, for the `*_with_limits`
+/// methods of `XDRDeserialize`.
+pub const DEFAULT_LIMITS: Limits = Limits {
+    depth: MARSHALLING_STACK_LIMIT,
+    len: MAX_MESSAGE_SIZE,
+};
+
 /// Provided for compatibility. Prefer using the `WriteXdr` trait with appropriate limits.
+///
+/// The methods without limits don't limit the values written.
 pub trait XDRSerialize {
-    fn write_xdr(&self, out: &mut Vec<u8>) -> crate::error::Result<u64>;
+    fn write_xdr_with_limits(&self, out: &mut Vec<u8>, limits: Limits)
+        -> crate::error::Result<u64>;
 
-    fn xdr_bytes(&self) -> crate::error::Result<Vec<u8>> {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> crate::error::Result<u64> {
+        self.write_xdr_with_limits(out, Limits::none())
+    }
+
+    fn xdr_bytes_with_limits(&self, limits: Limits) -> crate::error::Result<Vec<u8>> {
         let mut out = Vec::new();
-        self.write_xdr(&mut out)?;
+        self.write_xdr_with_limits(&mut out, limits)?;
         Ok(out)
     }
 
-    fn xdr_base64(&self) -> crate::error::Result<String> {
-        let bytes = self.xdr_bytes()?;
+    fn xdr_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        self.xdr_bytes_with_limits(Limits::none())
+    }
+
+    fn xdr_base64_with_limits(&self, limits: Limits) -> crate::error::Result<String> {
+        let bytes = self.xdr_bytes_with_limits(limits)?;
         let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes);
         Ok(encoded)
     }
+
+    fn xdr_base64(&self) -> crate::error::Result<String> {
+        self.xdr_base64_with_limits(Limits::none())
+    }
 }
 
 impl<T: WriteXdr + ?Sized> XDRSerialize for T {
-    fn write_xdr(&self, out: &mut Vec<u8>) -> crate::error::Result<u64> {
+    fn write_xdr_with_limits(
+        &self,
+        out: &mut Vec<u8>,
+        limits: Limits,
+    ) -> crate::error::Result<u64> {
         use std::io::Cursor;
-        let mut w = Limited::new(Cursor::new(out), Limits::none());
-        self.write_xdr(&mut w)
-            .map_err(|_| crate::error::Error::XdrError)?;
+        let mut w = Limited::new(Cursor::new(out), limits);
+        self.write_xdr(&mut w)?;
         let bytes_written = w.inner.position();
         Ok(bytes_written)
     }
 }
 
 /// Provided for compatibility. Prefer using the `ReadXdr` trait with appropriate limits.
+///
+/// The methods without limits don't limit the values read, use the
+/// `*_with_limits` methods with `DEFAULT_LIMITS` to decode untrusted input.
+/// Decoding fails if `buffer` contains bytes after the value.
 pub trait XDRDeserialize: Sized {
-    fn from_xdr_bytes(buffer: &[u8]) -> crate::error::Result<(Self, u64)>;
-    fn from_xdr_base64(encoded: &str) -> crate::error::Result<Self> {
+    fn from_xdr_bytes_with_limits(
+        buffer: &[u8],
+        limits: Limits,
+    ) -> crate::error::Result<(Self, u64)>;
+
+    fn from_xdr_bytes(buffer: &[u8]) -> crate::error::Result<(Self, u64)> {
+        Self::from_xdr_bytes_with_limits(buffer, Limits::none())
+    }
+
+    fn from_xdr_base64_with_limits(encoded: &str, limits: Limits) -> crate::error::Result<Self> {
+        // Base64 encodes 3 bytes in 4 characters.
+        if encoded.len() / 4 * 3 > limits.len.saturating_add(3) {
+            return Err(Error::LengthLimitExceeded.into());
+        }
         let decoded = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)?;
-        let (res, _) = Self::from_xdr_bytes(&decoded)?;
+        let (res, _) = Self::from_xdr_bytes_with_limits(&decoded, limits)?;
         Ok(res)
     }
+
+    fn from_xdr_base64(encoded: &str) -> crate::error::Result<Self> {
+        Self::from_xdr_base64_with_limits(encoded, Limits::none())
+    }
 }
 
 impl<T: ReadXdr + Sized> XDRDeserialize for T {
-    fn from_xdr_bytes(buffer: &[u8]) -> crate::error::Result<(Self, u64)> {
+    fn from_xdr_bytes_with_limits(
+        buffer: &[u8],
+        limits: Limits,
+    ) -> crate::error::Result<(Self, u64)> {
         use std::io::Cursor;
-        let mut r = Limited::new(Cursor::new(buffer), Limits::none());
-        let val = T::read_xdr(&mut r).map_err(|_| crate::error::Error::XdrError)?;
+        let mut r = Limited::new(Cursor::new(buffer), limits);
+        let val = T::read_xdr_to_end(&mut r)?;
         let bytes_read = r.inner.position();
         Ok((val, bytes_read))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{XDRDeserialize, XDRSerialize, DEFAULT_LIMITS};
+    use crate::error::Error;
+    use crate::xdr;
+
+    fn nested_sc_val(depth: usize) -> xdr::ScVal {
+        let mut val = xdr::ScVal::Void;
+        for _ in 0..depth {
+            val = xdr::ScVal::Vec(Some(xdr::ScVec(vec![val].try_into().unwrap())));
+        }
+        val
+    }
+
+    #[test]
+    fn test_round_trip_with_default_limits() {
+        let val = nested_sc_val(10);
+        let encoded = val.xdr_base64_with_limits(DEFAULT_LIMITS).unwrap();
+        assert_eq!(
+            val,
+            xdr::ScVal::from_xdr_base64_with_limits(&encoded, DEFAULT_LIMITS).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compatibility_methods_are_unlimited() {
+        let val = xdr::ScVal::Bytes(xdr::ScBytes(
+            vec![0; super::MAX_MESSAGE_SIZE + 1].try_into().unwrap(),
+        ));
+        let bytes = val.xdr_bytes().unwrap();
+        assert!(xdr::ScVal::from_xdr_bytes_with_limits(&bytes, DEFAULT_LIMITS).is_err());
+        assert_eq!(val, xdr::ScVal::from_xdr_bytes(&bytes).unwrap().0);
+        let encoded = val.xdr_base64().unwrap();
+        assert_eq!(val, xdr::ScVal::from_xdr_base64(&encoded).unwrap());
+    }
+
+    #[test]
+    fn test_reject_trailing_bytes() {
+        let mut bytes = xdr::ScVal::U32(1).xdr_bytes().unwrap();
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        let res = xdr::ScVal::from_xdr_bytes(&bytes);
+        assert!(matches!(res, Err(Error::XdrError(xdr::Error::Invalid))));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let bytes = nested_sc_val(20)
+            .xdr_bytes_with_limits(xdr::Limits::none())
+            .unwrap();
+        let limits = xdr::Limits {
+            depth: 10,
+            ..DEFAULT_LIMITS
+        };
+        let res = xdr::ScVal::from_xdr_bytes_with_limits(&bytes, limits.clone());
+        assert!(matches!(
+            res,
+            Err(Error::XdrError(xdr::Error::DepthLimitExceeded))
+        ));
+        let res = nested_sc_val(20).xdr_bytes_with_limits(limits);
+        assert!(matches!(
+            res,
+            Err(Error::XdrError(xdr::Error::DepthLimitExceeded))
+        ));
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let val = xdr::ScVal::Bytes(xdr::ScBytes(vec![0; 1000].try_into().unwrap()));
+        let encoded = val.xdr_base64().unwrap();
+        let limits = xdr::Limits::len(100);
+        let res = xdr::ScVal::from_xdr_base64_with_limits(&encoded, limits.clone());
+        assert!(matches!(
+            res,
+            Err(Error::XdrError(xdr::Error::LengthLimitExceeded))
+        ));
+        let bytes = val.xdr_bytes().unwrap();
+        let res = xdr::ScVal::from_xdr_bytes_with_limits(&bytes, limits);
+        assert!(matches!(
+            res,
+            Err(Error::XdrError(xdr::Error::LengthLimitExceeded))
+        ));
+    }
+}